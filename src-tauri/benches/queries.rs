@@ -0,0 +1,178 @@
+// Query latency benchmark for the hot paths.
+//
+// Seeds a throwaway database with thousands of tasks, journal entries and habit
+// completions, times the queries behind the kanban board, agenda, habits and
+// journal views, and checks that their plans use the indexes. Exits non-zero
+// when a query goes over its budget or falls back to a table scan, so it can
+// gate CI:
+//
+//     cargo bench --bench queries
+//
+// PPA_BENCH_TASKS and PPA_BENCH_ENTRIES change the data size.
+
+use personal_productivity_app_lib::commands;
+use rusqlite::Connection;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 25;
+const STATUSES: [&str; 5] = ["backlog", "todo", "inprogress", "review", "done"];
+const PRIORITIES: [&str; 4] = ["low", "medium", "high", "critical"];
+
+struct Seeded {
+    workstream_id: String,
+    persona_id: String,
+    today: String,
+}
+
+fn env_count(name: &str, default: usize) -> usize {
+    std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn seed(db: &Connection, tasks: usize, entries: usize) -> Result<Seeded, String> {
+    let tx = db.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let mut workstreams = Vec::new();
+    for p in 0..5 {
+        let persona = commands::create_persona(&tx, format!("Persona {}", p), None, "#3b82f6".to_string())?;
+        for w in 0..4 {
+            let workstream = commands::create_workstream(&tx, persona.id.clone(), format!("Workstream {}.{}", p, w), None, "active".to_string(), None, None, None)?;
+            workstreams.push((workstream.id, persona.id.clone()));
+        }
+    }
+
+    let today = chrono::Local::now().date_naive();
+    for i in 0..tasks {
+        let (workstream_id, _) = &workstreams[i % workstreams.len()];
+        let task = commands::create_project_task(
+            &tx,
+            workstream_id.clone(),
+            format!("Task {}", i),
+            Some("Seeded by the query benchmark".to_string()),
+            STATUSES[i % STATUSES.len()].to_string(),
+            PRIORITIES[i % PRIORITIES.len()].to_string(),
+        )?;
+        if i % 3 == 0 {
+            let due = today + chrono::Duration::days((i % 60) as i64 - 30);
+            tx.execute("UPDATE project_tasks SET due_date = ?1 WHERE id = ?2", [due.format("%Y-%m-%d").to_string(), task.id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    for i in 0..entries {
+        let (workstream_id, _) = &workstreams[i % workstreams.len()];
+        commands::create_journal_entry(&tx, format!("Entry {}", i), "Seeded entry".to_string(), Some(workstream_id.clone()), None, "note".to_string(), vec![])?;
+    }
+
+    for (workstream_id, _) in workstreams.iter().take(10) {
+        let habit = commands::create_habit(&tx, workstream_id.clone(), "Habit".to_string(), None, "daily".to_string(), None, None, None, None)?;
+        for day in 0..365 {
+            let date = today - chrono::Duration::days(day);
+            commands::log_habit_completion(&tx, habit.id.clone(), Some(date.format("%Y-%m-%d").to_string()), None, None)?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    db.execute_batch("ANALYZE;").map_err(|e| e.to_string())?;
+
+    Ok(Seeded {
+        workstream_id: workstreams[0].0.clone(),
+        persona_id: workstreams[0].1.clone(),
+        today: today.format("%Y-%m-%d").to_string(),
+    })
+}
+
+// Median and worst time of a query after one warm-up run.
+fn measure(mut run: impl FnMut() -> Result<(), String>) -> Result<(Duration, Duration), String> {
+    run()?;
+    let mut times = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        run()?;
+        times.push(start.elapsed());
+    }
+    times.sort();
+    Ok((times[times.len() / 2], times[times.len() - 1]))
+}
+
+fn query_plan(db: &Connection, sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<String, String> {
+    let mut stmt = db.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).map_err(|e| e.to_string())?;
+    let details = stmt.query_map(params, |row| row.get::<_, String>(3))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(details.join("; "))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<bool, String> {
+    let tasks = env_count("PPA_BENCH_TASKS", 5000);
+    let entries = env_count("PPA_BENCH_ENTRIES", 2000);
+
+    let path = std::env::temp_dir().join(format!("ppa-bench-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let db = commands::open_database(&path.to_string_lossy())?;
+
+    let start = Instant::now();
+    let s = seed(&db, tasks, entries)?;
+    println!("Seeded {} tasks, {} journal entries and 3650 habit completions in {:.1?}\n", tasks, entries, start.elapsed());
+
+    // (name, budget for the median, query)
+    type Bench<'a> = (&'a str, Duration, Box<dyn FnMut() -> Result<(), String> + 'a>);
+    let ms = Duration::from_millis;
+    let benches: Vec<Bench> = vec![
+        ("kanban, all tasks", ms(150), Box::new(|| commands::get_tasks_for_kanban(&db, None, None).map(drop))),
+        ("kanban, one workstream", ms(20), Box::new(|| commands::get_tasks_for_kanban(&db, Some(s.workstream_id.clone()), None).map(drop))),
+        ("kanban, one status", ms(40), Box::new(|| commands::get_tasks_for_kanban(&db, None, Some("inprogress".to_string())).map(drop))),
+        ("task counts, all", ms(30), Box::new(|| commands::get_task_counts_by_status(&db, None).map(drop))),
+        ("task counts, one workstream", ms(5), Box::new(|| commands::get_task_counts_by_status(&db, Some(s.workstream_id.clone())).map(drop))),
+        ("agenda", ms(60), Box::new(|| commands::get_agenda(&db, Some(s.today.clone())).map(drop))),
+        ("habits for a day", ms(5), Box::new(|| commands::get_habits(&db, Some(s.today.clone())).map(drop))),
+        ("journal, latest", ms(10), Box::new(|| commands::get_journal_entries(&db, None, None, None).map(drop))),
+        ("journal, one workstream", ms(10), Box::new(|| commands::get_journal_entries(&db, Some(s.workstream_id.clone()), None, None).map(drop))),
+        ("journal, one persona", ms(10), Box::new(|| commands::get_journal_entries(&db, None, Some(s.persona_id.clone()), None).map(drop))),
+    ];
+
+    let mut ok = true;
+    println!("{:<30} {:>10} {:>10} {:>10}", "query", "median", "max", "budget");
+    for (name, budget, mut run) in benches {
+        let (median, max) = measure(&mut run)?;
+        let verdict = if median > budget { ok = false; "  OVER BUDGET" } else { "" };
+        println!("{:<30} {:>10.2?} {:>10.2?} {:>10.0?}{}", name, median, max, budget, verdict);
+    }
+
+    // Plans that must stay on an index as the tables grow.
+    let plans: [(&str, &str, &[&dyn rusqlite::ToSql], &str); 7] = [
+        ("tasks by workstream", "SELECT * FROM project_tasks WHERE workstream_id = ?1", &[&s.workstream_id], "idx_project_tasks_workstream_status"),
+        ("tasks by status key", "SELECT * FROM project_tasks pt WHERE LOWER(TRIM(pt.status, '\"')) = ?1", &[&"todo"], "idx_project_tasks_status_key"),
+        ("kanban neighbour by rank", "SELECT id FROM project_tasks WHERE LOWER(TRIM(status, '\"')) = ?1 AND rank > ?2 ORDER BY rank LIMIT 1", &[&"todo", &"V"], "idx_project_tasks_status_rank"),
+        ("workstreams by persona", "SELECT * FROM workstreams WHERE persona_id = ?1", &[&s.persona_id], "idx_workstreams_persona"),
+        ("habit completions by date", "SELECT * FROM habit_completions WHERE date = ?1", &[&s.today], "idx_habit_completions_date"),
+        ("latest journal entries", "SELECT * FROM journal_entries ORDER BY created_at DESC LIMIT 50", &[], "idx_journal_entries_created_at"),
+        ("journal by workstream", "SELECT * FROM journal_entries WHERE workstream_id = ?1 ORDER BY created_at DESC LIMIT 50", &[&s.workstream_id], "idx_journal_entries_workstream"),
+    ];
+
+    println!();
+    for (name, sql, params, index) in plans {
+        let plan = query_plan(&db, sql, params)?;
+        let verdict = if plan.contains(index) { "ok" } else { ok = false; "MISSING INDEX" };
+        println!("{:<30} {:<14} {}", name, verdict, plan);
+    }
+
+    drop(db);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+
+    Ok(ok)
+}
@@ -0,0 +1,663 @@
+use crate::database::get_current_timestamp;
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Backup file layout: MAGIC, one flags byte, then either the payload or
+// salt + nonce + ciphertext. The header is authenticated when encrypted.
+const MAGIC: &[u8; 8] = b"PPABAK01";
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+const FLAG_COMPRESSED: u8 = 0b01;
+const FLAG_ENCRYPTED: u8 = 0b10;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub const BACKUP_PREFIX: &str = "ppa-backup-";
+pub const BACKUP_EXTENSION: &str = "ppabak";
+
+// Copy a few pages at a time and pause in between so other connections can
+// keep writing while a snapshot is taken.
+const PAGES_PER_STEP: i32 = 128;
+const STEP_PAUSE: Duration = Duration::from_millis(5);
+
+const DEFAULT_KEEP: u32 = 10;
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSettings {
+    pub directory: Option<String>,
+    pub interval_hours: u32,
+    pub keep: u32,
+    pub compress: bool,
+    pub encrypted: bool,
+    pub last_run: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BackupOptions {
+    pub compress: bool,
+    pub passphrase: Option<String>,
+    // 0 keeps every snapshot
+    pub keep: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: Option<String>,
+    pub compressed: bool,
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupReport {
+    pub backup: BackupInfo,
+    pub database_bytes: u64,
+    pub rotated: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub restored_from: String,
+    pub integrity: String,
+    pub personas: i64,
+    pub workstreams: i64,
+    pub project_tasks: i64,
+    pub journal_entries: i64,
+    pub restored_at: String,
+}
+
+// Settings
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM app_settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+pub fn delete_setting(conn: &Connection, key: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM app_settings WHERE key = ?1", [key])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    Ok(())
+}
+
+pub fn load_settings(conn: &Connection) -> Result<BackupSettings, String> {
+    let interval_hours = get_setting(conn, "backup_interval_hours")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let keep = get_setting(conn, "backup_keep")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_KEEP);
+    let compress = get_setting(conn, "backup_compress")?
+        .map(|v| v == "1")
+        .unwrap_or(true);
+
+    Ok(BackupSettings {
+        directory: get_setting(conn, "backup_directory")?,
+        interval_hours,
+        keep,
+        compress,
+        encrypted: get_setting(conn, "backup_passphrase")?.is_some(),
+        last_run: get_setting(conn, "backup_last_run")?,
+        last_error: get_setting(conn, "backup_last_error")?,
+    })
+}
+
+// The passphrase is kept on this device only (app_settings is never synced) so
+// scheduled backups can be encrypted without asking for it each time.
+pub fn save_settings(
+    conn: &Connection,
+    directory: &str,
+    interval_hours: u32,
+    keep: u32,
+    compress: bool,
+    passphrase: Option<&str>,
+) -> Result<BackupSettings, String> {
+    set_setting(conn, "backup_directory", directory)?;
+    set_setting(conn, "backup_interval_hours", &interval_hours.to_string())?;
+    set_setting(conn, "backup_keep", &keep.to_string())?;
+    set_setting(conn, "backup_compress", if compress { "1" } else { "0" })?;
+    match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => set_setting(conn, "backup_passphrase", passphrase)?,
+        None => delete_setting(conn, "backup_passphrase")?,
+    }
+    load_settings(conn)
+}
+
+pub fn options_from_settings(conn: &Connection) -> Result<BackupOptions, String> {
+    let settings = load_settings(conn)?;
+    Ok(BackupOptions {
+        compress: settings.compress,
+        passphrase: get_setting(conn, "backup_passphrase")?,
+        keep: settings.keep,
+    })
+}
+
+// Backups
+pub fn create_backup(
+    source: &Connection,
+    directory: &Path,
+    options: &BackupOptions,
+    label: Option<&str>,
+) -> Result<BackupReport, String> {
+    fs::create_dir_all(directory)
+        .map_err(|e| format!("Failed to create backup folder {}: {}", directory.display(), e))?;
+
+    let stamp = get_current_timestamp().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let file_name = match label {
+        Some(label) => format!("{}{}-{}.{}", BACKUP_PREFIX, stamp, label, BACKUP_EXTENSION),
+        None => format!("{}{}.{}", BACKUP_PREFIX, stamp, BACKUP_EXTENSION),
+    };
+    let path = directory.join(&file_name);
+    let snapshot_path = directory.join(format!(".{}.snapshot", file_name));
+
+    let snapshot = snapshot_to_file(source, &snapshot_path)
+        .and_then(|_| fs::read(&snapshot_path).map_err(|e| format!("Failed to read snapshot: {}", e)));
+    let _ = fs::remove_file(&snapshot_path);
+    let database = snapshot?;
+
+    let encoded = encode(&database, options.compress, options.passphrase.as_deref())?;
+
+    // Write next to the target and rename so a half-written file never looks like a backup.
+    let partial_path = directory.join(format!(".{}.partial", file_name));
+    fs::write(&partial_path, &encoded)
+        .map_err(|e| format!("Failed to write backup {}: {}", path.display(), e))?;
+    fs::rename(&partial_path, &path)
+        .map_err(|e| format!("Failed to write backup {}: {}", path.display(), e))?;
+
+    let rotated = rotate(directory, options.keep)?;
+
+    Ok(BackupReport {
+        backup: describe(&path)?,
+        database_bytes: database.len() as u64,
+        rotated,
+    })
+}
+
+fn snapshot_to_file(source: &Connection, target: &Path) -> Result<(), String> {
+    let _ = fs::remove_file(target);
+    let mut destination = Connection::open(target)
+        .map_err(|e| format!("Failed to open snapshot file: {}", e))?;
+    {
+        let backup = Backup::new(source, &mut destination)
+            .map_err(|e| format!("Backup error: {}", e))?;
+        backup.run_to_completion(PAGES_PER_STEP, STEP_PAUSE, None)
+            .map_err(|e| format!("Backup error: {}", e))?;
+    }
+    destination.close().map_err(|(_, e)| format!("Backup error: {}", e))
+}
+
+pub fn list_backups(directory: &Path) -> Result<Vec<BackupInfo>, String> {
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for path in backup_files(directory)? {
+        backups.push(describe(&path)?);
+    }
+    backups.reverse();
+    Ok(backups)
+}
+
+// Oldest first; the timestamp in the file name sorts chronologically.
+fn backup_files(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read backup folder {}: {}", directory.display(), e))?;
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path.extension().and_then(|e| e.to_str()) == Some(BACKUP_EXTENSION)
+                && path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(BACKUP_PREFIX))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn rotate(directory: &Path, keep: u32) -> Result<Vec<String>, String> {
+    if keep == 0 {
+        return Ok(Vec::new());
+    }
+
+    let files = backup_files(directory)?;
+    let excess = files.len().saturating_sub(keep as usize);
+    let mut removed = Vec::new();
+    for path in files.into_iter().take(excess) {
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove old backup {}: {}", path.display(), e))?;
+        removed.push(path.file_name().unwrap_or_default().to_string_lossy().to_string());
+    }
+    Ok(removed)
+}
+
+fn describe(path: &Path) -> Result<BackupInfo, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read backup {}: {}", path.display(), e))?;
+
+    let mut header = [0u8; MAGIC.len() + 1];
+    let flags = fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .ok()
+        .filter(|_| &header[..MAGIC.len()] == MAGIC)
+        .map(|_| header[MAGIC.len()])
+        .unwrap_or(0);
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let created_at = file_name
+        .strip_prefix(BACKUP_PREFIX)
+        .and_then(|rest| rest.get(..19))
+        .and_then(|stamp| chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S-%3f").ok())
+        .map(|dt| dt.and_utc().to_rfc3339());
+
+    Ok(BackupInfo {
+        file_name,
+        path: path.to_string_lossy().to_string(),
+        size_bytes: metadata.len(),
+        created_at,
+        compressed: flags & FLAG_COMPRESSED != 0,
+        encrypted: flags & FLAG_ENCRYPTED != 0,
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation error: {}", e))?;
+    Ok(key)
+}
+
+fn encode(database: &[u8], compress: bool, passphrase: Option<&str>) -> Result<Vec<u8>, String> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+
+    let payload = if compress {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(database)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| format!("Compression error: {}", e))?
+    } else {
+        database.to_vec()
+    };
+
+    let mut flags = 0;
+    if compress {
+        flags |= FLAG_COMPRESSED;
+    }
+    if passphrase.is_some() {
+        flags |= FLAG_ENCRYPTED;
+    }
+
+    let mut out = Vec::with_capacity(payload.len() + 64);
+    out.extend_from_slice(MAGIC);
+    out.push(flags);
+
+    match passphrase {
+        Some(passphrase) => {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let key = derive_key(passphrase, &salt)?;
+            let cipher = ChaCha20Poly1305::new(&key.into());
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let header = out.clone();
+            let ciphertext = cipher
+                .encrypt(&nonce, Payload { msg: &payload, aad: &header })
+                .map_err(|_| "Encryption error".to_string())?;
+            out.extend_from_slice(&salt);
+            out.extend_from_slice(&nonce);
+            out.extend_from_slice(&ciphertext);
+        }
+        None => out.extend_from_slice(&payload),
+    }
+
+    Ok(out)
+}
+
+fn decode(bytes: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, String> {
+    // Plain database files can be restored as-is.
+    if bytes.starts_with(SQLITE_MAGIC) {
+        return Ok(bytes.to_vec());
+    }
+    if bytes.len() <= MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err("This file is not a backup created by this app".to_string());
+    }
+
+    let header = &bytes[..MAGIC.len() + 1];
+    let flags = header[MAGIC.len()];
+    let body = &bytes[MAGIC.len() + 1..];
+
+    let payload = if flags & FLAG_ENCRYPTED != 0 {
+        let passphrase = passphrase
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| "This backup is encrypted. Enter its passphrase to restore it".to_string())?;
+        if body.len() < SALT_LEN + NONCE_LEN {
+            return Err("Backup file is truncated".to_string());
+        }
+        let (salt, rest) = body.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let key = derive_key(passphrase, salt)?;
+        ChaCha20Poly1305::new(&key.into())
+            .decrypt(nonce.into(), Payload { msg: ciphertext, aad: header })
+            .map_err(|_| "Could not decrypt backup: the passphrase is wrong or the file is damaged".to_string())?
+    } else {
+        body.to_vec()
+    };
+
+    if flags & FLAG_COMPRESSED != 0 {
+        let mut database = Vec::new();
+        GzDecoder::new(payload.as_slice())
+            .read_to_end(&mut database)
+            .map_err(|e| format!("Backup file is damaged: {}", e))?;
+        Ok(database)
+    } else {
+        Ok(payload)
+    }
+}
+
+// Restore
+pub fn restore_backup(live: &mut Connection, file: &Path, passphrase: Option<&str>) -> Result<RestoreReport, String> {
+    let bytes = fs::read(file)
+        .map_err(|e| format!("Failed to read backup {}: {}", file.display(), e))?;
+    let database = decode(&bytes, passphrase)?;
+
+    let local_state = read_local_state(live)?;
+
+    let candidate_path = file.with_extension("restore");
+    fs::write(&candidate_path, &database)
+        .map_err(|e| format!("Failed to unpack backup: {}", e))?;
+
+    let result = verify_and_copy(&candidate_path, live);
+    let _ = fs::remove_file(&candidate_path);
+    let integrity = result?;
+
+    // Older backups may predate newer tables and triggers.
+    live.execute_batch(crate::database::get_database_schema())
+        .map_err(|e| format!("Schema update error: {}", e))?;
+    crate::commands::run_migrations(live)?;
+    crate::sync::install_triggers(live)?;
+    write_local_state(live, &local_state)?;
+
+    let count = |table: &str| -> Result<i64, String> {
+        live.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .map_err(|e| format!("SQL query error: {}", e))
+    };
+
+    Ok(RestoreReport {
+        restored_from: file.to_string_lossy().to_string(),
+        integrity,
+        personas: count("personas")?,
+        workstreams: count("workstreams")?,
+        project_tasks: count("project_tasks")?,
+        journal_entries: count("journal_entries")?,
+        restored_at: get_current_timestamp().to_rfc3339(),
+    })
+}
+
+// Tables that belong to this device rather than to the data: its sync
+// identity, clock and cursors, uploads still queued and its settings. A
+// backup from another machine must not make this device pose as that one.
+const LOCAL_TABLES: [&str; 3] = ["sync_state", "sync_upload_queue", "app_settings"];
+
+type TableRows = (Vec<String>, Vec<Vec<rusqlite::types::Value>>);
+
+fn read_local_state(conn: &Connection) -> Result<Vec<TableRows>, String> {
+    LOCAL_TABLES.iter().map(|table| {
+        let mut stmt = conn.prepare(&format!("SELECT * FROM {}", table))
+            .map_err(|e| format!("SQL prepare error: {}", e))?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(str::to_string).collect();
+        let rows = stmt.query_map([], |row| (0..columns.len()).map(|i| row.get(i)).collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("SQL query error: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("SQL query error: {}", e))?;
+        Ok((columns, rows))
+    }).collect()
+}
+
+fn write_local_state(conn: &Connection, state: &[TableRows]) -> Result<(), String> {
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Restore error: {}", e))?;
+    for (table, (columns, rows)) in LOCAL_TABLES.iter().zip(state) {
+        tx.execute(&format!("DELETE FROM {}", table), [])
+            .map_err(|e| format!("SQL delete error: {}", e))?;
+        let insert = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        for row in rows {
+            tx.execute(&insert, rusqlite::params_from_iter(row))
+                .map_err(|e| format!("SQL insert error: {}", e))?;
+        }
+    }
+    tx.commit().map_err(|e| format!("Restore error: {}", e))
+}
+
+// Nothing touches the live database until the unpacked copy passes the checks.
+fn verify_and_copy(candidate_path: &Path, live: &mut Connection) -> Result<String, String> {
+    let candidate = Connection::open_with_flags(candidate_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open backup: {}", e))?;
+
+    let mut stmt = candidate.prepare("PRAGMA integrity_check")
+        .map_err(|e| format!("Backup is not a valid database: {}", e))?;
+    let problems = stmt.query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Backup is not a valid database: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Backup is not a valid database: {}", e))?;
+    drop(stmt);
+    if problems != ["ok"] {
+        return Err(format!("Backup failed the integrity check: {}", problems.join("; ")));
+    }
+
+    let has_core_tables: i64 = candidate.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('personas', 'workstreams', 'project_tasks')",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| format!("SQL query error: {}", e))?;
+    if has_core_tables != 3 {
+        return Err("Backup does not contain this app's data".to_string());
+    }
+
+    let backup = Backup::new(&candidate, live)
+        .map_err(|e| format!("Restore error: {}", e))?;
+    backup.run_to_completion(PAGES_PER_STEP, STEP_PAUSE, None)
+        .map_err(|e| format!("Restore error: {}", e))?;
+
+    Ok("ok".to_string())
+}
+
+// Scheduling
+pub fn run_scheduled(conn: &Connection) -> Result<Option<BackupReport>, String> {
+    let settings = load_settings(conn)?;
+    let directory = match settings.directory {
+        Some(directory) if settings.interval_hours > 0 => directory,
+        _ => return Ok(None),
+    };
+
+    let now = get_current_timestamp();
+    let due = match settings.last_run.as_deref().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()) {
+        Some(last) => now.signed_duration_since(last) >= chrono::Duration::hours(settings.interval_hours as i64),
+        None => true,
+    };
+    if !due {
+        return Ok(None);
+    }
+
+    let options = options_from_settings(conn)?;
+    match create_backup(conn, Path::new(&directory), &options, None) {
+        Ok(report) => {
+            set_setting(conn, "backup_last_run", &now.to_rfc3339())?;
+            delete_setting(conn, "backup_last_error")?;
+            Ok(Some(report))
+        }
+        Err(e) => {
+            set_setting(conn, "backup_last_error", &e)?;
+            Err(e)
+        }
+    }
+}
+
+// Scheduled backups use their own connection so the app keeps working while a
+// snapshot is copied.
+pub fn spawn_scheduler(database_path: &str) {
+    let database_path = database_path.to_string();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SCHEDULER_TICK);
+
+        let conn = match Connection::open(&database_path) {
+            Ok(conn) => conn,
+            Err(_) => continue,
+        };
+        let _ = conn.busy_timeout(Duration::from_secs(5));
+        let _ = run_scheduled(&conn);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+    use crate::database::generate_id;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> TempDir {
+            let dir = std::env::temp_dir().join(format!("backup-test-{}", generate_id()));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn database_bytes() -> Vec<u8> {
+        let mut bytes = SQLITE_MAGIC.to_vec();
+        bytes.extend((0..4096u32).map(|i| (i % 7) as u8));
+        bytes
+    }
+
+    #[test]
+    fn every_encoding_decodes_to_the_same_database() {
+        let database = database_bytes();
+        for (compress, passphrase) in [(false, None), (true, None), (false, Some("correct horse")), (true, Some("correct horse"))] {
+            let encoded = encode(&database, compress, passphrase).unwrap();
+            assert!(encoded.starts_with(MAGIC));
+            assert_eq!(encoded[MAGIC.len()] & FLAG_COMPRESSED != 0, compress);
+            assert_eq!(encoded[MAGIC.len()] & FLAG_ENCRYPTED != 0, passphrase.is_some());
+            assert_eq!(decode(&encoded, passphrase).unwrap(), database);
+        }
+        assert!(encode(&database, true, None).unwrap().len() < database.len());
+
+        // An empty passphrase means no encryption, and a plain database file restores as-is.
+        let unencrypted = encode(&database, false, Some("")).unwrap();
+        assert_eq!(unencrypted[MAGIC.len()] & FLAG_ENCRYPTED, 0);
+        assert_eq!(decode(&database, None).unwrap(), database);
+    }
+
+    #[test]
+    fn an_encrypted_backup_needs_the_right_passphrase_and_an_intact_file() {
+        let database = database_bytes();
+        let encoded = encode(&database, true, Some("correct horse")).unwrap();
+        assert_ne!(encode(&database, true, Some("correct horse")).unwrap(), encoded);
+
+        assert_eq!(decode(&encoded, None).unwrap_err(), "This backup is encrypted. Enter its passphrase to restore it");
+        let wrong = "Could not decrypt backup: the passphrase is wrong or the file is damaged";
+        assert_eq!(decode(&encoded, Some("battery staple")).unwrap_err(), wrong);
+
+        let mut tampered = encoded.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(decode(&tampered, Some("correct horse")).unwrap_err(), wrong);
+
+        // The flags are authenticated too.
+        let mut relabelled = encoded.clone();
+        relabelled[MAGIC.len()] &= !FLAG_COMPRESSED;
+        assert_eq!(decode(&relabelled, Some("correct horse")).unwrap_err(), wrong);
+
+        let truncated = &encoded[..MAGIC.len() + 1 + SALT_LEN];
+        assert_eq!(decode(truncated, Some("correct horse")).unwrap_err(), "Backup file is truncated");
+        assert_eq!(decode(b"not a backup", None).unwrap_err(), "This file is not a backup created by this app");
+    }
+
+    #[test]
+    fn a_backup_restores_into_another_database() {
+        let dir = TempDir::new();
+        let source = commands::open_database(":memory:").unwrap();
+        let persona = commands::create_persona(&source, "Work".into(), None, "#3366ff".into()).unwrap();
+        commands::create_workstream(&source, persona.id, "Launch".into(), None, "active".into(), None, None, None).unwrap();
+
+        let options = BackupOptions { compress: true, passphrase: Some("correct horse".into()), keep: 0 };
+        let report = create_backup(&source, &dir.0, &options, Some("manual")).unwrap();
+        assert!(report.backup.compressed && report.backup.encrypted);
+        assert!(report.backup.file_name.ends_with(&format!("-manual.{}", BACKUP_EXTENSION)));
+        assert_eq!(list_backups(&dir.0).unwrap().len(), 1);
+
+        let mut live = commands::open_database(":memory:").unwrap();
+        let file = PathBuf::from(&report.backup.path);
+        assert!(restore_backup(&mut live, &file, Some("battery staple")).is_err());
+        let restored = restore_backup(&mut live, &file, Some("correct horse")).unwrap();
+        assert_eq!((restored.integrity.as_str(), restored.personas, restored.workstreams), ("ok", 1, 1));
+        let name: String = live.query_row("SELECT name FROM workstreams", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Launch");
+    }
+
+    #[test]
+    fn a_restore_keeps_the_device_local_state() {
+        let dir = TempDir::new();
+        let other = commands::open_database(":memory:").unwrap();
+        commands::create_persona(&other, "Work".into(), None, "#3366ff".into()).unwrap();
+        let other_device = crate::sync::device_id(&other).unwrap();
+        other.execute("INSERT INTO sync_state (key, value) VALUES ('cursor:peer', '42')", []).unwrap();
+        other.execute("INSERT INTO sync_upload_queue (segment, device, body, queued_at) VALUES ('seg', 'other', '', '')", []).unwrap();
+        set_setting(&other, "backup_directory", "/other/machine").unwrap();
+        let options = BackupOptions { compress: false, passphrase: None, keep: 0 };
+        let report = create_backup(&other, &dir.0, &options, None).unwrap();
+
+        // A new machine restoring the other one's backup.
+        let mut live = commands::open_database(":memory:").unwrap();
+        let this_device = crate::sync::device_id(&live).unwrap();
+        set_setting(&live, "api_token", "local-token").unwrap();
+        let restored = restore_backup(&mut live, &PathBuf::from(&report.backup.path), None).unwrap();
+        assert_eq!(restored.personas, 1);
+
+        assert_eq!(crate::sync::device_id(&live).unwrap(), this_device);
+        assert_ne!(this_device, other_device);
+        let state: Vec<String> = live.prepare("SELECT key FROM sync_state ORDER BY key").unwrap()
+            .query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+        assert!(!state.contains(&"cursor:peer".to_string()), "{:?}", state);
+        let queued: i64 = live.query_row("SELECT COUNT(*) FROM sync_upload_queue", [], |row| row.get(0)).unwrap();
+        assert_eq!(queued, 0);
+        assert_eq!(get_setting(&live, "api_token").unwrap().as_deref(), Some("local-token"));
+        assert_eq!(get_setting(&live, "backup_directory").unwrap(), None);
+    }
+}
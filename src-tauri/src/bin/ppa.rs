@@ -0,0 +1,913 @@
+// Command-line client for the productivity database. It calls the same command
+// logic as the desktop app, so validation and storage formats stay identical.
+
+use clap::{Args, Parser, Subcommand};
+use personal_productivity_app_lib::{commands, DATABASE_PATH};
+use rusqlite::Connection;
+use serde_json::Value;
+use std::io::Read;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "ppa", version, about = "Personal productivity app from the terminal")]
+struct Cli {
+    /// Path to the database file
+    #[arg(long, global = true, env = "PPA_DB", default_value = DATABASE_PATH)]
+    db: String,
+
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add, move and list project tasks
+    #[command(subcommand)]
+    Task(TaskCommand),
+    /// Time tasks and log or report time spent
+    #[command(subcommand)]
+    Timer(TimerCommand),
+    /// Pomodoro focus sessions
+    #[command(subcommand)]
+    Focus(FocusCommand),
+    /// Change the status of workstreams, move and copy them
+    #[command(subcommand)]
+    Workstream(WorkstreamCommand),
+    /// Plan milestones and assign tasks to them
+    #[command(subcommand)]
+    Milestone(MilestoneCommand),
+    /// Create, list and log habits
+    #[command(subcommand)]
+    Habit(HabitCommand),
+    /// Write journal entries
+    #[command(subcommand)]
+    Journal(JournalCommand),
+    /// List and run saved filters
+    #[command(subcommand)]
+    Filter(FilterCommand),
+    /// Search with the query language, e.g. 'status:todo priority>=high due<7d -tag:blocked'
+    Search {
+        query: String,
+        /// Search journal entries instead of tasks
+        #[arg(long)]
+        journal: bool,
+        #[arg(long, short, default_value_t = 50)]
+        limit: u32,
+    },
+    /// How actual hours compare with estimates on finished tasks
+    Estimates {
+        /// persona, workstream, tag or priority
+        #[arg(long, default_value = "workstream")]
+        by: String,
+        /// week or month
+        #[arg(long, default_value = "month")]
+        period: String,
+        /// First day, defaults to six months before the last
+        #[arg(long, allow_hyphen_values = true)]
+        from: Option<String>,
+        /// Last day, defaults to today
+        #[arg(long, allow_hyphen_values = true)]
+        to: Option<String>,
+    },
+    /// Burndown, completion forecast and health of a workstream
+    Burndown {
+        /// Workstream name or ID
+        workstream: String,
+        /// Count estimated hours instead of tasks
+        #[arg(long)]
+        hours: bool,
+        /// Days of the series to show
+        #[arg(long, default_value_t = 14)]
+        days: usize,
+    },
+    /// Overdue and upcoming task and milestone deadlines
+    Deadlines {
+        /// Look this many days ahead
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+    },
+    /// Show overdue and due tasks, work in progress, habits and journal entries for a day
+    Agenda {
+        /// Day to show (YYYY-MM-DD), defaults to today
+        #[arg(long)]
+        date: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TaskCommand {
+    /// Add a task to a workstream
+    Add {
+        title: String,
+        /// Workstream name or ID
+        #[arg(long, short)]
+        workstream: String,
+        #[arg(long, short, default_value = "todo")]
+        status: String,
+        #[arg(long, short, default_value = "medium")]
+        priority: String,
+        #[arg(long, short)]
+        description: Option<String>,
+        /// Due day (YYYY-MM-DD, +3d, ...) or local time (YYYY-MM-DDTHH:MM)
+        #[arg(long)]
+        due: Option<String>,
+        /// Repeat with an RRULE, e.g. 'FREQ=WEEKLY;BYDAY=MO' or 'FREQ=MONTHLY;BYDAY=-1FR'
+        #[arg(long)]
+        repeat: Option<String>,
+        /// Count the repeat from the day each occurrence is completed
+        #[arg(long, requires = "repeat")]
+        from_completion: bool,
+        /// Estimated hours; a calibrated estimate is shown once there is enough history
+        #[arg(long)]
+        estimate: Option<f32>,
+    },
+    /// Move a task to another status column, or to a position within one
+    Move {
+        /// Task ID or a unique prefix of it
+        task: String,
+        /// backlog, todo, inprogress, review or done
+        status: String,
+        /// Place it directly below this task
+        #[arg(long, conflicts_with = "before")]
+        after: Option<String>,
+        /// Place it directly above this task
+        #[arg(long)]
+        before: Option<String>,
+    },
+    /// Move a task and its subtasks to another workstream
+    Transfer {
+        /// Task ID or a unique prefix of it
+        task: String,
+        /// Workstream name or ID
+        workstream: String,
+    },
+    /// List tasks
+    List {
+        /// Workstream name or ID
+        #[arg(long, short)]
+        workstream: Option<String>,
+        #[arg(long, short)]
+        status: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TimerCommand {
+    /// Start timing a task, stopping any other timer
+    Start {
+        /// Task ID or a unique prefix of it
+        task: String,
+        #[arg(long, short)]
+        note: Option<String>,
+    },
+    /// Pause the running timer
+    Pause,
+    /// Resume the paused timer
+    Resume,
+    /// Stop the running timer and log its time
+    Stop,
+    /// Show the running timer
+    Status,
+    /// Log time spent on a task by hand
+    Log {
+        /// Task ID or a unique prefix of it
+        task: String,
+        #[arg(long, short)]
+        minutes: f64,
+        /// When the work started (YYYY-MM-DDTHH:MM, a day, ...), defaults to that many minutes ago
+        #[arg(long)]
+        at: Option<String>,
+        #[arg(long, short)]
+        note: Option<String>,
+    },
+    /// Time spent per day, persona, workstream or task
+    Report {
+        #[arg(long, default_value = "day")]
+        by: String,
+        /// First day, defaults to six days before the last
+        #[arg(long, allow_hyphen_values = true)]
+        from: Option<String>,
+        /// Last day, defaults to today
+        #[arg(long, allow_hyphen_values = true)]
+        to: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FocusCommand {
+    /// Start a focus session, optionally logging its pomodoros on a task
+    Start {
+        /// Task ID or a unique prefix of it
+        task: Option<String>,
+    },
+    /// Pause the current phase
+    Pause,
+    /// Continue a paused phase or start one that is waiting
+    Resume,
+    /// Cut the current phase short and start the next one
+    Skip,
+    /// End the focus session
+    Stop,
+    /// Show the focus session
+    Status,
+    /// Pomodoros and focus time per day
+    Stats {
+        /// First day, defaults to six days before the last
+        #[arg(long, allow_hyphen_values = true)]
+        from: Option<String>,
+        /// Last day, defaults to today
+        #[arg(long, allow_hyphen_values = true)]
+        to: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HabitCommand {
+    /// Start tracking a habit in a workstream
+    Add {
+        name: String,
+        /// Workstream name or ID
+        #[arg(long, short)]
+        workstream: String,
+        /// Track weekly with this many days per week instead of daily
+        #[arg(long)]
+        weekly: Option<u8>,
+        #[arg(long)]
+        quantity: Option<u32>,
+        #[arg(long)]
+        unit: Option<String>,
+        #[arg(long, short)]
+        description: Option<String>,
+    },
+    /// List active habits and whether they are done for a day
+    List {
+        #[arg(long)]
+        date: Option<String>,
+    },
+    /// Mark a habit as done
+    Log(HabitLogArgs),
+}
+
+#[derive(Args)]
+struct HabitLogArgs {
+    /// Habit name or ID
+    habit: String,
+    /// Day to log (YYYY-MM-DD), defaults to today
+    #[arg(long)]
+    date: Option<String>,
+    #[arg(long, short)]
+    quantity: Option<u32>,
+    #[arg(long, short)]
+    notes: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum JournalCommand {
+    /// Write a journal entry; the content is read from stdin when --content is omitted
+    Add {
+        title: String,
+        #[arg(long, short)]
+        content: Option<String>,
+        /// Workstream name or ID
+        #[arg(long, short)]
+        workstream: Option<String>,
+        /// note, update, reflection or meeting
+        #[arg(long = "type", short = 't', default_value = "note")]
+        entry_type: String,
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkstreamCommand {
+    /// Pause, complete, cancel or reopen a workstream
+    Status {
+        /// Workstream name or ID
+        workstream: String,
+        /// planning, active, paused, completed or cancelled
+        status: String,
+        /// Reopen a completed or cancelled workstream
+        #[arg(long)]
+        reopen: bool,
+        /// Pause or resume the workstream's habits along with it
+        #[arg(long)]
+        habits: bool,
+        /// Open tasks on completing: keep, archive or close; on cancelling: keep, backlog or move
+        #[arg(long)]
+        open_tasks: Option<String>,
+        /// Workstream name or ID to move open tasks to
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Move a workstream with its tasks, habits and journal entries to another persona
+    Move {
+        /// Workstream name or ID
+        workstream: String,
+        /// Persona name or ID
+        persona: String,
+    },
+    /// Copy a workstream with its tasks, milestones and habits as a new one
+    Copy {
+        /// Workstream name or ID
+        workstream: String,
+        /// Defaults to the original name with "(copy)"
+        #[arg(long, short)]
+        name: Option<String>,
+        /// Persona name or ID, defaults to the original's
+        #[arg(long, short)]
+        persona: Option<String>,
+        /// Start the copied tasks over as to-do, with checklists unticked
+        #[arg(long)]
+        reset_progress: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MilestoneCommand {
+    /// Add a milestone to a workstream
+    Add {
+        name: String,
+        /// Workstream name or ID
+        #[arg(long, short)]
+        workstream: String,
+        /// Due day (YYYY-MM-DD, +3d, ...)
+        #[arg(long)]
+        due: String,
+        #[arg(long, short)]
+        description: Option<String>,
+    },
+    /// Show a workstream's milestones in timeline order
+    List {
+        /// Workstream name or ID
+        workstream: String,
+    },
+    /// Assign a task to a milestone
+    Assign {
+        /// Task ID or a unique prefix of it
+        task: String,
+        /// Milestone name or ID; leave out to take the task off its milestone
+        milestone: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FilterCommand {
+    /// List saved filters with how many items each one matches
+    List,
+    /// Show the tasks or journal entries a saved filter matches
+    Run {
+        /// Filter name or ID
+        filter: String,
+        #[arg(long, short, default_value_t = 50)]
+        limit: u32,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = commands::open_database(&cli.db).and_then(|db| execute(&db, cli.command));
+    match result {
+        Ok(output) => {
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&output.value).unwrap_or_default());
+            } else {
+                println!("{}", output.text);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct Output {
+    value: Value,
+    text: String,
+}
+
+fn execute(db: &Connection, command: Command) -> Result<Output, String> {
+    match command {
+        Command::Task(TaskCommand::Add { title, workstream, status, priority, description, due, repeat, from_completion, estimate }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+            let mut task = commands::create_project_task(&tx, workstream_id, title, description, status, priority)?;
+            if due.is_some() {
+                task = commands::set_task_due_date(&tx, task.id, due)?;
+            }
+            if repeat.is_some() {
+                task = commands::set_task_recurrence(&tx, task.id, repeat, from_completion)?;
+            }
+            if estimate.is_some() {
+                task = commands::set_task_estimate(&tx, task.id, estimate)?;
+            }
+            tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+            let calibrated = match task.estimated_hours.filter(|hours| *hours > 0.0) {
+                Some(hours) => commands::get_calibrated_estimate(db, task.workstream_id.clone(), hours as f64)?,
+                None => None,
+            };
+            let mut task = to_json(task)?;
+            let mut text = format!("Added task {} {}", short_id(&task["id"]), str_field(&task, "title"));
+            if let Some(calibrated) = calibrated {
+                text.push_str(&format!(
+                    "\nEstimate {}h, calibrated {}h ({}x over {} tasks in {})",
+                    calibrated.estimated_hours, calibrated.calibrated_hours, calibrated.factor, calibrated.tasks, calibrated.label
+                ));
+                task["calibrated_estimate"] = to_json(calibrated)?;
+            }
+            Ok(Output { value: task, text })
+        }
+        Command::Task(TaskCommand::Move { task, status, after, before }) => {
+            let task_id = commands::resolve_task_id(db, &task)?;
+            let change = if after.is_some() || before.is_some() {
+                let after = after.map(|t| commands::resolve_task_id(db, &t)).transpose()?;
+                let before = before.map(|t| commands::resolve_task_id(db, &t)).transpose()?;
+                let moved = commands::move_task(db, task_id, status, after, before)?;
+                commands::TaskStatusChange { task: moved.task, warnings: moved.warnings, next_occurrence: moved.next_occurrence }
+            } else {
+                commands::update_task_status(db, task_id, status)?
+            };
+            let task = to_json(change)?;
+            let mut text = format!("Moved {} {} to {}", short_id(&task["id"]), str_field(&task, "title"), clean(&task["status"]));
+            for warning in task["warnings"].as_array().cloned().unwrap_or_default() {
+                text.push_str(&format!("\nWarning: {}", warning.as_str().unwrap_or_default()));
+            }
+            if task["next_occurrence"].is_object() {
+                text.push_str(&format!("\nNext: {}", task_line(&task["next_occurrence"])));
+            }
+            Ok(Output { value: task, text })
+        }
+        Command::Task(TaskCommand::Transfer { task, workstream }) => {
+            let task_id = commands::resolve_task_id(db, &task)?;
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let transfer = commands::move_task_to_workstream(db, task_id, workstream_id)?;
+            let subtasks = if transfer.subtasks > 0 { format!(" with {} subtask(s)", transfer.subtasks) } else { String::new() };
+            let text = format!("Moved {}{}", task_line(&to_json(&transfer.task)?), subtasks);
+            Ok(Output { value: to_json(transfer)?, text })
+        }
+        Command::Task(TaskCommand::List { workstream, status }) => {
+            let workstream_id = workstream.map(|w| commands::resolve_workstream_id(db, &w)).transpose()?;
+            let tasks: Vec<Value> = commands::get_tasks_for_kanban(db, workstream_id, None)?
+                .into_iter()
+                .map(to_json)
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .filter(|task| status.as_ref().is_none_or(|s| clean(&task["status"]) == s.to_lowercase()))
+                .collect();
+            let text = if tasks.is_empty() {
+                "No tasks".to_string()
+            } else {
+                tasks.iter().map(task_line).collect::<Vec<_>>().join("\n")
+            };
+            Ok(Output { value: Value::Array(tasks), text })
+        }
+        Command::Timer(TimerCommand::Start { task, note }) => {
+            let task_id = commands::resolve_task_id(db, &task)?;
+            let change = to_json(commands::start_timer(db, task_id, note)?)?;
+            let mut text = String::new();
+            for stopped in change["stopped"].as_array().cloned().unwrap_or_default() {
+                text.push_str(&format!("Stopped {}\n", timer_line(&stopped)));
+            }
+            text.push_str(&format!("Timing {}", timer_line(&change["timer"])));
+            Ok(Output { value: change, text })
+        }
+        Command::Timer(command @ (TimerCommand::Pause | TimerCommand::Resume | TimerCommand::Stop)) => {
+            let (timer, verb) = match command {
+                TimerCommand::Pause => (commands::pause_timer(db)?, "Paused"),
+                TimerCommand::Resume => (commands::resume_timer(db)?, "Resumed"),
+                _ => (commands::stop_timer(db)?, "Stopped"),
+            };
+            let timer = to_json(timer)?;
+            let text = format!("{} {}", verb, timer_line(&timer));
+            Ok(Output { value: timer, text })
+        }
+        Command::Timer(TimerCommand::Status) => {
+            let timer = to_json(commands::get_running_timer(db)?)?;
+            let text = if timer.is_null() { "No timer is running".to_string() } else { timer_line(&timer) };
+            Ok(Output { value: timer, text })
+        }
+        Command::Timer(TimerCommand::Log { task, minutes, at, note }) => {
+            let task_id = commands::resolve_task_id(db, &task)?;
+            let at = at.unwrap_or_else(|| (chrono::Local::now() - chrono::Duration::seconds((minutes * 60.0) as i64)).to_rfc3339());
+            let entry = to_json(commands::add_time_entry(db, task_id, at, None, Some(minutes), note)?)?;
+            let text = format!("Logged {}", timer_line(&entry));
+            Ok(Output { value: entry, text })
+        }
+        Command::Timer(TimerCommand::Report { by, from, to }) => {
+            let report = to_json(commands::get_time_report(db, by, from, to, None, None)?)?;
+            let mut lines: Vec<String> = report["rows"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|row| format!("{:>7.2}h  {}", row["hours"].as_f64().unwrap_or_default(), str_field(row, "label")))
+                .collect();
+            lines.push(format!("{:>7.2}h  total {} to {}", report["total_hours"].as_f64().unwrap_or_default(), str_field(&report, "from"), str_field(&report, "to")));
+            Ok(Output { text: lines.join("\n"), value: report })
+        }
+        Command::Estimates { by, period, from, to } => {
+            let report = to_json(commands::get_estimate_accuracy(db, by, Some(period), from, to, None, None)?)?;
+            let ratio = |stats: &Value| match stats["ratio"].as_f64() {
+                Some(ratio) => format!("{:>5.2}x", ratio),
+                None => format!("{:>6}", "-"),
+            };
+            let factor = |stats: &Value| match stats["correction_factor"].as_f64() {
+                Some(factor) => format!("  correct by {:.2}x", factor),
+                None => String::new(),
+            };
+            let mut lines: Vec<String> = report["groups"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|group| format!("{}  {:>3} tasks  {}{}", ratio(group), group["tasks"], str_field(group, "label"), factor(group)))
+                .collect();
+            lines.push(format!("{}  {:>3} tasks  total {} to {}{}", ratio(&report), report["tasks"], str_field(&report, "from"), str_field(&report, "to"), factor(&report)));
+            for outlier in report["outliers"].as_array().cloned().unwrap_or_default() {
+                lines.push(format!(
+                    "Outlier: {} {} ({}h estimated, {}h actual)",
+                    short_id(&outlier["task_id"]),
+                    str_field(&outlier, "title"),
+                    outlier["estimated_hours"],
+                    outlier["actual_hours"]
+                ));
+            }
+            Ok(Output { text: lines.join("\n"), value: report })
+        }
+        Command::Burndown { workstream, hours, days } => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let unit = if hours { "hours" } else { "tasks" };
+            let burndown = to_json(commands::get_workstream_burndown(db, workstream_id, Some(unit.to_string()))?)?;
+            let series = burndown["series"].as_array().cloned().unwrap_or_default();
+            let mut lines: Vec<String> = series[series.len().saturating_sub(days)..]
+                .iter()
+                .map(|point| {
+                    let (remaining, scope) = (point["remaining"].as_f64().unwrap_or_default(), point["scope"].as_f64().unwrap_or_default());
+                    format!("{}  {:>6} left of {:>6}", str_field(point, "date"), remaining, scope)
+                })
+                .collect();
+            let forecast = &burndown["forecast"];
+            let day = |value: &Value| value.as_str().unwrap_or("not within two years").to_string();
+            if forecast.is_object() {
+                lines.push(format!(
+                    "Forecast: {} (50%), {} (85%), {} (95%) at {} {} a day",
+                    day(&forecast["p50"]), day(&forecast["p85"]), day(&forecast["p95"]), forecast["daily_throughput"], unit
+                ));
+            }
+            let health = if burndown["health"].is_null() { "no target".to_string() } else { clean(&burndown["health"]).replace('_', " ") };
+            lines.push(format!(
+                "{}: {}, {} {} left. {}",
+                str_field(&burndown, "workstream_name"), health, burndown["remaining"].as_f64().unwrap_or_default(), unit, str_field(&burndown, "reason")
+            ));
+            Ok(Output { text: lines.join("\n"), value: burndown })
+        }
+        Command::Focus(FocusCommand::Stats { from, to }) => {
+            let stats = to_json(commands::get_focus_stats(db, from, to)?)?;
+            let mut lines: Vec<String> = stats["days"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|day| format!("{}  {:>2} done  {:>2} cut short  {:>4} min", str_field(day, "date"), day["completed"], day["interrupted"], day["focus_minutes"]))
+                .collect();
+            lines.push(format!("{} pomodoros, {} min focus", stats["completed"], stats["focus_seconds"].as_i64().unwrap_or_default() / 60));
+            Ok(Output { text: lines.join("\n"), value: stats })
+        }
+        Command::Focus(command) => {
+            let change = match command {
+                FocusCommand::Start { task } => {
+                    let task_id = task.map(|t| commands::resolve_task_id(db, &t)).transpose()?;
+                    commands::start_focus_session(db, task_id)?
+                }
+                FocusCommand::Pause => commands::pause_focus_session(db)?,
+                FocusCommand::Resume => commands::resume_focus_session(db)?,
+                FocusCommand::Skip => commands::skip_focus_phase(db)?,
+                FocusCommand::Stop => commands::stop_focus_session(db)?,
+                _ => commands::get_focus_status(db)?,
+            };
+            let change = to_json(change)?;
+            let mut lines: Vec<String> = change["stopped_timers"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|timer| format!("Stopped timer {}", timer_line(timer)))
+                .collect();
+            for event in change["events"].as_array().cloned().unwrap_or_default() {
+                if str_field(&event, "kind") == "completed" {
+                    lines.push(format!("Pomodoro done ({} min)", event["pomodoro"]["focus_seconds"].as_i64().unwrap_or_default() / 60));
+                }
+            }
+            lines.push(focus_line(&change["status"]));
+            Ok(Output { text: lines.join("\n"), value: change })
+        }
+        Command::Habit(HabitCommand::Add { name, workstream, weekly, quantity, unit, description }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let frequency = if weekly.is_some() { "weekly" } else { "daily" };
+            let habit = to_json(commands::create_habit(db, workstream_id, name, description, frequency.to_string(), weekly, quantity, unit, None)?)?;
+            let text = format!("Added habit {} {}", short_id(&habit["id"]), str_field(&habit, "name"));
+            Ok(Output { value: habit, text })
+        }
+        Command::Habit(HabitCommand::List { date }) => {
+            let habits = to_json(commands::get_habits(db, date)?)?;
+            let text = match habits.as_array() {
+                Some(habits) if !habits.is_empty() => habits.iter().map(habit_line).collect::<Vec<_>>().join("\n"),
+                _ => "No active habits".to_string(),
+            };
+            Ok(Output { value: habits, text })
+        }
+        Command::Habit(HabitCommand::Log(args)) => {
+            let habit_id = commands::resolve_habit_id(db, &args.habit)?;
+            let completion = to_json(commands::log_habit_completion(db, habit_id, args.date, args.quantity, args.notes)?)?;
+            let text = format!("Logged {} for {}", args.habit, str_field(&completion, "date"));
+            Ok(Output { value: completion, text })
+        }
+        Command::Journal(JournalCommand::Add { title, content, workstream, entry_type, tags }) => {
+            let content = match content {
+                Some(content) => content,
+                None => {
+                    let mut content = String::new();
+                    std::io::stdin().read_to_string(&mut content)
+                        .map_err(|e| format!("Failed to read entry from stdin: {}", e))?;
+                    content
+                }
+            };
+            let workstream_id = workstream.map(|w| commands::resolve_workstream_id(db, &w)).transpose()?;
+            let entry = to_json(commands::create_journal_entry(db, title, content, workstream_id, None, entry_type, tags)?)?;
+            let text = format!("Saved journal entry {} {}", short_id(&entry["id"]), str_field(&entry, "title"));
+            Ok(Output { value: entry, text })
+        }
+        Command::Filter(FilterCommand::List) => {
+            let filters = to_json(commands::get_saved_filters(db, false)?)?;
+            let text = match filters.as_array() {
+                Some(filters) if !filters.is_empty() => filters.iter().map(filter_line).collect::<Vec<_>>().join("\n"),
+                _ => "No saved filters".to_string(),
+            };
+            Ok(Output { value: filters, text })
+        }
+        Command::Filter(FilterCommand::Run { filter, limit }) => {
+            let filter_id = commands::resolve_saved_filter_id(db, &filter)?;
+            let results = to_json(commands::run_saved_filter(db, filter_id, None, Some(limit))?)?;
+            let text = results_text(&results);
+            Ok(Output { value: results, text })
+        }
+        Command::Search { query, journal, limit } => {
+            let kind = if journal { "journal" } else { "tasks" };
+            let results = to_json(commands::search(db, kind.to_string(), query, None, Some(limit))?)?;
+            let text = results_text(&results);
+            Ok(Output { value: results, text })
+        }
+        Command::Workstream(WorkstreamCommand::Status { workstream, status, reopen, habits, open_tasks, to }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let target_id = to.map(|to| commands::resolve_workstream_id(db, &to)).transpose()?;
+            let change = commands::change_workstream_status(db, workstream_id, status, reopen, habits, open_tasks, target_id)?;
+            let name = change.workstream.name.clone();
+            let text = if change.changes.is_empty() {
+                format!("{} is already {}", name, change.workstream.status.key())
+            } else {
+                format!("{}\n{}", name, change.changes.iter().map(|line| format!("  {}", line)).collect::<Vec<_>>().join("\n"))
+            };
+            Ok(Output { value: to_json(change)?, text })
+        }
+        Command::Workstream(WorkstreamCommand::Move { workstream, persona }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let persona_id = commands::resolve_persona_id(db, &persona)?;
+            let transfer = commands::move_workstream_to_persona(db, workstream_id, persona_id)?;
+            let text = format!(
+                "Moved {} with {} task(s), {} habit(s) and {} journal entry(ies)",
+                transfer.workstream.name, transfer.tasks, transfer.habits, transfer.journal_entries
+            );
+            Ok(Output { value: to_json(transfer)?, text })
+        }
+        Command::Workstream(WorkstreamCommand::Copy { workstream, name, persona, reset_progress }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let persona_id = persona.map(|p| commands::resolve_persona_id(db, &p)).transpose()?;
+            let copy = commands::copy_workstream(db, workstream_id, name, persona_id, reset_progress)?;
+            let value = to_json(&copy)?;
+            let mut lines = vec![format!(
+                "Copied as {} {} with {} task(s), {} milestone(s) and {} habit(s)",
+                short_id(&value["workstream"]["id"]), copy.workstream.name, copy.tasks, copy.milestones, copy.habits
+            )];
+            if copy.progress_reset {
+                lines.push("  Tasks start over as to-do".to_string());
+            }
+            for dependency in &copy.external_dependencies {
+                lines.push(format!(
+                    "  {} still depends on {} outside the copy",
+                    dependency.task_title,
+                    dependency.depends_on_title.as_deref().unwrap_or(&dependency.depends_on_id)
+                ));
+            }
+            Ok(Output { value, text: lines.join("\n") })
+        }
+        Command::Milestone(MilestoneCommand::Add { name, workstream, due, description }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let milestone = to_json(commands::create_milestone(db, workstream_id, name, description, due)?)?;
+            let text = format!("Added milestone {}", milestone_line(&milestone));
+            Ok(Output { value: milestone, text })
+        }
+        Command::Milestone(MilestoneCommand::List { workstream }) => {
+            let workstream_id = commands::resolve_workstream_id(db, &workstream)?;
+            let milestones: Vec<Value> = commands::get_milestones(db, workstream_id)?
+                .into_iter()
+                .map(to_json)
+                .collect::<Result<Vec<_>, _>>()?;
+            let text = if milestones.is_empty() {
+                "No milestones".to_string()
+            } else {
+                milestones.iter().map(milestone_line).collect::<Vec<_>>().join("\n")
+            };
+            Ok(Output { value: Value::Array(milestones), text })
+        }
+        Command::Milestone(MilestoneCommand::Assign { task, milestone }) => {
+            let task_id = commands::resolve_task_id(db, &task)?;
+            let milestone_id = milestone.map(|m| commands::resolve_milestone_id(db, &m)).transpose()?;
+            let task = to_json(commands::set_task_milestone(db, task_id, milestone_id)?)?;
+            let text = if task["milestone_id"].is_string() {
+                format!("Assigned {} {} to its milestone", short_id(&task["id"]), str_field(&task, "title"))
+            } else {
+                format!("Took {} {} off its milestone", short_id(&task["id"]), str_field(&task, "title"))
+            };
+            Ok(Output { value: task, text })
+        }
+        Command::Deadlines { days } => {
+            let deadlines: Vec<Value> = commands::get_upcoming_deadlines(db, Some(days))?
+                .into_iter()
+                .map(to_json)
+                .collect::<Result<Vec<_>, _>>()?;
+            let text = if deadlines.is_empty() {
+                format!("Nothing due in the next {} days", days)
+            } else {
+                deadlines
+                    .iter()
+                    .map(|deadline| {
+                        let overdue = if deadline["overdue"].as_bool().unwrap_or_default() { "  overdue" } else { "" };
+                        format!(
+                            "{}  {:<9} {} ({}){}",
+                            str_field(deadline, "due_date"), str_field(deadline, "kind"), str_field(deadline, "title"), str_field(deadline, "workstream_name"), overdue
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            Ok(Output { value: Value::Array(deadlines), text })
+        }
+        Command::Agenda { date } => {
+            let agenda = to_json(commands::get_agenda(db, date)?)?;
+            let text = agenda_text(&agenda);
+            Ok(Output { value: agenda, text })
+        }
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Serialization error: {}", e))
+}
+
+// Text formatting
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap_or_default()
+}
+
+// Statuses are stored both as JSON strings and bare words.
+fn clean(value: &Value) -> String {
+    value.as_str().unwrap_or_default().trim_matches('"').to_lowercase()
+}
+
+fn short_id(value: &Value) -> &str {
+    let id = value.as_str().unwrap_or_default();
+    id.get(..8).unwrap_or(id)
+}
+
+fn milestone_line(milestone: &Value) -> String {
+    let state = if milestone["completed_date"].is_string() {
+        "  done"
+    } else if milestone["overdue"].as_bool().unwrap_or_default() {
+        "  overdue"
+    } else {
+        ""
+    };
+    format!(
+        "{}  {}  {} {}/{} tasks ({}%){}",
+        short_id(&milestone["id"]),
+        str_field(milestone, "due_date").get(..10).unwrap_or_default(),
+        str_field(milestone, "name"),
+        milestone["done_count"],
+        milestone["task_count"],
+        milestone["progress_percentage"],
+        state
+    )
+}
+
+fn task_line(task: &Value) -> String {
+    let due = task["due_date"].as_str().map(|d| format!("  due {}", d.get(..10).unwrap_or(d))).unwrap_or_default();
+    format!(
+        "{}  [{}] {} ({}, {}){}",
+        short_id(&task["id"]),
+        clean(&task["status"]),
+        str_field(task, "title"),
+        str_field(task, "workstream_name"),
+        clean(&task["priority"]),
+        due
+    )
+}
+
+fn timer_line(entry: &Value) -> String {
+    let seconds = entry["elapsed_seconds"].as_i64().unwrap_or_default();
+    let state = if entry["orphaned"].as_bool().unwrap_or(false) {
+        "  orphaned"
+    } else if entry["ended_at"].is_null() && !entry["paused_at"].is_null() {
+        "  paused"
+    } else {
+        ""
+    };
+    format!("{}:{:02}  {} ({}){}", seconds / 3600, seconds / 60 % 60, str_field(entry, "task_title"), str_field(entry, "workstream_name"), state)
+}
+
+fn focus_line(status: &Value) -> String {
+    if status.is_null() {
+        return "No focus session".to_string();
+    }
+    let seconds = status["seconds_left"].as_i64().unwrap_or_default();
+    let state = if status["running"].as_bool().unwrap_or(false) { "" } else { "  paused" };
+    let task = status["task_title"].as_str().map(|t| format!(" on {}", t)).unwrap_or_default();
+    format!(
+        "{} {}{}  {}:{:02} left, {} pomodoros done{}",
+        str_field(status, "phase").to_lowercase().replace("break", " break"),
+        status["cycle"],
+        task,
+        seconds / 60,
+        seconds % 60,
+        status["completed_pomodoros"],
+        state
+    )
+}
+
+fn habit_line(habit: &Value) -> String {
+    let mark = if habit["completed"].as_bool().unwrap_or(false) { "x" } else { " " };
+    format!("[{}] {}  {} ({})", mark, short_id(&habit["id"]), str_field(habit, "name"), str_field(habit, "workstream_name"))
+}
+
+fn journal_line(entry: &Value) -> String {
+    format!("{}  {} ({})", short_id(&entry["id"]), str_field(entry, "title"), clean(&entry["entry_type"]))
+}
+
+fn filter_line(filter: &Value) -> String {
+    let pin = if filter["pinned"].as_bool().unwrap_or(false) { "*" } else { " " };
+    format!("{} {}  {} ({}, {})", pin, short_id(&filter["id"]), str_field(filter, "name"), clean(&filter["kind"]), filter["count"])
+}
+
+// A page of tasks or journal entries, noting how many more matched.
+fn results_text(results: &Value) -> String {
+    let lines: Vec<String> = if let Some(tasks) = results["tasks"].as_array() {
+        tasks.iter().map(task_line).collect()
+    } else {
+        results["entries"].as_array().cloned().unwrap_or_default().iter().map(journal_line).collect()
+    };
+    let shown = lines.len();
+    let mut text = if lines.is_empty() { "Nothing matches".to_string() } else { lines.join("\n") };
+    if let Some(total) = results["total"].as_i64().filter(|t| *t as usize > shown) {
+        text.push_str(&format!("\n... {} more", total as usize - shown));
+    }
+    text
+}
+
+fn agenda_text(agenda: &Value) -> String {
+    let mut lines = vec![format!("Agenda for {}", str_field(agenda, "date"))];
+
+    let sections = [("Overdue", "overdue"), ("Due today", "due_today"), ("In progress", "in_progress")];
+    for (heading, key) in sections {
+        let tasks = agenda[key].as_array().cloned().unwrap_or_default();
+        if !tasks.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("{}:", heading));
+            lines.extend(tasks.iter().map(|t| format!("  {}", task_line(t))));
+        }
+    }
+
+    let habits = agenda["habits"].as_array().cloned().unwrap_or_default();
+    if !habits.is_empty() {
+        lines.push(String::new());
+        lines.push("Habits:".to_string());
+        lines.extend(habits.iter().map(|h| format!("  {}", habit_line(h))));
+    }
+
+    let journal = agenda["journal"].as_array().cloned().unwrap_or_default();
+    if !journal.is_empty() {
+        lines.push(String::new());
+        lines.push("Journal:".to_string());
+        lines.extend(journal.iter().map(|e| format!("  {}", journal_line(e))));
+    }
+
+    if lines.len() == 1 {
+        lines.push("Nothing scheduled".to_string());
+    }
+    lines.join("\n")
+}
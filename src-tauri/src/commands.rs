@@ -0,0 +1,4863 @@
+use crate::database::{self, get_database_schema, Persona, ProjectTask, Workstream, WorkstreamStatus, generate_id, get_current_timestamp};
+use crate::repository::{self, HabitView, TaskView, TimeEntryView, WorkstreamView};
+use crate::{backup, estimates, filters, forecast, journal_vault, pomodoro, pool, query_lang, rank, recurrence, sync, webdav};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Command logic shared by the Tauri commands and the command-line client.
+// Every function works on a plain connection so callers decide how it is shared.
+
+// Open a database file and bring its schema and sync triggers up to date.
+pub fn open_database(path: &str) -> Result<Connection, String> {
+    let conn = Connection::open(path)
+        .map_err(|e| format!("Database connection error: {}", e))?;
+    conn.busy_timeout(pool::BUSY_TIMEOUT)
+        .map_err(|e| format!("Database connection error: {}", e))?;
+    // WAL lets the reader connections keep working while a write is in progress.
+    conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Database connection error: {}", e))?;
+    conn.execute_batch("PRAGMA synchronous = NORMAL;")
+        .map_err(|e| format!("Database connection error: {}", e))?;
+    conn.execute_batch(get_database_schema())
+        .map_err(|e| format!("Failed to create database schema: {}", e))?;
+    run_migrations(&conn)?;
+    sync::install_triggers(&conn)?;
+    Ok(conn)
+}
+
+// Apply the schema migrations newer than the database's user_version, each in its own transaction.
+pub fn run_migrations(conn: &Connection) -> Result<(), String> {
+    let current: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("SQL query error: {}", e))?;
+
+    for migration in database::get_schema_migrations().iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| format!("Migration error: {}", e))?;
+        tx.execute_batch(migration.sql)
+            .map_err(|e| format!("Migration {} ({}) failed: {}", migration.version, migration.description, e))?;
+        tx.pragma_update(None, "user_version", migration.version)
+            .map_err(|e| format!("Migration error: {}", e))?;
+        tx.commit()
+            .map_err(|e| format!("Migration error: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Test database commands
+pub fn test_database_connection(db: &Connection) -> Result<String, String> {
+    // Test basic query to check if tables exist
+    let mut stmt = db.prepare("SELECT name FROM sqlite_master WHERE type='table'")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    
+    let table_names: Result<Vec<String>, rusqlite::Error> = stmt.query_map([], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).map_err(|e| format!("SQL query error: {}", e))?
+    .collect();
+    
+    let tables = table_names.map_err(|e| format!("SQL collect error: {}", e))?;
+    
+    Ok(format!("Database connected successfully! Found {} tables: {:?}", tables.len(), tables))
+}
+
+pub fn create_test_persona(db: &Connection) -> Result<String, String> {
+    let persona = Persona {
+        id: generate_id(),
+        name: "Test Persona".to_string(),
+        description: Some("This is a test persona created from Rust".to_string()),
+        color: "#3b82f6".to_string(),
+        created_at: get_current_timestamp(),
+        updated_at: get_current_timestamp(),
+        is_active: true,
+    };
+    
+    // Insert the persona into the database
+    db.execute(
+        "INSERT INTO personas (id, name, description, color, created_at, updated_at, is_active) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            persona.id,
+            persona.name,
+            persona.description,
+            persona.color,
+            persona.created_at.to_rfc3339(),
+            persona.updated_at.to_rfc3339(),
+            persona.is_active
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    
+    Ok(format!("Test persona created successfully! ID: {}, Name: {}", persona.id, persona.name))
+}
+
+pub fn get_all_personas(db: &Connection) -> Result<Vec<Persona>, String> {
+    repository::query_all(db, "SELECT * FROM personas ORDER BY created_at DESC", [])
+}
+
+pub fn delete_persona(db: &Connection, id: String) -> Result<String, String> {
+    // Check if persona exists first
+    let mut stmt = db.prepare("SELECT name FROM personas WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    
+    let persona_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+    
+    let persona_name = persona_name.map_err(|e| format!("SQL query error: {}", e))?;
+    
+    if persona_name.is_none() {
+        return Err(format!("Persona with ID '{}' not found", id));
+    }
+    
+    // Delete the persona
+    let changes = db.execute("DELETE FROM personas WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    
+    if changes == 0 {
+        return Err(format!("No persona was deleted with ID '{}'", id));
+    }
+    
+    Ok(format!("Successfully deleted persona '{}' with ID: {}", persona_name.unwrap(), id))
+}
+
+pub fn clear_all_personas(db: &Connection) -> Result<String, String> {
+    // Count personas before deletion
+    let mut stmt = db.prepare("SELECT COUNT(*) FROM personas")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    
+    let count: i32 = stmt.query_row([], |row| {
+        Ok(row.get::<_, i32>(0)?)
+    }).map_err(|e| format!("SQL query error: {}", e))?;
+    
+    if count == 0 {
+        return Ok("No personas found to delete".to_string());
+    }
+    
+    // Delete all personas
+    db.execute("DELETE FROM personas", [])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    
+    Ok(format!("Successfully deleted {} persona(s) from the database", count))
+}
+
+// Persona Management Commands
+pub fn create_persona(
+    db: &Connection,
+    name: String,
+    description: Option<String>,
+    color: String
+) -> Result<Persona, String> {
+    let persona = Persona {
+        id: generate_id(),
+        name: name.clone(),
+        description,
+        color,
+        created_at: get_current_timestamp(),
+        updated_at: get_current_timestamp(),
+        is_active: true,
+    };
+    
+    db.execute(
+        "INSERT INTO personas (id, name, description, color, created_at, updated_at, is_active) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            persona.id,
+            persona.name,
+            persona.description,
+            persona.color,
+            persona.created_at.to_rfc3339(),
+            persona.updated_at.to_rfc3339(),
+            persona.is_active
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    
+    Ok(persona)
+}
+
+pub fn update_persona(
+    db: &Connection,
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    color: Option<String>,
+    is_active: Option<bool>
+) -> Result<Persona, String> {
+    let existing = repository::get_persona(db, &id)?
+        .ok_or_else(|| format!("Persona with ID '{}' not found", id))?;
+
+    let updated_persona = Persona {
+        name: name.unwrap_or(existing.name),
+        description: description.or(existing.description),
+        color: color.unwrap_or(existing.color),
+        is_active: is_active.unwrap_or(existing.is_active),
+        updated_at: get_current_timestamp(),
+        ..existing
+    };
+
+    db.execute(
+        "UPDATE personas SET name = ?1, description = ?2, color = ?3, is_active = ?4, updated_at = ?5 WHERE id = ?6",
+        rusqlite::params![
+            updated_persona.name,
+            updated_persona.description,
+            updated_persona.color,
+            updated_persona.is_active,
+            updated_persona.updated_at.to_rfc3339(),
+            id
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    Ok(updated_persona)
+}
+
+// Workstream Management Commands
+
+// Workstream start and target dates are days (YYYY-MM-DD or relative like +30d).
+fn parse_workstream_date(value: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let day = filters::resolve_date(value.trim(), chrono::Local::now().date_naive())?;
+    Ok(day.and_time(chrono::NaiveTime::MIN).and_utc())
+}
+
+fn check_workstream_dates(
+    start_date: Option<chrono::DateTime<chrono::Utc>>,
+    target_date: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), String> {
+    match (start_date, target_date) {
+        (Some(start), Some(target)) if target < start => Err(format!(
+            "The target date {} is before the start date {}",
+            target.format("%Y-%m-%d"),
+            start.format("%Y-%m-%d")
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_workstream(
+    db: &Connection,
+    persona_id: String,
+    name: String,
+    description: Option<String>,
+    status: String,
+    priority: Option<String>,
+    start_date: Option<String>,
+    target_date: Option<String>,
+) -> Result<Workstream, String> {
+    // Verify persona exists
+    let mut stmt = db.prepare("SELECT id FROM personas WHERE id = ?1 AND is_active = 1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    
+    let persona_exists: Result<Option<String>, rusqlite::Error> = stmt.query_row([&persona_id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+    
+    let persona_exists = persona_exists.map_err(|e| format!("SQL query error: {}", e))?;
+    
+    if persona_exists.is_none() {
+        return Err(format!("Persona with ID '{}' not found or inactive", persona_id));
+    }
+    
+    // Parse status
+    let workstream_status = match status.as_str() {
+        "planning" => WorkstreamStatus::Planning,
+        "active" => WorkstreamStatus::Active,
+        "paused" => WorkstreamStatus::Paused,
+        "completed" => WorkstreamStatus::Completed,
+        "cancelled" => WorkstreamStatus::Cancelled,
+        _ => return Err(format!("Invalid status: {}. Valid statuses: planning, active, paused, completed, cancelled", status)),
+    };
+    let priority: database::Priority = priority.as_deref().unwrap_or("medium").parse()?;
+    let start_date = start_date.filter(|date| !date.trim().is_empty()).map(|date| parse_workstream_date(&date)).transpose()?;
+    let target_date = target_date.filter(|date| !date.trim().is_empty()).map(|date| parse_workstream_date(&date)).transpose()?;
+    check_workstream_dates(start_date, target_date)?;
+    
+    let workstream = Workstream {
+        id: generate_id(),
+        persona_id,
+        name: name.clone(),
+        description,
+        status: workstream_status,
+        priority,
+        start_date,
+        target_date,
+        completed_date: None,
+        progress_percentage: 0,
+        created_at: get_current_timestamp(),
+        updated_at: get_current_timestamp(),
+    };
+    
+    db.execute(
+        "INSERT INTO workstreams (id, persona_id, name, description, status, priority, start_date, target_date, completed_date, progress_percentage, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        rusqlite::params![
+            workstream.id,
+            workstream.persona_id,
+            workstream.name,
+            workstream.description,
+            serde_json::to_string(&workstream.status).map_err(|e| format!("Status serialization error: {}", e))?,
+            serde_json::to_string(&workstream.priority).map_err(|e| format!("Priority serialization error: {}", e))?,
+            workstream.start_date.map(|d| d.to_rfc3339()),
+            workstream.target_date.map(|d| d.to_rfc3339()),
+            workstream.completed_date.map(|d| d.to_rfc3339()),
+            workstream.progress_percentage,
+            workstream.created_at.to_rfc3339(),
+            workstream.updated_at.to_rfc3339()
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    
+    Ok(workstream)
+}
+
+pub fn get_workstreams_by_persona(db: &Connection, persona_id: String) -> Result<Vec<Workstream>, String> {
+    repository::query_all(db, "SELECT * FROM workstreams WHERE persona_id = ?1 ORDER BY created_at DESC", [&persona_id])
+}
+
+pub fn get_all_workstreams(db: &Connection) -> Result<Vec<WorkstreamView>, String> {
+    repository::query_all(db, &format!("{} ORDER BY w.created_at DESC", repository::WORKSTREAM_VIEW_SELECT), [])
+}
+
+// Fields left out keep their values; an empty date clears it. The status only
+// changes through change_workstream_status, which handles the side effects.
+#[allow(clippy::too_many_arguments)]
+pub fn update_workstream(
+    db: &Connection,
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    status: Option<String>,
+    priority: Option<String>,
+    start_date: Option<String>,
+    target_date: Option<String>,
+) -> Result<Workstream, String> {
+    let existing = repository::get_workstream(db, &id)?
+        .ok_or_else(|| format!("Workstream with ID '{}' not found", id))?;
+
+    if let Some(status) = status {
+        let status: WorkstreamStatus = status.parse()?;
+        if status.key() != existing.status.key() {
+            return Err(format!(
+                "Use change_workstream_status to change the status of a workstream from {} to {}",
+                existing.status.key(),
+                status.key()
+            ));
+        }
+    }
+    let priority = priority.map(|priority| priority.parse::<database::Priority>()).transpose()?.unwrap_or(existing.priority);
+    let date = |value: Option<String>, existing: Option<chrono::DateTime<chrono::Utc>>| match value {
+        Some(value) if value.trim().is_empty() => Ok(None),
+        Some(value) => parse_workstream_date(&value).map(Some),
+        None => Ok(existing),
+    };
+    let start_date = date(start_date, existing.start_date)?;
+    let target_date = date(target_date, existing.target_date)?;
+    check_workstream_dates(start_date, target_date)?;
+
+    let updated_workstream = Workstream {
+        name: name.unwrap_or(existing.name),
+        description: description.or(existing.description),
+        priority,
+        start_date,
+        target_date,
+        updated_at: get_current_timestamp(),
+        ..existing
+    };
+
+    db.execute(
+        "UPDATE workstreams SET name = ?1, description = ?2, priority = ?3, start_date = ?4, target_date = ?5, updated_at = ?6 WHERE id = ?7",
+        rusqlite::params![
+            updated_workstream.name,
+            updated_workstream.description,
+            updated_workstream.priority.key(),
+            updated_workstream.start_date.map(|d| d.to_rfc3339()),
+            updated_workstream.target_date.map(|d| d.to_rfc3339()),
+            updated_workstream.updated_at.to_rfc3339(),
+            id
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    Ok(updated_workstream)
+}
+
+// Statuses a workstream can move to. Completed and cancelled workstreams are
+// closed: they only go back to planning or active when explicitly reopened.
+fn workstream_transitions(from: &WorkstreamStatus) -> &'static [&'static str] {
+    match from {
+        WorkstreamStatus::Planning => &["active", "paused", "cancelled"],
+        WorkstreamStatus::Active => &["paused", "completed", "cancelled"],
+        WorkstreamStatus::Paused => &["active", "completed", "cancelled"],
+        WorkstreamStatus::Completed | WorkstreamStatus::Cancelled => &["planning", "active"],
+    }
+}
+
+fn is_closed(status: &WorkstreamStatus) -> bool {
+    matches!(status, WorkstreamStatus::Completed | WorkstreamStatus::Cancelled)
+}
+
+fn check_workstream_transition(from: &WorkstreamStatus, to: &WorkstreamStatus, reopen: bool) -> Result<(), String> {
+    if from.key() == to.key() {
+        return Ok(());
+    }
+    let allowed = workstream_transitions(from);
+    if !allowed.contains(&to.key()) {
+        return Err(format!(
+            "A {} workstream cannot become {}. Allowed: {}",
+            from.key(),
+            to.key(),
+            allowed.join(", ")
+        ));
+    }
+    if is_closed(from) && !reopen {
+        return Err(format!("The workstream is {}; reopen it to make it {}", from.key(), to.key()));
+    }
+    Ok(())
+}
+
+// Set on completion and kept while completed; cleared otherwise.
+fn completed_date_for(
+    status: &WorkstreamStatus,
+    existing: Option<chrono::DateTime<chrono::Utc>>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    match status {
+        WorkstreamStatus::Completed => existing.or(Some(now)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkstreamStatusChange {
+    pub workstream: Workstream,
+    pub previous_status: WorkstreamStatus,
+    pub habits_paused: usize,
+    pub habits_resumed: usize,
+    pub tasks_archived: usize,
+    pub tasks_restored: usize, // archived tasks brought back on reopening
+    pub tasks_closed: usize,
+    pub tasks_to_backlog: usize,
+    pub tasks_moved: usize,
+    pub moved_to: Option<String>, // workstream the open tasks went to
+    pub changes: Vec<String>,
+}
+
+// Change a workstream's status along the allowed transitions, with its side
+// effects, in one transaction:
+// - `habits` carries the change over to the workstream's habits: pausing,
+//   completing or cancelling stops them and making it active starts them again
+// - `open_tasks` says what happens to tasks that are not done: on completing
+//   keep, archive or close (mark done); on cancelling keep, backlog or move
+//   (to `target_workstream_id`)
+// - reopening a completed workstream brings back the tasks archived with it
+pub fn change_workstream_status(
+    db: &Connection,
+    id: String,
+    status: String,
+    reopen: bool,
+    habits: bool,
+    open_tasks: Option<String>,
+    target_workstream_id: Option<String>,
+) -> Result<WorkstreamStatusChange, String> {
+    let existing = repository::get_workstream(db, &id)?
+        .ok_or_else(|| format!("Workstream with ID '{}' not found", id))?;
+    let status: WorkstreamStatus = status.parse()?;
+    check_workstream_transition(&existing.status, &status, reopen)?;
+
+    let open_tasks = open_tasks.map(|action| action.trim().to_lowercase()).filter(|action| !action.is_empty());
+    let valid: &[&str] = match status {
+        WorkstreamStatus::Completed => &["keep", "archive", "close"],
+        WorkstreamStatus::Cancelled => &["keep", "backlog", "move"],
+        _ => &[],
+    };
+    if let Some(action) = open_tasks.as_deref() {
+        if valid.is_empty() {
+            return Err("Open tasks can only be handled when completing or cancelling a workstream".to_string());
+        }
+        if !valid.contains(&action) {
+            return Err(format!("Invalid action for open tasks: {}. Valid actions: {}", action, valid.join(", ")));
+        }
+    }
+    let target = match (open_tasks.as_deref(), target_workstream_id) {
+        (Some("move"), Some(target_id)) => {
+            let target = repository::get_workstream(db, &target_id)?
+                .ok_or_else(|| format!("Workstream with ID '{}' not found", target_id))?;
+            if target.id == existing.id {
+                return Err("Open tasks cannot be moved to the workstream being cancelled".to_string());
+            }
+            if is_closed(&target.status) {
+                return Err(format!("Cannot move tasks to the {} workstream '{}'", target.status.key(), target.name));
+            }
+            Some(target)
+        }
+        (Some("move"), None) => return Err("Moving open tasks needs a target_workstream_id".to_string()),
+        (_, Some(_)) => return Err("A target workstream is only used when moving open tasks".to_string()),
+        (_, None) => None,
+    };
+
+    let tx = db.unchecked_transaction()
+        .map_err(|e| format!("Transaction start error: {}", e))?;
+    let now = get_current_timestamp();
+    let previous_status = existing.status.clone();
+    let workstream = Workstream {
+        completed_date: completed_date_for(&status, existing.completed_date, now),
+        status,
+        updated_at: now,
+        ..existing
+    };
+    tx.execute(
+        "UPDATE workstreams SET status = ?1, completed_date = ?2, updated_at = ?3 WHERE id = ?4",
+        rusqlite::params![workstream.status.key(), workstream.completed_date.map(|d| d.to_rfc3339()), now.to_rfc3339(), id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    let mut change = WorkstreamStatusChange {
+        workstream,
+        previous_status,
+        habits_paused: 0,
+        habits_resumed: 0,
+        tasks_archived: 0,
+        tasks_restored: 0,
+        tasks_closed: 0,
+        tasks_to_backlog: 0,
+        tasks_moved: 0,
+        moved_to: None,
+        changes: Vec::new(),
+    };
+    if change.previous_status.key() == change.workstream.status.key() {
+        tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+        return Ok(change);
+    }
+    change.changes.push(format!(
+        "Status changed from {} to {}",
+        change.previous_status.key(),
+        change.workstream.status.key()
+    ));
+
+    // Habits are left alone when going back to planning
+    let habits_active = match change.workstream.status {
+        WorkstreamStatus::Planning => None,
+        WorkstreamStatus::Active => Some(true),
+        _ => Some(false),
+    };
+    if let Some(active) = habits_active.filter(|_| habits) {
+        let count = tx.execute(
+            "UPDATE habit_trackers SET is_active = ?1, updated_at = ?2 WHERE workstream_id = ?3 AND is_active = ?4",
+            rusqlite::params![active, now.to_rfc3339(), id, !active]
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+        if active {
+            change.habits_resumed = count;
+            change.changes.push(format!("Resumed {} habit(s)", count));
+        } else {
+            change.habits_paused = count;
+            change.changes.push(format!("Paused {} habit(s)", count));
+        }
+    }
+
+    if matches!(change.previous_status, WorkstreamStatus::Completed) {
+        change.tasks_restored = tx.execute(
+            "UPDATE project_tasks SET archived_at = NULL, updated_at = ?1 WHERE workstream_id = ?2 AND archived_at IS NOT NULL",
+            rusqlite::params![now.to_rfc3339(), id]
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+        if change.tasks_restored > 0 {
+            change.changes.push(format!("Restored {} archived task(s)", change.tasks_restored));
+        }
+    }
+
+    let open: Vec<ProjectTask> = repository::query_all(
+        &tx,
+        &format!(
+            "SELECT * FROM project_tasks pt WHERE pt.workstream_id = ?1 AND pt.archived_at IS NULL AND {} != 'done' ORDER BY pt.created_at",
+            filters::STATUS_KEY
+        ),
+        [&id],
+    )?;
+    match open_tasks.as_deref() {
+        Some("archive") => {
+            for task in &open {
+                tx.execute(
+                    "UPDATE project_tasks SET archived_at = ?1, updated_at = ?1 WHERE id = ?2",
+                    rusqlite::params![now.to_rfc3339(), task.id]
+                ).map_err(|e| format!("SQL update error: {}", e))?;
+            }
+            change.tasks_archived = open.len();
+            change.changes.push(format!("Archived {} open task(s)", open.len()));
+        }
+        Some(action @ ("close" | "backlog")) => {
+            let status = if action == "close" { "done" } else { "backlog" };
+            let mut occurrences = 0;
+            for task in &open {
+                let (warnings, next) = set_task_status(&tx, task, status)
+                    .map_err(|e| format!("Task '{}': {}", task.title, e))?;
+                change.changes.extend(warnings.into_iter().map(|warning| format!("Task '{}': {}", task.title, warning)));
+                occurrences += usize::from(next.is_some());
+            }
+            if occurrences > 0 {
+                change.changes.push(format!("Created the next occurrence of {} recurring task(s)", occurrences));
+            }
+            if action == "close" {
+                change.tasks_closed = open.len();
+                change.changes.push(format!("Closed {} open task(s)", open.len()));
+            } else {
+                change.tasks_to_backlog = open.len();
+                change.changes.push(format!("Moved {} open task(s) to the backlog", open.len()));
+            }
+        }
+        Some("move") => {
+            if let Some(target) = &target {
+                let ids: Vec<String> = open.iter().map(|task| task.id.clone()).collect();
+                change.tasks_moved = move_tasks_to_workstream(&tx, &ids, &target.id, now)?;
+                change.changes.push(format!("Moved {} open task(s) to '{}'", change.tasks_moved, target.name));
+                change.moved_to = Some(target.name.clone());
+            }
+        }
+        _ => {
+            if !open.is_empty() && is_closed(&change.workstream.status) {
+                change.changes.push(format!("Kept {} open task(s)", open.len()));
+            }
+        }
+    }
+
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(change)
+}
+
+// Move tasks to another workstream. They leave their milestones and the
+// archive, subtask links across the two workstreams are cut, and tasks are
+// mapped onto the states of the workflow they follow now. Returns the tasks moved.
+fn move_tasks_to_workstream(
+    db: &Connection,
+    task_ids: &[String],
+    target_id: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<usize, String> {
+    let mut sources: Vec<String> = Vec::new();
+    let mut moved = 0;
+    for task_id in task_ids {
+        let source: String = db.query_row("SELECT workstream_id FROM project_tasks WHERE id = ?1", [task_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("SQL query error: {}", e))?
+            .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+        if source == target_id {
+            continue;
+        }
+        db.execute(
+            "UPDATE project_tasks SET workstream_id = ?1, milestone_id = NULL, archived_at = NULL, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![target_id, now.to_rfc3339(), task_id]
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+        moved += 1;
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+
+    for source in &sources {
+        db.execute(
+            "UPDATE project_tasks SET parent_id = NULL, subtask_rank = NULL, updated_at = ?3
+             WHERE (workstream_id = ?1 AND parent_id IN (SELECT id FROM project_tasks WHERE workstream_id = ?2))
+                OR (workstream_id = ?2 AND parent_id IN (SELECT id FROM project_tasks WHERE workstream_id = ?1))",
+            rusqlite::params![target_id, source, now.to_rfc3339()]
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+        refresh_milestones(db, "workstream_id = ?1", source)?;
+    }
+    if !sources.is_empty() {
+        remap_task_states(db, &[target_id.to_string()])?;
+    }
+    Ok(moved)
+}
+
+// Moving and copying across parents
+
+fn open_workstream_for(db: &Connection, workstream_id: &str) -> Result<Workstream, String> {
+    let workstream = repository::get_workstream(db, workstream_id)?
+        .ok_or_else(|| format!("Workstream with ID '{}' not found", workstream_id))?;
+    if is_closed(&workstream.status) {
+        return Err(format!("Cannot move tasks to the {} workstream '{}'", workstream.status.key(), workstream.name));
+    }
+    Ok(workstream)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTransfer {
+    pub task: TaskView,
+    pub subtasks: usize, // moved along with it
+    pub from_workstream_id: String,
+}
+
+// Move a task and its subtasks to another workstream. A subtask moved on its
+// own leaves its parent behind.
+pub fn move_task_to_workstream(db: &Connection, task_id: String, workstream_id: String) -> Result<TaskTransfer, String> {
+    let task = repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    if task.workstream_id == workstream_id {
+        return Err("The task is already in that workstream".to_string());
+    }
+    open_workstream_for(db, &workstream_id)?;
+
+    let mut stmt = db.prepare(&format!("{} SELECT id FROM descendants", DESCENDANTS))
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let mut ids = vec![task.id.clone()];
+    ids.extend(
+        stmt.query_map([&task.id], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("SQL query error: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("SQL collect error: {}", e))?,
+    );
+
+    let tx = db.unchecked_transaction()
+        .map_err(|e| format!("Transaction start error: {}", e))?;
+    let moved = move_tasks_to_workstream(&tx, &ids, &workstream_id, get_current_timestamp())?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(TaskTransfer {
+        task: repository::get_task_view(db, &task.id)?
+            .ok_or_else(|| format!("Project task with ID '{}' not found", task.id))?,
+        subtasks: moved.saturating_sub(1),
+        from_workstream_id: task.workstream_id,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkstreamTransfer {
+    pub workstream: Workstream,
+    pub from_persona_id: String,
+    pub tasks: usize,
+    pub habits: usize,
+    pub journal_entries: usize,
+    pub remapped_tasks: usize, // tasks put on states of the new persona's workflow
+}
+
+// Move a workstream to another persona. Its tasks and habits go with it, and
+// so do its journal entries, which also record the persona they were written for.
+pub fn move_workstream_to_persona(db: &Connection, workstream_id: String, persona_id: String) -> Result<WorkstreamTransfer, String> {
+    let existing = repository::get_workstream(db, &workstream_id)?
+        .ok_or_else(|| format!("Workstream with ID '{}' not found", workstream_id))?;
+    let persona = repository::get_persona(db, &persona_id)?
+        .filter(|persona| persona.is_active)
+        .ok_or_else(|| format!("Persona with ID '{}' not found or inactive", persona_id))?;
+    if existing.persona_id == persona.id {
+        return Err(format!("The workstream already belongs to '{}'", persona.name));
+    }
+
+    let count = |table: &str| -> Result<usize, String> {
+        db.query_row(&format!("SELECT COUNT(*) FROM {} WHERE workstream_id = ?1", table), [&workstream_id], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(|e| format!("SQL query error: {}", e))
+    };
+    let (tasks, habits) = (count("project_tasks")?, count("habit_trackers")?);
+    let from_persona_id = existing.persona_id.clone();
+    let now = get_current_timestamp();
+
+    let tx = db.unchecked_transaction()
+        .map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(
+        "UPDATE workstreams SET persona_id = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![persona.id, now.to_rfc3339(), workstream_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    let journal_entries = tx.execute(
+        "UPDATE journal_entries SET persona_id = CASE WHEN persona_id IS NULL THEN NULL ELSE ?1 END, updated_at = ?2 WHERE workstream_id = ?3",
+        rusqlite::params![persona.id, now.to_rfc3339(), workstream_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    // A workstream without its own workflow follows its persona's
+    let remapped_tasks = remap_task_states(&tx, std::slice::from_ref(&workstream_id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(WorkstreamTransfer {
+        workstream: Workstream { persona_id: persona.id, updated_at: now, ..existing },
+        from_persona_id,
+        tasks,
+        habits,
+        journal_entries,
+        remapped_tasks,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalDependency {
+    pub task_id: String, // the copied task
+    pub task_title: String,
+    pub depends_on_id: String,
+    pub depends_on_title: Option<String>, // None when that task no longer exists
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkstreamCopy {
+    pub workstream: Workstream,
+    pub source_workstream_id: String,
+    pub tasks: usize,
+    pub checklist_items: usize,
+    pub milestones: usize,
+    pub habits: usize,
+    pub policies: usize, // WIP limits, transition rules and completion policies
+    pub workflow_copied: bool,
+    // Whether the copied tasks started over as to-do, with checklists unticked
+    pub progress_reset: bool,
+    // Dependencies on tasks outside the workstream; the copies keep them
+    pub external_dependencies: Vec<ExternalDependency>,
+}
+
+// Insert a copy of a row under a new ID, with some columns set to other
+// values. The columns are the ones sync tracks, which is every column but the ID.
+fn copy_row(
+    db: &Connection,
+    table: &str,
+    old_id: &str,
+    new_id: &str,
+    overrides: &[(&str, rusqlite::types::Value)],
+) -> Result<(), String> {
+    let columns = sync::SYNCED_TABLES.iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, columns)| *columns)
+        .ok_or_else(|| format!("Cannot copy rows of {}", table))?;
+    let mut params = vec![rusqlite::types::Value::Text(new_id.to_string()), rusqlite::types::Value::Text(old_id.to_string())];
+    let values: Vec<String> = columns.iter()
+        .map(|column| match overrides.iter().find(|(name, _)| name == column) {
+            Some((_, value)) => {
+                params.push(value.clone());
+                format!("?{}", params.len())
+            }
+            None => column.to_string(),
+        })
+        .collect();
+    db.execute(
+        &format!("INSERT INTO {0} (id, {1}) SELECT ?1, {2} FROM {0} WHERE id = ?2", table, columns.join(", "), values.join(", ")),
+        rusqlite::params_from_iter(params)
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    Ok(())
+}
+
+fn ids_where(db: &Connection, sql: &str, param: &str) -> Result<Vec<String>, String> {
+    let mut stmt = db.prepare(sql)
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([param], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+    Ok(ids)
+}
+
+// Copy a workstream, as a new workstream in planning, with its own workflow,
+// kanban policies, milestones, tasks (with their subtasks and checklists) and
+// habits under new IDs. The copied tasks keep their states, completion dates
+// and ticked checklist items, unless `reset_progress` starts them over as
+// to-do with their checklists unticked and milestones open. Logged time, habit
+// completions and journal entries stay with the original. Dependencies between
+// copied tasks point at the copies.
+pub fn copy_workstream(
+    db: &Connection,
+    workstream_id: String,
+    name: Option<String>,
+    persona_id: Option<String>,
+    reset_progress: bool,
+) -> Result<WorkstreamCopy, String> {
+    use rusqlite::types::Value as SqlValue;
+
+    let source = repository::get_workstream(db, &workstream_id)?
+        .ok_or_else(|| format!("Workstream with ID '{}' not found", workstream_id))?;
+    let persona_id = match persona_id {
+        Some(persona_id) => repository::get_persona(db, &persona_id)?
+            .filter(|persona| persona.is_active)
+            .map(|persona| persona.id)
+            .ok_or_else(|| format!("Persona with ID '{}' not found or inactive", persona_id))?,
+        None => source.persona_id.clone(),
+    };
+    let name = name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("{} (copy)", source.name));
+
+    let now = get_current_timestamp();
+    let stamp = || SqlValue::Text(now.to_rfc3339());
+    let text = |value: &str| SqlValue::Text(value.to_string());
+    let tx = db.unchecked_transaction()
+        .map_err(|e| format!("Transaction start error: {}", e))?;
+
+    let new_id = generate_id();
+    copy_row(&tx, "workstreams", &source.id, &new_id, &[
+        ("persona_id", text(&persona_id)),
+        ("name", text(&name)),
+        ("status", text(WorkstreamStatus::Planning.key())),
+        ("completed_date", SqlValue::Null),
+        ("progress_percentage", SqlValue::Integer(0)),
+        ("created_at", stamp()),
+        ("updated_at", stamp()),
+    ])?;
+
+    let mut workflow_copied = false;
+    for workflow_id in ids_where(&tx, "SELECT id FROM workflows WHERE workstream_id = ?1", &source.id)? {
+        let copy_id = generate_id();
+        copy_row(&tx, "workflows", &workflow_id, &copy_id, &[("workstream_id", text(&new_id)), ("created_at", stamp()), ("updated_at", stamp())])?;
+        for state_id in ids_where(&tx, "SELECT id FROM workflow_states WHERE workflow_id = ?1", &workflow_id)? {
+            copy_row(&tx, "workflow_states", &state_id, &generate_id(), &[("workflow_id", text(&copy_id))])?;
+        }
+        workflow_copied = true;
+    }
+
+    let mut policies = 0;
+    for table in ["wip_limits", "status_transitions", "completion_policies"] {
+        for id in ids_where(&tx, &format!("SELECT id FROM {} WHERE workstream_id = ?1", table), &source.id)? {
+            copy_row(&tx, table, &id, &generate_id(), &[("workstream_id", text(&new_id)), ("created_at", stamp()), ("updated_at", stamp())])?;
+            policies += 1;
+        }
+    }
+
+    let mut milestone_ids = std::collections::HashMap::new();
+    for id in ids_where(&tx, "SELECT id FROM milestones WHERE workstream_id = ?1", &source.id)? {
+        let copy_id = generate_id();
+        let mut overrides = vec![("workstream_id", text(&new_id)), ("created_at", stamp()), ("updated_at", stamp())];
+        if reset_progress {
+            overrides.push(("completed_date", SqlValue::Null));
+        }
+        copy_row(&tx, "milestones", &id, &copy_id, &overrides)?;
+        milestone_ids.insert(id, copy_id);
+    }
+
+    // Copied bottom first, each to the top of its column, so they keep their order
+    let tasks: Vec<ProjectTask> = repository::query_all(
+        &tx,
+        "SELECT * FROM project_tasks WHERE workstream_id = ?1 ORDER BY rank IS NOT NULL, rank DESC, created_at DESC",
+        [&source.id],
+    )?;
+    let task_ids: std::collections::HashMap<String, String> = tasks.iter().map(|task| (task.id.clone(), generate_id())).collect();
+    let mut series_ids: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let mut external_dependencies = Vec::new();
+    for task in &tasks {
+        let copy_id = &task_ids[&task.id];
+        let dependencies: Vec<String> = task.dependencies.iter()
+            .map(|dependency| task_ids.get(dependency).cloned().unwrap_or_else(|| dependency.clone()))
+            .collect();
+        for dependency in task.dependencies.iter().filter(|dependency| !task_ids.contains_key(*dependency)) {
+            external_dependencies.push(ExternalDependency {
+                task_id: copy_id.clone(),
+                task_title: task.title.clone(),
+                depends_on_id: dependency.clone(),
+                depends_on_title: tx.query_row("SELECT title FROM project_tasks WHERE id = ?1", [dependency], |row| row.get(0))
+                    .optional()
+                    .map_err(|e| format!("SQL query error: {}", e))?,
+            });
+        }
+        let series = task.recurrence.as_ref()
+            .map(|recurrence| series_ids.entry(recurrence.series_id.clone()).or_insert_with(generate_id).clone());
+        let remap = |id: &Option<String>, ids: &std::collections::HashMap<String, String>| {
+            id.as_ref().and_then(|id| ids.get(id)).map_or(SqlValue::Null, |id| SqlValue::Text(id.clone()))
+        };
+        let (status, workflow_state) = if reset_progress {
+            resolve_task_state(&tx, &new_id, "todo", None)?
+        } else {
+            resolve_task_state(&tx, &new_id, task.status.key(), task.workflow_state.as_deref())?
+        };
+        let rank = top_of_column(&tx, status.key())?;
+        let completed_date = match &task.completed_date {
+            Some(date) if !reset_progress && status.key() == "done" => SqlValue::Text(date.to_rfc3339()),
+            _ => SqlValue::Null,
+        };
+        copy_row(&tx, "project_tasks", &task.id, copy_id, &[
+            ("workstream_id", text(&new_id)),
+            ("status", text(status.key())),
+            ("workflow_state", workflow_state.map_or(SqlValue::Null, SqlValue::Text)),
+            ("rank", rank.map_or(SqlValue::Null, SqlValue::Text)),
+            ("completed_date", completed_date),
+            ("actual_hours", SqlValue::Null),
+            ("dependencies", SqlValue::Text(serde_json::to_string(&dependencies).map_err(|e| format!("Dependencies serialization error: {}", e))?)),
+            ("parent_id", remap(&task.parent_id, &task_ids)),
+            ("milestone_id", remap(&task.milestone_id, &milestone_ids)),
+            ("recurrence_series", series.map_or(SqlValue::Null, SqlValue::Text)),
+            ("archived_at", SqlValue::Null),
+            ("created_at", stamp()),
+            ("updated_at", stamp()),
+        ])?;
+        record_status_change(&tx, copy_id, None, &status, now)?;
+    }
+
+    let mut checklist_items = 0;
+    for task in &tasks {
+        for id in ids_where(&tx, "SELECT id FROM task_checklist_items WHERE task_id = ?1", &task.id)? {
+            let mut overrides = vec![("task_id", text(&task_ids[&task.id])), ("created_at", stamp()), ("updated_at", stamp())];
+            if reset_progress {
+                overrides.push(("done", SqlValue::Integer(0)));
+            }
+            copy_row(&tx, "task_checklist_items", &id, &generate_id(), &overrides)?;
+            checklist_items += 1;
+        }
+    }
+
+    let habit_ids = ids_where(&tx, "SELECT id FROM habit_trackers WHERE workstream_id = ?1", &source.id)?;
+    for id in &habit_ids {
+        copy_row(&tx, "habit_trackers", id, &generate_id(), &[("workstream_id", text(&new_id)), ("created_at", stamp()), ("updated_at", stamp())])?;
+    }
+
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(WorkstreamCopy {
+        workstream: repository::get_workstream(db, &new_id)?
+            .ok_or_else(|| format!("Workstream with ID '{}' not found", new_id))?,
+        source_workstream_id: source.id,
+        tasks: tasks.len(),
+        checklist_items,
+        milestones: milestone_ids.len(),
+        habits: habit_ids.len(),
+        policies,
+        workflow_copied,
+        progress_reset: reset_progress,
+        external_dependencies,
+    })
+}
+
+pub fn delete_workstream(db: &Connection, id: String) -> Result<String, String> {
+    // Check if workstream exists first
+    let mut stmt = db.prepare("SELECT name FROM workstreams WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    
+    let workstream_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+    
+    let workstream_name = workstream_name.map_err(|e| format!("SQL query error: {}", e))?;
+    
+    if workstream_name.is_none() {
+        return Err(format!("Workstream with ID '{}' not found", id));
+    }
+    
+    // Delete the workstream
+    let changes = db.execute("DELETE FROM workstreams WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    
+    if changes == 0 {
+        return Err(format!("No workstream was deleted with ID '{}'", id));
+    }
+    
+    Ok(format!("Successfully deleted workstream '{}' with ID: {}", workstream_name.unwrap(), id))
+}
+
+// Project Task Management Commands
+pub fn create_project_task(
+    db: &Connection,
+    workstream_id: String,
+    title: String,
+    description: Option<String>,
+    status: String,
+    priority: String
+) -> Result<ProjectTask, String> {
+    // Verify workstream exists
+    let mut stmt = db.prepare("SELECT id FROM workstreams WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let workstream_exists: Result<Option<String>, rusqlite::Error> = stmt.query_row([&workstream_id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+
+    let workstream_exists = workstream_exists.map_err(|e| format!("SQL query error: {}", e))?;
+
+    if workstream_exists.is_none() {
+        return Err(format!("Workstream with ID '{}' not found", workstream_id));
+    }
+
+    // Parse status (or a state of the workstream's workflow) and priority
+    let (task_status, workflow_state) = resolve_task_state(db, &workstream_id, &status, None)?;
+
+    let task_priority = match priority.as_str() {
+        "low" => database::Priority::Low,
+        "medium" => database::Priority::Medium,
+        "high" => database::Priority::High,
+        "critical" => database::Priority::Critical,
+        _ => return Err(format!("Invalid priority: {}. Valid priorities: low, medium, high, critical", priority)),
+    };
+
+    // New tasks go to the top of their column
+    let rank = top_of_column(db, task_status.key())?;
+    let now = database::get_current_timestamp();
+
+    let task = database::ProjectTask {
+        id: database::generate_id(),
+        workstream_id,
+        title: title.clone(),
+        description,
+        completed_date: (task_status.key() == "done").then_some(now),
+        status: task_status,
+        priority: task_priority,
+        due_date: None,
+        estimated_hours: None,
+        actual_hours: None,
+        tags: Vec::new(),
+        dependencies: Vec::new(),
+        rank,
+        workflow_state,
+        parent_id: None,
+        subtask_rank: None,
+        milestone_id: None,
+        archived_at: None,
+        recurrence: None,
+        created_at: database::get_current_timestamp(),
+        updated_at: database::get_current_timestamp(),
+    };
+
+    db.execute(
+        "INSERT INTO project_tasks (id, workstream_id, title, description, status, priority, due_date, completed_date, estimated_hours, actual_hours, tags, dependencies, rank, workflow_state, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        rusqlite::params![
+            task.id,
+            task.workstream_id,
+            task.title,
+            task.description,
+            serde_json::to_string(&task.status).map_err(|e| format!("Status serialization error: {}", e))?,
+            serde_json::to_string(&task.priority).map_err(|e| format!("Priority serialization error: {}", e))?,
+            task.due_date.map(|d| d.to_rfc3339()),
+            task.completed_date.map(|d| d.to_rfc3339()),
+            task.estimated_hours,
+            task.actual_hours,
+            serde_json::to_string(&task.tags).map_err(|e| format!("Tags serialization error: {}", e))?,
+            serde_json::to_string(&task.dependencies).map_err(|e| format!("Dependencies serialization error: {}", e))?,
+            task.rank,
+            task.workflow_state,
+            task.created_at.to_rfc3339(),
+            task.updated_at.to_rfc3339()
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    record_status_change(db, &task.id, None, &task.status, now)?;
+
+    Ok(task)
+}
+
+// Keep the status history the burndown charts are built from, and the
+// completion date, which is set on entering Done and cleared on leaving it.
+fn record_status_change(
+    db: &Connection,
+    task_id: &str,
+    from: Option<&database::TaskStatus>,
+    to: &database::TaskStatus,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(), String> {
+    if from.is_some_and(|from| from.key() == to.key()) {
+        return Ok(());
+    }
+    db.execute(
+        "INSERT INTO task_status_history (id, task_id, from_status, to_status, changed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![generate_id(), task_id, from.map(|from| from.key()), to.key(), now.to_rfc3339()]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    db.execute(
+        "UPDATE project_tasks SET completed_date = CASE WHEN ?1 = 'done' THEN COALESCE(completed_date, ?2) END WHERE id = ?3",
+        rusqlite::params![to.key(), now.to_rfc3339(), task_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    refresh_milestones(db, "id = (SELECT milestone_id FROM project_tasks WHERE id = ?1)", task_id)
+}
+
+pub fn get_tasks_by_workstream(db: &Connection, workstream_id: String) -> Result<Vec<ProjectTask>, String> {
+    repository::query_all(db, "SELECT * FROM project_tasks WHERE workstream_id = ?1 ORDER BY created_at DESC", [&workstream_id])
+}
+
+pub fn get_all_project_tasks(db: &Connection) -> Result<Vec<TaskView>, String> {
+    repository::query_all(db, &format!("{} ORDER BY pt.created_at DESC", repository::TASK_VIEW_SELECT), [])
+}
+
+pub fn update_project_task(
+    db: &Connection,
+    id: String,
+    title: Option<String>,
+    description: Option<String>,
+    status: String,
+    priority: String
+) -> Result<ProjectTask, String> {
+    let existing = repository::get_task(db, &id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", id))?;
+
+    let (status, workflow_state) = resolve_task_state(db, &existing.workstream_id, &status, existing.workflow_state.as_deref())?;
+    let priority: database::Priority = priority.parse()?;
+
+    // Blocking column policies apply to edits too; warnings are only reported
+    // by update_task_status and move_task.
+    check_column_policies(db, &existing, status.key())?;
+
+    // A task entering another column goes to its top
+    let rank = if status.key() == existing.status.key() { existing.rank.clone() } else { top_of_column(db, status.key())? };
+    let existing_status = existing.status.clone();
+    let now = get_current_timestamp();
+
+    let updated_task = ProjectTask {
+        title: title.unwrap_or(existing.title),
+        description: description.or(existing.description),
+        completed_date: if status.key() == "done" { existing.completed_date.or(Some(now)) } else { None },
+        status,
+        priority,
+        rank,
+        workflow_state,
+        updated_at: now,
+        ..existing
+    };
+
+    db.execute(
+        "UPDATE project_tasks SET title = ?1, description = ?2, status = ?3, priority = ?4, rank = ?5, workflow_state = ?6, updated_at = ?7 WHERE id = ?8",
+        rusqlite::params![
+            updated_task.title,
+            updated_task.description,
+            updated_task.status.key(),
+            updated_task.priority.key(),
+            updated_task.rank,
+            updated_task.workflow_state,
+            updated_task.updated_at.to_rfc3339(),
+            id
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    record_status_change(db, &id, Some(&existing_status), &updated_task.status, now)?;
+
+    if updated_task.status.key() == "done" && existing_status.key() != "done" {
+        next_occurrence(db, &updated_task)?;
+    }
+
+    Ok(updated_task)
+}
+
+pub fn delete_project_task(db: &Connection, id: String) -> Result<String, String> {
+    // Get task title for confirmation message
+    let mut stmt = db.prepare("SELECT title FROM project_tasks WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let task_title: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+
+    let task_title = task_title.map_err(|e| format!("SQL query error: {}", e))?;
+
+    if task_title.is_none() {
+        return Err(format!("Project task with ID '{}' not found", id));
+    }
+
+    // Subtasks and checklist items go with their task
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(&format!("{} DELETE FROM task_checklist_items WHERE task_id = ?1 OR task_id IN (SELECT id FROM descendants)", DESCENDANTS), [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    tx.execute(&format!("{} DELETE FROM time_entries WHERE task_id = ?1 OR task_id IN (SELECT id FROM descendants)", DESCENDANTS), [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    tx.execute(&format!("{} DELETE FROM task_status_history WHERE task_id = ?1 OR task_id IN (SELECT id FROM descendants)", DESCENDANTS), [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    unlink_focus_history(&tx, &format!("{} SELECT ?1 UNION SELECT id FROM descendants", DESCENDANTS), &id)?;
+    tx.execute(&format!("{} DELETE FROM project_tasks WHERE id IN (SELECT id FROM descendants)", DESCENDANTS), [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+
+    let workstream_id: String = tx.query_row("SELECT workstream_id FROM project_tasks WHERE id = ?1", [&id], |row| row.get(0))
+        .map_err(|e| format!("SQL query error: {}", e))?;
+    let changes = tx.execute("DELETE FROM project_tasks WHERE id = ?1", rusqlite::params![id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+
+    if changes == 0 {
+        return Err(format!("No task was deleted with ID '{}'", id));
+    }
+    // a milestone whose last open task went may be finished now
+    refresh_milestones(&tx, "workstream_id = ?1", &workstream_id)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(format!("Successfully deleted project task '{}' with ID: {}", task_title.unwrap(), id))
+}
+
+// Focus sessions and pomodoros outlive their task so the focus statistics
+// stay put; `tasks` selects the task IDs with ?1.
+fn unlink_focus_history(db: &Connection, tasks: &str, id: &str) -> Result<(), String> {
+    db.execute(&format!("UPDATE focus_sessions SET task_id = NULL WHERE task_id IN ({})", tasks), [id])
+        .map_err(|e| format!("SQL update error: {}", e))?;
+    db.execute(&format!("UPDATE pomodoros SET task_id = NULL, time_entry_id = NULL WHERE task_id IN ({})", tasks), [id])
+        .map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+// Dependency checking functions
+pub fn check_persona_dependencies(db: &Connection, id: String) -> Result<serde_json::Value, String> {
+    // Check if persona exists
+    let mut stmt = db.prepare("SELECT name FROM personas WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let persona_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+
+    let persona_name = persona_name.map_err(|e| format!("SQL query error: {}", e))?;
+
+    if persona_name.is_none() {
+        return Err(format!("Persona with ID '{}' not found", id));
+    }
+
+    // Count workstreams
+    let mut stmt = db.prepare("SELECT COUNT(*) FROM workstreams WHERE persona_id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let workstream_count: i64 = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, i64>(0)?)
+    }).map_err(|e| format!("SQL query error: {}", e))?;
+
+    // Count tasks through workstreams
+    let mut stmt = db.prepare("
+        SELECT COUNT(*) FROM project_tasks pt 
+        JOIN workstreams w ON pt.workstream_id = w.id 
+        WHERE w.persona_id = ?1
+    ")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let task_count: i64 = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, i64>(0)?)
+    }).map_err(|e| format!("SQL query error: {}", e))?;
+
+    let result = serde_json::json!({
+        "persona_name": persona_name.unwrap(),
+        "workstream_count": workstream_count,
+        "task_count": task_count,
+        "has_dependencies": workstream_count > 0 || task_count > 0
+    });
+
+    Ok(result)
+}
+
+pub fn check_workstream_dependencies(db: &Connection, id: String) -> Result<serde_json::Value, String> {
+    // Check if workstream exists
+    let mut stmt = db.prepare("SELECT name FROM workstreams WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let workstream_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, String>(0)?)
+    }).optional();
+
+    let workstream_name = workstream_name.map_err(|e| format!("SQL query error: {}", e))?;
+
+    if workstream_name.is_none() {
+        return Err(format!("Workstream with ID '{}' not found", id));
+    }
+
+    // Count tasks
+    let mut stmt = db.prepare("SELECT COUNT(*) FROM project_tasks WHERE workstream_id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let task_count: i64 = stmt.query_row([&id], |row| {
+        Ok(row.get::<_, i64>(0)?)
+    }).map_err(|e| format!("SQL query error: {}", e))?;
+
+    let result = serde_json::json!({
+        "workstream_name": workstream_name.unwrap(),
+        "task_count": task_count,
+        "has_dependencies": task_count > 0
+    });
+
+    Ok(result)
+}
+
+// Cascade delete functions
+pub fn cascade_delete_persona(db: &mut Connection, id: String) -> Result<String, String> {
+    // Get persona name for confirmation message
+    let persona_name: Result<Option<String>, rusqlite::Error> = {
+        let mut stmt = db.prepare("SELECT name FROM personas WHERE id = ?1")
+            .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+        stmt.query_row([&id], |row| {
+            Ok(row.get::<_, String>(0)?)
+        }).optional()
+    };
+
+    let persona_name = persona_name.map_err(|e| format!("SQL query error: {}", e))?;
+
+    if persona_name.is_none() {
+        return Err(format!("Persona with ID '{}' not found", id));
+    }
+
+    // Start transaction for cascade delete
+    let tx = db.transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+    // Delete tasks first (deepest level)
+    tx.execute("DELETE FROM task_checklist_items WHERE task_id IN (
+        SELECT pt.id FROM project_tasks pt JOIN workstreams w ON pt.workstream_id = w.id WHERE w.persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete checklist items error: {}", e))?;
+    tx.execute("DELETE FROM time_entries WHERE task_id IN (
+        SELECT pt.id FROM project_tasks pt JOIN workstreams w ON pt.workstream_id = w.id WHERE w.persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete time entries error: {}", e))?;
+    tx.execute("DELETE FROM task_status_history WHERE task_id IN (
+        SELECT pt.id FROM project_tasks pt JOIN workstreams w ON pt.workstream_id = w.id WHERE w.persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete task history error: {}", e))?;
+    unlink_focus_history(&tx, "SELECT pt.id FROM project_tasks pt JOIN workstreams w ON pt.workstream_id = w.id WHERE w.persona_id = ?1", &id)?;
+    tx.execute("DELETE FROM project_tasks WHERE workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete tasks error: {}", e))?;
+    tx.execute("DELETE FROM wip_limits WHERE workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete WIP limits error: {}", e))?;
+    tx.execute("DELETE FROM status_transitions WHERE workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete transition rules error: {}", e))?;
+    tx.execute("DELETE FROM workflow_states WHERE workflow_id IN (
+        SELECT id FROM workflows WHERE persona_id = ?1 OR workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1))", [&id])
+        .map_err(|e| format!("SQL delete workflow states error: {}", e))?;
+    tx.execute("DELETE FROM workflows WHERE persona_id = ?1 OR workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete workflows error: {}", e))?;
+    tx.execute("DELETE FROM completion_policies WHERE workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete completion policies error: {}", e))?;
+    tx.execute("DELETE FROM milestones WHERE workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete milestones error: {}", e))?;
+
+    // Delete workstreams
+    tx.execute("DELETE FROM workstreams WHERE persona_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete workstreams error: {}", e))?;
+
+    // Delete persona
+    tx.execute("DELETE FROM personas WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete persona error: {}", e))?;
+
+    // Commit transaction
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(format!("Successfully deleted persona '{}' and all associated workstreams and tasks", persona_name.unwrap()))
+}
+
+pub fn cascade_delete_workstream(db: &mut Connection, id: String) -> Result<String, String> {
+    // Get workstream name for confirmation message
+    let workstream_name: Result<Option<String>, rusqlite::Error> = {
+        let mut stmt = db.prepare("SELECT name FROM workstreams WHERE id = ?1")
+            .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+        stmt.query_row([&id], |row| {
+            Ok(row.get::<_, String>(0)?)
+        }).optional()
+    };
+
+    let workstream_name = workstream_name.map_err(|e| format!("SQL query error: {}", e))?;
+
+    if workstream_name.is_none() {
+        return Err(format!("Workstream with ID '{}' not found", id));
+    }
+
+    // Start transaction for cascade delete
+    let tx = db.transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+    // Delete tasks first
+    tx.execute("DELETE FROM task_checklist_items WHERE task_id IN (SELECT id FROM project_tasks WHERE workstream_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete checklist items error: {}", e))?;
+    tx.execute("DELETE FROM time_entries WHERE task_id IN (SELECT id FROM project_tasks WHERE workstream_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete time entries error: {}", e))?;
+    tx.execute("DELETE FROM task_status_history WHERE task_id IN (SELECT id FROM project_tasks WHERE workstream_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete task history error: {}", e))?;
+    unlink_focus_history(&tx, "SELECT id FROM project_tasks WHERE workstream_id = ?1", &id)?;
+    tx.execute("DELETE FROM project_tasks WHERE workstream_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete tasks error: {}", e))?;
+
+    // and the workstream's kanban policies
+    tx.execute("DELETE FROM wip_limits WHERE workstream_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete WIP limits error: {}", e))?;
+    tx.execute("DELETE FROM status_transitions WHERE workstream_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete transition rules error: {}", e))?;
+    tx.execute("DELETE FROM workflow_states WHERE workflow_id IN (SELECT id FROM workflows WHERE workstream_id = ?1)", [&id])
+        .map_err(|e| format!("SQL delete workflow states error: {}", e))?;
+    tx.execute("DELETE FROM workflows WHERE workstream_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete workflows error: {}", e))?;
+    tx.execute("DELETE FROM completion_policies WHERE workstream_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete completion policies error: {}", e))?;
+    tx.execute("DELETE FROM milestones WHERE workstream_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete milestones error: {}", e))?;
+
+    // Delete workstream
+    tx.execute("DELETE FROM workstreams WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete workstream error: {}", e))?;
+
+    // Commit transaction
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(format!("Successfully deleted workstream '{}' and all associated tasks", workstream_name.unwrap()))
+}
+
+// Kanban board specific functions
+pub fn get_tasks_for_kanban(
+    db: &Connection,
+    workstream_filter: Option<String>,
+    status_filter: Option<String>
+) -> Result<Vec<TaskView>, String> {
+    let mut query = format!("{} WHERE pt.archived_at IS NULL", repository::TASK_VIEW_SELECT);
+
+    let mut params: Vec<String> = Vec::new();
+
+    if let Some(workstream_id) = workstream_filter {
+        if workstream_id != "all" {
+            query.push_str(" AND pt.workstream_id = ?");
+            params.push(workstream_id);
+        }
+    }
+
+    // Statuses are stored both as JSON strings and bare words, so compare normalised keys.
+    // Anything that is not a built-in status is taken as a workflow state.
+    if let Some(status) = status_filter {
+        if status != "all" {
+            match status.parse::<database::TaskStatus>() {
+                Ok(status) => {
+                    query.push_str(" AND LOWER(TRIM(pt.status, '\"')) = ?");
+                    params.push(status.key().to_string());
+                }
+                Err(_) => {
+                    query.push_str(" AND pt.workflow_state = ?");
+                    params.push(state_key(&status));
+                }
+            }
+        }
+    }
+
+    query.push_str(&format!(" ORDER BY {}", KANBAN_ORDER));
+
+    repository::query_all(db, &query, rusqlite::params_from_iter(params.iter()))
+}
+
+// General task search with multi-value filters, sorting and cursor pagination.
+pub fn query_tasks(db: &Connection, query: filters::TaskQuery) -> Result<filters::TaskPage, String> {
+    filters::query_tasks(db, &query, chrono::Local::now().date_naive())
+}
+
+pub fn get_task_counts_by_status(
+    db: &Connection,
+    workstream_filter: Option<String>
+) -> Result<serde_json::Value, String> {
+    let mut query = "
+        SELECT 
+            pt.status,
+            COUNT(*) as count
+        FROM project_tasks pt
+        JOIN workstreams w ON pt.workstream_id = w.id
+        WHERE pt.archived_at IS NULL
+    ".to_string();
+
+    let mut params: Vec<String> = Vec::new();
+
+    let workstream_id = workstream_filter.filter(|id| id != "all");
+    if let Some(workstream_id) = &workstream_id {
+        query.push_str(" AND pt.workstream_id = ?");
+        params.push(workstream_id.clone());
+    }
+
+    query.push_str(" GROUP BY pt.status");
+
+    let mut stmt = db.prepare(&query)
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })
+    .map_err(|e| format!("SQL query error: {}", e))?;
+
+    let mut counts = serde_json::json!({
+        "backlog": 0,
+        "todo": 0,
+        "inprogress": 0,
+        "review": 0,
+        "done": 0
+    });
+
+    // The same status can be stored in more than one spelling, so add the groups up.
+    for row in rows {
+        let (status, count): (String, i64) = row.map_err(|e| format!("Row processing error: {}", e))?;
+        if let Ok(status) = status.parse::<database::TaskStatus>() {
+            let total = counts[status.key()].as_i64().unwrap_or(0) + count;
+            counts[status.key()] = serde_json::Value::Number(serde_json::Number::from(total));
+        }
+    }
+
+    // The WIP limit of each column next to the count it applies to: the
+    // workstream's own limit when it has one, otherwise the board's.
+    let mut limits = serde_json::Map::new();
+    for status in ["backlog", "todo", "inprogress", "review", "done"] {
+        let own = match workstream_id.as_deref() {
+            Some(id) => wip_limit_for(db, Some(id), status)?.map(|limit| (limit, Some(id))),
+            None => None,
+        };
+        let limit = match own {
+            Some(own) => Some(own),
+            None => wip_limit_for(db, None, status)?.map(|limit| (limit, None)),
+        };
+        if let Some((limit, scope)) = limit {
+            let count = column_count(db, scope, status)?;
+            limits.insert(status.to_string(), serde_json::json!({
+                "max_tasks": limit.max_tasks,
+                "count": count,
+                "scope": if scope.is_some() { "workstream" } else { "board" },
+                "enforcement": limit.enforcement.key(),
+                "exceeded": count > limit.max_tasks,
+            }));
+        }
+    }
+    counts["limits"] = Value::Object(limits);
+
+    // Columns of a workstream with its own states
+    if let Some(workflow) = match workstream_id.as_deref() {
+        Some(id) => custom_workflow_for(db, id)?,
+        None => None,
+    } {
+        let mut states = serde_json::Map::new();
+        for state in &workflow.states {
+            let count: i64 = db.query_row(
+                "SELECT COUNT(*) FROM project_tasks WHERE workstream_id = ?1 AND workflow_state = ?2 AND archived_at IS NULL",
+                rusqlite::params![workstream_id, state.key],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("SQL query error: {}", e))?;
+            states.insert(state.key.clone(), count.into());
+        }
+        counts["states"] = Value::Object(states);
+    }
+
+    Ok(counts)
+}
+
+// A status change together with the column policies it breaks without being
+// blocked by them, and the next occurrence when it completed a recurring task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStatusChange {
+    #[serde(flatten)]
+    pub task: TaskView,
+    pub warnings: Vec<String>,
+    pub next_occurrence: Option<TaskView>,
+}
+
+pub fn update_task_status(db: &Connection, task_id: String, new_status: String) -> Result<TaskStatusChange, String> {
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+    let existing = repository::get_task(&tx, &task_id)?
+        .ok_or_else(|| format!("Task with ID '{}' not found", task_id))?;
+    let (warnings, next_occurrence) = set_task_status(&tx, &existing, &new_status)?;
+
+    // Return updated task data
+    let task = repository::get_task_view(&tx, &task_id)?
+        .ok_or_else(|| format!("Task with ID '{}' not found", task_id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(TaskStatusChange { task, warnings, next_occurrence })
+}
+
+// Move a task to a status, or a state of its workflow, under the column
+// policies. Returns the policy warnings and the next occurrence created when
+// it completed a recurring task.
+fn set_task_status(db: &Connection, existing: &ProjectTask, new_status: &str) -> Result<(Vec<String>, Option<TaskView>), String> {
+    let (status, workflow_state) = resolve_task_state(db, &existing.workstream_id, new_status, existing.workflow_state.as_deref())?;
+    let warnings = check_column_policies(db, existing, status.key())?;
+
+    // A task entering another column goes to its top
+    let rank = if status.key() == existing.status.key() { existing.rank.clone() } else { top_of_column(db, status.key())? };
+    let now = get_current_timestamp();
+    db.execute(
+        "UPDATE project_tasks SET status = ?1, rank = ?2, workflow_state = ?3, updated_at = ?4 WHERE id = ?5",
+        rusqlite::params![status.key(), rank, workflow_state, now.to_rfc3339(), existing.id]
+    )
+    .map_err(|e| format!("SQL update error: {}", e))?;
+    record_status_change(db, &existing.id, Some(&existing.status), &status, now)?;
+
+    let next_occurrence = if status.key() == "done" && existing.status.key() != "done" {
+        let updated_task = repository::get_task(db, &existing.id)?
+            .ok_or_else(|| format!("Task with ID '{}' not found", existing.id))?;
+        next_occurrence(db, &updated_task)?
+    } else {
+        None
+    };
+    Ok((warnings, next_occurrence))
+}
+
+// Manual kanban order. Each task has a lexicographic rank within its status
+// column (see rank.rs); unranked tasks from before ranking existed come after
+// the ranked ones, newest first.
+const KANBAN_ORDER: &str = "pt.rank IS NULL, pt.rank, pt.created_at DESC";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskMove {
+    pub task: TaskView,
+    // The tasks now directly above and below it in the column
+    pub previous: Option<TaskView>,
+    pub next: Option<TaskView>,
+    // Set when the column had to be renumbered; reload it instead of patching
+    pub rebalanced: bool,
+    pub warnings: Vec<String>,
+    pub next_occurrence: Option<TaskView>,
+}
+
+fn column_rank(db: &Connection, status_key: &str, first: bool) -> Result<Option<String>, String> {
+    let order = if first { "ASC" } else { "DESC" };
+    db.query_row(
+        &format!("SELECT rank FROM project_tasks WHERE LOWER(TRIM(status, '\"')) = ?1 AND rank IS NOT NULL ORDER BY rank {} LIMIT 1", order),
+        [status_key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))
+}
+
+// Rank for a task placed at the top of a column; None leaves it unranked when
+// the column holds a malformed rank, and the next move renumbers the column.
+fn top_of_column(db: &Connection, status_key: &str) -> Result<Option<String>, String> {
+    let first = column_rank(db, status_key, true)?;
+    Ok(rank::between(None, first.as_deref()).ok())
+}
+
+// The ranked neighbour above (`above`) or below a rank in a column.
+fn column_neighbour(db: &Connection, status_key: &str, rank: &str, exclude_id: &str, above: bool) -> Result<Option<(String, String)>, String> {
+    let (compare, order) = if above { ("<", "DESC") } else { (">", "ASC") };
+    db.query_row(
+        &format!(
+            "SELECT id, rank FROM project_tasks WHERE LOWER(TRIM(status, '\"')) = ?1 AND rank {} ?2 AND id != ?3 ORDER BY rank {} LIMIT 1",
+            compare, order
+        ),
+        rusqlite::params![status_key, rank, exclude_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))
+}
+
+// Give every task in a column (but `exclude_id`) a fresh, evenly spaced rank in its current order.
+fn rebalance_column(db: &Connection, status_key: &str, exclude_id: &str) -> Result<(), String> {
+    let mut stmt = db.prepare(&format!(
+        "SELECT pt.id FROM project_tasks pt WHERE LOWER(TRIM(pt.status, '\"')) = ?1 AND pt.id != ?2 ORDER BY {}",
+        KANBAN_ORDER
+    ))
+    .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([status_key, exclude_id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    for (id, rank) in ids.iter().zip(rank::spread(ids.len())) {
+        db.execute("UPDATE project_tasks SET rank = ?1 WHERE id = ?2", [rank, id.clone()])
+            .map_err(|e| format!("SQL update error: {}", e))?;
+    }
+    Ok(())
+}
+
+// Ranks of the tasks a moved task goes between, renumbering the column first
+// when it has unranked tasks or clashing ranks.
+fn move_bounds(
+    db: &Connection,
+    task_id: &str,
+    status_key: &str,
+    after_id: Option<&str>,
+    before_id: Option<&str>,
+) -> Result<(Option<String>, Option<String>, bool), String> {
+    let neighbour = |id: &str| -> Result<Option<String>, String> {
+        let row: Option<(String, Option<String>)> = db.query_row(
+            "SELECT LOWER(TRIM(status, '\"')), rank FROM project_tasks WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))?;
+        match row {
+            None => Err(format!("Task with ID '{}' not found", id)),
+            Some((status, _)) if status != status_key => Err(format!("Task '{}' is not in the {} column", id, status_key)),
+            Some((_, rank)) => Ok(rank),
+        }
+    };
+
+    for attempt in 0..2 {
+        let lower = after_id.map(neighbour).transpose()?.flatten();
+        let upper = before_id.map(neighbour).transpose()?.flatten();
+        let unranked = (after_id.is_some() && lower.is_none()) || (before_id.is_some() && upper.is_none());
+
+        let (lower, upper) = match (after_id, before_id) {
+            (Some(_), Some(_)) => (lower, upper),
+            (Some(_), None) => {
+                let next = match &lower {
+                    Some(rank) => column_neighbour(db, status_key, rank, task_id, false)?.map(|(_, rank)| rank),
+                    None => None,
+                };
+                (lower, next)
+            }
+            (None, Some(_)) => {
+                let previous = match &upper {
+                    Some(rank) => column_neighbour(db, status_key, rank, task_id, true)?.map(|(_, rank)| rank),
+                    None => None,
+                };
+                (previous, upper)
+            }
+            // no neighbours given: the end of the column
+            (None, None) => (column_rank(db, status_key, false)?, None),
+        };
+
+        let valid = lower.as_deref().is_none_or(rank::is_valid) && upper.as_deref().is_none_or(rank::is_valid);
+        let ordered = match (&lower, &upper) {
+            (Some(lower), Some(upper)) => lower < upper,
+            _ => true,
+        };
+        // Moving to the bottom needs the unranked tasks ranked too, or the task would sort above them.
+        let unranked_below = after_id.is_none() && before_id.is_none() && db.query_row(
+            "SELECT EXISTS (SELECT 1 FROM project_tasks WHERE LOWER(TRIM(status, '\"')) = ?1 AND rank IS NULL AND id != ?2)",
+            [status_key, task_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("SQL query error: {}", e))?;
+
+        let needs_rebalance = unranked || unranked_below || !valid || (!ordered && lower == upper);
+        if !needs_rebalance {
+            if !ordered {
+                return Err(format!(
+                    "Task '{}' is not above task '{}'",
+                    after_id.unwrap_or_default(),
+                    before_id.unwrap_or_default()
+                ));
+            }
+            return Ok((lower, upper, attempt > 0));
+        }
+        if attempt > 0 {
+            break;
+        }
+        rebalance_column(db, status_key, task_id)?;
+    }
+
+    Err("Could not find a position for the task".to_string())
+}
+
+// Move a task to a position in a status column: below `after_id`, above
+// `before_id`, or to the bottom when neither is given.
+pub fn move_task(
+    db: &Connection,
+    task_id: String,
+    status: String,
+    after_id: Option<String>,
+    before_id: Option<String>,
+) -> Result<TaskMove, String> {
+    if after_id.as_deref() == Some(task_id.as_str()) || before_id.as_deref() == Some(task_id.as_str()) {
+        return Err("A task can't be placed next to itself".to_string());
+    }
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+    let existing = repository::get_task(&tx, &task_id)?
+        .ok_or_else(|| format!("Task with ID '{}' not found", task_id))?;
+    let (status, workflow_state) = resolve_task_state(&tx, &existing.workstream_id, &status, existing.workflow_state.as_deref())?;
+    let status_key = status.key();
+    let warnings = check_column_policies(&tx, &existing, status_key)?;
+
+    let (lower, upper, rebalanced) = move_bounds(&tx, &task_id, status_key, after_id.as_deref(), before_id.as_deref())?;
+    let rank = rank::between(lower.as_deref(), upper.as_deref())?;
+
+    tx.execute(
+        "UPDATE project_tasks SET status = ?1, rank = ?2, workflow_state = ?3, updated_at = ?4 WHERE id = ?5",
+        rusqlite::params![status_key, rank, workflow_state, get_current_timestamp().to_rfc3339(), task_id]
+    )
+    .map_err(|e| format!("SQL update error: {}", e))?;
+    record_status_change(&tx, &task_id, Some(&existing.status), &status, get_current_timestamp())?;
+
+    let neighbour = |above: bool| -> Result<Option<TaskView>, String> {
+        match column_neighbour(&tx, status_key, &rank, &task_id, above)? {
+            Some((id, _)) => repository::get_task_view(&tx, &id),
+            None => Ok(None),
+        }
+    };
+    let previous = neighbour(true)?;
+    let next = neighbour(false)?;
+    let task = repository::get_task_view(&tx, &task_id)?
+        .ok_or_else(|| format!("Task with ID '{}' not found", task_id))?;
+    let next_occurrence = if status_key == "done" && existing.status.key() != "done" {
+        next_occurrence(&tx, &existing)?
+    } else {
+        None
+    };
+
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(TaskMove { task, previous, next, rebalanced, warnings, next_occurrence })
+}
+
+// Kanban column policies: WIP limits and allowed moves between columns, for
+// the whole board or for one workstream.
+fn ensure_workstream(db: &Connection, workstream_id: Option<&str>) -> Result<(), String> {
+    match workstream_id {
+        Some(id) if repository::get_workstream(db, id)?.is_none() => Err(format!("Workstream with ID '{}' not found", id)),
+        _ => Ok(()),
+    }
+}
+
+// Set the WIP limit of a column, replacing the one already set for the same scope.
+pub fn set_wip_limit(
+    db: &Connection,
+    workstream_id: Option<String>,
+    status: String,
+    max_tasks: i64,
+    enforcement: Option<String>,
+) -> Result<database::WipLimit, String> {
+    let status: database::TaskStatus = status.parse()?;
+    let enforcement: database::PolicyEnforcement = enforcement.as_deref().unwrap_or("warn").parse()?;
+    if max_tasks < 0 {
+        return Err("A WIP limit can't be negative".to_string());
+    }
+    ensure_workstream(db, workstream_id.as_deref())?;
+
+    let now = get_current_timestamp().to_rfc3339();
+    let existing: Option<String> = db.query_row(
+        "SELECT id FROM wip_limits WHERE workstream_id IS ?1 AND status = ?2 ORDER BY updated_at DESC LIMIT 1",
+        rusqlite::params![workstream_id, status.key()],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))?;
+
+    let id = match existing {
+        Some(id) => {
+            db.execute(
+                "UPDATE wip_limits SET max_tasks = ?1, enforcement = ?2, updated_at = ?3 WHERE id = ?4",
+                rusqlite::params![max_tasks, enforcement.key(), now, id]
+            ).map_err(|e| format!("SQL update error: {}", e))?;
+            id
+        }
+        None => {
+            let id = generate_id();
+            db.execute(
+                "INSERT INTO wip_limits (id, workstream_id, status, max_tasks, enforcement, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                rusqlite::params![id, workstream_id, status.key(), max_tasks, enforcement.key(), now]
+            ).map_err(|e| format!("SQL insert error: {}", e))?;
+            id
+        }
+    };
+
+    repository::query_one(db, "SELECT * FROM wip_limits WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("WIP limit with ID '{}' not found", id))
+}
+
+pub fn delete_wip_limit(db: &Connection, id: String) -> Result<String, String> {
+    let changes = db.execute("DELETE FROM wip_limits WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    if changes == 0 {
+        return Err(format!("WIP limit with ID '{}' not found", id));
+    }
+    Ok(format!("Deleted WIP limit {}", id))
+}
+
+// Board-wide limits plus those of one workstream, or every limit when no workstream is given.
+pub fn get_wip_limits(db: &Connection, workstream_id: Option<String>) -> Result<Vec<database::WipLimit>, String> {
+    match workstream_id {
+        Some(id) => repository::query_all(db, "SELECT * FROM wip_limits WHERE workstream_id IS NULL OR workstream_id = ?1 ORDER BY workstream_id IS NOT NULL, status", [id]),
+        None => repository::query_all(db, "SELECT * FROM wip_limits ORDER BY workstream_id IS NOT NULL, workstream_id, status", []),
+    }
+}
+
+// Allow or forbid moving tasks from one column to another, replacing the rule already set for the same scope.
+pub fn set_status_transition(
+    db: &Connection,
+    workstream_id: Option<String>,
+    from_status: String,
+    to_status: String,
+    allowed: bool,
+    enforcement: Option<String>,
+) -> Result<database::StatusTransition, String> {
+    let from_status: database::TaskStatus = from_status.parse()?;
+    let to_status: database::TaskStatus = to_status.parse()?;
+    let enforcement: database::PolicyEnforcement = enforcement.as_deref().unwrap_or("block").parse()?;
+    if from_status.key() == to_status.key() {
+        return Err("A transition needs two different statuses".to_string());
+    }
+    ensure_workstream(db, workstream_id.as_deref())?;
+
+    let now = get_current_timestamp().to_rfc3339();
+    let existing: Option<String> = db.query_row(
+        "SELECT id FROM status_transitions WHERE workstream_id IS ?1 AND from_status = ?2 AND to_status = ?3 ORDER BY updated_at DESC LIMIT 1",
+        rusqlite::params![workstream_id, from_status.key(), to_status.key()],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))?;
+
+    let id = match existing {
+        Some(id) => {
+            db.execute(
+                "UPDATE status_transitions SET allowed = ?1, enforcement = ?2, updated_at = ?3 WHERE id = ?4",
+                rusqlite::params![allowed, enforcement.key(), now, id]
+            ).map_err(|e| format!("SQL update error: {}", e))?;
+            id
+        }
+        None => {
+            let id = generate_id();
+            db.execute(
+                "INSERT INTO status_transitions (id, workstream_id, from_status, to_status, allowed, enforcement, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                rusqlite::params![id, workstream_id, from_status.key(), to_status.key(), allowed, enforcement.key(), now]
+            ).map_err(|e| format!("SQL insert error: {}", e))?;
+            id
+        }
+    };
+
+    repository::query_one(db, "SELECT * FROM status_transitions WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Transition rule with ID '{}' not found", id))
+}
+
+pub fn delete_status_transition(db: &Connection, id: String) -> Result<String, String> {
+    let changes = db.execute("DELETE FROM status_transitions WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    if changes == 0 {
+        return Err(format!("Transition rule with ID '{}' not found", id));
+    }
+    Ok(format!("Deleted transition rule {}", id))
+}
+
+pub fn get_status_transitions(db: &Connection, workstream_id: Option<String>) -> Result<Vec<database::StatusTransition>, String> {
+    match workstream_id {
+        Some(id) => repository::query_all(db, "SELECT * FROM status_transitions WHERE workstream_id IS NULL OR workstream_id = ?1 ORDER BY workstream_id IS NOT NULL, from_status, to_status", [id]),
+        None => repository::query_all(db, "SELECT * FROM status_transitions ORDER BY workstream_id IS NOT NULL, workstream_id, from_status, to_status", []),
+    }
+}
+
+// The limit that applies to a column, the workstream's own before the board's.
+fn wip_limit_for(db: &Connection, workstream_id: Option<&str>, status_key: &str) -> Result<Option<database::WipLimit>, String> {
+    repository::query_one(
+        db,
+        "SELECT * FROM wip_limits WHERE workstream_id IS ?1 AND status = ?2 ORDER BY updated_at DESC LIMIT 1",
+        rusqlite::params![workstream_id, status_key],
+    )
+}
+
+fn column_count(db: &Connection, workstream_id: Option<&str>, status_key: &str) -> Result<i64, String> {
+    db.query_row(
+        "SELECT COUNT(*) FROM project_tasks WHERE LOWER(TRIM(status, '\"')) = ?1 AND (?2 IS NULL OR workstream_id = ?2) AND archived_at IS NULL",
+        rusqlite::params![status_key, workstream_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("SQL query error: {}", e))
+}
+
+// Check moving a task into another column against the transition rules and
+// the WIP limits of its workstream and of the board. Blocking violations fail
+// with their messages; the others are returned as warnings.
+fn check_column_policies(db: &Connection, task: &ProjectTask, to_status: &str) -> Result<Vec<String>, String> {
+    let from_status = task.status.key();
+    if from_status == to_status {
+        return Ok(Vec::new());
+    }
+
+    let mut blocking = Vec::new();
+    let mut warnings = Vec::new();
+    let mut report = |enforcement: database::PolicyEnforcement, message: String| match enforcement {
+        database::PolicyEnforcement::Block => blocking.push(message),
+        database::PolicyEnforcement::Warn => warnings.push(message),
+    };
+
+    let rule: Option<database::StatusTransition> = repository::query_one(
+        db,
+        "SELECT * FROM status_transitions WHERE from_status = ?1 AND to_status = ?2 AND (workstream_id IS NULL OR workstream_id = ?3)
+         ORDER BY workstream_id IS NULL, updated_at DESC LIMIT 1",
+        [from_status, to_status, task.workstream_id.as_str()],
+    )?;
+    if let Some(rule) = rule.filter(|rule| !rule.allowed) {
+        report(rule.enforcement, format!("Moving tasks from {} to {} is not allowed", from_status, to_status));
+    }
+
+    for workstream_id in [Some(task.workstream_id.as_str()), None] {
+        if let Some(limit) = wip_limit_for(db, workstream_id, to_status)? {
+            let count = column_count(db, workstream_id, to_status)?;
+            if count + 1 > limit.max_tasks {
+                let scope = if workstream_id.is_some() { "this workstream" } else { "the board" };
+                report(limit.enforcement, format!(
+                    "The {} column of {} would hold {} tasks, over its WIP limit of {}",
+                    to_status, scope, count + 1, limit.max_tasks
+                ));
+            }
+        }
+    }
+
+    if to_status == "done" {
+        let policy: Option<database::CompletionPolicy> = repository::query_one(
+            db,
+            "SELECT * FROM completion_policies WHERE workstream_id IS NULL OR workstream_id = ?1 ORDER BY workstream_id IS NULL, updated_at DESC LIMIT 1",
+            [task.workstream_id.as_str()],
+        )?;
+        if let Some(policy) = policy {
+            if policy.require_subtasks_done {
+                let open: i64 = db.query_row(
+                    &format!("{} SELECT COUNT(*) FROM project_tasks WHERE id IN (SELECT id FROM descendants) AND LOWER(TRIM(status, '\"')) != 'done'", DESCENDANTS),
+                    [&task.id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("SQL query error: {}", e))?;
+                if open > 0 {
+                    report(policy.enforcement, format!("The task still has {} open subtask(s)", open));
+                }
+            }
+            if policy.require_checklist_done {
+                let open: i64 = db.query_row(
+                    "SELECT COUNT(*) FROM task_checklist_items WHERE task_id = ?1 AND NOT done",
+                    [&task.id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("SQL query error: {}", e))?;
+                if open > 0 {
+                    report(policy.enforcement, format!("The task still has {} unticked checklist item(s)", open));
+                }
+            }
+        }
+    }
+
+    if blocking.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(blocking.join(". "))
+    }
+}
+
+// Workflows: named task states for a workstream or for all workstreams of a
+// persona. A workstream follows its own workflow, else its persona's, else the
+// default one made of the five built-in statuses. Tasks on a custom workflow
+// keep their state's key in workflow_state and its category's status in status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStateInput {
+    pub key: Option<String>, // derived from the name when missing
+    pub name: String,
+    pub category: String,
+}
+
+pub fn default_workflow() -> database::Workflow {
+    use database::TaskStatus::*;
+    let states = [(Backlog, "Backlog"), (ToDo, "To Do"), (InProgress, "In Progress"), (Review, "Review"), (Done, "Done")]
+        .into_iter()
+        .enumerate()
+        .map(|(position, (status, name))| database::WorkflowState {
+            id: format!("default:{}", status.key()),
+            workflow_id: "default".to_string(),
+            key: status.key().to_string(),
+            name: name.to_string(),
+            category: status.category(),
+            position: position as i64,
+        })
+        .collect();
+
+    database::Workflow {
+        id: "default".to_string(),
+        name: "Default".to_string(),
+        persona_id: None,
+        workstream_id: None,
+        states,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    }
+}
+
+fn load_workflow(db: &Connection, id: &str) -> Result<Option<database::Workflow>, String> {
+    let workflow: Option<database::Workflow> = repository::query_one(db, "SELECT * FROM workflows WHERE id = ?1", [id])?;
+    match workflow {
+        Some(mut workflow) => {
+            workflow.states = repository::query_all(db, "SELECT * FROM workflow_states WHERE workflow_id = ?1 ORDER BY position, name", [id])?;
+            Ok(Some(workflow))
+        }
+        None => Ok(None),
+    }
+}
+
+// The custom workflow a workstream's tasks follow, if it has one.
+fn custom_workflow_for(db: &Connection, workstream_id: &str) -> Result<Option<database::Workflow>, String> {
+    let id: Option<String> = db.query_row(
+        "SELECT wf.id FROM workstreams w
+         JOIN workflows wf ON wf.workstream_id = w.id OR (wf.workstream_id IS NULL AND wf.persona_id = w.persona_id)
+         WHERE w.id = ?1 ORDER BY wf.workstream_id IS NULL, wf.updated_at DESC LIMIT 1",
+        [workstream_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))?;
+
+    match id {
+        Some(id) => load_workflow(db, &id),
+        None => Ok(None),
+    }
+}
+
+// "Waiting on parts" -> "waiting_on_parts"
+fn state_key(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+// Check the states of a workflow and number them in the given order.
+fn build_states(workflow_id: &str, states: Vec<WorkflowStateInput>) -> Result<Vec<database::WorkflowState>, String> {
+    let mut built: Vec<database::WorkflowState> = Vec::new();
+    for (position, state) in states.into_iter().enumerate() {
+        let name = state.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Workflow states need a name".to_string());
+        }
+        let key = state_key(state.key.as_deref().unwrap_or(&name));
+        if key.is_empty() {
+            return Err(format!("State '{}' needs a key made of letters or digits", name));
+        }
+        if built.iter().any(|other| other.key == key) {
+            return Err(format!("Two states use the key '{}'", key));
+        }
+        built.push(database::WorkflowState {
+            id: generate_id(),
+            workflow_id: workflow_id.to_string(),
+            key,
+            name,
+            category: state.category.parse()?,
+            position: position as i64,
+        });
+    }
+
+    // Tasks are mapped between workflows by category, so each one needs a state.
+    for category in [database::StateCategory::NotStarted, database::StateCategory::Active, database::StateCategory::Done] {
+        if !built.iter().any(|state| state.category == category) {
+            return Err(format!("A workflow needs at least one {} state", category.key()));
+        }
+    }
+    Ok(built)
+}
+
+fn insert_workflow_state(db: &Connection, state: &database::WorkflowState) -> Result<(), String> {
+    db.execute(
+        "INSERT INTO workflow_states (id, workflow_id, key, name, category, position) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![state.id, state.workflow_id, state.key, state.name, state.category.key(), state.position]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    Ok(())
+}
+
+// The workstreams a workflow with this scope applies to.
+fn scope_workstreams(db: &Connection, persona_id: Option<&str>, workstream_id: Option<&str>) -> Result<Vec<String>, String> {
+    let mut stmt = db.prepare("SELECT id FROM workstreams WHERE id = ?1 OR (?1 IS NULL AND persona_id = ?2)")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map(rusqlite::params![workstream_id, persona_id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e));
+    ids
+}
+
+// Point the tasks of some workstreams at states of the workflow they follow
+// now. A task keeps its state while the workflow still has it, otherwise it
+// gets the first state of its status's category. Returns the tasks changed.
+fn remap_task_states(db: &Connection, workstream_ids: &[String]) -> Result<usize, String> {
+    let mut changed = 0;
+    for workstream_id in workstream_ids {
+        let workflow = custom_workflow_for(db, workstream_id)?;
+        let mut stmt = db.prepare("SELECT id, status, workflow_state FROM project_tasks WHERE workstream_id = ?1")
+            .map_err(|e| format!("SQL prepare error: {}", e))?;
+        let tasks = stmt.query_map([workstream_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, database::TaskStatus>(1)?, row.get::<_, Option<String>>(2)?))
+        })
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+        for (id, stored, current) in tasks {
+            let (state, status) = match &workflow {
+                None => (None, stored.clone()),
+                Some(workflow) => match workflow.states.iter().find(|state| Some(&state.key) == current.as_ref()) {
+                    // a state whose category changed takes its tasks along
+                    Some(state) if state.category != stored.category() => (Some(state.key.clone()), state.category.status()),
+                    Some(state) => (Some(state.key.clone()), stored.clone()),
+                    None => (
+                        workflow.states.iter().find(|state| state.category == stored.category()).map(|state| state.key.clone()),
+                        stored.clone(),
+                    ),
+                },
+            };
+            if state != current || status.key() != stored.key() {
+                db.execute(
+                    "UPDATE project_tasks SET workflow_state = ?1, status = ?2 WHERE id = ?3",
+                    rusqlite::params![state, status.key(), id]
+                ).map_err(|e| format!("SQL update error: {}", e))?;
+                record_status_change(db, &id, Some(&stored), &status, get_current_timestamp())?;
+                changed += 1;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+// The status and workflow state of a task set to `input`: a state key or name
+// of its workflow, or a built-in status, which keeps the task's current state
+// when that is in the same category and picks the category's first state otherwise.
+fn resolve_task_state(
+    db: &Connection,
+    workstream_id: &str,
+    input: &str,
+    current: Option<&str>,
+) -> Result<(database::TaskStatus, Option<String>), String> {
+    let Some(workflow) = custom_workflow_for(db, workstream_id)? else {
+        return Ok((input.parse()?, None));
+    };
+
+    let key = state_key(input);
+    if let Some(state) = workflow.states.iter().find(|state| state.key == key || state.name.eq_ignore_ascii_case(input.trim())) {
+        return Ok((state.category.status(), Some(state.key.clone())));
+    }
+
+    match input.parse::<database::TaskStatus>() {
+        Ok(status) => {
+            let category = status.category();
+            let state = workflow.states.iter()
+                .find(|state| state.category == category && Some(state.key.as_str()) == current)
+                .or_else(|| workflow.states.iter().find(|state| state.category == category));
+            Ok((status, state.map(|state| state.key.clone())))
+        }
+        Err(_) => {
+            let keys: Vec<&str> = workflow.states.iter().map(|state| state.key.as_str()).collect();
+            Err(format!("Invalid state: {}. States of the '{}' workflow: {}", input, workflow.name, keys.join(", ")))
+        }
+    }
+}
+
+// A workflow for a persona or a workstream; tasks already there are mapped onto its states.
+pub fn create_workflow(
+    db: &Connection,
+    name: String,
+    persona_id: Option<String>,
+    workstream_id: Option<String>,
+    states: Vec<WorkflowStateInput>,
+) -> Result<database::Workflow, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("A workflow needs a name".to_string());
+    }
+    match (&persona_id, &workstream_id) {
+        (Some(persona_id), None) => {
+            if repository::get_persona(db, persona_id)?.is_none() {
+                return Err(format!("Persona with ID '{}' not found", persona_id));
+            }
+        }
+        (None, Some(workstream_id)) => ensure_workstream(db, Some(workstream_id))?,
+        _ => return Err("A workflow belongs to either a persona or a workstream".to_string()),
+    }
+
+    let taken: Option<String> = db.query_row(
+        "SELECT name FROM workflows WHERE persona_id IS ?1 AND workstream_id IS ?2",
+        rusqlite::params![persona_id, workstream_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))?;
+    if let Some(taken) = taken {
+        let scope = if workstream_id.is_some() { "workstream" } else { "persona" };
+        return Err(format!("This {} already uses the '{}' workflow; update that one instead", scope, taken));
+    }
+
+    let id = generate_id();
+    let states = build_states(&id, states)?;
+    let now = get_current_timestamp().to_rfc3339();
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(
+        "INSERT INTO workflows (id, name, persona_id, workstream_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+        rusqlite::params![id, name, persona_id, workstream_id, now]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    for state in &states {
+        insert_workflow_state(&tx, state)?;
+    }
+    remap_task_states(&tx, &scope_workstreams(&tx, persona_id.as_deref(), workstream_id.as_deref())?)?;
+    let workflow = load_workflow(&tx, &id)?
+        .ok_or_else(|| format!("Workflow with ID '{}' not found", id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(workflow)
+}
+
+// Rename a workflow or replace its states. States are matched by key, so
+// renaming or reordering keeps their tasks; tasks of removed states move to
+// the first state of the same category.
+pub fn update_workflow(
+    db: &Connection,
+    id: String,
+    name: Option<String>,
+    states: Option<Vec<WorkflowStateInput>>,
+) -> Result<database::Workflow, String> {
+    let existing = load_workflow(db, &id)?
+        .ok_or_else(|| format!("Workflow with ID '{}' not found", id))?;
+    let name = name.map(|name| name.trim().to_string());
+    if name.as_deref() == Some("") {
+        return Err("A workflow needs a name".to_string());
+    }
+    let states = states.map(|states| build_states(&id, states)).transpose()?;
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(
+        "UPDATE workflows SET name = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![name.unwrap_or(existing.name), get_current_timestamp().to_rfc3339(), id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    if let Some(states) = states {
+        for state in &states {
+            match existing.states.iter().find(|old| old.key == state.key) {
+                Some(old) => {
+                    tx.execute(
+                        "UPDATE workflow_states SET name = ?1, category = ?2, position = ?3 WHERE id = ?4",
+                        rusqlite::params![state.name, state.category.key(), state.position, old.id]
+                    ).map_err(|e| format!("SQL update error: {}", e))?;
+                }
+                None => insert_workflow_state(&tx, state)?,
+            }
+        }
+        for old in existing.states.iter().filter(|old| !states.iter().any(|state| state.key == old.key)) {
+            tx.execute("DELETE FROM workflow_states WHERE id = ?1", [&old.id])
+                .map_err(|e| format!("SQL delete error: {}", e))?;
+        }
+        remap_task_states(&tx, &scope_workstreams(&tx, existing.persona_id.as_deref(), existing.workstream_id.as_deref())?)?;
+    }
+
+    let workflow = load_workflow(&tx, &id)?
+        .ok_or_else(|| format!("Workflow with ID '{}' not found", id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(workflow)
+}
+
+// Tasks fall back to the persona's workflow or the default one, matched by category.
+pub fn delete_workflow(db: &Connection, id: String) -> Result<String, String> {
+    let existing = load_workflow(db, &id)?
+        .ok_or_else(|| format!("Workflow with ID '{}' not found", id))?;
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute("DELETE FROM workflow_states WHERE workflow_id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    tx.execute("DELETE FROM workflows WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    let moved = remap_task_states(&tx, &scope_workstreams(&tx, existing.persona_id.as_deref(), existing.workstream_id.as_deref())?)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(format!("Deleted workflow '{}' ({} tasks moved to other states)", existing.name, moved))
+}
+
+// The custom workflows, persona ones first.
+pub fn get_workflows(db: &Connection) -> Result<Vec<database::Workflow>, String> {
+    let mut workflows: Vec<database::Workflow> = repository::query_all(db, "SELECT * FROM workflows ORDER BY workstream_id IS NOT NULL, name", [])?;
+    for workflow in &mut workflows {
+        workflow.states = repository::query_all(db, "SELECT * FROM workflow_states WHERE workflow_id = ?1 ORDER BY position, name", [&workflow.id])?;
+    }
+    Ok(workflows)
+}
+
+pub fn get_workflow_for_workstream(db: &Connection, workstream_id: String) -> Result<database::Workflow, String> {
+    ensure_workstream(db, Some(&workstream_id))?;
+    Ok(custom_workflow_for(db, &workstream_id)?.unwrap_or_else(default_workflow))
+}
+
+// Subtasks and checklists. A subtask is a task of the same workstream with a
+// parent_id: it has its own status and estimate and shows up on the board like
+// any other task. Checklist items are plain tick boxes inside a task.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskRollup {
+    pub subtasks: i64, // at every level below the task
+    pub subtasks_done: i64,
+    pub checklist_items: i64,
+    pub checklist_done: i64,
+    // the task's own hours plus those of its subtasks
+    pub estimated_hours: f32,
+    pub actual_hours: f32,
+    pub progress: f32, // share of subtasks and checklist items done, 0 to 1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTree {
+    #[serde(flatten)]
+    pub task: TaskView,
+    pub checklist: Vec<database::ChecklistItem>,
+    pub subtasks: Vec<TaskTree>,
+    pub rollup: TaskRollup,
+}
+
+// Every task below ?1, bounded in depth so a parent cycle written by sync can't loop forever.
+const DESCENDANTS: &str = "WITH RECURSIVE descendants(id, depth) AS (
+        SELECT id, 1 FROM project_tasks WHERE parent_id = ?1
+        UNION ALL
+        SELECT pt.id, d.depth + 1 FROM project_tasks pt JOIN descendants d ON pt.parent_id = d.id WHERE d.depth < 32
+    )";
+
+const SUBTASK_ORDER: &str = "pt.subtask_rank IS NULL, pt.subtask_rank, pt.created_at";
+
+fn get_checklist(db: &Connection, task_id: &str) -> Result<Vec<database::ChecklistItem>, String> {
+    repository::query_all(db, "SELECT * FROM task_checklist_items WHERE task_id = ?1 ORDER BY rank IS NULL, rank, created_at", [task_id])
+}
+
+fn subtask_ranks(db: &Connection, parent_id: &str, exclude_id: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut stmt = db.prepare(&format!("SELECT pt.id, pt.subtask_rank FROM project_tasks pt WHERE pt.parent_id = ?1 AND pt.id != ?2 ORDER BY {}", SUBTASK_ORDER))
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ranks = stmt.query_map([parent_id, exclude_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e));
+    ranks
+}
+
+// `sql` takes the id as ?1 and the rank as ?2.
+fn write_ranks(db: &Connection, sql: &str, ranks: Vec<(String, String)>) -> Result<(), String> {
+    for (id, rank) in ranks {
+        db.execute(sql, [id, rank]).map_err(|e| format!("SQL update error: {}", e))?;
+    }
+    Ok(())
+}
+
+fn task_tree(db: &Connection, task: TaskView, depth: usize) -> Result<TaskTree, String> {
+    let children: Vec<TaskView> = if depth < 32 {
+        repository::query_all(db, &format!("{} WHERE pt.parent_id = ?1 ORDER BY {}", repository::TASK_VIEW_SELECT, SUBTASK_ORDER), [&task.task.id])?
+    } else {
+        Vec::new()
+    };
+    let subtasks = children.into_iter()
+        .map(|child| task_tree(db, child, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    let checklist = get_checklist(db, &task.task.id)?;
+
+    let mut rollup = TaskRollup {
+        checklist_items: checklist.len() as i64,
+        checklist_done: checklist.iter().filter(|item| item.done).count() as i64,
+        estimated_hours: task.task.estimated_hours.unwrap_or(0.0),
+        actual_hours: task.task.actual_hours.unwrap_or(0.0),
+        ..Default::default()
+    };
+    for subtask in &subtasks {
+        rollup.subtasks += 1 + subtask.rollup.subtasks;
+        rollup.subtasks_done += subtask.rollup.subtasks_done + i64::from(subtask.task.task.status.key() == "done");
+        rollup.estimated_hours += subtask.rollup.estimated_hours;
+        rollup.actual_hours += subtask.rollup.actual_hours;
+    }
+    let parts = rollup.subtasks + rollup.checklist_items;
+    rollup.progress = if parts == 0 {
+        if task.task.status.key() == "done" { 1.0 } else { 0.0 }
+    } else {
+        (rollup.subtasks_done + rollup.checklist_done) as f32 / parts as f32
+    };
+
+    Ok(TaskTree { task, checklist, subtasks, rollup })
+}
+
+// A task with its checklist and its subtasks, nested, each with its roll-up.
+pub fn get_task_tree(db: &Connection, task_id: String) -> Result<TaskTree, String> {
+    let task = repository::get_task_view(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    task_tree(db, task, 0)
+}
+
+// A new subtask at the end of the parent's list. The status defaults to todo
+// (the first not-started state on a custom workflow), the priority to the parent's.
+#[allow(clippy::too_many_arguments)]
+pub fn create_subtask(
+    db: &Connection,
+    parent_id: String,
+    title: String,
+    description: Option<String>,
+    status: Option<String>,
+    priority: Option<String>,
+    estimated_hours: Option<f32>,
+) -> Result<ProjectTask, String> {
+    let parent = repository::get_task(db, &parent_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", parent_id))?;
+    if estimated_hours.is_some_and(|hours| hours < 0.0) {
+        return Err("An estimate can't be negative".to_string());
+    }
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    let task = create_project_task(
+        &tx,
+        parent.workstream_id.clone(),
+        title,
+        description,
+        status.unwrap_or_else(|| "todo".to_string()),
+        priority.unwrap_or_else(|| parent.priority.key().to_string()),
+    )?;
+    let ranks = rank::place(&subtask_ranks(&tx, &parent_id, &task.id)?, &task.id, None, None)?;
+    write_ranks(&tx, "UPDATE project_tasks SET subtask_rank = ?2 WHERE id = ?1", ranks)?;
+    tx.execute(
+        "UPDATE project_tasks SET parent_id = ?1, estimated_hours = ?2 WHERE id = ?3",
+        rusqlite::params![parent_id, estimated_hours, task.id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    let task = repository::get_task(&tx, &task.id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task.id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(task)
+}
+
+// Reorder a subtask among its siblings or move it under another parent of the
+// same workstream: below `after_id`, above `before_id`, or last. A parent_id of
+// None turns it back into a top-level task.
+pub fn move_subtask(
+    db: &Connection,
+    task_id: String,
+    parent_id: Option<String>,
+    after_id: Option<String>,
+    before_id: Option<String>,
+) -> Result<ProjectTask, String> {
+    let task = repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    match &parent_id {
+        Some(parent_id) => {
+            let parent = repository::get_task(&tx, parent_id)?
+                .ok_or_else(|| format!("Project task with ID '{}' not found", parent_id))?;
+            if parent.workstream_id != task.workstream_id {
+                return Err("A subtask has to be in the same workstream as its parent".to_string());
+            }
+            let below_task: bool = tx.query_row(
+                &format!("{} SELECT ?2 = ?1 OR ?2 IN (SELECT id FROM descendants)", DESCENDANTS),
+                [&task_id, parent_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("SQL query error: {}", e))?;
+            if below_task {
+                return Err("A task can't become a subtask of itself or of one of its subtasks".to_string());
+            }
+
+            let siblings = subtask_ranks(&tx, parent_id, &task_id)?;
+            let ranks = rank::place(&siblings, &task_id, after_id.as_deref(), before_id.as_deref())
+                .map_err(|e| format!("Can't place the subtask: {}", e))?;
+            write_ranks(&tx, "UPDATE project_tasks SET subtask_rank = ?2 WHERE id = ?1", ranks)?;
+        }
+        None => {
+            if after_id.is_some() || before_id.is_some() {
+                return Err("Top-level tasks are ordered on the board; use move_task".to_string());
+            }
+            tx.execute("UPDATE project_tasks SET subtask_rank = NULL WHERE id = ?1", [&task_id])
+                .map_err(|e| format!("SQL update error: {}", e))?;
+        }
+    }
+    tx.execute(
+        "UPDATE project_tasks SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![parent_id, get_current_timestamp().to_rfc3339(), task_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    let task = repository::get_task(&tx, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(task)
+}
+
+pub fn set_task_estimate(db: &Connection, task_id: String, estimated_hours: Option<f32>) -> Result<ProjectTask, String> {
+    if estimated_hours.is_some_and(|hours| hours < 0.0) {
+        return Err("An estimate can't be negative".to_string());
+    }
+    let changes = db.execute(
+        "UPDATE project_tasks SET estimated_hours = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![estimated_hours, get_current_timestamp().to_rfc3339(), task_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    if changes == 0 {
+        return Err(format!("Project task with ID '{}' not found", task_id));
+    }
+    repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))
+}
+
+pub fn add_checklist_item(db: &Connection, task_id: String, text: String) -> Result<database::ChecklistItem, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("A checklist item needs some text".to_string());
+    }
+    if repository::get_task(db, &task_id)?.is_none() {
+        return Err(format!("Project task with ID '{}' not found", task_id));
+    }
+
+    let id = generate_id();
+    let items: Vec<(String, Option<String>)> = get_checklist(db, &task_id)?.into_iter().map(|item| (item.id, item.rank)).collect();
+    let ranks = rank::place(&items, &id, None, None)?;
+    let now = get_current_timestamp().to_rfc3339();
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(
+        "INSERT INTO task_checklist_items (id, task_id, text, done, rank, created_at, updated_at) VALUES (?1, ?2, ?3, 0, NULL, ?4, ?4)",
+        rusqlite::params![id, task_id, text, now]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    write_ranks(&tx, "UPDATE task_checklist_items SET rank = ?2 WHERE id = ?1", ranks)?;
+    let item = repository::query_one(&tx, "SELECT * FROM task_checklist_items WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Checklist item with ID '{}' not found", id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    Ok(item)
+}
+
+// Change the text of a checklist item or tick it off.
+pub fn update_checklist_item(db: &Connection, id: String, text: Option<String>, done: Option<bool>) -> Result<database::ChecklistItem, String> {
+    let existing: database::ChecklistItem = repository::query_one(db, "SELECT * FROM task_checklist_items WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Checklist item with ID '{}' not found", id))?;
+    let text = text.map(|text| text.trim().to_string()).unwrap_or(existing.text);
+    if text.is_empty() {
+        return Err("A checklist item needs some text".to_string());
+    }
+
+    db.execute(
+        "UPDATE task_checklist_items SET text = ?1, done = ?2, updated_at = ?3 WHERE id = ?4",
+        rusqlite::params![text, done.unwrap_or(existing.done), get_current_timestamp().to_rfc3339(), id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    repository::query_one(db, "SELECT * FROM task_checklist_items WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Checklist item with ID '{}' not found", id))
+}
+
+// Place a checklist item below `after_id`, above `before_id`, or last; returns the reordered checklist.
+pub fn move_checklist_item(
+    db: &Connection,
+    id: String,
+    after_id: Option<String>,
+    before_id: Option<String>,
+) -> Result<Vec<database::ChecklistItem>, String> {
+    let existing: database::ChecklistItem = repository::query_one(db, "SELECT * FROM task_checklist_items WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Checklist item with ID '{}' not found", id))?;
+    let items: Vec<(String, Option<String>)> = get_checklist(db, &existing.task_id)?
+        .into_iter()
+        .filter(|item| item.id != id)
+        .map(|item| (item.id, item.rank))
+        .collect();
+    let ranks = rank::place(&items, &id, after_id.as_deref(), before_id.as_deref())
+        .map_err(|e| format!("Can't place the checklist item: {}", e))?;
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    write_ranks(&tx, "UPDATE task_checklist_items SET rank = ?2 WHERE id = ?1", ranks)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    get_checklist(db, &existing.task_id)
+}
+
+pub fn delete_checklist_item(db: &Connection, id: String) -> Result<String, String> {
+    let changes = db.execute("DELETE FROM task_checklist_items WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    if changes == 0 {
+        return Err(format!("Checklist item with ID '{}' not found", id));
+    }
+    Ok(format!("Deleted checklist item {}", id))
+}
+
+// Require subtasks and/or checklist items to be finished before a task moves
+// to done, replacing the policy already set for the same scope.
+pub fn set_completion_policy(
+    db: &Connection,
+    workstream_id: Option<String>,
+    require_subtasks_done: bool,
+    require_checklist_done: bool,
+    enforcement: Option<String>,
+) -> Result<database::CompletionPolicy, String> {
+    let enforcement: database::PolicyEnforcement = enforcement.as_deref().unwrap_or("block").parse()?;
+    ensure_workstream(db, workstream_id.as_deref())?;
+
+    let now = get_current_timestamp().to_rfc3339();
+    let existing: Option<String> = db.query_row(
+        "SELECT id FROM completion_policies WHERE workstream_id IS ?1 ORDER BY updated_at DESC LIMIT 1",
+        [&workstream_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("SQL query error: {}", e))?;
+
+    let id = match existing {
+        Some(id) => {
+            db.execute(
+                "UPDATE completion_policies SET require_subtasks_done = ?1, require_checklist_done = ?2, enforcement = ?3, updated_at = ?4 WHERE id = ?5",
+                rusqlite::params![require_subtasks_done, require_checklist_done, enforcement.key(), now, id]
+            ).map_err(|e| format!("SQL update error: {}", e))?;
+            id
+        }
+        None => {
+            let id = generate_id();
+            db.execute(
+                "INSERT INTO completion_policies (id, workstream_id, require_subtasks_done, require_checklist_done, enforcement, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                rusqlite::params![id, workstream_id, require_subtasks_done, require_checklist_done, enforcement.key(), now]
+            ).map_err(|e| format!("SQL insert error: {}", e))?;
+            id
+        }
+    };
+
+    repository::query_one(db, "SELECT * FROM completion_policies WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Completion policy with ID '{}' not found", id))
+}
+
+pub fn delete_completion_policy(db: &Connection, id: String) -> Result<String, String> {
+    let changes = db.execute("DELETE FROM completion_policies WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    if changes == 0 {
+        return Err(format!("Completion policy with ID '{}' not found", id));
+    }
+    Ok(format!("Deleted completion policy {}", id))
+}
+
+pub fn get_completion_policies(db: &Connection, workstream_id: Option<String>) -> Result<Vec<database::CompletionPolicy>, String> {
+    match workstream_id {
+        Some(id) => repository::query_all(db, "SELECT * FROM completion_policies WHERE workstream_id IS NULL OR workstream_id = ?1 ORDER BY workstream_id IS NOT NULL", [id]),
+        None => repository::query_all(db, "SELECT * FROM completion_policies ORDER BY workstream_id IS NOT NULL, workstream_id", []),
+    }
+}
+
+// Due dates and recurring tasks. A due date is either a bare day or a local
+// time stored with its UTC offset, so its first ten characters are always the
+// local day the agenda and filters compare. Occurrences of a recurring task
+// share recurrence_series and count up in recurrence_index.
+fn parse_due_date(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if let Ok(instant) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(instant.with_timezone(&chrono::Local).to_rfc3339());
+    }
+    for format in ["%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(value, format) {
+            return recurrence::local_time(&chrono::Local, naive.date(), naive.time())
+                .map(|instant| instant.to_rfc3339())
+                .ok_or_else(|| format!("Invalid due date: {}", value));
+        }
+    }
+    filters::resolve_date(value, chrono::Local::now().date_naive()).map(|day| day.format("%Y-%m-%d").to_string())
+}
+
+// The local day of a stored due date, and its wall-clock time unless it is a bare day.
+fn due_parts(value: &str) -> Option<(chrono::NaiveDate, Option<chrono::NaiveTime>)> {
+    if let Ok(day) = chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d") {
+        return Some((day, None));
+    }
+    let local = repository::parse_timestamp(value)?.with_timezone(&chrono::Local);
+    Some((local.date_naive(), Some(local.time())))
+}
+
+fn raw_due_date(db: &Connection, task_id: &str) -> Result<Option<String>, String> {
+    db.query_row("SELECT due_date FROM project_tasks WHERE id = ?1", [task_id], |row| row.get::<_, Option<String>>(0))
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))
+}
+
+// `due_date` takes YYYY-MM-DD, a local YYYY-MM-DDTHH:MM, RFC 3339 or a relative day like +3d; None clears it.
+pub fn set_task_due_date(db: &Connection, task_id: String, due_date: Option<String>) -> Result<ProjectTask, String> {
+    let due_date = due_date.filter(|due| !due.trim().is_empty()).map(|due| parse_due_date(&due)).transpose()?;
+    let changes = db.execute(
+        "UPDATE project_tasks SET due_date = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![due_date, get_current_timestamp().to_rfc3339(), task_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    if changes == 0 {
+        return Err(format!("Project task with ID '{}' not found", task_id));
+    }
+    repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))
+}
+
+// Make a task repeat, or stop it repeating with None. Scheduled rules run from
+// the task's due date, so it needs one; rules counted from completion don't.
+pub fn set_task_recurrence(db: &Connection, task_id: String, rule: Option<String>, from_completion: bool) -> Result<ProjectTask, String> {
+    let task = repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+
+    let Some(rule) = rule.filter(|rule| !rule.trim().is_empty()) else {
+        db.execute(
+            "UPDATE project_tasks SET recurrence_rule = NULL, recurrence_from_completion = 0, recurrence_start = NULL, updated_at = ?1 WHERE id = ?2",
+            rusqlite::params![get_current_timestamp().to_rfc3339(), task_id]
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+        return repository::get_task(db, &task_id)?
+            .ok_or_else(|| format!("Project task with ID '{}' not found", task_id));
+    };
+    let rule = recurrence::parse(&rule)?;
+
+    let due = raw_due_date(db, &task_id)?.as_deref().and_then(due_parts);
+    let start = match due {
+        Some((day, _)) => day,
+        None if from_completion => chrono::Local::now().date_naive(),
+        None => return Err("Give the task a due date first: scheduled repeats count from it".to_string()),
+    };
+    let (series_id, index) = match &task.recurrence {
+        Some(recurrence) => (recurrence.series_id.clone(), recurrence.index),
+        None => (task.id.clone(), 1),
+    };
+
+    db.execute(
+        "UPDATE project_tasks SET recurrence_rule = ?1, recurrence_from_completion = ?2, recurrence_start = ?3, recurrence_series = ?4, recurrence_index = ?5, updated_at = ?6 WHERE id = ?7",
+        rusqlite::params![rule.to_string(), from_completion, start.format("%Y-%m-%d").to_string(), series_id, index, get_current_timestamp().to_rfc3339(), task_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))
+}
+
+// The first days a rule produces from `start` (default today), to check a rule before saving it.
+pub fn preview_recurrence(rule: String, start: Option<String>, limit: Option<usize>) -> Result<Vec<String>, String> {
+    let rule = recurrence::parse(&rule)?;
+    let today = chrono::Local::now().date_naive();
+    let start = match start {
+        Some(start) => filters::resolve_date(&start, today)?,
+        None => today,
+    };
+    Ok(rule.preview(start, limit.unwrap_or(5).clamp(1, 100))
+        .into_iter()
+        .map(|day| day.format("%Y-%m-%d").to_string())
+        .collect())
+}
+
+// Create the occurrence after a recurring task that has just been completed:
+// same title, tags, estimate, priority and unticked checklist, due on the
+// rule's next day at the same local time. Scheduled rules never land before
+// today, so finishing late doesn't leave the next occurrence overdue.
+fn next_occurrence(db: &Connection, task: &ProjectTask) -> Result<Option<TaskView>, String> {
+    let Some(series) = &task.recurrence else {
+        return Ok(None);
+    };
+    let rule = recurrence::parse(&series.rule)?;
+    if rule.count.is_some_and(|count| series.index >= count as i64) {
+        return Ok(None);
+    }
+    // Reopening and completing an occurrence again doesn't create a second one.
+    let exists: bool = db.query_row(
+        "SELECT EXISTS (SELECT 1 FROM project_tasks WHERE recurrence_series = ?1 AND recurrence_index > ?2)",
+        rusqlite::params![series.series_id, series.index],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("SQL query error: {}", e))?;
+    if exists {
+        return Ok(None);
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let due = raw_due_date(db, &task.id)?.as_deref().and_then(due_parts);
+    let next_day = if series.from_completion {
+        rule.next_after(today, today)
+    } else {
+        let current = due.map(|(day, _)| day).unwrap_or(today);
+        rule.next_after(series.start, current.max(today.pred_opt().unwrap_or(today)))
+    };
+    let Some(next_day) = next_day else {
+        return Ok(None);
+    };
+    let next_due = match due.and_then(|(_, time)| time) {
+        Some(time) => recurrence::local_time(&chrono::Local, next_day, time)
+            .map(|instant| instant.to_rfc3339())
+            .unwrap_or_else(|| next_day.format("%Y-%m-%d").to_string()),
+        None => next_day.format("%Y-%m-%d").to_string(),
+    };
+
+    let next = create_project_task(
+        db,
+        task.workstream_id.clone(),
+        task.title.clone(),
+        task.description.clone(),
+        "todo".to_string(),
+        task.priority.key().to_string(),
+    )?;
+    db.execute(
+        "UPDATE project_tasks SET due_date = ?1, estimated_hours = ?2, tags = ?3, recurrence_rule = ?4, recurrence_from_completion = ?5,
+         recurrence_start = ?6, recurrence_series = ?7, recurrence_index = ?8 WHERE id = ?9",
+        rusqlite::params![
+            next_due,
+            task.estimated_hours,
+            serde_json::to_string(&task.tags).map_err(|e| format!("Tags serialization error: {}", e))?,
+            series.rule,
+            series.from_completion,
+            series.start.format("%Y-%m-%d").to_string(),
+            series.series_id,
+            series.index + 1,
+            next.id
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    if let Some(parent_id) = &task.parent_id {
+        let ranks = rank::place(&subtask_ranks(db, parent_id, &next.id)?, &next.id, None, None)?;
+        write_ranks(db, "UPDATE project_tasks SET subtask_rank = ?2 WHERE id = ?1", ranks)?;
+        db.execute("UPDATE project_tasks SET parent_id = ?1 WHERE id = ?2", [parent_id, &next.id])
+            .map_err(|e| format!("SQL update error: {}", e))?;
+    }
+    let now = get_current_timestamp().to_rfc3339();
+    for item in get_checklist(db, &task.id)? {
+        db.execute(
+            "INSERT INTO task_checklist_items (id, task_id, text, done, rank, created_at, updated_at) VALUES (?1, ?2, ?3, 0, ?4, ?5, ?5)",
+            rusqlite::params![generate_id(), next.id, item.text, item.rank, now]
+        ).map_err(|e| format!("SQL insert error: {}", e))?;
+    }
+
+    repository::get_task_view(db, &next.id)
+}
+
+// Time tracking. Entries are either timers or logged by hand; one timer runs
+// at a time, and a task's actual_hours is the sum of its finished entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerChange {
+    pub timer: TimeEntryView,
+    pub stopped: Vec<TimeEntryView>, // timers the new one replaced
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeReportRow {
+    pub key: String,
+    pub label: String,
+    pub seconds: i64,
+    pub hours: f64,
+    pub entries: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeReport {
+    pub group_by: String,
+    pub from: String,
+    pub to: String,
+    pub total_seconds: i64,
+    pub total_hours: f64,
+    pub rows: Vec<TimeReportRow>,
+}
+
+fn hours(seconds: i64) -> f64 {
+    (seconds as f64 / 36.0).round() / 100.0
+}
+
+// An instant given as RFC 3339, a local YYYY-MM-DDTHH:MM, or a day (the start of that local day).
+fn parse_instant(value: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let value = value.trim();
+    if let Ok(instant) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(instant.with_timezone(&chrono::Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok());
+    let (day, time) = match naive {
+        Some(naive) => (naive.date(), naive.time()),
+        None => (filters::resolve_date(value, chrono::Local::now().date_naive())?, chrono::NaiveTime::MIN),
+    };
+    recurrence::local_time(&chrono::Local, day, time)
+        .map(|instant| instant.with_timezone(&chrono::Utc))
+        .ok_or_else(|| format!("Invalid time: {}", value))
+}
+
+// The UTC bounds of the local days from..=to.
+fn day_range(from: chrono::NaiveDate, to: chrono::NaiveDate) -> Result<(String, String), String> {
+    let start = |day: chrono::NaiveDate| {
+        recurrence::local_time(&chrono::Local, day, chrono::NaiveTime::MIN)
+            .map(|instant| instant.with_timezone(&chrono::Utc).to_rfc3339())
+            .ok_or_else(|| format!("Invalid day: {}", day))
+    };
+    Ok((start(from)?, start(to.succ_opt().unwrap_or(to))?))
+}
+
+fn get_time_entry(db: &Connection, id: &str) -> Result<TimeEntryView, String> {
+    repository::query_one(db, &format!("{} WHERE te.id = ?1", repository::TIME_ENTRY_VIEW_SELECT), [id])?
+        .ok_or_else(|| format!("Time entry with ID '{}' not found", id))
+}
+
+fn running_timers(db: &Connection) -> Result<Vec<TimeEntryView>, String> {
+    repository::query_all(db, &format!("{} WHERE te.ended_at IS NULL ORDER BY te.started_at DESC", repository::TIME_ENTRY_VIEW_SELECT), [])
+}
+
+fn roll_up_actual_hours(db: &Connection, task_id: &str) -> Result<(), String> {
+    db.execute(
+        "UPDATE project_tasks SET actual_hours = (
+            SELECT SUM(duration_seconds) / 3600.0 FROM time_entries WHERE task_id = ?1 AND ended_at IS NOT NULL
+         ), updated_at = ?2 WHERE id = ?1",
+        rusqlite::params![task_id, get_current_timestamp().to_rfc3339()]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+// End a timer at `at`; a paused timer ends where it was paused.
+fn finish_timer(db: &Connection, entry: &database::TimeEntry, at: chrono::DateTime<chrono::Utc>) -> Result<(), String> {
+    let at = at.max(entry.started_at);
+    let mut finished = entry.clone();
+    if let Some(paused_at) = entry.paused_at {
+        finished.paused_seconds += (at - paused_at).num_seconds().max(0);
+    }
+    finished.ended_at = Some(at);
+    finished.paused_at = None;
+    finished.duration_seconds = None;
+    db.execute(
+        "UPDATE time_entries SET ended_at = ?1, paused_at = NULL, paused_seconds = ?2, duration_seconds = ?3, updated_at = ?4 WHERE id = ?5",
+        rusqlite::params![
+            at.to_rfc3339(),
+            finished.paused_seconds,
+            finished.elapsed_seconds(at),
+            get_current_timestamp().to_rfc3339(),
+            entry.id
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    roll_up_actual_hours(db, &entry.task_id)
+}
+
+fn current_timer(db: &Connection) -> Result<TimeEntryView, String> {
+    running_timers(db)?.into_iter().next().ok_or_else(|| "No timer is running".to_string())
+}
+
+// Stop running timers, an orphaned one where it was last seen.
+fn stop_timers(db: &Connection, timers: Vec<TimeEntryView>, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<TimeEntryView>, String> {
+    let mut stopped = Vec::new();
+    for running in timers {
+        let at = if running.orphaned { running.entry.last_seen_at.unwrap_or(now) } else { now };
+        finish_timer(db, &running.entry, at)?;
+        stopped.push(get_time_entry(db, &running.entry.id)?);
+    }
+    Ok(stopped)
+}
+
+// Start timing a task. A timer already running on another task is stopped
+// first (an orphaned one where it was last seen); starting the task that is
+// already timed resumes it if paused.
+pub fn start_timer(db: &Connection, task_id: String, note: Option<String>) -> Result<TimerChange, String> {
+    repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    let db = &tx;
+    let now = get_current_timestamp();
+
+    let mut running = running_timers(db)?;
+    let current = running
+        .iter()
+        .position(|timer| timer.entry.task_id == task_id && !timer.orphaned)
+        .map(|index| running.remove(index));
+    let stopped = stop_timers(db, running, now)?;
+
+    let id = match current {
+        Some(running) => {
+            if running.entry.paused_at.is_some() {
+                resume(db, &running.entry, now)?;
+            }
+            running.entry.id
+        }
+        None => {
+            let id = generate_id();
+            db.execute(
+                "INSERT INTO time_entries (id, task_id, source, started_at, paused_seconds, last_seen_at, note, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, 0, ?4, ?5, ?4, ?4)",
+                rusqlite::params![
+                    id,
+                    task_id,
+                    database::TimeEntrySource::Timer.key(),
+                    now.to_rfc3339(),
+                    note.filter(|note| !note.trim().is_empty())
+                ]
+            ).map_err(|e| format!("SQL insert error: {}", e))?;
+            id
+        }
+    };
+    let timer = get_time_entry(db, &id)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(TimerChange { timer, stopped })
+}
+
+fn resume(db: &Connection, entry: &database::TimeEntry, now: chrono::DateTime<chrono::Utc>) -> Result<(), String> {
+    let paused = entry.paused_at.map(|paused_at| (now - paused_at).num_seconds().max(0)).unwrap_or(0);
+    db.execute(
+        "UPDATE time_entries SET paused_at = NULL, paused_seconds = paused_seconds + ?1, last_seen_at = ?2, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![paused, now.to_rfc3339(), entry.id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+pub fn pause_timer(db: &Connection) -> Result<TimeEntryView, String> {
+    let running = current_timer(db)?;
+    if running.entry.paused_at.is_some() {
+        return Err("The timer is already paused".to_string());
+    }
+    let now = get_current_timestamp().to_rfc3339();
+    db.execute(
+        "UPDATE time_entries SET paused_at = ?1, last_seen_at = ?1, updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![now, running.entry.id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    get_time_entry(db, &running.entry.id)
+}
+
+pub fn resume_timer(db: &Connection) -> Result<TimeEntryView, String> {
+    let running = current_timer(db)?;
+    if running.entry.paused_at.is_none() {
+        return Err("The timer is not paused".to_string());
+    }
+    resume(db, &running.entry, get_current_timestamp())?;
+    get_time_entry(db, &running.entry.id)
+}
+
+pub fn stop_timer(db: &Connection) -> Result<TimeEntryView, String> {
+    let running = current_timer(db)?;
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    finish_timer(&tx, &running.entry, get_current_timestamp())?;
+    let stopped = get_time_entry(&tx, &running.entry.id)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(stopped)
+}
+
+pub fn get_running_timer(db: &Connection) -> Result<Option<TimeEntryView>, String> {
+    Ok(running_timers(db)?.into_iter().next())
+}
+
+// Called by the app while it runs. Orphaned timers are left alone so the
+// user still gets to decide about the time the app wasn't running.
+pub fn timer_heartbeat(db: &Connection) -> Result<(), String> {
+    let now = get_current_timestamp().to_rfc3339();
+    for running in running_timers(db)? {
+        if !running.orphaned && running.entry.paused_at.is_none() {
+            db.execute("UPDATE time_entries SET last_seen_at = ?1 WHERE id = ?2", [&now, &running.entry.id])
+                .map_err(|e| format!("SQL update error: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// Settle a timer left running by a crash: "resume" counts the time since and
+// keeps it running, "stop" ends it when the app was last seen, "discard" drops it.
+pub fn resolve_orphaned_timer(db: &Connection, action: String) -> Result<Option<TimeEntryView>, String> {
+    let orphan = running_timers(db)?
+        .into_iter()
+        .find(|running| running.orphaned)
+        .ok_or_else(|| "No orphaned timer".to_string())?;
+    let now = get_current_timestamp();
+    match action.trim().to_lowercase().as_str() {
+        "resume" => {
+            db.execute(
+                "UPDATE time_entries SET last_seen_at = ?1 WHERE id = ?2",
+                [&now.to_rfc3339(), &orphan.entry.id]
+            ).map_err(|e| format!("SQL update error: {}", e))?;
+        }
+        "stop" => {
+            let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+            finish_timer(&tx, &orphan.entry, orphan.entry.last_seen_at.unwrap_or(now))?;
+            tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+        }
+        "discard" => {
+            db.execute("DELETE FROM time_entries WHERE id = ?1", [&orphan.entry.id])
+                .map_err(|e| format!("SQL delete error: {}", e))?;
+            return Ok(None);
+        }
+        _ => return Err(format!("Invalid action: {}. Valid actions: resume, stop, discard", action)),
+    }
+    get_time_entry(db, &orphan.entry.id).map(Some)
+}
+
+// A finished entry; the task's actual_hours follows.
+pub(crate) fn insert_time_entry(
+    db: &Connection,
+    task_id: &str,
+    source: database::TimeEntrySource,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    paused_seconds: i64,
+    note: Option<String>,
+) -> Result<String, String> {
+    let id = generate_id();
+    db.execute(
+        "INSERT INTO time_entries (id, task_id, source, started_at, ended_at, paused_seconds, duration_seconds, note, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+        rusqlite::params![
+            id,
+            task_id,
+            source.key(),
+            start.to_rfc3339(),
+            end.to_rfc3339(),
+            paused_seconds,
+            (end - start).num_seconds() - paused_seconds,
+            note.filter(|note| !note.trim().is_empty()),
+            get_current_timestamp().to_rfc3339()
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    roll_up_actual_hours(db, task_id)?;
+    Ok(id)
+}
+
+// Log time by hand: a start and either an end or a number of minutes.
+pub fn add_time_entry(
+    db: &Connection,
+    task_id: String,
+    started_at: String,
+    ended_at: Option<String>,
+    minutes: Option<f64>,
+    note: Option<String>,
+) -> Result<TimeEntryView, String> {
+    repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    let start = parse_instant(&started_at)?;
+    let end = match (ended_at.filter(|end| !end.trim().is_empty()), minutes) {
+        (Some(end), None) => parse_instant(&end)?,
+        (None, Some(minutes)) if minutes.is_finite() => start + chrono::Duration::seconds((minutes * 60.0).round() as i64),
+        (None, Some(minutes)) => return Err(format!("Invalid number of minutes: {}", minutes)),
+        (None, None) => return Err("Give an end time or a number of minutes".to_string()),
+        _ => return Err("Give either an end time or a number of minutes, not both".to_string()),
+    };
+    let duration = (end - start).num_seconds();
+    if duration <= 0 {
+        return Err("A time entry must end after it starts".to_string());
+    }
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    let id = insert_time_entry(&tx, &task_id, database::TimeEntrySource::Manual, start, end, 0, note)?;
+    let entry = get_time_entry(&tx, &id)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(entry)
+}
+
+// Correct the start, end or note of an entry; an empty note clears it. A
+// running timer can get an earlier start but no end (stop it instead).
+pub fn update_time_entry(
+    db: &Connection,
+    id: String,
+    started_at: Option<String>,
+    ended_at: Option<String>,
+    note: Option<String>,
+) -> Result<TimeEntryView, String> {
+    let mut entry = get_time_entry(db, &id)?.entry;
+    if let Some(start) = started_at {
+        entry.started_at = parse_instant(&start)?;
+    }
+    if let Some(end) = ended_at {
+        if entry.ended_at.is_none() {
+            return Err("Stop the timer instead of giving it an end".to_string());
+        }
+        entry.ended_at = Some(parse_instant(&end)?);
+    }
+    if let Some(note) = note {
+        entry.note = Some(note).filter(|note| !note.trim().is_empty());
+    }
+    let duration = entry.ended_at.map(|end| (end - entry.started_at).num_seconds() - entry.paused_seconds);
+    if duration.is_some_and(|duration| duration <= 0) {
+        return Err("A time entry must end after it starts".to_string());
+    }
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(
+        "UPDATE time_entries SET started_at = ?1, ended_at = ?2, duration_seconds = ?3, note = ?4, updated_at = ?5 WHERE id = ?6",
+        rusqlite::params![
+            entry.started_at.to_rfc3339(),
+            entry.ended_at.map(|end| end.to_rfc3339()),
+            duration,
+            entry.note,
+            get_current_timestamp().to_rfc3339(),
+            id
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    roll_up_actual_hours(&tx, &entry.task_id)?;
+    let updated = get_time_entry(&tx, &id)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(updated)
+}
+
+pub fn delete_time_entry(db: &Connection, id: String) -> Result<String, String> {
+    let entry = get_time_entry(db, &id)?;
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute("DELETE FROM time_entries WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    tx.execute("UPDATE pomodoros SET time_entry_id = NULL WHERE time_entry_id = ?1", [&id])
+        .map_err(|e| format!("SQL update error: {}", e))?;
+    roll_up_actual_hours(&tx, &entry.entry.task_id)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(format!("Successfully deleted time entry of '{}' with ID: {}", entry.task_title, id))
+}
+
+// Entries of one task, or of every task, started between two local days; newest first.
+pub fn get_time_entries(
+    db: &Connection,
+    task_id: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<TimeEntryView>, String> {
+    let today = chrono::Local::now().date_naive();
+    let from = from
+        .map(|from| filters::resolve_date(&from, today).and_then(|day| day_range(day, day)))
+        .transpose()?
+        .map(|(start, _)| start);
+    let to = to
+        .map(|to| filters::resolve_date(&to, today).and_then(|day| day_range(day, day)))
+        .transpose()?
+        .map(|(_, end)| end);
+    repository::query_all(
+        db,
+        &format!(
+            "{} WHERE (?1 IS NULL OR te.task_id = ?1) AND (?2 IS NULL OR te.started_at >= ?2) AND (?3 IS NULL OR te.started_at < ?3)
+             ORDER BY te.started_at DESC",
+            repository::TIME_ENTRY_VIEW_SELECT
+        ),
+        rusqlite::params![task_id, from, to],
+    )
+}
+
+// Time per persona, workstream, task or day between two local days (the last
+// seven by default), including a running timer so far. An entry counts
+// towards the day it started.
+pub fn get_time_report(
+    db: &Connection,
+    group_by: String,
+    from: Option<String>,
+    to: Option<String>,
+    persona_id: Option<String>,
+    workstream_id: Option<String>,
+) -> Result<TimeReport, String> {
+    let group_by = group_by.trim().to_lowercase();
+    if !["persona", "workstream", "task", "day"].contains(&group_by.as_str()) {
+        return Err(format!("Invalid grouping: {}. Valid groupings: persona, workstream, task, day", group_by));
+    }
+    let today = chrono::Local::now().date_naive();
+    let to = to.map(|to| filters::resolve_date(&to, today)).transpose()?.unwrap_or(today);
+    let from = match from {
+        Some(from) => filters::resolve_date(&from, today)?,
+        None => to - chrono::Days::new(6),
+    };
+    if to < from {
+        return Err("The report must end on or after its first day".to_string());
+    }
+    let (start, end) = day_range(from, to)?;
+    let entries: Vec<TimeEntryView> = repository::query_all(
+        db,
+        &format!(
+            "{} WHERE te.started_at >= ?1 AND te.started_at < ?2 AND (?3 IS NULL OR w.persona_id = ?3) AND (?4 IS NULL OR pt.workstream_id = ?4)",
+            repository::TIME_ENTRY_VIEW_SELECT
+        ),
+        rusqlite::params![start, end, persona_id, workstream_id],
+    )?;
+
+    let mut rows: Vec<TimeReportRow> = Vec::new();
+    for view in &entries {
+        let (key, label) = match group_by.as_str() {
+            "persona" => (view.persona_id.clone(), view.persona_name.clone()),
+            "workstream" => (view.workstream_id.clone(), view.workstream_name.clone()),
+            "task" => (view.entry.task_id.clone(), view.task_title.clone()),
+            _ => {
+                let day = view.entry.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string();
+                (day.clone(), day)
+            }
+        };
+        let row = match rows.iter().position(|row| row.key == key) {
+            Some(index) => &mut rows[index],
+            None => {
+                rows.push(TimeReportRow { key, label, seconds: 0, hours: 0.0, entries: 0 });
+                rows.last_mut().unwrap()
+            }
+        };
+        row.seconds += view.elapsed_seconds;
+        row.entries += 1;
+    }
+    for row in &mut rows {
+        row.hours = hours(row.seconds);
+    }
+    if group_by == "day" {
+        rows.sort_by(|a, b| a.key.cmp(&b.key));
+    } else {
+        rows.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.label.cmp(&b.label)));
+    }
+    let total_seconds = rows.iter().map(|row| row.seconds).sum();
+    Ok(TimeReport {
+        group_by,
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        total_seconds,
+        total_hours: hours(total_seconds),
+        rows,
+    })
+}
+
+// Pomodoro focus sessions (see pomodoro.rs). Every call first moves the
+// session past the phases that have run out, and returns what that changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusChange {
+    pub status: Option<pomodoro::FocusStatus>,
+    pub events: Vec<pomodoro::FocusEvent>,
+    pub stopped_timers: Vec<TimeEntryView>, // timers stopped so a task isn't timed twice
+}
+
+pub fn get_pomodoro_settings(db: &Connection) -> Result<pomodoro::PomodoroSettings, String> {
+    pomodoro::load_settings(db)
+}
+
+// Settings left out keep their values; they apply from the next session on.
+pub fn configure_pomodoro(
+    db: &Connection,
+    work_minutes: Option<u32>,
+    short_break_minutes: Option<u32>,
+    long_break_minutes: Option<u32>,
+    long_break_every: Option<u32>,
+    auto_start_breaks: Option<bool>,
+    auto_start_work: Option<bool>,
+) -> Result<pomodoro::PomodoroSettings, String> {
+    let current = pomodoro::load_settings(db)?;
+    pomodoro::save_settings(db, &pomodoro::PomodoroSettings {
+        work_minutes: work_minutes.unwrap_or(current.work_minutes),
+        short_break_minutes: short_break_minutes.unwrap_or(current.short_break_minutes),
+        long_break_minutes: long_break_minutes.unwrap_or(current.long_break_minutes),
+        long_break_every: long_break_every.unwrap_or(current.long_break_every),
+        auto_start_breaks: auto_start_breaks.unwrap_or(current.auto_start_breaks),
+        auto_start_work: auto_start_work.unwrap_or(current.auto_start_work),
+    })
+}
+
+// Run `action` on the session after catching it up, in one transaction.
+fn change_focus(
+    db: &Connection,
+    action: impl FnOnce(&Connection, chrono::DateTime<chrono::Utc>) -> Result<(Vec<pomodoro::FocusEvent>, Vec<TimeEntryView>), String>,
+) -> Result<FocusChange, String> {
+    let now = get_current_timestamp();
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    let mut events = pomodoro::advance(&tx, now)?;
+    let (more, stopped_timers) = action(&tx, now)?;
+    events.extend(more);
+    let status = pomodoro::active_status(&tx, now)?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(FocusChange { status, events, stopped_timers })
+}
+
+pub fn get_focus_status(db: &Connection) -> Result<FocusChange, String> {
+    change_focus(db, |_, _| Ok((Vec::new(), Vec::new())))
+}
+
+// The active session as it stands, without catching it up, and whether a
+// phase has run out so that get_focus_status has something to write.
+pub fn peek_focus_status(db: &Connection) -> Result<(Option<pomodoro::FocusStatus>, bool), String> {
+    let now = get_current_timestamp();
+    let status = pomodoro::active_status(db, now)?;
+    let due = status.as_ref().and_then(|status| status.session.phase_ends_at).is_some_and(|ends_at| ends_at <= now);
+    Ok((status, due))
+}
+
+// Start a session, on a task or without one; a timer running on any task is stopped.
+pub fn start_focus_session(db: &Connection, task_id: Option<String>) -> Result<FocusChange, String> {
+    change_focus(db, |db, now| {
+        if let Some(task_id) = &task_id {
+            repository::get_task(db, task_id)?
+                .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+        }
+        pomodoro::start(db, task_id, now)?;
+        Ok((Vec::new(), stop_timers(db, running_timers(db)?, now)?))
+    })
+}
+
+pub fn pause_focus_session(db: &Connection) -> Result<FocusChange, String> {
+    change_focus(db, |db, now| pomodoro::pause(db, now).map(|_| (Vec::new(), Vec::new())))
+}
+
+pub fn resume_focus_session(db: &Connection) -> Result<FocusChange, String> {
+    change_focus(db, |db, now| pomodoro::resume(db, now).map(|_| (Vec::new(), Vec::new())))
+}
+
+// Cut the current phase short and start the next one.
+pub fn skip_focus_phase(db: &Connection) -> Result<FocusChange, String> {
+    change_focus(db, |db, now| pomodoro::skip(db, now).map(|events| (events, Vec::new())))
+}
+
+pub fn stop_focus_session(db: &Connection) -> Result<FocusChange, String> {
+    change_focus(db, |db, now| pomodoro::stop(db, now).map(|_| (Vec::new(), Vec::new())))
+}
+
+// Daily focus between two local days, the last seven by default.
+pub fn get_focus_stats(db: &Connection, from: Option<String>, to: Option<String>) -> Result<pomodoro::FocusStats, String> {
+    let today = chrono::Local::now().date_naive();
+    let to = to.map(|to| filters::resolve_date(&to, today)).transpose()?.unwrap_or(today);
+    let from = match from {
+        Some(from) => filters::resolve_date(&from, today)?,
+        None => to - chrono::Days::new(6),
+    };
+    if to < from || (to - from).num_days() > 366 {
+        return Err("Focus statistics cover one day up to a year".to_string());
+    }
+    pomodoro::stats(db, from, to)
+}
+
+// Milestones: dated goals inside a workstream with their own share of its tasks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneView {
+    #[serde(flatten)]
+    pub milestone: database::Milestone,
+    pub task_count: i64,
+    pub done_count: i64,
+    pub progress_percentage: u8,
+    pub overdue: bool,
+    pub tasks: Vec<TaskView>,
+}
+
+// Complete the selected milestones (`condition` on milestones, with ?1) whose
+// tasks are all done, and open the others again.
+fn refresh_milestones(db: &Connection, condition: &str, param: &str) -> Result<(), String> {
+    let finished = format!(
+        "(EXISTS (SELECT 1 FROM project_tasks pt WHERE pt.milestone_id = milestones.id)
+          AND NOT EXISTS (SELECT 1 FROM project_tasks pt WHERE pt.milestone_id = milestones.id AND {} != 'done'))",
+        filters::STATUS_KEY
+    );
+    db.execute(
+        &format!(
+            "UPDATE milestones SET completed_date = CASE WHEN {0} THEN ?2 END, updated_at = ?2
+             WHERE ({1}) AND {0} = (completed_date IS NULL)",
+            finished,
+            condition
+        ),
+        rusqlite::params![param, get_current_timestamp().to_rfc3339()]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+fn milestone_view(db: &Connection, milestone: database::Milestone, today: chrono::NaiveDate) -> Result<MilestoneView, String> {
+    let tasks: Vec<TaskView> = repository::query_all(
+        db,
+        &format!("{} WHERE pt.milestone_id = ?1 ORDER BY pt.due_date IS NULL, pt.due_date, pt.created_at", repository::TASK_VIEW_SELECT),
+        [&milestone.id],
+    )?;
+    let task_count = tasks.len() as i64;
+    let done_count = tasks.iter().filter(|task| task.task.status.key() == "done").count() as i64;
+    Ok(MilestoneView {
+        progress_percentage: if task_count == 0 { 0 } else { (done_count * 100 / task_count) as u8 },
+        overdue: milestone.completed_date.is_none() && milestone.due_date.date_naive() < today,
+        milestone,
+        task_count,
+        done_count,
+        tasks,
+    })
+}
+
+fn get_milestone(db: &Connection, id: &str) -> Result<MilestoneView, String> {
+    let milestone = repository::query_one(db, "SELECT * FROM milestones WHERE id = ?1", [id])?
+        .ok_or_else(|| format!("Milestone with ID '{}' not found", id))?;
+    milestone_view(db, milestone, chrono::Local::now().date_naive())
+}
+
+fn parse_milestone_due(due_date: &str) -> Result<String, String> {
+    filters::resolve_date(due_date.trim(), chrono::Local::now().date_naive())
+        .map(|day| day.format("%Y-%m-%d").to_string())
+}
+
+pub fn create_milestone(db: &Connection, workstream_id: String, name: String, description: Option<String>, due_date: String) -> Result<MilestoneView, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("A milestone needs a name".to_string());
+    }
+    if repository::get_workstream(db, &workstream_id)?.is_none() {
+        return Err(format!("Workstream with ID '{}' not found", workstream_id));
+    }
+    let id = generate_id();
+    let now = get_current_timestamp().to_rfc3339();
+    db.execute(
+        "INSERT INTO milestones (id, workstream_id, name, description, due_date, completed_date, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, NULL, ?6, ?6)",
+        rusqlite::params![id, workstream_id, name, description, parse_milestone_due(&due_date)?, now]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    get_milestone(db, &id)
+}
+
+// Fields left out keep their values; an empty description clears it.
+pub fn update_milestone(
+    db: &Connection,
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    due_date: Option<String>,
+) -> Result<MilestoneView, String> {
+    let existing = get_milestone(db, &id)?.milestone;
+    let name = name.map(|name| name.trim().to_string()).unwrap_or(existing.name);
+    if name.is_empty() {
+        return Err("A milestone needs a name".to_string());
+    }
+    let description = match description {
+        Some(description) if description.trim().is_empty() => None,
+        Some(description) => Some(description),
+        None => existing.description,
+    };
+    let due_date = match due_date {
+        Some(due_date) => parse_milestone_due(&due_date)?,
+        None => existing.due_date.format("%Y-%m-%d").to_string(),
+    };
+    db.execute(
+        "UPDATE milestones SET name = ?1, description = ?2, due_date = ?3, updated_at = ?4 WHERE id = ?5",
+        rusqlite::params![name, description, due_date, get_current_timestamp().to_rfc3339(), id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    get_milestone(db, &id)
+}
+
+// The milestone's tasks stay in the workstream without one.
+pub fn delete_milestone(db: &Connection, id: String) -> Result<String, String> {
+    let milestone = get_milestone(db, &id)?.milestone;
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute("UPDATE project_tasks SET milestone_id = NULL WHERE milestone_id = ?1", [&id])
+        .map_err(|e| format!("SQL update error: {}", e))?;
+    tx.execute("DELETE FROM milestones WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(format!("Deleted milestone '{}'", milestone.name))
+}
+
+// A workstream's milestones by due date, i.e. in timeline order.
+pub fn get_milestones(db: &Connection, workstream_id: String) -> Result<Vec<MilestoneView>, String> {
+    let today = chrono::Local::now().date_naive();
+    let milestones: Vec<database::Milestone> = repository::query_all(
+        db,
+        "SELECT * FROM milestones WHERE workstream_id = ?1 ORDER BY due_date, created_at",
+        [&workstream_id],
+    )?;
+    milestones.into_iter().map(|milestone| milestone_view(db, milestone, today)).collect()
+}
+
+// Assign a task to a milestone of its workstream, or take it off with None.
+pub fn set_task_milestone(db: &Connection, task_id: String, milestone_id: Option<String>) -> Result<ProjectTask, String> {
+    let task = repository::get_task(db, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    let milestone_id = milestone_id.filter(|id| !id.trim().is_empty());
+    if let Some(milestone_id) = &milestone_id {
+        let milestone = get_milestone(db, milestone_id)?.milestone;
+        if milestone.workstream_id != task.workstream_id {
+            return Err(format!("Milestone '{}' belongs to another workstream", milestone.name));
+        }
+    }
+
+    let tx = db.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    tx.execute(
+        "UPDATE project_tasks SET milestone_id = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![milestone_id, get_current_timestamp().to_rfc3339(), task_id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    for id in [task.milestone_id.as_ref(), milestone_id.as_ref()].into_iter().flatten() {
+        refresh_milestones(&tx, "id = ?1", id)?;
+    }
+    let task = repository::get_task(&tx, &task_id)?
+        .ok_or_else(|| format!("Project task with ID '{}' not found", task_id))?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(task)
+}
+
+// Upcoming deadlines for the dashboard: open tasks and milestones due within
+// `days` days, overdue ones included, soonest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deadline {
+    pub kind: String, // task or milestone
+    pub id: String,
+    pub title: String,
+    pub due_date: String,
+    pub priority: Option<String>,
+    pub workstream_id: String,
+    pub workstream_name: String,
+    pub persona_id: String,
+    pub persona_name: String,
+    pub overdue: bool,
+    pub progress_percentage: Option<u8>, // milestones only
+}
+
+pub fn get_upcoming_deadlines(db: &Connection, days: Option<u32>) -> Result<Vec<Deadline>, String> {
+    let days = days.unwrap_or(7).min(366);
+    let today = chrono::Local::now().date_naive();
+    let until = (today + chrono::Days::new(days as u64)).format("%Y-%m-%d").to_string();
+    let today_key = today.format("%Y-%m-%d").to_string();
+
+    let tasks: Vec<TaskView> = repository::query_all(
+        db,
+        &format!(
+            "{} WHERE {} != 'done' AND pt.archived_at IS NULL AND pt.due_date IS NOT NULL AND substr(pt.due_date, 1, 10) <= ?1",
+            repository::TASK_VIEW_SELECT,
+            filters::STATUS_KEY
+        ),
+        [&until],
+    )?;
+    let mut deadlines: Vec<Deadline> = tasks
+        .into_iter()
+        .map(|view| {
+            // the stored day, as the agenda compares it
+            let due_date: String = raw_due_date(db, &view.task.id)?.unwrap_or_default().chars().take(10).collect();
+            Ok(Deadline {
+                kind: "task".to_string(),
+                overdue: due_date < today_key,
+                due_date,
+                id: view.task.id,
+                title: view.task.title,
+                priority: Some(view.task.priority.key().to_string()),
+                workstream_id: view.task.workstream_id,
+                workstream_name: view.workstream_name,
+                persona_id: view.persona_id,
+                persona_name: view.persona_name,
+                progress_percentage: None,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    let milestones: Vec<database::Milestone> = repository::query_all(
+        db,
+        "SELECT * FROM milestones WHERE completed_date IS NULL AND substr(due_date, 1, 10) <= ?1",
+        [&until],
+    )?;
+    for milestone in milestones {
+        let workstream = repository::get_workstream(db, &milestone.workstream_id)?
+            .ok_or_else(|| format!("Workstream with ID '{}' not found", milestone.workstream_id))?;
+        let persona_name: String = db
+            .query_row("SELECT name FROM personas WHERE id = ?1", [&workstream.persona_id], |row| row.get(0))
+            .map_err(|e| format!("SQL query error: {}", e))?;
+        let view = milestone_view(db, milestone, today)?;
+        deadlines.push(Deadline {
+            kind: "milestone".to_string(),
+            id: view.milestone.id,
+            title: view.milestone.name,
+            due_date: view.milestone.due_date.format("%Y-%m-%d").to_string(),
+            priority: None,
+            workstream_id: workstream.id,
+            workstream_name: workstream.name,
+            persona_id: workstream.persona_id,
+            persona_name,
+            overdue: view.overdue,
+            progress_percentage: Some(view.progress_percentage),
+        });
+    }
+    deadlines.sort_by(|a, b| a.due_date.cmp(&b.due_date).then_with(|| a.title.cmp(&b.title)));
+    Ok(deadlines)
+}
+
+// Estimate accuracy (see estimates.rs) over tasks finished between two local
+// days, the last half year by default, per month or week.
+pub fn get_estimate_accuracy(
+    db: &Connection,
+    group_by: String,
+    period: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    persona_id: Option<String>,
+    workstream_id: Option<String>,
+) -> Result<estimates::EstimateAccuracy, String> {
+    let group_by = group_by.trim().to_lowercase();
+    if !["persona", "workstream", "tag", "priority"].contains(&group_by.as_str()) {
+        return Err(format!("Invalid grouping: {}. Valid groupings: persona, workstream, tag, priority", group_by));
+    }
+    let period = period.map(|period| period.trim().to_lowercase()).unwrap_or_else(|| "month".to_string());
+    if !["week", "month"].contains(&period.as_str()) {
+        return Err(format!("Invalid period: {}. Valid periods: week, month", period));
+    }
+    let today = chrono::Local::now().date_naive();
+    let to = to.map(|to| filters::resolve_date(&to, today)).transpose()?.unwrap_or(today);
+    let from = match from {
+        Some(from) => filters::resolve_date(&from, today)?,
+        None => to - chrono::Months::new(6),
+    };
+    if to < from {
+        return Err("The report must end on or after its first day".to_string());
+    }
+    let (start, end) = day_range(from, to)?;
+    let samples = estimates::load_samples(db, &start, &end, persona_id.as_deref(), workstream_id.as_deref())?;
+    let (overall, periods, groups, outliers) = estimates::accuracy(&samples, &group_by, &period);
+    Ok(estimates::EstimateAccuracy {
+        group_by,
+        period,
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        overall,
+        periods,
+        groups,
+        outliers,
+    })
+}
+
+// A raw estimate scaled by the past year's correction factor, for showing
+// next to the estimate on a new task. None until there is enough history.
+pub fn get_calibrated_estimate(db: &Connection, workstream_id: String, estimated_hours: f64) -> Result<Option<estimates::CalibratedEstimate>, String> {
+    if !estimated_hours.is_finite() || estimated_hours <= 0.0 {
+        return Err("An estimate must be more than zero hours".to_string());
+    }
+    let today = chrono::Local::now().date_naive();
+    let (start, end) = day_range(today - chrono::Days::new(365), today)?;
+    let samples = estimates::load_samples(db, &start, &end, None, None)?;
+    estimates::calibrate(db, &workstream_id, estimated_hours, &samples)
+}
+
+// Burndown series, completion forecast and health of a workstream (see
+// forecast.rs), counting tasks or estimated hours.
+pub fn get_workstream_burndown(db: &Connection, workstream_id: String, unit: Option<String>) -> Result<forecast::Burndown, String> {
+    let unit = unit.map(|unit| unit.trim().to_lowercase()).unwrap_or_else(|| "tasks".to_string());
+    if !["tasks", "hours"].contains(&unit.as_str()) {
+        return Err(format!("Invalid unit: {}. Valid units: tasks, hours", unit));
+    }
+    let workstream = repository::get_workstream(db, &workstream_id)?
+        .ok_or_else(|| format!("Workstream with ID '{}' not found", workstream_id))?;
+    forecast::burndown(db, &workstream, &unit, chrono::Local::now().date_naive())
+}
+
+// Lookup helpers for callers that only know a name or a short ID
+pub fn resolve_workstream_id(db: &Connection, id_or_name: &str) -> Result<String, String> {
+    let mut stmt = db.prepare("SELECT id FROM workstreams WHERE id = ?1 OR id LIKE ?1 || '%' OR LOWER(name) = LOWER(?1)")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([id_or_name], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    match ids.len() {
+        0 => Err(format!("Workstream '{}' not found", id_or_name)),
+        1 => Ok(ids[0].clone()),
+        _ => Err(format!("'{}' matches {} workstreams. Use a longer ID", id_or_name, ids.len())),
+    }
+}
+
+pub fn resolve_persona_id(db: &Connection, id_or_name: &str) -> Result<String, String> {
+    let mut stmt = db.prepare("SELECT id FROM personas WHERE id = ?1 OR id LIKE ?1 || '%' OR LOWER(name) = LOWER(?1)")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([id_or_name], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    match ids.len() {
+        0 => Err(format!("Persona '{}' not found", id_or_name)),
+        1 => Ok(ids[0].clone()),
+        _ => Err(format!("'{}' matches {} personas. Use a longer ID", id_or_name, ids.len())),
+    }
+}
+
+pub fn resolve_milestone_id(db: &Connection, id_or_name: &str) -> Result<String, String> {
+    let mut stmt = db.prepare("SELECT id FROM milestones WHERE id = ?1 OR id LIKE ?1 || '%' OR LOWER(name) = LOWER(?1)")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([id_or_name], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    match ids.len() {
+        0 => Err(format!("Milestone '{}' not found", id_or_name)),
+        1 => Ok(ids[0].clone()),
+        _ => Err(format!("'{}' matches {} milestones. Use a longer ID", id_or_name, ids.len())),
+    }
+}
+
+pub fn resolve_task_id(db: &Connection, id_prefix: &str) -> Result<String, String> {
+    let mut stmt = db.prepare("SELECT id FROM project_tasks WHERE id = ?1 OR id LIKE ?1 || '%'")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([id_prefix], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    match ids.len() {
+        0 => Err(format!("Task with ID '{}' not found", id_prefix)),
+        1 => Ok(ids[0].clone()),
+        _ => Err(format!("'{}' matches {} tasks. Use a longer ID", id_prefix, ids.len())),
+    }
+}
+
+pub fn resolve_habit_id(db: &Connection, id_or_name: &str) -> Result<String, String> {
+    let mut stmt = db.prepare("SELECT id FROM habit_trackers WHERE id = ?1 OR id LIKE ?1 || '%' OR LOWER(name) = LOWER(?1)")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([id_or_name], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    match ids.len() {
+        0 => Err(format!("Habit '{}' not found", id_or_name)),
+        1 => Ok(ids[0].clone()),
+        _ => Err(format!("'{}' matches {} habits. Use a longer ID", id_or_name, ids.len())),
+    }
+}
+
+fn parse_day(date: Option<String>) -> Result<String, String> {
+    match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map_err(|_| format!("Invalid date: {}. Use YYYY-MM-DD", date)),
+        None => Ok(chrono::Local::now().format("%Y-%m-%d").to_string()),
+    }
+}
+
+// Habit commands
+#[allow(clippy::too_many_arguments)]
+pub fn create_habit(
+    db: &Connection,
+    workstream_id: String,
+    name: String,
+    description: Option<String>,
+    frequency: String,
+    target_days: Option<u8>,
+    target_quantity: Option<u32>,
+    unit: Option<String>,
+    color: Option<String>
+) -> Result<database::HabitTracker, String> {
+    let mut stmt = db.prepare("SELECT id FROM workstreams WHERE id = ?1")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let workstream_exists: Option<String> = stmt.query_row([&workstream_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))?;
+
+    if workstream_exists.is_none() {
+        return Err(format!("Workstream with ID '{}' not found", workstream_id));
+    }
+
+    let target_frequency = match frequency.as_str() {
+        "daily" => database::HabitFrequency::Daily,
+        "weekly" => database::HabitFrequency::Weekly { target_days: target_days.unwrap_or(1).clamp(1, 7) },
+        _ => return Err(format!("Invalid frequency: {}. Valid frequencies: daily, weekly", frequency)),
+    };
+
+    let habit = database::HabitTracker {
+        id: generate_id(),
+        workstream_id,
+        name,
+        description,
+        target_frequency,
+        target_quantity,
+        unit,
+        is_active: true,
+        color: color.unwrap_or_else(|| "#10b981".to_string()),
+        created_at: get_current_timestamp(),
+        updated_at: get_current_timestamp(),
+    };
+
+    db.execute(
+        "INSERT INTO habit_trackers (id, workstream_id, name, description, target_frequency, target_quantity, unit, is_active, color, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![
+            habit.id,
+            habit.workstream_id,
+            habit.name,
+            habit.description,
+            serde_json::to_string(&habit.target_frequency).map_err(|e| format!("Frequency serialization error: {}", e))?,
+            habit.target_quantity,
+            habit.unit,
+            habit.is_active,
+            habit.color,
+            habit.created_at.to_rfc3339(),
+            habit.updated_at.to_rfc3339()
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    Ok(habit)
+}
+
+pub fn get_habits(db: &Connection, date: Option<String>) -> Result<Vec<HabitView>, String> {
+    let date = parse_day(date)?;
+
+    repository::query_all(db, "
+        SELECT h.*, w.name AS workstream_name, ?1 AS completion_date, c.completed, c.quantity_completed
+        FROM habit_trackers h
+        JOIN workstreams w ON h.workstream_id = w.id
+        LEFT JOIN habit_completions c ON c.habit_tracker_id = h.id AND c.date = ?1
+        WHERE h.is_active = 1
+        ORDER BY w.name, h.name
+    ", [&date])
+}
+
+pub fn log_habit_completion(
+    db: &Connection,
+    habit_id: String,
+    date: Option<String>,
+    quantity: Option<u32>,
+    notes: Option<String>
+) -> Result<database::HabitCompletion, String> {
+    let date = parse_day(date)?;
+
+    if repository::get_habit(db, &habit_id)?.is_none() {
+        return Err(format!("Habit with ID '{}' not found", habit_id));
+    }
+
+    // One completion row per habit and day; logging again updates it.
+    db.execute(
+        "INSERT INTO habit_completions (id, habit_tracker_id, date, completed, quantity_completed, notes, completed_at) VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6)
+         ON CONFLICT(habit_tracker_id, date) DO UPDATE SET completed = 1, quantity_completed = COALESCE(excluded.quantity_completed, quantity_completed), notes = COALESCE(excluded.notes, notes), completed_at = excluded.completed_at",
+        rusqlite::params![generate_id(), habit_id, date, quantity, notes, get_current_timestamp().to_rfc3339()]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    repository::query_one(db, "SELECT * FROM habit_completions WHERE habit_tracker_id = ?1 AND date = ?2", [&habit_id, &date])?
+        .ok_or_else(|| format!("Habit with ID '{}' not found", habit_id))
+}
+
+// Journal commands
+pub fn create_journal_entry(
+    db: &Connection,
+    title: String,
+    content: String,
+    workstream_id: Option<String>,
+    persona_id: Option<String>,
+    entry_type: String,
+    tags: Vec<String>
+) -> Result<database::JournalEntry, String> {
+    let entry_type: database::JournalEntryType = entry_type.parse()?;
+
+    // An entry filed under a workstream also belongs to that workstream's persona.
+    let persona_id = match &workstream_id {
+        Some(workstream_id) => {
+            let owner: Option<String> = db.query_row("SELECT persona_id FROM workstreams WHERE id = ?1", [workstream_id], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("SQL query error: {}", e))?;
+            match owner {
+                Some(owner) => Some(persona_id.unwrap_or(owner)),
+                None => return Err(format!("Workstream with ID '{}' not found", workstream_id)),
+            }
+        }
+        None => persona_id,
+    };
+
+    if let Some(persona_id) = &persona_id {
+        let exists: Option<String> = db.query_row("SELECT id FROM personas WHERE id = ?1", [persona_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("SQL query error: {}", e))?;
+        if exists.is_none() {
+            return Err(format!("Persona with ID '{}' not found", persona_id));
+        }
+    }
+
+    let entry = database::JournalEntry {
+        id: generate_id(),
+        workstream_id,
+        persona_id,
+        title,
+        content,
+        entry_type,
+        tags: tags.into_iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        created_at: get_current_timestamp(),
+        updated_at: get_current_timestamp(),
+    };
+
+    db.execute(
+        "INSERT INTO journal_entries (id, workstream_id, persona_id, title, content, entry_type, tags, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            entry.id,
+            entry.workstream_id,
+            entry.persona_id,
+            entry.title,
+            entry.content,
+            serde_json::to_string(&entry.entry_type).map_err(|e| format!("Entry type serialization error: {}", e))?,
+            serde_json::to_string(&entry.tags).map_err(|e| format!("Tags serialization error: {}", e))?,
+            entry.created_at.to_rfc3339(),
+            entry.updated_at.to_rfc3339()
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    Ok(entry)
+}
+
+pub fn get_journal_entries(
+    db: &Connection,
+    workstream_id: Option<String>,
+    persona_id: Option<String>,
+    limit: Option<u32>
+) -> Result<Vec<database::JournalEntry>, String> {
+    let mut query = "SELECT * FROM journal_entries WHERE 1=1".to_string();
+    let mut params: Vec<String> = Vec::new();
+
+    if let Some(workstream_id) = workstream_id {
+        query.push_str(" AND workstream_id = ?");
+        params.push(workstream_id);
+    }
+
+    if let Some(persona_id) = persona_id {
+        query.push_str(" AND persona_id = ?");
+        params.push(persona_id);
+    }
+
+    query.push_str(&format!(" ORDER BY created_at DESC LIMIT {}", limit.unwrap_or(50)));
+
+    repository::query_all(db, &query, rusqlite::params_from_iter(params.iter()))
+}
+
+// Agenda
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Agenda {
+    pub date: String,
+    pub overdue: Vec<TaskView>,
+    pub due_today: Vec<TaskView>,
+    pub in_progress: Vec<TaskView>,
+    pub habits: Vec<HabitView>,
+    pub journal: Vec<database::JournalEntry>,
+}
+
+fn agenda_tasks(db: &Connection, condition: &str, date: &str) -> Result<Vec<TaskView>, String> {
+    let query = format!(
+        "{} WHERE LOWER(TRIM(pt.status, '\"')) != 'done' AND pt.archived_at IS NULL AND {} ORDER BY pt.due_date, pt.created_at",
+        repository::TASK_VIEW_SELECT,
+        condition
+    );
+    repository::query_all(db, &query, [date])
+}
+
+pub fn get_agenda(db: &Connection, date: Option<String>) -> Result<Agenda, String> {
+    let date = parse_day(date)?;
+
+    Ok(Agenda {
+        overdue: agenda_tasks(db, "pt.due_date IS NOT NULL AND substr(pt.due_date, 1, 10) < ?1", &date)?,
+        due_today: agenda_tasks(db, "substr(pt.due_date, 1, 10) = ?1", &date)?,
+        in_progress: agenda_tasks(
+            db,
+            "LOWER(TRIM(pt.status, '\"')) = 'inprogress' AND (pt.due_date IS NULL OR substr(pt.due_date, 1, 10) > ?1)",
+            &date,
+        )?,
+        habits: get_habits(db, Some(date.clone()))?,
+        journal: repository::query_all(db, "SELECT * FROM journal_entries WHERE substr(created_at, 1, 10) = ?1 ORDER BY created_at", [&date])?,
+        date,
+    })
+}
+
+// Journal search with the same filters saved filters use.
+pub fn query_journal(db: &Connection, query: filters::JournalQuery) -> Result<filters::JournalPage, String> {
+    filters::query_journal(db, &query, chrono::Local::now().date_naive())
+}
+
+// Query language search, e.g. `status:todo priority>=high due<7d -tag:blocked "release notes"`.
+pub fn search(db: &Connection, kind: String, q: String, cursor: Option<String>, limit: Option<u32>) -> Result<filters::FilterResults, String> {
+    let today = chrono::Local::now().date_naive();
+    match kind.parse::<database::SavedFilterKind>()? {
+        database::SavedFilterKind::Tasks => {
+            let query = filters::TaskQuery { q: Some(q), cursor, limit, ..Default::default() };
+            filters::query_tasks(db, &query, today).map(filters::FilterResults::Tasks)
+        }
+        database::SavedFilterKind::Journal => {
+            let query = filters::JournalQuery { q: Some(q), cursor, limit, ..Default::default() };
+            filters::query_journal(db, &query, today).map(filters::FilterResults::Journal)
+        }
+    }
+}
+
+pub fn check_query(kind: String, q: String) -> Result<query_lang::QueryCheck, String> {
+    Ok(query_lang::check(kind.parse()?, &q, chrono::Local::now().date_naive()))
+}
+
+// `cursor` is the caret position in characters.
+pub fn complete_query(db: &Connection, kind: String, q: String, cursor: Option<usize>) -> Result<Vec<query_lang::Completion>, String> {
+    let cursor = cursor.unwrap_or_else(|| q.chars().count());
+    query_lang::complete(db, kind.parse()?, &q, cursor)
+}
+
+// Saved filters (smart lists)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedFilterView {
+    #[serde(flatten)]
+    pub filter: database::SavedFilter,
+    pub count: i64,
+}
+
+// Check a query against its kind and store it without paging state.
+fn normalize_filter_query(db: &Connection, kind: database::SavedFilterKind, query: Value) -> Result<String, String> {
+    let today = chrono::Local::now().date_naive();
+    let normalized = match kind {
+        database::SavedFilterKind::Tasks => {
+            let mut query: filters::TaskQuery = serde_json::from_value(query)
+                .map_err(|e| format!("Invalid task filter: {}", e))?;
+            query.cursor = None;
+            filters::count_tasks(db, &query, today)?;
+            serde_json::to_string(&query)
+        }
+        database::SavedFilterKind::Journal => {
+            let mut query: filters::JournalQuery = serde_json::from_value(query)
+                .map_err(|e| format!("Invalid journal filter: {}", e))?;
+            query.cursor = None;
+            filters::count_journal(db, &query, today)?;
+            serde_json::to_string(&query)
+        }
+    };
+    normalized.map_err(|e| format!("Serialization error: {}", e))
+}
+
+fn count_saved_filter(db: &Connection, filter: &database::SavedFilter) -> Result<i64, String> {
+    let today = chrono::Local::now().date_naive();
+    match filter.kind {
+        database::SavedFilterKind::Tasks => {
+            let query: filters::TaskQuery = serde_json::from_value(filter.query.clone())
+                .map_err(|e| format!("Invalid task filter: {}", e))?;
+            filters::count_tasks(db, &query, today)
+        }
+        database::SavedFilterKind::Journal => {
+            let query: filters::JournalQuery = serde_json::from_value(filter.query.clone())
+                .map_err(|e| format!("Invalid journal filter: {}", e))?;
+            filters::count_journal(db, &query, today)
+        }
+    }
+}
+
+pub fn create_saved_filter(
+    db: &Connection,
+    name: String,
+    kind: String,
+    query: Value,
+    pinned: bool
+) -> Result<database::SavedFilter, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Filter name cannot be empty".to_string());
+    }
+    let kind: database::SavedFilterKind = kind.parse()?;
+    let query = normalize_filter_query(db, kind, query)?;
+
+    // New filters go to the end of the sidebar.
+    let position: i64 = db.query_row("SELECT COALESCE(MAX(position) + 1, 0) FROM saved_filters", [], |row| row.get(0))
+        .map_err(|e| format!("SQL query error: {}", e))?;
+    let id = generate_id();
+    let now = get_current_timestamp().to_rfc3339();
+
+    db.execute(
+        "INSERT INTO saved_filters (id, name, kind, query, pinned, position, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        rusqlite::params![id, name, kind.key(), query, pinned, position, now]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    repository::get_saved_filter(db, &id)?
+        .ok_or_else(|| format!("Saved filter with ID '{}' not found", id))
+}
+
+pub fn update_saved_filter(
+    db: &Connection,
+    id: String,
+    name: Option<String>,
+    query: Option<Value>,
+    pinned: Option<bool>
+) -> Result<database::SavedFilter, String> {
+    let existing = repository::get_saved_filter(db, &id)?
+        .ok_or_else(|| format!("Saved filter with ID '{}' not found", id))?;
+
+    let name = match name.map(|n| n.trim().to_string()) {
+        Some(name) if name.is_empty() => return Err("Filter name cannot be empty".to_string()),
+        Some(name) => name,
+        None => existing.name,
+    };
+    let query = normalize_filter_query(db, existing.kind, query.unwrap_or(existing.query))?;
+
+    db.execute(
+        "UPDATE saved_filters SET name = ?1, query = ?2, pinned = ?3, updated_at = ?4 WHERE id = ?5",
+        rusqlite::params![name, query, pinned.unwrap_or(existing.pinned), get_current_timestamp().to_rfc3339(), id]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    repository::get_saved_filter(db, &id)?
+        .ok_or_else(|| format!("Saved filter with ID '{}' not found", id))
+}
+
+pub fn delete_saved_filter(db: &Connection, id: String) -> Result<String, String> {
+    let changes = db.execute("DELETE FROM saved_filters WHERE id = ?1", [&id])
+        .map_err(|e| format!("SQL delete error: {}", e))?;
+
+    if changes == 0 {
+        return Err(format!("Saved filter with ID '{}' not found", id));
+    }
+
+    Ok(format!("Saved filter with ID '{}' deleted successfully", id))
+}
+
+// Saved filters in sidebar order with the number of rows each one matches right now.
+pub fn get_saved_filters(db: &Connection, pinned_only: bool) -> Result<Vec<SavedFilterView>, String> {
+    let sql = if pinned_only {
+        "SELECT * FROM saved_filters WHERE pinned = 1 ORDER BY position, name"
+    } else {
+        "SELECT * FROM saved_filters ORDER BY pinned DESC, position, name"
+    };
+
+    repository::query_all::<database::SavedFilter, _>(db, sql, [])?
+        .into_iter()
+        .map(|filter| {
+            // A filter that no longer compiles (say a removed status) still shows up, with no matches.
+            let count = count_saved_filter(db, &filter).unwrap_or(0);
+            Ok(SavedFilterView { filter, count })
+        })
+        .collect()
+}
+
+// Set the sidebar order; filters left out keep their relative order after the listed ones.
+pub fn reorder_saved_filters(db: &mut Connection, ids: Vec<String>) -> Result<Vec<SavedFilterView>, String> {
+    let tx = db.transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+    let mut order: Vec<String> = ids.clone();
+    let mut stmt = tx.prepare("SELECT id FROM saved_filters ORDER BY position, name")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let existing = stmt.query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+    drop(stmt);
+
+    if let Some(unknown) = ids.iter().find(|id| !existing.contains(id)) {
+        return Err(format!("Saved filter with ID '{}' not found", unknown));
+    }
+    order.extend(existing.into_iter().filter(|id| !ids.contains(id)));
+
+    for (position, id) in order.iter().enumerate() {
+        tx.execute(
+            "UPDATE saved_filters SET position = ?1 WHERE id = ?2 AND position != ?1",
+            rusqlite::params![position as i64, id]
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+
+    get_saved_filters(db, false)
+}
+
+// Run a saved filter, optionally continuing from a cursor of an earlier page.
+pub fn run_saved_filter(
+    db: &Connection,
+    id: String,
+    cursor: Option<String>,
+    limit: Option<u32>
+) -> Result<filters::FilterResults, String> {
+    let filter = repository::get_saved_filter(db, &id)?
+        .ok_or_else(|| format!("Saved filter with ID '{}' not found", id))?;
+    let today = chrono::Local::now().date_naive();
+
+    match filter.kind {
+        database::SavedFilterKind::Tasks => {
+            let mut query: filters::TaskQuery = serde_json::from_value(filter.query)
+                .map_err(|e| format!("Invalid task filter: {}", e))?;
+            query.cursor = cursor;
+            query.limit = limit.or(query.limit);
+            filters::query_tasks(db, &query, today).map(filters::FilterResults::Tasks)
+        }
+        database::SavedFilterKind::Journal => {
+            let mut query: filters::JournalQuery = serde_json::from_value(filter.query)
+                .map_err(|e| format!("Invalid journal filter: {}", e))?;
+            query.cursor = cursor;
+            query.limit = limit.or(query.limit);
+            filters::query_journal(db, &query, today).map(filters::FilterResults::Journal)
+        }
+    }
+}
+
+pub fn resolve_saved_filter_id(db: &Connection, id_or_name: &str) -> Result<String, String> {
+    let mut stmt = db.prepare("SELECT id FROM saved_filters WHERE id = ?1 OR id LIKE ?1 || '%' OR LOWER(name) = LOWER(?1)")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let ids = stmt.query_map([id_or_name], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    match ids.len() {
+        0 => Err(format!("Saved filter '{}' not found", id_or_name)),
+        1 => Ok(ids[0].clone()),
+        _ => Err(format!("'{}' matches {} saved filters. Use a longer ID", id_or_name, ids.len())),
+    }
+}
+
+// Journal vault (Markdown mirror) commands
+pub fn export_journal_vault(db: &Connection, vault_path: String) -> Result<Value, String> {
+    let report = journal_vault::export_vault(db, &vault_path)?;
+
+    serde_json::to_value(report).map_err(|e| format!("Serialization error: {}", e))
+}
+
+pub fn sync_journal_vault(db: &Connection, vault_path: String) -> Result<Value, String> {
+    let report = journal_vault::sync_vault(db, &vault_path)?;
+
+    serde_json::to_value(report).map_err(|e| format!("Serialization error: {}", e))
+}
+
+// Folder sync commands
+pub fn enable_folder_sync(db: &Connection, folder: String) -> Result<Value, String> {
+    let store = sync::FolderStore::new(std::path::Path::new(&folder))?;
+    let engine = sync::SyncEngine::open(db, store)?;
+    sync::set_state(db, "folder", &folder)?;
+
+    Ok(serde_json::json!({
+        "device_id": engine.device_id(),
+        "folder": folder
+    }))
+}
+
+pub fn run_folder_sync(db: &Connection, folder: Option<String>) -> Result<Value, String> {
+    let folder = match folder {
+        Some(folder) => folder,
+        None => sync::get_state(db, "folder")?
+            .ok_or_else(|| "Folder sync is not set up. Choose a sync folder first".to_string())?,
+    };
+
+    let store = sync::FolderStore::new(std::path::Path::new(&folder))?;
+    let mut engine = sync::SyncEngine::open(db, store)?;
+    let report = engine.sync()?;
+
+    serde_json::to_value(report).map_err(|e| format!("Serialization error: {}", e))
+}
+
+pub fn get_sync_conflicts(db: &Connection) -> Result<Value, String> {
+    let conflicts = sync::list_conflicts(db)?;
+
+    serde_json::to_value(conflicts).map_err(|e| format!("Serialization error: {}", e))
+}
+
+pub fn resolve_sync_conflict(db: &Connection, conflict_id: String, keep: String) -> Result<Value, String> {
+    let conflict = sync::resolve_conflict(db, &conflict_id, &keep)?;
+
+    serde_json::to_value(conflict).map_err(|e| format!("Serialization error: {}", e))
+}
+
+// WebDAV sync commands
+pub fn configure_webdav_sync(
+    db: &Connection,
+    url: String,
+    username: Option<String>,
+    password: Option<String>
+) -> Result<Value, String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Invalid WebDAV URL: {}. It must start with http:// or https://", url));
+    }
+
+    // Make sure the server answers before saving anything.
+    let mut client = webdav::HttpDav::new(&url, username.as_deref(), password.as_deref());
+    webdav::DavClient::list(&mut client, "")?;
+
+    sync::set_state(db, "webdav_url", &url)?;
+    sync::set_state(db, "webdav_username", username.as_deref().unwrap_or_default())?;
+    sync::set_state(db, "webdav_password", password.as_deref().unwrap_or_default())?;
+
+    Ok(serde_json::json!({
+        "device_id": sync::device_id(db)?,
+        "url": url
+    }))
+}
+
+pub fn run_webdav_sync(db: &Connection) -> Result<Value, String> {
+    let url = sync::get_state(db, "webdav_url")?
+        .ok_or_else(|| "WebDAV sync is not set up. Configure a server first".to_string())?;
+    let username = sync::get_state(db, "webdav_username")?.filter(|u| !u.is_empty());
+    let password = sync::get_state(db, "webdav_password")?.filter(|p| !p.is_empty());
+
+    let store = webdav::WebDavStore::new(webdav::HttpDav::new(&url, username.as_deref(), password.as_deref()));
+    let mut engine = sync::SyncEngine::open(db, store)?;
+    let report = engine.sync()?;
+
+    serde_json::to_value(report).map_err(|e| format!("Serialization error: {}", e))
+}
+
+// Backup commands
+pub fn configure_backups(
+    db: &Connection,
+    directory: String,
+    interval_hours: u32,
+    keep: u32,
+    compress: bool,
+    passphrase: Option<String>
+) -> Result<Value, String> {
+    if directory.trim().is_empty() {
+        return Err("Backup folder cannot be empty".to_string());
+    }
+    std::fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create backup folder {}: {}", directory, e))?;
+
+    let settings = backup::save_settings(db, directory.trim(), interval_hours, keep, compress, passphrase.as_deref())?;
+
+    serde_json::to_value(settings).map_err(|e| format!("Serialization error: {}", e))
+}
+
+pub fn get_backup_settings(db: &Connection) -> Result<Value, String> {
+    let settings = backup::load_settings(db)?;
+
+    serde_json::to_value(settings).map_err(|e| format!("Serialization error: {}", e))
+}
+
+// The snapshot is copied a few pages at a time from `conn`, so callers should
+// pass a reader connection to keep writes going while it is taken.
+pub fn create_backup_now(
+    conn: &Connection,
+    directory: Option<String>,
+    passphrase: Option<String>
+) -> Result<Value, String> {
+    let directory = match directory {
+        Some(directory) => directory,
+        None => backup::load_settings(conn)?.directory
+            .ok_or_else(|| "No backup folder is set. Choose one first".to_string())?,
+    };
+    let mut options = backup::options_from_settings(conn)?;
+    if passphrase.is_some() {
+        options.passphrase = passphrase;
+    }
+
+    let report = backup::create_backup(conn, std::path::Path::new(&directory), &options, None)?;
+
+    serde_json::to_value(report).map_err(|e| format!("Serialization error: {}", e))
+}
+
+pub fn list_backups(db: &Connection, directory: Option<String>) -> Result<Value, String> {
+    let directory = match directory {
+        Some(directory) => directory,
+        None => backup::load_settings(db)?.directory
+            .ok_or_else(|| "No backup folder is set. Choose one first".to_string())?,
+    };
+
+    let backups = backup::list_backups(std::path::Path::new(&directory))?;
+
+    serde_json::to_value(backups).map_err(|e| format!("Serialization error: {}", e))
+}
+
+pub fn restore_backup(
+    db: &mut Connection,
+    file: String,
+    passphrase: Option<String>
+) -> Result<Value, String> {
+    let file = std::path::PathBuf::from(file);
+    if !file.is_file() {
+        return Err(format!("Backup not found: {}", file.display()));
+    }
+
+    // Keep a copy of the current data next to the other backups in case the restore was a mistake.
+    let safety_directory = match backup::load_settings(db)?.directory {
+        Some(directory) => std::path::PathBuf::from(directory),
+        None => file.parent().map(|p| p.to_path_buf()).unwrap_or_default(),
+    };
+    let mut options = backup::options_from_settings(db)?;
+    options.keep = 0;
+    let safety = backup::create_backup(db, &safety_directory, &options, Some("pre-restore"))?;
+
+    let report = backup::restore_backup(db, &file, passphrase.as_deref())?;
+
+    Ok(serde_json::json!({
+        "restore": report,
+        "safety_backup": safety.backup
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Connection, Persona, Workstream) {
+        let db = open_database(":memory:").unwrap();
+        let persona = create_persona(&db, "Work".into(), None, "#3366ff".into()).unwrap();
+        let workstream = create_workstream(&db, persona.id.clone(), "Launch".into(), None, "active".into(), None, None, None).unwrap();
+        (db, persona, workstream)
+    }
+
+    fn add_task(db: &Connection, workstream_id: &str, title: &str, status: &str) -> ProjectTask {
+        create_project_task(db, workstream_id.to_string(), title.to_string(), None, status.to_string(), "medium".to_string()).unwrap()
+    }
+
+    #[test]
+    fn moves_to_the_current_parent_are_rejected() {
+        let (db, persona, workstream) = setup();
+        let task = add_task(&db, &workstream.id, "Draft", "todo");
+        let milestone = create_milestone(&db, workstream.id.clone(), "Beta".into(), None, "2030-01-01".into()).unwrap();
+        set_task_milestone(&db, task.id.clone(), Some(milestone.milestone.id.clone())).unwrap();
+
+        let error = move_task_to_workstream(&db, task.id.clone(), workstream.id.clone()).unwrap_err();
+        assert!(error.contains("already"), "{}", error);
+        assert!(repository::get_task(&db, &task.id).unwrap().unwrap().milestone_id.is_some());
+
+        let error = move_workstream_to_persona(&db, workstream.id.clone(), persona.id.clone()).unwrap_err();
+        assert!(error.contains("already"), "{}", error);
+    }
+
+    #[test]
+    fn update_workstream_leaves_the_status_to_change_workstream_status() {
+        let (db, _, workstream) = setup();
+        let error = update_workstream(&db, workstream.id.clone(), None, None, Some("completed".into()), None, None, None).unwrap_err();
+        assert!(error.contains("change_workstream_status"), "{}", error);
+
+        let updated = update_workstream(&db, workstream.id.clone(), Some("Relaunch".into()), None, Some("active".into()), None, None, None).unwrap();
+        assert_eq!(updated.name, "Relaunch");
+        assert_eq!(updated.status.key(), "active");
+        assert!(updated.completed_date.is_none());
+    }
+
+    #[test]
+    fn status_updates_rank_only_tasks_that_change_column() {
+        let (db, _, workstream) = setup();
+        let first = add_task(&db, &workstream.id, "First", "todo");
+        let second = add_task(&db, &workstream.id, "Second", "todo");
+        let rank = |id: &str| repository::get_task(&db, id).unwrap().unwrap().rank;
+        let before = rank(&first.id);
+
+        update_task_status(&db, first.id.clone(), "todo".into()).unwrap();
+        assert_eq!(rank(&first.id), before);
+
+        set_task_recurrence(&db, second.id.clone(), Some("FREQ=DAILY".into()), true).unwrap();
+        let change = update_task_status(&db, second.id.clone(), "done".into()).unwrap();
+        assert!(change.next_occurrence.is_some());
+    }
+
+    #[test]
+    fn closing_open_tasks_follows_the_column_policies() {
+        let (db, _, workstream) = setup();
+        let task = add_task(&db, &workstream.id, "Report", "in_progress");
+        set_task_recurrence(&db, task.id.clone(), Some("FREQ=WEEKLY".into()), true).unwrap();
+        set_status_transition(&db, Some(workstream.id.clone()), "in_progress".into(), "done".into(), false, Some("block".into())).unwrap();
+
+        let error = change_workstream_status(&db, workstream.id.clone(), "completed".into(), false, false, Some("close".into()), None).unwrap_err();
+        assert!(error.contains("not allowed"), "{}", error);
+        assert_eq!(repository::get_workstream(&db, &workstream.id).unwrap().unwrap().status.key(), "active");
+
+        set_status_transition(&db, Some(workstream.id.clone()), "in_progress".into(), "done".into(), true, None).unwrap();
+        let change = change_workstream_status(&db, workstream.id.clone(), "completed".into(), false, false, Some("close".into()), None).unwrap();
+        assert_eq!(change.tasks_closed, 1);
+        let series: i64 = db
+            .query_row("SELECT COUNT(*) FROM project_tasks WHERE recurrence_series IS NOT NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(series, 2);
+    }
+
+    #[test]
+    fn archived_tasks_leave_column_counts_and_wip_limits() {
+        let (db, persona, workstream) = setup();
+        let other = create_workstream(&db, persona.id.clone(), "Next".into(), None, "active".into(), None, None, None).unwrap();
+        add_task(&db, &workstream.id, "Open", "todo");
+        add_task(&db, &workstream.id, "Also open", "todo");
+        set_wip_limit(&db, None, "todo".into(), 2, Some("block".into())).unwrap();
+        let waiting = add_task(&db, &other.id, "Waiting", "backlog");
+        assert!(update_task_status(&db, waiting.id.clone(), "todo".into()).is_err());
+
+        change_workstream_status(&db, workstream.id.clone(), "completed".into(), false, false, Some("archive".into()), None).unwrap();
+        let counts = get_task_counts_by_status(&db, Some(workstream.id.clone())).unwrap();
+        assert_eq!(counts["todo"], 0);
+        let counts = get_task_counts_by_status(&db, None).unwrap();
+        assert_eq!(counts["limits"]["todo"]["count"], 0);
+        update_task_status(&db, waiting.id.clone(), "todo".into()).unwrap();
+    }
+
+    #[test]
+    fn copied_tasks_keep_their_progress() {
+        let (db, _, workstream) = setup();
+        let first = add_task(&db, &workstream.id, "First", "todo");
+        let second = add_task(&db, &workstream.id, "Second", "todo");
+        let finished = add_task(&db, &workstream.id, "Shipped", "done");
+        add_checklist_item(&db, finished.id.clone(), "Announce".into()).unwrap();
+        db.execute("UPDATE task_checklist_items SET done = 1", []).unwrap();
+        let completed_date = repository::get_task(&db, &finished.id).unwrap().unwrap().completed_date;
+        assert!(completed_date.is_some());
+
+        let copy = copy_workstream(&db, workstream.id.clone(), None, None, false).unwrap();
+        assert!(!copy.progress_reset);
+        let todo = get_tasks_for_kanban(&db, Some(copy.workstream.id.clone()), Some("todo".into())).unwrap();
+        let titles: Vec<&str> = todo.iter().map(|task| task.task.title.as_str()).collect();
+        assert_eq!(titles, ["Second", "First"]);
+        let original = get_tasks_for_kanban(&db, Some(workstream.id.clone()), Some("todo".into())).unwrap();
+        assert_eq!((&original[0].task.id, &original[1].task.id), (&second.id, &first.id));
+        let done = get_tasks_for_kanban(&db, Some(copy.workstream.id.clone()), Some("done".into())).unwrap();
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].task.completed_date, completed_date);
+
+        let ticked: i64 = db.query_row(
+            "SELECT COUNT(*) FROM task_checklist_items c JOIN project_tasks pt ON c.task_id = pt.id WHERE pt.workstream_id = ?1 AND c.done = 1",
+            [&copy.workstream.id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(ticked, 1);
+        let burndown = get_workstream_burndown(&db, copy.workstream.id.clone(), None).unwrap();
+        assert_eq!((burndown.scope, burndown.completed), (3.0, 1.0));
+    }
+
+    #[test]
+    fn copied_tasks_start_over_as_todo_on_reset() {
+        let (db, _, workstream) = setup();
+        let first = add_task(&db, &workstream.id, "First", "todo");
+        let second = add_task(&db, &workstream.id, "Second", "todo");
+        let finished = add_task(&db, &workstream.id, "Shipped", "done");
+        add_checklist_item(&db, finished.id.clone(), "Announce".into()).unwrap();
+        db.execute("UPDATE task_checklist_items SET done = 1", []).unwrap();
+
+        let copy = copy_workstream(&db, workstream.id.clone(), None, None, true).unwrap();
+        assert!(copy.progress_reset);
+        let tasks = get_tasks_for_kanban(&db, Some(copy.workstream.id.clone()), Some("todo".into())).unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert!(tasks.iter().all(|task| task.task.completed_date.is_none()));
+        let titles: Vec<&str> = tasks.iter().map(|task| task.task.title.as_str()).collect();
+        let original = get_tasks_for_kanban(&db, Some(workstream.id.clone()), Some("todo".into())).unwrap();
+        assert_eq!(original[0].task.id, second.id);
+        assert_eq!(original[1].task.id, first.id);
+        assert_eq!(&titles[..2], ["Second", "First"]);
+
+        let unticked: i64 = db.query_row(
+            "SELECT COUNT(*) FROM task_checklist_items c JOIN project_tasks pt ON c.task_id = pt.id WHERE pt.workstream_id = ?1 AND c.done = 0",
+            [&copy.workstream.id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(unticked, 1);
+        let burndown = get_workstream_burndown(&db, copy.workstream.id.clone(), None).unwrap();
+        assert_eq!((burndown.scope, burndown.completed), (3.0, 0.0));
+    }
+
+    // Move a timer's clock back, as if it had been started `minutes_ago`.
+    fn backdate_timer(db: &Connection, id: &str, minutes_ago: i64, paused_minutes_ago: Option<i64>, seen_minutes_ago: i64) {
+        let now = get_current_timestamp();
+        let ago = |minutes: i64| (now - chrono::Duration::minutes(minutes)).to_rfc3339();
+        db.execute(
+            "UPDATE time_entries SET started_at = ?1, paused_at = ?2, last_seen_at = ?3 WHERE id = ?4",
+            rusqlite::params![ago(minutes_ago), paused_minutes_ago.map(ago), ago(seen_minutes_ago), id],
+        ).unwrap();
+    }
+
+    // To the minute or so, as timers run on the real clock.
+    fn actual_hours(db: &Connection, task_id: &str) -> Option<f32> {
+        repository::get_task(db, task_id).unwrap().unwrap().actual_hours.map(|hours| (hours * 100.0).round() / 100.0)
+    }
+
+    #[test]
+    fn starting_a_second_timer_stops_the_first() {
+        let (db, _, workstream) = setup();
+        let first = add_task(&db, &workstream.id, "First", "todo");
+        let second = add_task(&db, &workstream.id, "Second", "todo");
+        let timer = start_timer(&db, first.id.clone(), None).unwrap().timer;
+        backdate_timer(&db, &timer.entry.id, 30, None, 0);
+
+        let change = start_timer(&db, second.id.clone(), None).unwrap();
+        assert_eq!(change.stopped.len(), 1);
+        assert_eq!(change.stopped[0].entry.id, timer.entry.id);
+        assert!((1800..=1802).contains(&change.stopped[0].entry.duration_seconds.unwrap()));
+        assert_eq!(get_running_timer(&db).unwrap().unwrap().entry.task_id, second.id);
+        assert_eq!(actual_hours(&db, &first.id), Some(0.5));
+
+        // Starting the timed task again keeps the same timer.
+        let again = start_timer(&db, second.id, None).unwrap();
+        assert!(again.stopped.is_empty());
+        assert_eq!(again.timer.entry.id, change.timer.entry.id);
+    }
+
+    #[test]
+    fn pauses_are_left_out_of_the_time_worked() {
+        let (db, _, workstream) = setup();
+        let task = add_task(&db, &workstream.id, "Write", "todo");
+        let timer = start_timer(&db, task.id.clone(), None).unwrap().timer;
+        pause_timer(&db).unwrap();
+        assert_eq!(pause_timer(&db).unwrap_err(), "The timer is already paused");
+        // Started an hour ago and paused for the last twenty minutes.
+        backdate_timer(&db, &timer.entry.id, 60, Some(20), 20);
+
+        let resumed = resume_timer(&db).unwrap();
+        assert!((1199..=1201).contains(&resumed.entry.paused_seconds), "{}", resumed.entry.paused_seconds);
+        assert!(resumed.entry.paused_at.is_none());
+        assert_eq!(resume_timer(&db).unwrap_err(), "The timer is not paused");
+
+        let stopped = stop_timer(&db).unwrap();
+        assert!((2399..=2402).contains(&stopped.entry.duration_seconds.unwrap()));
+        assert!(get_running_timer(&db).unwrap().is_none());
+    }
+
+    #[test]
+    fn an_orphaned_timer_is_stopped_where_it_was_last_seen() {
+        let (db, _, workstream) = setup();
+        let crashed = add_task(&db, &workstream.id, "Crashed", "todo");
+        let next = add_task(&db, &workstream.id, "Next", "todo");
+        let timer = start_timer(&db, crashed.id.clone(), None).unwrap().timer;
+        backdate_timer(&db, &timer.entry.id, 120, None, 60);
+        let running = get_running_timer(&db).unwrap().unwrap();
+        assert!(running.orphaned);
+
+        // The heartbeat leaves it for the user to decide about.
+        timer_heartbeat(&db).unwrap();
+        assert!(get_running_timer(&db).unwrap().unwrap().orphaned);
+
+        let change = start_timer(&db, next.id, None).unwrap();
+        let stopped = &change.stopped[0].entry;
+        assert_eq!(stopped.ended_at, running.entry.last_seen_at);
+        assert_eq!(stopped.duration_seconds, Some(3600));
+        assert_eq!(actual_hours(&db, &crashed.id), Some(1.0));
+    }
+
+    #[test]
+    fn logged_time_keeps_actual_hours_in_sync() {
+        let (db, _, workstream) = setup();
+        let task = add_task(&db, &workstream.id, "Review", "todo");
+        let logged = add_time_entry(&db, task.id.clone(), "2026-03-02T09:00:00Z".into(), None, Some(90.0), None).unwrap();
+        assert_eq!(logged.entry.duration_seconds, Some(5400));
+        add_time_entry(&db, task.id.clone(), "2026-03-03T09:00:00Z".into(), Some("2026-03-03T09:30:00Z".into()), None, Some("Follow-up".into())).unwrap();
+        assert_eq!(actual_hours(&db, &task.id), Some(2.0));
+
+        update_time_entry(&db, logged.entry.id.clone(), None, Some("2026-03-02T10:00:00Z".into()), None).unwrap();
+        assert_eq!(actual_hours(&db, &task.id), Some(1.5));
+        assert_eq!(
+            update_time_entry(&db, logged.entry.id.clone(), Some("2026-03-02T11:00:00Z".into()), None, None).unwrap_err(),
+            "A time entry must end after it starts"
+        );
+
+        delete_time_entry(&db, logged.entry.id).unwrap();
+        assert_eq!(actual_hours(&db, &task.id), Some(0.5));
+        assert_eq!(
+            add_time_entry(&db, task.id.clone(), "2026-03-04T09:00:00Z".into(), Some("2026-03-04T10:00:00Z".into()), Some(60.0), None).unwrap_err(),
+            "Give either an end time or a number of minutes, not both"
+        );
+
+        // A running timer doesn't count until it stops.
+        start_timer(&db, task.id.clone(), None).unwrap();
+        assert_eq!(actual_hours(&db, &task.id), Some(0.5));
+    }
+
+    #[test]
+    fn peeking_at_the_focus_session_changes_nothing() {
+        let (db, _, _) = setup();
+        assert!(matches!(peek_focus_status(&db).unwrap(), (None, false)));
+        start_focus_session(&db, None).unwrap();
+        let (status, due) = peek_focus_status(&db).unwrap();
+        assert!(status.is_some_and(|status| status.running) && !due);
+
+        db.execute("UPDATE focus_sessions SET phase_ends_at = ?1", [(get_current_timestamp() - chrono::Duration::seconds(1)).to_rfc3339()]).unwrap();
+        let (status, due) = peek_focus_status(&db).unwrap();
+        assert!(due);
+        assert_eq!(status.unwrap().session.completed_pomodoros, 0);
+        let change = get_focus_status(&db).unwrap();
+        assert_eq!(change.status.unwrap().session.completed_pomodoros, 1);
+        assert!(!peek_focus_status(&db).unwrap().1);
+    }
+
+    #[test]
+    fn a_milestone_completes_with_its_last_task_and_reopens_with_it() {
+        let (db, persona, workstream) = setup();
+        let milestone = create_milestone(&db, workstream.id.clone(), "Beta".into(), None, "2099-12-31".into()).unwrap().milestone;
+        let first = add_task(&db, &workstream.id, "First", "todo");
+        let second = add_task(&db, &workstream.id, "Second", "todo");
+        for task in [&first, &second] {
+            set_task_milestone(&db, task.id.clone(), Some(milestone.id.clone())).unwrap();
+        }
+        let completed = |db: &Connection| get_milestone(db, &milestone.id).unwrap().milestone.completed_date.is_some();
+
+        update_task_status(&db, first.id.clone(), "done".into()).unwrap();
+        assert!(!completed(&db));
+        assert_eq!(get_milestone(&db, &milestone.id).unwrap().progress_percentage, 50);
+        update_task_status(&db, second.id.clone(), "done".into()).unwrap();
+        assert!(completed(&db));
+
+        update_task_status(&db, second.id.clone(), "inprogress".into()).unwrap();
+        assert!(!completed(&db));
+        // Taking the open task off leaves only finished ones.
+        set_task_milestone(&db, second.id.clone(), None).unwrap();
+        assert!(completed(&db));
+
+        let other = create_workstream(&db, persona.id, "Other".into(), None, "active".into(), None, None, None).unwrap();
+        let stranger = add_task(&db, &other.id, "Stranger", "todo");
+        assert_eq!(
+            set_task_milestone(&db, stranger.id, Some(milestone.id)).unwrap_err(),
+            "Milestone 'Beta' belongs to another workstream"
+        );
+    }
+
+    #[test]
+    fn overdue_milestones_stay_in_the_upcoming_deadlines() {
+        let (db, _, workstream) = setup();
+        let today = chrono::Local::now().date_naive();
+        let day = |offset: i64| (today + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+        let late = create_milestone(&db, workstream.id.clone(), "Late".into(), None, day(-3)).unwrap().milestone;
+        create_milestone(&db, workstream.id.clone(), "Later".into(), None, day(30)).unwrap();
+        let task = add_task(&db, &workstream.id, "Finish", "todo");
+        set_task_milestone(&db, task.id.clone(), Some(late.id.clone())).unwrap();
+
+        let deadlines = get_upcoming_deadlines(&db, None).unwrap();
+        let milestones: Vec<(&str, bool, Option<u8>)> = deadlines
+            .iter()
+            .filter(|deadline| deadline.kind == "milestone")
+            .map(|deadline| (deadline.title.as_str(), deadline.overdue, deadline.progress_percentage))
+            .collect();
+        assert_eq!(milestones, [("Late", true, Some(0))]);
+        assert_eq!(deadlines[0].due_date, day(-3));
+        assert_eq!(get_upcoming_deadlines(&db, Some(60)).unwrap().len(), 2);
+
+        update_task_status(&db, task.id, "done".into()).unwrap();
+        assert!(get_upcoming_deadlines(&db, None).unwrap().is_empty());
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use uuid::Uuid;
 
 // Database Models
@@ -45,6 +45,13 @@ pub struct ProjectTask {
     pub actual_hours: Option<f32>,
     pub tags: Vec<String>,
     pub dependencies: Vec<String>,
+    pub rank: Option<String>, // manual kanban order within a status column
+    pub workflow_state: Option<String>, // state key on a custom workflow
+    pub parent_id: Option<String>, // set on subtasks
+    pub subtask_rank: Option<String>, // order among the parent's subtasks
+    pub milestone_id: Option<String>,
+    pub archived_at: Option<DateTime<Utc>>, // set when archived with a completed workstream
+    pub recurrence: Option<TaskRecurrence>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -88,6 +95,175 @@ pub struct JournalEntry {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedFilter {
+    pub id: String,
+    pub name: String,
+    pub kind: SavedFilterKind,
+    pub query: serde_json::Value, // TaskQuery or JournalQuery, see filters.rs
+    pub pinned: bool,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Kanban column policies; a workstream_id of None applies to the whole board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WipLimit {
+    pub id: String,
+    pub workstream_id: Option<String>,
+    pub status: TaskStatus,
+    pub max_tasks: i64,
+    pub enforcement: PolicyEnforcement,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A workstream rule for a move overrides the board-wide one; moves without a rule are allowed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub id: String,
+    pub workstream_id: Option<String>,
+    pub from_status: TaskStatus,
+    pub to_status: TaskStatus,
+    pub allowed: bool,
+    pub enforcement: PolicyEnforcement,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// How a task repeats; see recurrence.rs. Completing an occurrence creates the
+// next one of the same series.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecurrence {
+    pub rule: String, // RRULE, e.g. FREQ=WEEKLY;BYDAY=MO,TH
+    pub from_completion: bool, // count from the day an occurrence is done instead of the schedule
+    pub start: NaiveDate, // DTSTART: the series' first due day
+    pub series_id: String,
+    pub index: i64, // 1 for the first occurrence
+}
+
+// A dated goal inside a workstream. It completes by itself once all its tasks
+// are done, and opens again when one of them is reopened or added.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    pub id: String,
+    pub workstream_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub due_date: DateTime<Utc>,
+    pub completed_date: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub id: String,
+    pub task_id: String,
+    pub text: String,
+    pub done: bool,
+    pub rank: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// What has to be finished inside a task before it can move to done; a
+// workstream's policy overrides the board-wide one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionPolicy {
+    pub id: String,
+    pub workstream_id: Option<String>,
+    pub require_subtasks_done: bool,
+    pub require_checklist_done: bool,
+    pub enforcement: PolicyEnforcement,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Named task states for one workstream or for every workstream of a persona.
+// Tasks keep the built-in status of their state's category, so kanban
+// policies, due dates and analytics work the same on any workflow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    pub id: String, // "default" for the built-in workflow of the five statuses
+    pub name: String,
+    pub persona_id: Option<String>,
+    pub workstream_id: Option<String>,
+    pub states: Vec<WorkflowState>, // in board order
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowState {
+    pub id: String,
+    pub workflow_id: String,
+    pub key: String,
+    pub name: String,
+    pub category: StateCategory,
+    pub position: i64,
+}
+
+// Time spent on a task. A timer entry runs until ended_at is set; time spent
+// paused is kept out of its duration. The app refreshes last_seen_at of the
+// running timer while it is open, so a timer left running by a crash shows up
+// as orphaned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: String,
+    pub task_id: String,
+    pub source: TimeEntrySource,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>, // None while the timer runs
+    pub paused_at: Option<DateTime<Utc>>,
+    pub paused_seconds: i64,
+    pub duration_seconds: Option<i64>, // set once the entry has ended
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A run of pomodoros, optionally on one task. The lengths are copied from the
+// settings when it starts. phase_ends_at is set while the phase counts down;
+// a paused phase, or one waiting to be started, keeps remaining_seconds instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSession {
+    pub id: String,
+    pub task_id: Option<String>,
+    pub phase: FocusPhase,
+    pub cycle: i64, // number of the current or last work phase, from 1
+    pub work_seconds: i64,
+    pub short_break_seconds: i64,
+    pub long_break_seconds: i64,
+    pub long_break_every: i64,
+    pub auto_start_breaks: bool,
+    pub auto_start_work: bool,
+    pub phase_started_at: Option<DateTime<Utc>>, // None until a waiting phase is started
+    pub phase_ends_at: Option<DateTime<Utc>>,
+    pub remaining_seconds: Option<i64>,
+    pub completed_pomodoros: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// One work phase of a focus session, finished or cut short.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pomodoro {
+    pub id: String,
+    pub session_id: String,
+    pub task_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub focus_seconds: i64,
+    pub completed: bool,
+    pub time_entry_id: Option<String>, // the time logged on the task for a completed pomodoro
+    pub created_at: DateTime<Utc>,
+}
+
 // Enums
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,6 +307,40 @@ pub enum JournalEntryType {
     Meeting,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavedFilterKind {
+    Tasks,
+    Journal,
+}
+
+// Whether breaking a column policy refuses the move or only warns about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyEnforcement {
+    Warn,
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeEntrySource {
+    Timer,
+    Manual,
+    Pomodoro,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FocusPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateCategory {
+    NotStarted,
+    Active,
+    Done,
+}
+
 // Database Schema Creation
 
 pub fn get_database_schema() -> &'static str {
@@ -225,9 +435,359 @@ pub fn get_database_schema() -> &'static str {
         FOREIGN KEY (workstream_id) REFERENCES workstreams (id),
         FOREIGN KEY (persona_id) REFERENCES personas (id)
     );
+
+    -- journal_vault_files table (last synced state of each Markdown mirror)
+    -- entry_id has no foreign key: the row outlives a deleted entry so the
+    -- vault sync can tell a file of a deleted entry from a new one.
+    CREATE TABLE IF NOT EXISTS journal_vault_files (
+        entry_id TEXT PRIMARY KEY,
+        relative_path TEXT NOT NULL,
+        content_hash TEXT NOT NULL,
+        synced_at TEXT NOT NULL
+    );
+
+    -- sync_state table (device ID, clock, cursors and flags of the sync engine)
+    CREATE TABLE IF NOT EXISTS sync_state (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    -- sync_outbox table (row changes captured by triggers, waiting to be pushed)
+    CREATE TABLE IF NOT EXISTS sync_outbox (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        table_name TEXT NOT NULL,
+        row_id TEXT NOT NULL,
+        op TEXT NOT NULL,
+        row_json TEXT,
+        changed_at TEXT NOT NULL
+    );
+
+    -- sync_field_clocks table (last known value and clock of every synced field)
+    CREATE TABLE IF NOT EXISTS sync_field_clocks (
+        table_name TEXT NOT NULL,
+        row_id TEXT NOT NULL,
+        field TEXT NOT NULL,
+        hlc TEXT NOT NULL,
+        value TEXT,
+        PRIMARY KEY (table_name, row_id, field)
+    );
+
+    -- sync_conflicts table (concurrent edits waiting for a human decision)
+    CREATE TABLE IF NOT EXISTS sync_conflicts (
+        id TEXT PRIMARY KEY,
+        table_name TEXT NOT NULL,
+        row_id TEXT NOT NULL,
+        field TEXT NOT NULL,
+        local_value TEXT,
+        remote_value TEXT,
+        local_hlc TEXT,
+        remote_hlc TEXT NOT NULL,
+        remote_device TEXT NOT NULL,
+        winner TEXT NOT NULL,
+        detected_at TEXT NOT NULL,
+        resolved_at TEXT,
+        resolution TEXT
+    );
+
+    -- sync_upload_queue table (change sets waiting to be uploaded to a remote store)
+    CREATE TABLE IF NOT EXISTS sync_upload_queue (
+        segment TEXT PRIMARY KEY,
+        device TEXT NOT NULL,
+        body TEXT NOT NULL,
+        queued_at TEXT NOT NULL
+    );
+
+    -- app_settings table (device-local preferences such as the backup schedule)
+    CREATE TABLE IF NOT EXISTS app_settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
     "#
 }
 
+// Schema migrations
+//
+// Applied in order on top of the base schema. The base schema is version 1 and
+// the highest applied version is kept in PRAGMA user_version.
+pub struct SchemaMigration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub fn get_schema_migrations() -> &'static [SchemaMigration] {
+    &[
+        SchemaMigration {
+            version: 2,
+            description: "add indexes for the kanban, agenda, habit and journal queries",
+            sql: r#"
+            CREATE INDEX IF NOT EXISTS idx_workstreams_persona ON workstreams (persona_id);
+
+            -- kanban filter and status counts per workstream
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_workstream_status ON project_tasks (workstream_id, status);
+            -- statuses are stored in more than one spelling, so the filters compare a normalised key
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_status_key ON project_tasks (LOWER(TRIM(status, '"')));
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_due_date ON project_tasks (due_date);
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_created_at ON project_tasks (created_at);
+
+            CREATE INDEX IF NOT EXISTS idx_habit_trackers_workstream ON habit_trackers (workstream_id);
+            CREATE INDEX IF NOT EXISTS idx_habit_completions_date ON habit_completions (date);
+
+            CREATE INDEX IF NOT EXISTS idx_journal_entries_created_at ON journal_entries (created_at);
+            CREATE INDEX IF NOT EXISTS idx_journal_entries_workstream ON journal_entries (workstream_id, created_at);
+            CREATE INDEX IF NOT EXISTS idx_journal_entries_persona ON journal_entries (persona_id, created_at);
+            "#,
+        },
+        SchemaMigration {
+            version: 3,
+            description: "add manual rank to project tasks",
+            sql: r#"
+            ALTER TABLE project_tasks ADD COLUMN rank TEXT;
+            "#,
+        },
+        SchemaMigration {
+            version: 4,
+            description: "add saved filters",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS saved_filters (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                query TEXT NOT NULL, -- JSON
+                pinned BOOLEAN NOT NULL DEFAULT 0,
+                position INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            "#,
+        },
+        SchemaMigration {
+            version: 5,
+            description: "index kanban columns by rank",
+            sql: r#"
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_status_rank ON project_tasks (LOWER(TRIM(status, '"')), rank);
+            "#,
+        },
+        SchemaMigration {
+            version: 6,
+            description: "add kanban WIP limits and transition rules",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS wip_limits (
+                id TEXT PRIMARY KEY,
+                workstream_id TEXT, -- NULL for the whole board
+                status TEXT NOT NULL,
+                max_tasks INTEGER NOT NULL,
+                enforcement TEXT NOT NULL DEFAULT 'warn',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (workstream_id) REFERENCES workstreams (id)
+            );
+
+            CREATE TABLE IF NOT EXISTS status_transitions (
+                id TEXT PRIMARY KEY,
+                workstream_id TEXT, -- NULL for the whole board
+                from_status TEXT NOT NULL,
+                to_status TEXT NOT NULL,
+                allowed BOOLEAN NOT NULL,
+                enforcement TEXT NOT NULL DEFAULT 'block',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (workstream_id) REFERENCES workstreams (id)
+            );
+            "#,
+        },
+        SchemaMigration {
+            version: 7,
+            description: "add custom workflow states",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS workflows (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                persona_id TEXT, -- set for a persona's workflow
+                workstream_id TEXT, -- set for a workstream's own workflow
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (persona_id) REFERENCES personas (id),
+                FOREIGN KEY (workstream_id) REFERENCES workstreams (id)
+            );
+
+            CREATE TABLE IF NOT EXISTS workflow_states (
+                id TEXT PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                name TEXT NOT NULL,
+                category TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY (workflow_id) REFERENCES workflows (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_workflow_states_workflow ON workflow_states (workflow_id, position);
+
+            -- NULL for tasks on the default workflow, whose state is their status
+            ALTER TABLE project_tasks ADD COLUMN workflow_state TEXT;
+            "#,
+        },
+        SchemaMigration {
+            version: 8,
+            description: "add subtasks, checklists and completion policies",
+            sql: r#"
+            ALTER TABLE project_tasks ADD COLUMN parent_id TEXT;
+            ALTER TABLE project_tasks ADD COLUMN subtask_rank TEXT;
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_parent ON project_tasks (parent_id, subtask_rank);
+
+            CREATE TABLE IF NOT EXISTS task_checklist_items (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                text TEXT NOT NULL,
+                done BOOLEAN NOT NULL DEFAULT 0,
+                rank TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (task_id) REFERENCES project_tasks (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_checklist_items_task ON task_checklist_items (task_id, rank);
+
+            CREATE TABLE IF NOT EXISTS completion_policies (
+                id TEXT PRIMARY KEY,
+                workstream_id TEXT, -- NULL for the whole board
+                require_subtasks_done BOOLEAN NOT NULL DEFAULT 1,
+                require_checklist_done BOOLEAN NOT NULL DEFAULT 0,
+                enforcement TEXT NOT NULL DEFAULT 'block',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (workstream_id) REFERENCES workstreams (id)
+            );
+            "#,
+        },
+        SchemaMigration {
+            version: 9,
+            description: "add recurring tasks",
+            sql: r#"
+            ALTER TABLE project_tasks ADD COLUMN recurrence_rule TEXT;
+            ALTER TABLE project_tasks ADD COLUMN recurrence_from_completion BOOLEAN NOT NULL DEFAULT 0;
+            ALTER TABLE project_tasks ADD COLUMN recurrence_start TEXT;
+            ALTER TABLE project_tasks ADD COLUMN recurrence_series TEXT;
+            ALTER TABLE project_tasks ADD COLUMN recurrence_index INTEGER;
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_recurrence ON project_tasks (recurrence_series, recurrence_index);
+            "#,
+        },
+        SchemaMigration {
+            version: 10,
+            description: "add time tracking",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS time_entries (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                source TEXT NOT NULL DEFAULT 'manual',
+                started_at TEXT NOT NULL,
+                ended_at TEXT, -- NULL while the timer runs
+                paused_at TEXT,
+                paused_seconds INTEGER NOT NULL DEFAULT 0,
+                duration_seconds INTEGER,
+                last_seen_at TEXT,
+                note TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (task_id) REFERENCES project_tasks (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_time_entries_task ON time_entries (task_id, started_at);
+            CREATE INDEX IF NOT EXISTS idx_time_entries_started ON time_entries (started_at);
+            CREATE INDEX IF NOT EXISTS idx_time_entries_running ON time_entries (ended_at) WHERE ended_at IS NULL;
+            "#,
+        },
+        SchemaMigration {
+            version: 11,
+            description: "add pomodoro focus sessions",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS focus_sessions (
+                id TEXT PRIMARY KEY,
+                task_id TEXT,
+                phase TEXT NOT NULL DEFAULT 'work',
+                cycle INTEGER NOT NULL DEFAULT 1,
+                work_seconds INTEGER NOT NULL,
+                short_break_seconds INTEGER NOT NULL,
+                long_break_seconds INTEGER NOT NULL,
+                long_break_every INTEGER NOT NULL,
+                auto_start_breaks BOOLEAN NOT NULL DEFAULT 1,
+                auto_start_work BOOLEAN NOT NULL DEFAULT 0,
+                phase_started_at TEXT,
+                phase_ends_at TEXT,
+                remaining_seconds INTEGER,
+                completed_pomodoros INTEGER NOT NULL DEFAULT 0,
+                started_at TEXT NOT NULL,
+                ended_at TEXT, -- NULL while the session is active
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (task_id) REFERENCES project_tasks (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_focus_sessions_active ON focus_sessions (ended_at) WHERE ended_at IS NULL;
+
+            CREATE TABLE IF NOT EXISTS pomodoros (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                task_id TEXT,
+                started_at TEXT NOT NULL,
+                ended_at TEXT NOT NULL,
+                focus_seconds INTEGER NOT NULL,
+                completed BOOLEAN NOT NULL,
+                time_entry_id TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES focus_sessions (id),
+                FOREIGN KEY (task_id) REFERENCES project_tasks (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_pomodoros_ended ON pomodoros (ended_at);
+            CREATE INDEX IF NOT EXISTS idx_pomodoros_session ON pomodoros (session_id);
+            "#,
+        },
+        SchemaMigration {
+            version: 12,
+            description: "add task status history",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS task_status_history (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                from_status TEXT, -- NULL when the task was created
+                to_status TEXT NOT NULL,
+                changed_at TEXT NOT NULL,
+                FOREIGN KEY (task_id) REFERENCES project_tasks (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_status_history_task ON task_status_history (task_id, changed_at);
+
+            -- completion dates weren't recorded before; the last update is the best guess
+            UPDATE project_tasks SET completed_date = updated_at
+            WHERE completed_date IS NULL AND LOWER(TRIM(status, '"')) = 'done';
+            "#,
+        },
+        SchemaMigration {
+            version: 13,
+            description: "add milestones",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS milestones (
+                id TEXT PRIMARY KEY,
+                workstream_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT,
+                due_date TEXT NOT NULL,
+                completed_date TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (workstream_id) REFERENCES workstreams (id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_milestones_workstream ON milestones (workstream_id, due_date);
+
+            ALTER TABLE project_tasks ADD COLUMN milestone_id TEXT REFERENCES milestones (id);
+            CREATE INDEX IF NOT EXISTS idx_project_tasks_milestone ON project_tasks (milestone_id);
+            "#,
+        },
+        SchemaMigration {
+            version: 14,
+            description: "add archived tasks",
+            sql: r#"
+            ALTER TABLE project_tasks ADD COLUMN archived_at TEXT;
+            "#,
+        },
+    ]
+}
+
 // Helper functions for ID generation
 pub fn generate_id() -> String {
     Uuid::new_v4().to_string()
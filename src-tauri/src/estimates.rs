@@ -0,0 +1,432 @@
+use crate::filters;
+use crate::repository::{self, TaskView};
+use chrono::{DateTime, Datelike, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+// How actual hours compare with estimates on finished tasks. Each task is
+// measured on its own estimate and its own logged hours, never on the
+// subtask roll-up, so a parent and its subtasks don't count the same hours
+// twice. Tasks without an estimate or without logged time tell us nothing
+// and are left out.
+
+// A correction factor is only suggested once a group has this many tasks.
+pub const MIN_SAMPLES: usize = 5;
+
+// Tasks further than this many interquartile ranges outside the middle half
+// of the (log) ratios are reported as outliers, but never tasks within a
+// factor of two of it, which matters when most ratios are alike.
+const OUTLIER_FENCE: f64 = 1.5;
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub task: TaskView,
+    pub finished_at: DateTime<Utc>,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+}
+
+impl Sample {
+    pub fn ratio(&self) -> f64 {
+        self.actual_hours / self.estimated_hours
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccuracyStats {
+    pub tasks: usize,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+    pub ratio: Option<f64>, // total actual over total estimated hours
+    pub median_ratio: Option<f64>,
+    pub correction_factor: Option<f64>, // the median ratio, once there are MIN_SAMPLES tasks
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccuracyPeriod {
+    pub period: String,
+    #[serde(flatten)]
+    pub stats: AccuracyStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccuracyGroup {
+    pub key: String,
+    pub label: String,
+    #[serde(flatten)]
+    pub stats: AccuracyStats,
+    pub periods: Vec<AccuracyPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateOutlier {
+    pub task_id: String,
+    pub title: String,
+    pub workstream_name: String,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+    pub ratio: f64,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateAccuracy {
+    pub group_by: String,
+    pub period: String,
+    pub from: String,
+    pub to: String,
+    #[serde(flatten)]
+    pub overall: AccuracyStats,
+    pub periods: Vec<AccuracyPeriod>,
+    pub groups: Vec<AccuracyGroup>,
+    pub outliers: Vec<EstimateOutlier>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibratedEstimate {
+    pub estimated_hours: f64,
+    pub calibrated_hours: f64,
+    pub factor: f64,
+    pub basis: String, // workstream, persona or all
+    pub label: String,
+    pub tasks: usize,
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) { (values[middle - 1] + values[middle]) / 2.0 } else { values[middle] })
+}
+
+// Linear interpolation between the closest ranks of sorted values.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = (sorted.len() - 1) as f64 * q;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+pub fn summarize(samples: &[&Sample]) -> AccuracyStats {
+    let estimated: f64 = samples.iter().map(|sample| sample.estimated_hours).sum();
+    let actual: f64 = samples.iter().map(|sample| sample.actual_hours).sum();
+    let median_ratio = median(&mut samples.iter().map(|sample| sample.ratio()).collect::<Vec<_>>());
+    AccuracyStats {
+        tasks: samples.len(),
+        estimated_hours: round(estimated),
+        actual_hours: round(actual),
+        ratio: (estimated > 0.0).then(|| round(actual / estimated)),
+        median_ratio: median_ratio.map(round),
+        correction_factor: median_ratio.filter(|_| samples.len() >= MIN_SAMPLES).map(round),
+    }
+}
+
+// Ratios are compared on a log scale so that taking twice as long and half
+// as long are the same distance from the estimate.
+pub fn outliers(samples: &[&Sample]) -> Vec<EstimateOutlier> {
+    if samples.len() < 4 {
+        return Vec::new();
+    }
+    let mut logs: Vec<f64> = samples.iter().map(|sample| sample.ratio().ln()).collect();
+    logs.sort_by(f64::total_cmp);
+    let (q1, q3) = (quantile(&logs, 0.25), quantile(&logs, 0.75));
+    let fence = ((q3 - q1) * OUTLIER_FENCE).max(2f64.ln());
+    let mut outliers: Vec<EstimateOutlier> = samples
+        .iter()
+        .filter(|sample| {
+            let log = sample.ratio().ln();
+            log < q1 - fence || log > q3 + fence
+        })
+        .map(|sample| EstimateOutlier {
+            task_id: sample.task.task.id.clone(),
+            title: sample.task.task.title.clone(),
+            workstream_name: sample.task.workstream_name.clone(),
+            estimated_hours: round(sample.estimated_hours),
+            actual_hours: round(sample.actual_hours),
+            ratio: round(sample.ratio()),
+            finished_at: sample.finished_at,
+        })
+        .collect();
+    outliers.sort_by(|a, b| b.ratio.ln().abs().total_cmp(&a.ratio.ln().abs()));
+    outliers
+}
+
+// Finished tasks with both an estimate and logged time, finished within
+// [start, end). Tasks finished before completion dates were recorded fall
+// back on their last update.
+pub fn load_samples(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    persona_id: Option<&str>,
+    workstream_id: Option<&str>,
+) -> Result<Vec<Sample>, String> {
+    let tasks: Vec<TaskView> = repository::query_all(
+        conn,
+        &format!(
+            "{} WHERE {} = 'done' AND pt.estimated_hours > 0 AND pt.actual_hours > 0
+               AND COALESCE(pt.completed_date, pt.updated_at) >= ?1 AND COALESCE(pt.completed_date, pt.updated_at) < ?2
+               AND (?3 IS NULL OR w.persona_id = ?3) AND (?4 IS NULL OR pt.workstream_id = ?4)
+             ORDER BY COALESCE(pt.completed_date, pt.updated_at)",
+            repository::TASK_VIEW_SELECT,
+            filters::STATUS_KEY
+        ),
+        rusqlite::params![start, end, persona_id, workstream_id],
+    )?;
+    Ok(tasks
+        .into_iter()
+        .map(|task| Sample {
+            finished_at: task.task.completed_date.unwrap_or(task.task.updated_at),
+            estimated_hours: task.task.estimated_hours.unwrap_or_default() as f64,
+            actual_hours: task.task.actual_hours.unwrap_or_default() as f64,
+            task,
+        })
+        .collect())
+}
+
+// The local week (its Monday) or month a sample was finished in.
+fn period_key(sample: &Sample, period: &str) -> String {
+    let day = sample.finished_at.with_timezone(&chrono::Local).date_naive();
+    if period == "week" {
+        (day - chrono::Days::new(day.weekday().num_days_from_monday() as u64)).format("%Y-%m-%d").to_string()
+    } else {
+        day.format("%Y-%m").to_string()
+    }
+}
+
+fn by_period(samples: &[&Sample], period: &str) -> Vec<AccuracyPeriod> {
+    let mut periods: Vec<(String, Vec<&Sample>)> = Vec::new();
+    for sample in samples {
+        let key = period_key(sample, period);
+        match periods.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, members)) => members.push(sample),
+            None => periods.push((key, vec![sample])),
+        }
+    }
+    periods.sort_by(|a, b| a.0.cmp(&b.0));
+    periods
+        .into_iter()
+        .map(|(period, members)| AccuracyPeriod { period, stats: summarize(&members) })
+        .collect()
+}
+
+// The groups a sample belongs to; a task with several tags counts once in each.
+fn group_keys(sample: &Sample, group_by: &str) -> Vec<(String, String)> {
+    let view = &sample.task;
+    match group_by {
+        "persona" => vec![(view.persona_id.clone(), view.persona_name.clone())],
+        "workstream" => vec![(view.task.workstream_id.clone(), view.workstream_name.clone())],
+        "priority" => {
+            let key = view.task.priority.key().to_string();
+            vec![(key.clone(), key)]
+        }
+        _ if view.task.tags.is_empty() => vec![(String::new(), "(untagged)".to_string())],
+        _ => {
+            let mut tags: Vec<String> = view.task.tags.iter().map(|tag| tag.to_lowercase()).collect();
+            tags.sort();
+            tags.dedup();
+            tags.into_iter().map(|tag| (tag.clone(), tag)).collect()
+        }
+    }
+}
+
+pub fn accuracy(samples: &[Sample], group_by: &str, period: &str) -> (AccuracyStats, Vec<AccuracyPeriod>, Vec<AccuracyGroup>, Vec<EstimateOutlier>) {
+    let all: Vec<&Sample> = samples.iter().collect();
+    let mut groups: Vec<(String, String, Vec<&Sample>)> = Vec::new();
+    for sample in samples {
+        for (key, label) in group_keys(sample, group_by) {
+            match groups.iter_mut().find(|(existing, _, _)| *existing == key) {
+                Some((_, _, members)) => members.push(sample),
+                None => groups.push((key, label, vec![sample])),
+            }
+        }
+    }
+    let mut groups: Vec<AccuracyGroup> = groups
+        .into_iter()
+        .map(|(key, label, members)| AccuracyGroup {
+            key,
+            label,
+            stats: summarize(&members),
+            periods: by_period(&members, period),
+        })
+        .collect();
+    groups.sort_by(|a, b| b.stats.tasks.cmp(&a.stats.tasks).then_with(|| a.label.cmp(&b.label)));
+    (summarize(&all), by_period(&all, period), groups, outliers(&all))
+}
+
+// Scale an estimate by how the most specific history with enough finished
+// tasks has run over: the workstream, then its persona, then everything.
+pub fn calibrate(conn: &Connection, workstream_id: &str, estimated_hours: f64, samples: &[Sample]) -> Result<Option<CalibratedEstimate>, String> {
+    let (persona_id, workstream_name, persona_name): (String, String, String) = conn
+        .query_row(
+            "SELECT w.persona_id, w.name, p.name FROM workstreams w JOIN personas p ON w.persona_id = p.id WHERE w.id = ?1",
+            [workstream_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Workstream with ID '{}' not found", workstream_id),
+            e => format!("SQL query error: {}", e),
+        })?;
+
+    let candidates: [(&str, String, Vec<&Sample>); 3] = [
+        ("workstream", workstream_name, samples.iter().filter(|sample| sample.task.task.workstream_id == workstream_id).collect()),
+        ("persona", persona_name, samples.iter().filter(|sample| sample.task.persona_id == persona_id).collect()),
+        ("all", "All tasks".to_string(), samples.iter().collect()),
+    ];
+    Ok(candidates.into_iter().find_map(|(basis, label, members)| {
+        let stats = summarize(&members);
+        stats.correction_factor.map(|factor| CalibratedEstimate {
+            estimated_hours,
+            calibrated_hours: round(estimated_hours * factor),
+            factor,
+            basis: basis.to_string(),
+            label,
+            tasks: stats.tasks,
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    struct Fixture {
+        db: Connection,
+        persona_id: String,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let db = commands::open_database(":memory:").unwrap();
+            let persona_id = commands::create_persona(&db, "Work".into(), None, "#3366ff".into()).unwrap().id;
+            Fixture { db, persona_id }
+        }
+
+        fn workstream(&self, name: &str) -> String {
+            commands::create_workstream(&self.db, self.persona_id.clone(), name.into(), None, "active".into(), None, None, None).unwrap().id
+        }
+
+        // A task finished at noon UTC on a mid-week day, so the local week
+        // and month it falls in don't depend on the time zone.
+        fn finished(&self, workstream_id: &str, title: &str, estimated: f64, actual: f64, day: &str, tags: &[&str]) -> String {
+            let id = commands::create_project_task(&self.db, workstream_id.into(), title.into(), None, "todo".into(), "medium".into()).unwrap().id;
+            self.db
+                .execute(
+                    "UPDATE project_tasks SET status = 'done', estimated_hours = ?2, actual_hours = ?3, completed_date = ?4, tags = ?5 WHERE id = ?1",
+                    rusqlite::params![id, estimated, actual, format!("{}T12:00:00Z", day), serde_json::to_string(tags).unwrap()],
+                )
+                .unwrap();
+            id
+        }
+
+        fn samples(&self) -> Vec<Sample> {
+            load_samples(&self.db, "2026-01-01", "2027-01-01", None, None).unwrap()
+        }
+    }
+
+    #[test]
+    fn a_correction_factor_needs_enough_tasks() {
+        let fixture = Fixture::new();
+        let ws = fixture.workstream("Launch");
+        for (i, actual) in [1.0, 2.0, 3.0, 8.0].into_iter().enumerate() {
+            fixture.finished(&ws, &format!("Task {}", i), 2.0, actual, "2026-03-11", &[]);
+        }
+        let samples = fixture.samples();
+        let stats = summarize(&samples.iter().collect::<Vec<_>>());
+        assert_eq!((stats.tasks, stats.estimated_hours, stats.actual_hours), (4, 8.0, 14.0));
+        assert_eq!(stats.ratio, Some(1.75));
+        assert_eq!(stats.median_ratio, Some(1.25));
+        assert_eq!(stats.correction_factor, None);
+
+        fixture.finished(&ws, "Task 4", 2.0, 3.0, "2026-03-11", &[]);
+        let samples = fixture.samples();
+        let stats = summarize(&samples.iter().collect::<Vec<_>>());
+        assert_eq!(stats.correction_factor, Some(1.5));
+        assert_eq!(summarize(&[]).ratio, None);
+    }
+
+    #[test]
+    fn tasks_without_an_estimate_or_logged_time_are_left_out() {
+        let fixture = Fixture::new();
+        let ws = fixture.workstream("Launch");
+        fixture.finished(&ws, "Counted", 2.0, 3.0, "2026-03-11", &[]);
+        fixture.finished(&ws, "No estimate", 0.0, 3.0, "2026-03-11", &[]);
+        fixture.finished(&ws, "No time", 2.0, 0.0, "2026-03-11", &[]);
+        fixture.finished(&ws, "Too late", 2.0, 3.0, "2027-02-10", &[]);
+        let open = commands::create_project_task(&fixture.db, ws, "Open".into(), None, "todo".into(), "medium".into()).unwrap().id;
+        fixture.db.execute("UPDATE project_tasks SET estimated_hours = 2, actual_hours = 1 WHERE id = ?1", [&open]).unwrap();
+
+        let titles: Vec<String> = fixture.samples().into_iter().map(|sample| sample.task.task.title).collect();
+        assert_eq!(titles, ["Counted"]);
+    }
+
+    #[test]
+    fn outliers_are_far_from_the_middle_on_a_log_scale() {
+        let fixture = Fixture::new();
+        let ws = fixture.workstream("Launch");
+        for (i, actual) in [4.0, 4.0, 4.0, 4.0, 7.0].into_iter().enumerate() {
+            fixture.finished(&ws, &format!("Usual {}", i), 4.0, actual, "2026-03-11", &[]);
+        }
+        let samples = fixture.samples();
+        assert!(outliers(&samples.iter().collect::<Vec<_>>()).is_empty());
+
+        fixture.finished(&ws, "Blew up", 1.0, 6.0, "2026-03-11", &[]);
+        fixture.finished(&ws, "Overestimated", 8.0, 1.0, "2026-03-11", &[]);
+        let samples = fixture.samples();
+        let found = outliers(&samples.iter().collect::<Vec<_>>());
+        let titles: Vec<&str> = found.iter().map(|outlier| outlier.title.as_str()).collect();
+        assert_eq!(titles, ["Overestimated", "Blew up"]);
+        assert_eq!(found[0].ratio, 0.13);
+
+        // Too few tasks to tell what usual is.
+        assert!(outliers(&samples.iter().take(3).collect::<Vec<_>>()).is_empty());
+    }
+
+    #[test]
+    fn accuracy_groups_by_each_tag_and_period() {
+        let fixture = Fixture::new();
+        let ws = fixture.workstream("Launch");
+        fixture.finished(&ws, "Both", 2.0, 4.0, "2026-03-11", &["Design", "backend", "design"]);
+        fixture.finished(&ws, "Backend", 2.0, 2.0, "2026-03-18", &["backend"]);
+        fixture.finished(&ws, "Plain", 2.0, 1.0, "2026-04-15", &[]);
+        let samples = fixture.samples();
+
+        let (overall, periods, groups, _) = accuracy(&samples, "tag", "month");
+        assert_eq!(overall.tasks, 3);
+        let periods: Vec<(&str, usize)> = periods.iter().map(|period| (period.period.as_str(), period.stats.tasks)).collect();
+        assert_eq!(periods, [("2026-03", 2), ("2026-04", 1)]);
+        let groups: Vec<(&str, &str, usize)> = groups.iter().map(|group| (group.key.as_str(), group.label.as_str(), group.stats.tasks)).collect();
+        assert_eq!(groups, [("backend", "backend", 2), ("", "(untagged)", 1), ("design", "design", 1)]);
+
+        let (_, weeks, _, _) = accuracy(&samples, "workstream", "week");
+        let weeks: Vec<&str> = weeks.iter().map(|week| week.period.as_str()).collect();
+        assert_eq!(weeks, ["2026-03-09", "2026-03-16", "2026-04-13"]);
+    }
+
+    #[test]
+    fn calibration_uses_the_most_specific_history_with_enough_tasks() {
+        let fixture = Fixture::new();
+        let busy = fixture.workstream("Busy");
+        let quiet = fixture.workstream("Quiet");
+        for i in 0..MIN_SAMPLES {
+            fixture.finished(&busy, &format!("Busy {}", i), 2.0, 4.0, "2026-03-11", &[]);
+        }
+        fixture.finished(&quiet, "Quiet", 2.0, 2.0, "2026-03-11", &[]);
+        let samples = fixture.samples();
+
+        let own = calibrate(&fixture.db, &busy, 3.0, &samples).unwrap().unwrap();
+        assert_eq!((own.basis.as_str(), own.label.as_str(), own.factor, own.calibrated_hours), ("workstream", "Busy", 2.0, 6.0));
+
+        let fallback = calibrate(&fixture.db, &quiet, 3.0, &samples).unwrap().unwrap();
+        assert_eq!((fallback.basis.as_str(), fallback.label.as_str(), fallback.tasks), ("persona", "Work", MIN_SAMPLES + 1));
+
+        assert!(calibrate(&fixture.db, &quiet, 3.0, &samples[..2]).unwrap().is_none());
+        assert_eq!(calibrate(&fixture.db, "missing", 3.0, &samples).unwrap_err(), "Workstream with ID 'missing' not found");
+    }
+}
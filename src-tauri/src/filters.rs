@@ -0,0 +1,541 @@
+// Task and journal filters.
+//
+// A TaskQuery or JournalQuery describes which rows to show and in what order.
+// It compiles to a parameterised WHERE clause and pages with an opaque keyset
+// cursor, so results stay stable while rows are added or moved. Saved filters
+// store these queries as JSON. `q` takes a query-language string (see
+// query_lang.rs) that is ANDed with the other fields.
+
+use crate::database::{JournalEntry, JournalEntryType, Priority, SavedFilterKind, TaskStatus};
+use crate::query_lang;
+use crate::repository::{self, FromRow, TaskView};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+// Statuses and priorities are stored both as JSON strings and bare keys.
+pub const STATUS_KEY: &str = "LOWER(TRIM(pt.status, '\"'))";
+pub const PRIORITY_KEY: &str = "LOWER(TRIM(pt.priority, '\"'))";
+// Tasks on the default workflow are in the state named after their status.
+pub const STATE_KEY: &str = "COALESCE(pt.workflow_state, LOWER(TRIM(pt.status, '\"')))";
+
+// A task is blocked while any task it depends on is not done.
+pub const BLOCKED: &str = "EXISTS (
+    SELECT 1 FROM json_each(CASE WHEN json_valid(pt.dependencies) THEN pt.dependencies ELSE '[]' END) dep_id
+    JOIN project_tasks dep ON dep.id = dep_id.value
+    WHERE LOWER(TRIM(dep.status, '\"')) != 'done')";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSort {
+    #[default]
+    Created,
+    DueDate,
+    Priority,
+    Rank,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskQuery {
+    pub persona_ids: Vec<String>,
+    pub workstream_ids: Vec<String>,
+    pub statuses: Vec<String>,
+    pub priorities: Vec<String>,
+    // Tasks carrying at least one of these tags
+    pub tags_any: Vec<String>,
+    // Tasks carrying every one of these tags
+    pub tags_all: Vec<String>,
+    // Inclusive due-date range: YYYY-MM-DD or a relative date (see resolve_date)
+    pub due_from: Option<String>,
+    pub due_to: Option<String>,
+    pub overdue: bool,
+    pub text: Option<String>,
+    pub blocked: Option<bool>,
+    // Tasks archived with a completed workstream are left out unless asked for
+    pub include_archived: bool,
+    pub q: Option<String>,
+    // A `sort:` or `order:` term in `q` overrides these
+    pub sort: TaskSort,
+    // Defaults to newest first for Created and ascending for the other sorts
+    pub descending: Option<bool>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskPage {
+    pub tasks: Vec<TaskView>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+// Journal entries are always listed newest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalQuery {
+    pub persona_ids: Vec<String>,
+    pub workstream_ids: Vec<String>,
+    pub entry_types: Vec<String>,
+    pub tags_any: Vec<String>,
+    pub tags_all: Vec<String>,
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub text: Option<String>,
+    pub q: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalPage {
+    pub entries: Vec<JournalEntry>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+// Results of a saved filter, tagged with the kind of rows it returns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FilterResults {
+    Tasks(TaskPage),
+    Journal(JournalPage),
+}
+
+// WHERE conditions joined with AND, with their positional parameters.
+#[derive(Debug, Clone, Default)]
+pub struct SqlFilter {
+    pub conditions: Vec<String>,
+    pub params: Vec<SqlValue>,
+}
+
+impl SqlFilter {
+    pub fn push(&mut self, condition: impl Into<String>, params: impl IntoIterator<Item = SqlValue>) {
+        self.conditions.push(condition.into());
+        self.params.extend(params);
+    }
+
+    pub fn push_in(&mut self, expr: &str, values: Vec<String>) {
+        if values.is_empty() {
+            return;
+        }
+        let placeholders = vec!["?"; values.len()].join(", ");
+        self.push(format!("{} IN ({})", expr, placeholders), values.into_iter().map(SqlValue::Text));
+    }
+
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", self.conditions.join(" AND "))
+        }
+    }
+}
+
+// A row whose JSON tag list holds the tag bound to `?`, compared case-insensitively.
+pub fn has_tag(column: &str) -> String {
+    format!("EXISTS (SELECT 1 FROM json_each(CASE WHEN json_valid({column}) THEN {column} ELSE '[]' END) WHERE LOWER(value) = LOWER(?))")
+}
+
+fn push_tags(filter: &mut SqlFilter, column: &str, any: &[String], all: &[String]) {
+    if !any.is_empty() {
+        let conditions = vec![has_tag(column); any.len()].join(" OR ");
+        filter.push(format!("({})", conditions), any.iter().cloned().map(SqlValue::Text));
+    }
+    for tag in all {
+        filter.push(has_tag(column), [SqlValue::Text(tag.clone())]);
+    }
+}
+
+// Escape LIKE wildcards so search text matches literally.
+pub fn like_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+pub fn parse_statuses(values: &[String]) -> Result<Vec<String>, String> {
+    values.iter()
+        .map(|s| s.parse::<TaskStatus>().map(|s| s.key().to_string()))
+        .collect()
+}
+
+pub fn parse_priorities(values: &[String]) -> Result<Vec<String>, String> {
+    values.iter()
+        .map(|p| p.parse::<Priority>().map(|p| p.key().to_string()))
+        .collect()
+}
+
+// Dates in saved filters are usually relative so they don't go stale:
+// today, tomorrow, yesterday, +3d, -2w, 1m, start_of_week, end_of_week,
+// start_of_month and end_of_month. Weeks start on Monday.
+pub fn resolve_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let value = value.trim().to_lowercase();
+    let invalid = || format!("Invalid date: {}. Use YYYY-MM-DD, today, +3d, -2w, end_of_week or end_of_month", value);
+
+    if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    let week_start = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let month_start = today.with_day(1).ok_or_else(invalid)?;
+    match value.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Days::new(1)),
+        "yesterday" => return Ok(today - Days::new(1)),
+        "start_of_week" => return Ok(week_start),
+        "end_of_week" => return Ok(week_start + Days::new(6)),
+        "start_of_month" => return Ok(month_start),
+        "end_of_month" => return (month_start + Months::new(1)).pred_opt().ok_or_else(invalid),
+        _ => {}
+    }
+
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(&value)),
+    };
+    let unit = rest.chars().last().ok_or_else(invalid)?;
+    let amount: u32 = rest[..rest.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+    let shifted = match (unit, negative) {
+        ('d', false) => today.checked_add_days(Days::new(amount as u64)),
+        ('d', true) => today.checked_sub_days(Days::new(amount as u64)),
+        ('w', false) => today.checked_add_days(Days::new(amount as u64 * 7)),
+        ('w', true) => today.checked_sub_days(Days::new(amount as u64 * 7)),
+        ('m', false) => today.checked_add_months(Months::new(amount)),
+        ('m', true) => today.checked_sub_months(Months::new(amount)),
+        _ => None,
+    };
+    shifted.ok_or_else(invalid)
+}
+
+fn date_param(value: &str, today: NaiveDate) -> Result<SqlValue, String> {
+    resolve_date(value, today).map(|d| SqlValue::Text(d.format("%Y-%m-%d").to_string()))
+}
+
+// Appends the conditions of a query-language string to the form filter and
+// returns the sort it asked for, if any.
+fn push_language(filter: &mut SqlFilter, kind: SavedFilterKind, q: Option<&str>, today: NaiveDate) -> Result<(Option<TaskSort>, Option<bool>), String> {
+    let Some(q) = q.map(str::trim).filter(|q| !q.is_empty()) else {
+        return Ok((None, None));
+    };
+    let compiled = query_lang::compile(kind, q, today)
+        .map_err(|errors| query_lang::errors_to_string(&errors))?;
+    filter.conditions.extend(compiled.filter.conditions);
+    filter.params.extend(compiled.filter.params);
+    Ok((compiled.sort, compiled.descending))
+}
+
+pub fn task_filter(query: &TaskQuery, today: NaiveDate) -> Result<SqlFilter, String> {
+    task_plan(query, today).map(|(filter, _, _)| filter)
+}
+
+// The WHERE clause plus the sort and direction to use.
+fn task_plan(query: &TaskQuery, today: NaiveDate) -> Result<(SqlFilter, TaskSort, bool), String> {
+    let mut filter = SqlFilter::default();
+
+    if !query.include_archived {
+        filter.push("pt.archived_at IS NULL", []);
+    }
+    filter.push_in("w.persona_id", query.persona_ids.clone());
+    filter.push_in("pt.workstream_id", query.workstream_ids.clone());
+    filter.push_in(STATUS_KEY, parse_statuses(&query.statuses)?);
+    filter.push_in(PRIORITY_KEY, parse_priorities(&query.priorities)?);
+
+    push_tags(&mut filter, "pt.tags", &query.tags_any, &query.tags_all);
+
+    if let Some(from) = &query.due_from {
+        filter.push("substr(pt.due_date, 1, 10) >= ?", [date_param(from, today)?]);
+    }
+    if let Some(to) = &query.due_to {
+        filter.push("substr(pt.due_date, 1, 10) <= ?", [date_param(to, today)?]);
+    }
+    if query.overdue {
+        filter.push(
+            format!("pt.due_date IS NOT NULL AND substr(pt.due_date, 1, 10) < ? AND {} != 'done'", STATUS_KEY),
+            [SqlValue::Text(today.format("%Y-%m-%d").to_string())],
+        );
+    }
+
+    if let Some(text) = query.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let pattern = like_pattern(text);
+        filter.push(
+            "(pt.title LIKE ? ESCAPE '\\' OR pt.description LIKE ? ESCAPE '\\')",
+            [SqlValue::Text(pattern.clone()), SqlValue::Text(pattern)],
+        );
+    }
+
+    match query.blocked {
+        Some(true) => filter.push(BLOCKED, []),
+        Some(false) => filter.push(format!("NOT {}", BLOCKED), []),
+        None => {}
+    }
+
+    let (sort, descending) = push_language(&mut filter, SavedFilterKind::Tasks, query.q.as_deref(), today)?;
+    let sort = sort.unwrap_or(query.sort);
+    let descending = descending.or(query.descending).unwrap_or(sort == TaskSort::Created);
+
+    Ok((filter, sort, descending))
+}
+
+// Every sort is reduced to a single text key on the task view `v`, so the
+// cursor only needs the last key and ID; ties fall back to the task ID.
+fn sort_key(sort: TaskSort) -> &'static str {
+    match sort {
+        TaskSort::Created => "v.created_at",
+        TaskSort::DueDate => "COALESCE(substr(v.due_date, 1, 10), '9999-12-31')",
+        // critical, high, medium, low, then by due date
+        TaskSort::Priority => "CASE LOWER(TRIM(v.priority, '\"')) WHEN 'critical' THEN 0 WHEN 'high' THEN 1 WHEN 'medium' THEN 2 WHEN 'low' THEN 3 ELSE 4 END || '|' || COALESCE(substr(v.due_date, 1, 10), '9999-12-31')",
+        // unranked tasks go last, oldest first
+        TaskSort::Rank => "COALESCE(v.rank, '~') || '|' || v.created_at",
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    key: String,
+    id: String,
+}
+
+fn encode_cursor(key: String, id: String) -> String {
+    let json = serde_json::to_vec(&Cursor { key, id }).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+fn decode_cursor(cursor: &str) -> Result<Cursor, String> {
+    URL_SAFE_NO_PAD.decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| "Invalid cursor".to_string())
+}
+
+fn count(db: &Connection, view: &str, params: &[SqlValue]) -> Result<i64, String> {
+    db.query_row(&format!("SELECT COUNT(*) FROM ({})", view), rusqlite::params_from_iter(params.iter()), |row| row.get(0))
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+pub fn count_tasks(db: &Connection, query: &TaskQuery, today: NaiveDate) -> Result<i64, String> {
+    let filter = task_filter(query, today)?;
+    count(db, &format!("{}{}", repository::TASK_VIEW_SELECT, filter.where_clause()), &filter.params)
+}
+
+pub fn query_tasks(db: &Connection, query: &TaskQuery, today: NaiveDate) -> Result<TaskPage, String> {
+    let (filter, sort, descending) = task_plan(query, today)?;
+    let view = format!("{}{}", repository::TASK_VIEW_SELECT, filter.where_clause());
+    let mut params = filter.params;
+
+    let total = count(db, &view, &params)?;
+
+    let key = sort_key(sort);
+    let (direction, after) = if descending { ("DESC", "<") } else { ("ASC", ">") };
+
+    let mut after_cursor = String::new();
+    if let Some(cursor) = &query.cursor {
+        let cursor = decode_cursor(cursor)?;
+        after_cursor = format!(" WHERE ({key} {after} ? OR ({key} = ? AND v.id {after} ?))");
+        params.extend([SqlValue::Text(cursor.key.clone()), SqlValue::Text(cursor.key), SqlValue::Text(cursor.id)]);
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let sql = format!(
+        "SELECT v.*, {key} AS sort_key FROM ({view}) v{after_cursor} ORDER BY sort_key {direction}, v.id {direction} LIMIT {}",
+        limit + 1
+    );
+
+    let mut stmt = db.prepare(&sql)
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let mut rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        Ok((TaskView::from_row(row)?, row.get::<_, String>("sort_key")?))
+    })
+    .map_err(|e| format!("SQL query error: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    // One extra row was fetched to tell whether another page follows.
+    let next_cursor = if rows.len() > limit as usize {
+        rows.truncate(limit as usize);
+        rows.last().map(|(task, key)| encode_cursor(key.clone(), task.task.id.clone()))
+    } else {
+        None
+    };
+
+    Ok(TaskPage {
+        tasks: rows.into_iter().map(|(task, _)| task).collect(),
+        total,
+        next_cursor,
+    })
+}
+
+// Journal
+pub fn journal_filter(query: &JournalQuery, today: NaiveDate) -> Result<SqlFilter, String> {
+    let mut filter = SqlFilter::default();
+
+    filter.push_in("je.persona_id", query.persona_ids.clone());
+    filter.push_in("je.workstream_id", query.workstream_ids.clone());
+    let entry_types = query.entry_types.iter()
+        .map(|t| t.parse::<JournalEntryType>().map(|t| t.key().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    filter.push_in("LOWER(TRIM(je.entry_type, '\"'))", entry_types);
+
+    push_tags(&mut filter, "je.tags", &query.tags_any, &query.tags_all);
+
+    if let Some(from) = &query.created_from {
+        filter.push("substr(je.created_at, 1, 10) >= ?", [date_param(from, today)?]);
+    }
+    if let Some(to) = &query.created_to {
+        filter.push("substr(je.created_at, 1, 10) <= ?", [date_param(to, today)?]);
+    }
+
+    if let Some(text) = query.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let pattern = like_pattern(text);
+        filter.push(
+            "(je.title LIKE ? ESCAPE '\\' OR je.content LIKE ? ESCAPE '\\')",
+            [SqlValue::Text(pattern.clone()), SqlValue::Text(pattern)],
+        );
+    }
+
+    push_language(&mut filter, SavedFilterKind::Journal, query.q.as_deref(), today)?;
+
+    Ok(filter)
+}
+
+pub fn count_journal(db: &Connection, query: &JournalQuery, today: NaiveDate) -> Result<i64, String> {
+    let filter = journal_filter(query, today)?;
+    count(db, &format!("SELECT je.* FROM journal_entries je{}", filter.where_clause()), &filter.params)
+}
+
+pub fn query_journal(db: &Connection, query: &JournalQuery, today: NaiveDate) -> Result<JournalPage, String> {
+    let mut filter = journal_filter(query, today)?;
+    let total = count(db, &format!("SELECT je.* FROM journal_entries je{}", filter.where_clause()), &filter.params)?;
+
+    if let Some(cursor) = &query.cursor {
+        let cursor = decode_cursor(cursor)?;
+        filter.push(
+            "(je.created_at < ? OR (je.created_at = ? AND je.id < ?))",
+            [SqlValue::Text(cursor.key.clone()), SqlValue::Text(cursor.key), SqlValue::Text(cursor.id)],
+        );
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let sql = format!(
+        "SELECT je.* FROM journal_entries je{} ORDER BY je.created_at DESC, je.id DESC LIMIT {}",
+        filter.where_clause(),
+        limit + 1
+    );
+
+    let mut stmt = db.prepare(&sql)
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let mut rows = stmt.query_map(rusqlite::params_from_iter(filter.params.iter()), |row| {
+        Ok((JournalEntry::from_row(row)?, row.get::<_, String>("created_at")?))
+    })
+    .map_err(|e| format!("SQL query error: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    let next_cursor = if rows.len() > limit as usize {
+        rows.truncate(limit as usize);
+        rows.last().map(|(entry, key)| encode_cursor(key.clone(), entry.id.clone()))
+    } else {
+        None
+    };
+
+    Ok(JournalPage {
+        entries: rows.into_iter().map(|(entry, _)| entry).collect(),
+        total,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn relative_dates_resolve_against_today() {
+        let today = date("2026-01-30"); // a Friday
+        for (value, expected) in [
+            ("today", "2026-01-30"),
+            ("Tomorrow", "2026-01-31"),
+            ("+3d", "2026-02-02"),
+            ("-2w", "2026-01-16"),
+            ("1m", "2026-02-28"),
+            ("start_of_week", "2026-01-26"),
+            ("end_of_week", "2026-02-01"),
+            ("end_of_month", "2026-01-31"),
+            ("2026-07-01", "2026-07-01"),
+        ] {
+            assert_eq!(resolve_date(value, today), Ok(date(expected)), "{}", value);
+        }
+        for value in ["", "soon", "3y", "d", "+-3d"] {
+            assert!(resolve_date(value, today).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn search_text_matches_wildcards_literally() {
+        assert_eq!(like_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+
+    #[test]
+    fn pages_follow_the_cursor_without_gaps_or_repeats() {
+        let db = commands::open_database(":memory:").unwrap();
+        let persona = commands::create_persona(&db, "Work".into(), None, "#3366ff".into()).unwrap();
+        let workstream = commands::create_workstream(&db, persona.id, "Launch".into(), None, "active".into(), None, None, None).unwrap();
+        for i in 0..7 {
+            let priority = if i % 2 == 0 { "high" } else { "low" };
+            commands::create_project_task(&db, workstream.id.clone(), format!("Task {}", i), None, "todo".into(), priority.into()).unwrap();
+        }
+        let today = date("2026-01-30");
+
+        let mut query = TaskQuery { sort: TaskSort::Priority, limit: Some(3), ..Default::default() };
+        let mut seen = Vec::new();
+        loop {
+            let page = query_tasks(&db, &query, today).unwrap();
+            assert_eq!(page.total, 7);
+            seen.extend(page.tasks.iter().map(|task| (task.task.priority.key().to_string(), task.task.id.clone())));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen.len(), 7);
+        assert_eq!(seen.iter().filter(|(priority, _)| priority == "high").count(), 4);
+        assert!(seen[..4].iter().all(|(priority, _)| priority == "high"));
+        let mut ids: Vec<&String> = seen.iter().map(|(_, id)| id).collect();
+        ids.dedup();
+        assert_eq!(ids.len(), 7);
+
+        query.cursor = Some("not a cursor".into());
+        assert_eq!(query_tasks(&db, &query, today).unwrap_err(), "Invalid cursor");
+    }
+
+    #[test]
+    fn form_fields_and_query_language_combine() {
+        let query = TaskQuery {
+            statuses: vec!["todo".into(), "done".into()],
+            tags_all: vec!["a".into(), "b".into()],
+            overdue: true,
+            q: Some("priority:high sort:rank".into()),
+            ..Default::default()
+        };
+        let (filter, sort, descending) = task_plan(&query, date("2026-01-30")).unwrap();
+        assert_eq!(filter.conditions[0], "pt.archived_at IS NULL");
+        assert_eq!(filter.conditions.len(), 6);
+        assert_eq!(filter.params.len(), 6);
+        assert_eq!((sort, descending), (TaskSort::Rank, false));
+
+        let archived = TaskQuery { include_archived: true, ..Default::default() };
+        assert!(task_filter(&archived, date("2026-01-30")).unwrap().conditions.is_empty());
+        let bad = TaskQuery { statuses: vec!["later".into()], ..Default::default() };
+        assert!(task_filter(&bad, date("2026-01-30")).is_err());
+    }
+}
@@ -0,0 +1,403 @@
+use crate::database::Workstream;
+use crate::filters;
+use crate::repository;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Burndown and burnup series for a workstream, rebuilt from the task status
+// history, and a Monte Carlo forecast of when the remaining work will be done
+// at the pace of recent weeks. Tasks from before the history was recorded
+// fall back on their creation and completion dates.
+
+// Days of recent throughput the forecast draws from.
+const THROUGHPUT_DAYS: u64 = 42;
+const TRIALS: usize = 2000;
+// Trials still going after this many days count as never finishing.
+const MAX_FORECAST_DAYS: usize = 730;
+
+// Chances of finishing by the target date that count as on track and at risk.
+const ON_TRACK_PROBABILITY: f64 = 0.85;
+const AT_RISK_PROBABILITY: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkstreamHealth {
+    OnTrack,
+    AtRisk,
+    Late,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurndownPoint {
+    pub date: String,
+    pub scope: f64,
+    pub completed: f64,
+    pub remaining: f64,
+    pub ideal: Option<f64>, // straight line from the starting scope to zero on the target date
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Forecast {
+    pub trials: usize,
+    pub throughput_days: u64,
+    pub daily_throughput: f64, // mean completed per day in those days
+    pub p50: Option<String>,   // days by which half, 85% and 95% of the trials finished
+    pub p85: Option<String>,
+    pub p95: Option<String>,
+    pub on_target_probability: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Burndown {
+    pub workstream_id: String,
+    pub workstream_name: String,
+    pub unit: String, // tasks or hours (estimated)
+    pub start_date: String,
+    pub target_date: Option<String>,
+    pub scope: f64,
+    pub completed: f64,
+    pub remaining: f64,
+    pub series: Vec<BurndownPoint>,
+    pub forecast: Option<Forecast>, // None while nothing was completed recently
+    pub health: Option<WorkstreamHealth>, // None without a target date
+    pub reason: String,
+}
+
+struct StatusChange {
+    changed_at: DateTime<Utc>,
+    from: Option<String>,
+    to: String,
+}
+
+struct TaskHistory {
+    created_at: DateTime<Utc>,
+    done_since: Option<DateTime<Utc>>, // for tasks without recorded history
+    size: f64,
+    changes: Vec<StatusChange>,
+}
+
+impl TaskHistory {
+    // Whether the task existed and whether it was done at an instant.
+    fn at(&self, instant: DateTime<Utc>) -> Option<bool> {
+        if self.created_at > instant {
+            return None;
+        }
+        if let Some(first) = self.changes.first() {
+            return Some(match self.changes.iter().rev().find(|change| change.changed_at <= instant) {
+                Some(change) => change.to == "done",
+                None => first.from.as_deref() == Some("done"),
+            });
+        }
+        Some(self.done_since.is_some_and(|since| since <= instant))
+    }
+}
+
+// A small deterministic generator (SplitMix64), seeded from the workstream
+// and the day, so a forecast doesn't jump around between two refreshes.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn seeded(seed: &str) -> Self {
+        SplitMix(seed.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)))
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        ((z ^ (z >> 31)) % bound as u64) as usize
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn day_end(day: NaiveDate) -> Result<DateTime<Utc>, String> {
+    let next = day.succ_opt().unwrap_or(day);
+    crate::recurrence::local_time(&chrono::Local, next, NaiveTime::MIN)
+        .map(|instant| instant.with_timezone(&Utc) - chrono::Duration::nanoseconds(1))
+        .ok_or_else(|| format!("Invalid day: {}", day))
+}
+
+fn load_history(conn: &Connection, workstream_id: &str, hours: bool) -> Result<Vec<TaskHistory>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT pt.id, pt.created_at, {} = 'done', COALESCE(pt.completed_date, pt.updated_at), COALESCE(pt.estimated_hours, 0)
+             FROM project_tasks pt WHERE pt.workstream_id = ?1",
+            filters::STATUS_KEY
+        ))
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let rows = stmt
+        .query_map([workstream_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?, row.get::<_, String>(3)?, row.get::<_, f64>(4)?))
+        })
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT h.task_id, h.changed_at, h.from_status, h.to_status FROM task_status_history h
+             JOIN project_tasks pt ON h.task_id = pt.id WHERE pt.workstream_id = ?1 ORDER BY h.changed_at",
+        )
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let mut changes: HashMap<String, Vec<StatusChange>> = HashMap::new();
+    let history = stmt
+        .query_map([workstream_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, String>(3)?))
+        })
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))?;
+    for (task_id, changed_at, from, to) in history {
+        if let Some(changed_at) = repository::parse_timestamp(&changed_at) {
+            changes.entry(task_id).or_default().push(StatusChange { changed_at, from, to });
+        }
+    }
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, created_at, done, finished, estimate)| {
+            Some(TaskHistory {
+                created_at: repository::parse_timestamp(&created_at)?,
+                done_since: if done { repository::parse_timestamp(&finished) } else { None },
+                size: if hours { estimate } else { 1.0 },
+                changes: changes.remove(&id).unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+// Days until each trial, drawing a random recent day's throughput for every
+// day, has finished the remaining work; None for trials that never do.
+fn simulate(throughput: &[f64], remaining: f64, rng: &mut SplitMix) -> Vec<Option<usize>> {
+    let mut results: Vec<Option<usize>> = (0..TRIALS)
+        .map(|_| {
+            let mut done = 0.0;
+            for day in 1..=MAX_FORECAST_DAYS {
+                done += throughput[rng.below(throughput.len())];
+                if done >= remaining {
+                    return Some(day);
+                }
+            }
+            None
+        })
+        .collect();
+    results.sort_by_key(|days| days.unwrap_or(usize::MAX));
+    results
+}
+
+pub fn burndown(conn: &Connection, workstream: &Workstream, unit: &str, today: NaiveDate) -> Result<Burndown, String> {
+    let tasks = load_history(conn, &workstream.id, unit == "hours")?;
+    let target = workstream.target_date.map(|date| date.date_naive());
+    let start = workstream
+        .start_date
+        .map(|date| date.date_naive())
+        .or_else(|| tasks.iter().map(|task| task.created_at.with_timezone(&chrono::Local).date_naive()).min())
+        .unwrap_or(today)
+        .min(today);
+
+    // Daily totals from whichever comes first, the start or the throughput window
+    let first = start.min(today - chrono::Days::new(THROUGHPUT_DAYS));
+    let mut days: Vec<(NaiveDate, f64, f64)> = Vec::new();
+    for day in first.iter_days().take_while(|day| *day <= today) {
+        let end = day_end(day)?;
+        let (mut scope, mut completed) = (0.0, 0.0);
+        for task in &tasks {
+            match task.at(end) {
+                Some(true) => {
+                    scope += task.size;
+                    completed += task.size;
+                }
+                Some(false) => scope += task.size,
+                None => {}
+            }
+        }
+        days.push((day, scope, completed));
+    }
+
+    let start_scope = days.iter().find(|(day, _, _)| *day == start).map(|(_, scope, _)| *scope).unwrap_or_default();
+    let series: Vec<BurndownPoint> = days
+        .iter()
+        .filter(|(day, _, _)| *day >= start)
+        .map(|(day, scope, completed)| BurndownPoint {
+            date: day.format("%Y-%m-%d").to_string(),
+            scope: round(*scope),
+            completed: round(*completed),
+            remaining: round(scope - completed),
+            ideal: target.filter(|target| *target > start).map(|target| {
+                let elapsed = (*day - start).num_days() as f64 / (target - start).num_days() as f64;
+                round((start_scope * (1.0 - elapsed)).max(0.0))
+            }),
+        })
+        .collect();
+    let (_, scope, completed) = days.last().copied().unwrap_or((today, 0.0, 0.0));
+    let remaining = scope - completed;
+
+    // Reopened work makes a day's throughput negative; it counts as nothing done
+    let throughput: Vec<f64> = days
+        .windows(2)
+        .rev()
+        .take(THROUGHPUT_DAYS as usize)
+        .map(|pair| (pair[1].2 - pair[0].2).max(0.0))
+        .collect();
+    let forecast = if remaining > 0.0 && throughput.iter().any(|done| *done > 0.0) {
+        let mut rng = SplitMix::seeded(&format!("{}{}", workstream.id, today));
+        let trials = simulate(&throughput, remaining, &mut rng);
+        let date = |share: f64| {
+            trials[((TRIALS as f64 * share).ceil() as usize).clamp(1, TRIALS) - 1]
+                .map(|days| (today + chrono::Days::new(days as u64 - 1)).format("%Y-%m-%d").to_string())
+        };
+        Some(Forecast {
+            trials: TRIALS,
+            throughput_days: throughput.len() as u64,
+            daily_throughput: round(throughput.iter().sum::<f64>() / throughput.len() as f64),
+            p50: date(0.5),
+            p85: date(0.85),
+            p95: date(0.95),
+            on_target_probability: target.map(|target| {
+                let days_left = (target - today).num_days() + 1;
+                let finished = trials.iter().filter(|days| days.is_some_and(|days| days as i64 <= days_left)).count();
+                round(finished as f64 / TRIALS as f64)
+            }),
+        })
+    } else {
+        None
+    };
+
+    let (health, reason) = match target {
+        None => (None, "No target date".to_string()),
+        Some(_) if remaining <= 0.0 => (Some(WorkstreamHealth::OnTrack), "Nothing left to do".to_string()),
+        Some(target) if target < today => (Some(WorkstreamHealth::Late), format!("The target date {} has passed", target)),
+        Some(_) => match forecast.as_ref().and_then(|forecast| forecast.on_target_probability) {
+            None => (Some(WorkstreamHealth::AtRisk), format!("Nothing was completed in the last {} days", THROUGHPUT_DAYS)),
+            Some(chance) => {
+                let health = if chance >= ON_TRACK_PROBABILITY {
+                    WorkstreamHealth::OnTrack
+                } else if chance >= AT_RISK_PROBABILITY {
+                    WorkstreamHealth::AtRisk
+                } else {
+                    WorkstreamHealth::Late
+                };
+                (Some(health), format!("{}% chance of finishing by the target date", (chance * 100.0).round()))
+            }
+        },
+    };
+
+    Ok(Burndown {
+        workstream_id: workstream.id.clone(),
+        workstream_name: workstream.name.clone(),
+        unit: unit.to_string(),
+        start_date: start.format("%Y-%m-%d").to_string(),
+        target_date: target.map(|target| target.format("%Y-%m-%d").to_string()),
+        scope: round(scope),
+        completed: round(completed),
+        remaining: round(remaining),
+        series,
+        forecast,
+        health,
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        repository::parse_timestamp(value).unwrap()
+    }
+
+    fn change(changed_at: &str, from: Option<&str>, to: &str) -> StatusChange {
+        StatusChange { changed_at: at(changed_at), from: from.map(str::to_string), to: to.to_string() }
+    }
+
+    #[test]
+    fn task_history_follows_status_changes() {
+        let reopened = TaskHistory {
+            created_at: at("2026-03-01T09:00:00Z"),
+            done_since: None,
+            size: 1.0,
+            changes: vec![
+                change("2026-03-05T09:00:00Z", Some("todo"), "done"),
+                change("2026-03-08T09:00:00Z", Some("done"), "inprogress"),
+            ],
+        };
+        assert_eq!(reopened.at(at("2026-02-28T09:00:00Z")), None);
+        assert_eq!(reopened.at(at("2026-03-02T09:00:00Z")), Some(false));
+        assert_eq!(reopened.at(at("2026-03-06T09:00:00Z")), Some(true));
+        assert_eq!(reopened.at(at("2026-03-09T09:00:00Z")), Some(false));
+
+        // Without history the completion date decides.
+        let legacy = TaskHistory { changes: Vec::new(), done_since: Some(at("2026-03-04T09:00:00Z")), ..reopened };
+        assert_eq!(legacy.at(at("2026-03-03T09:00:00Z")), Some(false));
+        assert_eq!(legacy.at(at("2026-03-04T09:00:00Z")), Some(true));
+    }
+
+    #[test]
+    fn simulation_finishes_at_the_pace_drawn() {
+        let mut rng = SplitMix::seeded("steady");
+        assert!(simulate(&[2.0], 5.0, &mut rng).iter().all(|days| *days == Some(3)));
+        assert!(simulate(&[0.0], 1.0, &mut rng).iter().all(|days| days.is_none()));
+
+        let trials = simulate(&[0.0, 1.0], 10.0, &mut rng);
+        assert!(trials.windows(2).all(|pair| pair[0].unwrap_or(usize::MAX) <= pair[1].unwrap_or(usize::MAX)));
+        assert!(trials.iter().all(|days| days.is_some_and(|days| days >= 10)));
+    }
+
+    #[test]
+    fn the_generator_repeats_for_the_same_seed() {
+        let draw = |seed: &str| {
+            let mut rng = SplitMix::seeded(seed);
+            (0..50).map(|_| rng.below(7)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw("ws2026-03-20"), draw("ws2026-03-20"));
+        assert_ne!(draw("ws2026-03-20"), draw("ws2026-03-21"));
+        assert!(draw("any").iter().all(|value| *value < 7));
+    }
+
+    #[test]
+    fn burndown_rebuilds_the_series_and_forecasts_from_history() {
+        let db = commands::open_database(":memory:").unwrap();
+        let persona = commands::create_persona(&db, "Work".into(), None, "#3366ff".into()).unwrap();
+        let workstream = commands::create_workstream(&db, persona.id, "Launch".into(), None, "active".into(), None, None, None).unwrap();
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            ids.push(commands::create_project_task(&db, workstream.id.clone(), format!("Task {}", i), None, "todo".into(), "medium".into()).unwrap().id);
+        }
+        db.execute("DELETE FROM task_status_history", []).unwrap();
+        db.execute("UPDATE project_tasks SET created_at = '2026-03-10T12:00:00Z'", []).unwrap();
+        db.execute("UPDATE workstreams SET start_date = '2026-03-10T12:00:00Z', target_date = '2026-06-30T12:00:00Z'", []).unwrap();
+        for (id, day) in [(&ids[0], "2026-03-14"), (&ids[1], "2026-03-17")] {
+            db.execute("UPDATE project_tasks SET status = 'done' WHERE id = ?1", [id]).unwrap();
+            db.execute(
+                "INSERT INTO task_status_history (id, task_id, from_status, to_status, changed_at) VALUES (?1, ?2, 'todo', 'done', ?3)",
+                [crate::database::generate_id(), id.to_string(), format!("{}T12:00:00Z", day)],
+            ).unwrap();
+        }
+        let workstream = repository::get_workstream(&db, &workstream.id).unwrap().unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+
+        let report = burndown(&db, &workstream, "tasks", today).unwrap();
+        assert_eq!((report.scope, report.completed, report.remaining), (4.0, 2.0, 2.0));
+        assert_eq!(report.series.len(), 11);
+        assert_eq!(report.series[0].date, "2026-03-10");
+        assert_eq!(report.series[0].ideal, Some(4.0));
+        let completed: Vec<f64> = report.series.iter().map(|point| point.completed).collect();
+        assert_eq!(completed[3..8], [0.0, 1.0, 1.0, 1.0, 2.0]);
+
+        let forecast = report.forecast.unwrap();
+        assert_eq!(forecast.throughput_days, THROUGHPUT_DAYS);
+        assert!(forecast.p50 <= forecast.p85 && forecast.p85 <= forecast.p95);
+        assert!(forecast.on_target_probability.is_some_and(|chance| chance >= ON_TRACK_PROBABILITY));
+        assert_eq!(report.health, Some(WorkstreamHealth::OnTrack));
+
+        let overdue = Workstream { target_date: Some(at("2026-03-15T12:00:00Z")), ..workstream };
+        assert_eq!(burndown(&db, &overdue, "tasks", today).unwrap().health, Some(WorkstreamHealth::Late));
+    }
+}
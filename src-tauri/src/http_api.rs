@@ -0,0 +1,818 @@
+use crate::backup::{delete_setting, get_setting, set_setting};
+use crate::commands;
+use crate::pool::DbPool;
+use crate::webdav::percent_decode;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub const DEFAULT_PORT: u16 = 7311;
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: Option<String>,
+}
+
+// Settings (kept in app_settings so the token never leaves this device)
+pub fn load_settings(conn: &Connection) -> Result<ApiSettings, String> {
+    Ok(ApiSettings {
+        enabled: get_setting(conn, "api_enabled")?.as_deref() == Some("1"),
+        port: get_setting(conn, "api_port")?
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_PORT),
+        token: get_setting(conn, "api_token")?,
+    })
+}
+
+pub fn save_settings(conn: &Connection, enabled: bool, port: u16) -> Result<ApiSettings, String> {
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    set_setting(conn, "api_enabled", if enabled { "1" } else { "0" })?;
+    set_setting(conn, "api_port", &port.to_string())?;
+    if enabled && get_setting(conn, "api_token")?.is_none() {
+        regenerate_token(conn)?;
+    }
+    load_settings(conn)
+}
+
+pub fn regenerate_token(conn: &Connection) -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    delete_setting(conn, "api_token")?;
+    set_setting(conn, "api_token", &token)?;
+    Ok(token)
+}
+
+// Server
+pub struct ApiServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+}
+
+impl ApiServer {
+    // Only ever binds the loopback interface.
+    pub fn start(db: Arc<DbPool>, port: u16, token: String) -> Result<Self, String> {
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| format!("Failed to start HTTP API on port {}: {}", port, e))?;
+        let port = server.server_addr().to_ip().map(|a| a.port()).unwrap_or(port);
+        let server = Arc::new(server);
+
+        let listener = server.clone();
+        let thread = std::thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                handle(&db, &token, request);
+            }
+        });
+
+        Ok(ApiServer { server, thread: Some(thread), port })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn stop(mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        ApiError { status, message: message.into() }
+    }
+}
+
+// Command errors are plain strings; the wording tells missing rows apart from bad input.
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        let status = if message.contains("not found") { 404 } else { 400 };
+        ApiError { status, message }
+    }
+}
+
+fn handle(db: &DbPool, token: &str, mut request: Request) {
+    // Preflight requests carry no credentials; answer them so browser extensions can call in.
+    if *request.method() == Method::Options {
+        let _ = request.respond(with_cors(Response::empty(204)));
+        return;
+    }
+
+    let result = authorize(&request, token)
+        .and_then(|_| read_body(&mut request))
+        .and_then(|body| {
+            let (path, query) = split_url(request.url());
+            let method = request.method().clone();
+            let routed = if reads_only(&method, &path) {
+                db.with_reader(|conn| Ok(route(conn, &method, &path, &query, body)))
+            } else {
+                db.with_writer(|conn| Ok(route(conn, &method, &path, &query, body)))
+            };
+            routed.map_err(|e| ApiError::new(500, e))?
+        });
+
+    let (status, body) = match result {
+        Ok((status, body)) => (status, body),
+        Err(e) => (e.status, serde_json::json!({ "error": e.message })),
+    };
+
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    let _ = request.respond(with_cors(response));
+}
+
+// GETs and searches only read, so they use the reader connections. Looking
+// at the focus session moves it past finished phases, so that one writes.
+fn reads_only(method: &Method, path: &str) -> bool {
+    (*method == Method::Get && path != "/api/focus") || path.ends_with("/query")
+}
+
+fn with_cors<R: Read>(response: Response<R>) -> Response<R> {
+    response
+        .with_header(Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap())
+        .with_header(Header::from_bytes("Access-Control-Allow-Methods", "GET, POST, PATCH, DELETE, OPTIONS").unwrap())
+        .with_header(Header::from_bytes("Access-Control-Allow-Headers", "Authorization, Content-Type").unwrap())
+}
+
+fn authorize(request: &Request, token: &str) -> Result<(), ApiError> {
+    let presented = request.headers().iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(str::trim);
+
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => Ok(()),
+        _ => Err(ApiError::new(401, "Missing or invalid API token")),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn read_body(request: &mut Request) -> Result<Value, ApiError> {
+    if request.body_length().unwrap_or(0) as u64 > MAX_BODY_BYTES {
+        return Err(ApiError::new(413, "Request body is too large"));
+    }
+
+    let mut body = String::new();
+    request.as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::new(400, format!("Failed to read request body: {}", e)))?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(ApiError::new(413, "Request body is too large"));
+    }
+
+    if body.trim().is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    match serde_json::from_str::<Value>(&body) {
+        Ok(value) if value.is_object() => Ok(value),
+        Ok(_) => Err(ApiError::new(400, "Request body must be a JSON object")),
+        Err(e) => Err(ApiError::new(400, format!("Invalid JSON: {}", e))),
+    }
+}
+
+fn split_url(url: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(&key.replace('+', " ")), percent_decode(&value.replace('+', " ")))
+        })
+        .collect();
+    (path.trim_end_matches('/').to_string(), query)
+}
+
+// Body helpers
+fn optional(body: &Value, field: &str) -> Option<String> {
+    body.get(field).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn required(body: &Value, field: &str) -> Result<String, ApiError> {
+    optional(body, field).ok_or_else(|| ApiError::new(400, format!("Missing field: {}", field)))
+}
+
+fn optional_u32(body: &Value, field: &str) -> Option<u32> {
+    body.get(field).and_then(|v| v.as_u64()).and_then(|v| u32::try_from(v).ok())
+}
+
+fn optional_hours(body: &Value, field: &str) -> Option<f32> {
+    body.get(field).and_then(|v| v.as_f64()).map(|v| v as f32)
+}
+
+fn workflow_states(body: &Value) -> Result<Option<Vec<commands::WorkflowStateInput>>, ApiError> {
+    body.get("states")
+        .map(|states| serde_json::from_value(states.clone()))
+        .transpose()
+        .map_err(|e| ApiError::new(400, format!("Invalid workflow states: {}", e)))
+}
+
+fn json<T: Serialize>(status: u16, value: T) -> Result<(u16, Value), ApiError> {
+    serde_json::to_value(value)
+        .map(|v| (status, v))
+        .map_err(|e| ApiError::new(500, format!("Serialization error: {}", e)))
+}
+
+fn message(text: String) -> Result<(u16, Value), ApiError> {
+    Ok((200, serde_json::json!({ "message": text })))
+}
+
+// Task fields left out of a PATCH keep their stored values.
+fn current_task_field(conn: &Connection, id: &str, column: &str) -> Result<String, ApiError> {
+    let value: String = conn.query_row(
+        &format!("SELECT {} FROM project_tasks WHERE id = ?1", column),
+        [id],
+        |row| row.get(0),
+    ).map_err(|_| ApiError::new(404, format!("Project task with ID '{}' not found", id)))?;
+    Ok(value.trim_matches('"').to_lowercase())
+}
+
+fn route(
+    conn: &Connection,
+    method: &Method,
+    path: &str,
+    query: &HashMap<String, String>,
+    body: Value,
+) -> Result<(u16, Value), ApiError> {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let param = |name: &str| query.get(name).cloned();
+
+    match (method, segments.as_slice()) {
+        // Personas
+        (Method::Get, ["api", "personas"]) => json(200, commands::get_all_personas(conn)?),
+        (Method::Post, ["api", "personas"]) => json(201, commands::create_persona(
+            conn,
+            required(&body, "name")?,
+            optional(&body, "description"),
+            optional(&body, "color").unwrap_or_else(|| "#3b82f6".to_string()),
+        )?),
+        (Method::Patch, ["api", "personas", id]) => json(200, commands::update_persona(
+            conn,
+            id.to_string(),
+            optional(&body, "name"),
+            optional(&body, "description"),
+            optional(&body, "color"),
+            body.get("is_active").and_then(|v| v.as_bool()),
+        )?),
+        (Method::Delete, ["api", "personas", id]) => message(commands::delete_persona(conn, id.to_string())?),
+
+        // Workstreams
+        (Method::Get, ["api", "workstreams"]) => match param("persona_id") {
+            Some(persona_id) => json(200, commands::get_workstreams_by_persona(conn, persona_id)?),
+            None => json(200, commands::get_all_workstreams(conn)?),
+        },
+        (Method::Post, ["api", "workstreams"]) => json(201, commands::create_workstream(
+            conn,
+            required(&body, "persona_id")?,
+            required(&body, "name")?,
+            optional(&body, "description"),
+            optional(&body, "status").unwrap_or_else(|| "planning".to_string()),
+            optional(&body, "priority"),
+            optional(&body, "start_date"),
+            optional(&body, "target_date"),
+        )?),
+        (Method::Patch, ["api", "workstreams", id]) => json(200, commands::update_workstream(
+            conn,
+            id.to_string(),
+            optional(&body, "name"),
+            optional(&body, "description"),
+            optional(&body, "status"),
+            optional(&body, "priority"),
+            optional(&body, "start_date"),
+            optional(&body, "target_date"),
+        )?),
+        (Method::Post, ["api", "workstreams", id, "status"]) => json(200, commands::change_workstream_status(
+            conn,
+            id.to_string(),
+            required(&body, "status")?,
+            body.get("reopen").and_then(|r| r.as_bool()).unwrap_or(false),
+            body.get("habits").and_then(|h| h.as_bool()).unwrap_or(false),
+            optional(&body, "open_tasks"),
+            optional(&body, "target_workstream_id"),
+        )?),
+        (Method::Patch, ["api", "workstreams", id, "persona"]) => json(200, commands::move_workstream_to_persona(conn, id.to_string(), required(&body, "persona_id")?)?),
+        (Method::Post, ["api", "workstreams", id, "copy"]) => json(201, commands::copy_workstream(
+            conn,
+            id.to_string(),
+            optional(&body, "name"),
+            optional(&body, "persona_id"),
+            body.get("reset_progress").and_then(|r| r.as_bool()).unwrap_or(false),
+        )?),
+        (Method::Delete, ["api", "workstreams", id]) => message(commands::delete_workstream(conn, id.to_string())?),
+
+        // Tasks
+        (Method::Get, ["api", "tasks"]) => json(200, commands::get_tasks_for_kanban(conn, param("workstream_id"), param("status"))?),
+        (Method::Post, ["api", "tasks", "query"]) => {
+            let query = serde_json::from_value(body)
+                .map_err(|e| ApiError::new(400, format!("Invalid task query: {}", e)))?;
+            json(200, commands::query_tasks(conn, query)?)
+        }
+        (Method::Post, ["api", "tasks"]) => json(201, commands::create_project_task(
+            conn,
+            required(&body, "workstream_id")?,
+            required(&body, "title")?,
+            optional(&body, "description"),
+            optional(&body, "status").unwrap_or_else(|| "todo".to_string()),
+            optional(&body, "priority").unwrap_or_else(|| "medium".to_string()),
+        )?),
+        (Method::Patch, ["api", "tasks", id]) => {
+            let only_status = body.as_object().is_some_and(|o| o.len() == 1 && o.contains_key("status"));
+            if only_status {
+                json(200, commands::update_task_status(conn, id.to_string(), required(&body, "status")?)?)
+            } else {
+                let status = match optional(&body, "status") {
+                    Some(status) => status,
+                    None => current_task_field(conn, id, "status")?,
+                };
+                let priority = match optional(&body, "priority") {
+                    Some(priority) => priority,
+                    None => current_task_field(conn, id, "priority")?,
+                };
+                json(200, commands::update_project_task(
+                    conn,
+                    id.to_string(),
+                    optional(&body, "title"),
+                    optional(&body, "description"),
+                    status,
+                    priority,
+                )?)
+            }
+        }
+        (Method::Delete, ["api", "tasks", id]) => message(commands::delete_project_task(conn, id.to_string())?),
+        (Method::Post, ["api", "tasks", id, "move"]) => json(200, commands::move_task(
+            conn,
+            id.to_string(),
+            required(&body, "status")?,
+            optional(&body, "after_id"),
+            optional(&body, "before_id"),
+        )?),
+        (Method::Patch, ["api", "tasks", id, "workstream"]) => json(200, commands::move_task_to_workstream(conn, id.to_string(), required(&body, "workstream_id")?)?),
+        (Method::Patch, ["api", "tasks", id, "due"]) => json(200, commands::set_task_due_date(conn, id.to_string(), optional(&body, "due_date"))?),
+        (Method::Patch, ["api", "tasks", id, "recurrence"]) => json(200, commands::set_task_recurrence(
+            conn,
+            id.to_string(),
+            optional(&body, "rule"),
+            body.get("from_completion").and_then(|f| f.as_bool()).unwrap_or(false),
+        )?),
+        (Method::Get, ["api", "recurrence", "preview"]) => json(200, commands::preview_recurrence(
+            param("rule").unwrap_or_default(),
+            param("start"),
+            param("limit").and_then(|l| l.parse().ok()),
+        )?),
+        (Method::Get, ["api", "tasks", id, "tree"]) => json(200, commands::get_task_tree(conn, id.to_string())?),
+        (Method::Post, ["api", "tasks", id, "subtasks"]) => json(201, commands::create_subtask(
+            conn,
+            id.to_string(),
+            required(&body, "title")?,
+            optional(&body, "description"),
+            optional(&body, "status"),
+            optional(&body, "priority"),
+            optional_hours(&body, "estimated_hours"),
+        )?),
+        (Method::Post, ["api", "tasks", id, "parent"]) => json(200, commands::move_subtask(
+            conn,
+            id.to_string(),
+            optional(&body, "parent_id"),
+            optional(&body, "after_id"),
+            optional(&body, "before_id"),
+        )?),
+        (Method::Patch, ["api", "tasks", id, "estimate"]) => json(200, commands::set_task_estimate(
+            conn,
+            id.to_string(),
+            optional_hours(&body, "estimated_hours"),
+        )?),
+        (Method::Post, ["api", "tasks", id, "checklist"]) => json(201, commands::add_checklist_item(conn, id.to_string(), required(&body, "text")?)?),
+        (Method::Patch, ["api", "checklist", id]) => json(200, commands::update_checklist_item(
+            conn,
+            id.to_string(),
+            optional(&body, "text"),
+            body.get("done").and_then(|d| d.as_bool()),
+        )?),
+        (Method::Post, ["api", "checklist", id, "move"]) => json(200, commands::move_checklist_item(
+            conn,
+            id.to_string(),
+            optional(&body, "after_id"),
+            optional(&body, "before_id"),
+        )?),
+        (Method::Delete, ["api", "checklist", id]) => message(commands::delete_checklist_item(conn, id.to_string())?),
+
+        // Kanban column policies
+        (Method::Get, ["api", "policies", "wip"]) => json(200, commands::get_wip_limits(conn, param("workstream_id"))?),
+        (Method::Post, ["api", "policies", "wip"]) => json(200, commands::set_wip_limit(
+            conn,
+            optional(&body, "workstream_id"),
+            required(&body, "status")?,
+            body.get("max_tasks")
+                .and_then(|m| m.as_i64())
+                .ok_or_else(|| ApiError::new(400, "Missing field: max_tasks"))?,
+            optional(&body, "enforcement"),
+        )?),
+        (Method::Delete, ["api", "policies", "wip", id]) => message(commands::delete_wip_limit(conn, id.to_string())?),
+        (Method::Get, ["api", "policies", "transitions"]) => json(200, commands::get_status_transitions(conn, param("workstream_id"))?),
+        (Method::Post, ["api", "policies", "transitions"]) => json(200, commands::set_status_transition(
+            conn,
+            optional(&body, "workstream_id"),
+            required(&body, "from_status")?,
+            required(&body, "to_status")?,
+            body.get("allowed").and_then(|a| a.as_bool()).unwrap_or(false),
+            optional(&body, "enforcement"),
+        )?),
+        (Method::Delete, ["api", "policies", "transitions", id]) => message(commands::delete_status_transition(conn, id.to_string())?),
+        (Method::Get, ["api", "policies", "completion"]) => json(200, commands::get_completion_policies(conn, param("workstream_id"))?),
+        (Method::Post, ["api", "policies", "completion"]) => json(200, commands::set_completion_policy(
+            conn,
+            optional(&body, "workstream_id"),
+            body.get("require_subtasks_done").and_then(|r| r.as_bool()).unwrap_or(true),
+            body.get("require_checklist_done").and_then(|r| r.as_bool()).unwrap_or(false),
+            optional(&body, "enforcement"),
+        )?),
+        (Method::Delete, ["api", "policies", "completion", id]) => message(commands::delete_completion_policy(conn, id.to_string())?),
+
+        // Milestones and forecasts
+        (Method::Get, ["api", "workstreams", id, "milestones"]) => json(200, commands::get_milestones(conn, id.to_string())?),
+        (Method::Post, ["api", "workstreams", id, "milestones"]) => json(201, commands::create_milestone(
+            conn,
+            id.to_string(),
+            required(&body, "name")?,
+            optional(&body, "description"),
+            required(&body, "due_date")?,
+        )?),
+        (Method::Patch, ["api", "milestones", id]) => json(200, commands::update_milestone(
+            conn,
+            id.to_string(),
+            optional(&body, "name"),
+            optional(&body, "description"),
+            optional(&body, "due_date"),
+        )?),
+        (Method::Delete, ["api", "milestones", id]) => message(commands::delete_milestone(conn, id.to_string())?),
+        (Method::Patch, ["api", "tasks", id, "milestone"]) => json(200, commands::set_task_milestone(conn, id.to_string(), optional(&body, "milestone_id"))?),
+        (Method::Get, ["api", "workstreams", id, "burndown"]) => json(200, commands::get_workstream_burndown(conn, id.to_string(), param("unit"))?),
+
+        // Workflows
+        (Method::Get, ["api", "workflows"]) => json(200, commands::get_workflows(conn)?),
+        (Method::Get, ["api", "workstreams", id, "workflow"]) => json(200, commands::get_workflow_for_workstream(conn, id.to_string())?),
+        (Method::Post, ["api", "workflows"]) => json(201, commands::create_workflow(
+            conn,
+            required(&body, "name")?,
+            optional(&body, "persona_id"),
+            optional(&body, "workstream_id"),
+            workflow_states(&body)?.ok_or_else(|| ApiError::new(400, "Missing field: states"))?,
+        )?),
+        (Method::Patch, ["api", "workflows", id]) => json(200, commands::update_workflow(
+            conn,
+            id.to_string(),
+            optional(&body, "name"),
+            workflow_states(&body)?,
+        )?),
+        (Method::Delete, ["api", "workflows", id]) => message(commands::delete_workflow(conn, id.to_string())?),
+
+        // Time tracking
+        (Method::Get, ["api", "timer"]) => json(200, commands::get_running_timer(conn)?),
+        (Method::Post, ["api", "timer", "start"]) => json(200, commands::start_timer(conn, required(&body, "task_id")?, optional(&body, "note"))?),
+        (Method::Post, ["api", "timer", "pause"]) => json(200, commands::pause_timer(conn)?),
+        (Method::Post, ["api", "timer", "resume"]) => json(200, commands::resume_timer(conn)?),
+        (Method::Post, ["api", "timer", "stop"]) => json(200, commands::stop_timer(conn)?),
+        (Method::Post, ["api", "timer", "orphan"]) => json(200, commands::resolve_orphaned_timer(conn, required(&body, "action")?)?),
+        (Method::Get, ["api", "time-entries"]) => json(200, commands::get_time_entries(conn, param("task_id"), param("from"), param("to"))?),
+        (Method::Post, ["api", "time-entries"]) => json(201, commands::add_time_entry(
+            conn,
+            required(&body, "task_id")?,
+            required(&body, "started_at")?,
+            optional(&body, "ended_at"),
+            body.get("minutes").and_then(|v| v.as_f64()),
+            optional(&body, "note"),
+        )?),
+        (Method::Patch, ["api", "time-entries", id]) => json(200, commands::update_time_entry(
+            conn,
+            id.to_string(),
+            optional(&body, "started_at"),
+            optional(&body, "ended_at"),
+            optional(&body, "note"),
+        )?),
+        (Method::Delete, ["api", "time-entries", id]) => message(commands::delete_time_entry(conn, id.to_string())?),
+        (Method::Get, ["api", "reports", "time"]) => json(200, commands::get_time_report(
+            conn,
+            param("group_by").unwrap_or_else(|| "day".to_string()),
+            param("from"),
+            param("to"),
+            param("persona_id"),
+            param("workstream_id"),
+        )?),
+        (Method::Get, ["api", "reports", "estimates"]) => json(200, commands::get_estimate_accuracy(
+            conn,
+            param("group_by").unwrap_or_else(|| "workstream".to_string()),
+            param("period"),
+            param("from"),
+            param("to"),
+            param("persona_id"),
+            param("workstream_id"),
+        )?),
+        (Method::Get, ["api", "estimates", "calibrate"]) => {
+            let workstream_id = param("workstream_id").ok_or_else(|| ApiError::new(400, "Missing parameter: workstream_id"))?;
+            let hours = param("hours")
+                .and_then(|hours| hours.parse::<f64>().ok())
+                .ok_or_else(|| ApiError::new(400, "Missing or invalid parameter: hours"))?;
+            json(200, commands::get_calibrated_estimate(conn, workstream_id, hours)?)
+        }
+
+        // Pomodoro focus sessions
+        (Method::Get, ["api", "focus"]) => json(200, commands::get_focus_status(conn)?),
+        (Method::Post, ["api", "focus", "start"]) => json(200, commands::start_focus_session(conn, optional(&body, "task_id"))?),
+        (Method::Post, ["api", "focus", "pause"]) => json(200, commands::pause_focus_session(conn)?),
+        (Method::Post, ["api", "focus", "resume"]) => json(200, commands::resume_focus_session(conn)?),
+        (Method::Post, ["api", "focus", "skip"]) => json(200, commands::skip_focus_phase(conn)?),
+        (Method::Post, ["api", "focus", "stop"]) => json(200, commands::stop_focus_session(conn)?),
+        (Method::Get, ["api", "focus", "stats"]) => json(200, commands::get_focus_stats(conn, param("from"), param("to"))?),
+        (Method::Get, ["api", "focus", "settings"]) => json(200, commands::get_pomodoro_settings(conn)?),
+        (Method::Patch, ["api", "focus", "settings"]) => json(200, commands::configure_pomodoro(
+            conn,
+            optional_u32(&body, "work_minutes"),
+            optional_u32(&body, "short_break_minutes"),
+            optional_u32(&body, "long_break_minutes"),
+            optional_u32(&body, "long_break_every"),
+            body.get("auto_start_breaks").and_then(|v| v.as_bool()),
+            body.get("auto_start_work").and_then(|v| v.as_bool()),
+        )?),
+
+        // Habits
+        (Method::Get, ["api", "habits"]) => json(200, commands::get_habits(conn, param("date"))?),
+        (Method::Post, ["api", "habits"]) => json(201, commands::create_habit(
+            conn,
+            required(&body, "workstream_id")?,
+            required(&body, "name")?,
+            optional(&body, "description"),
+            optional(&body, "frequency").unwrap_or_else(|| "daily".to_string()),
+            optional_u32(&body, "target_days").and_then(|d| u8::try_from(d).ok()),
+            optional_u32(&body, "target_quantity"),
+            optional(&body, "unit"),
+            optional(&body, "color"),
+        )?),
+        (Method::Post, ["api", "habits", id, "completions"]) => json(201, commands::log_habit_completion(
+            conn,
+            id.to_string(),
+            optional(&body, "date"),
+            optional_u32(&body, "quantity"),
+            optional(&body, "notes"),
+        )?),
+
+        // Journal
+        (Method::Get, ["api", "journal"]) => json(200, commands::get_journal_entries(
+            conn,
+            param("workstream_id"),
+            param("persona_id"),
+            param("limit").and_then(|l| l.parse().ok()),
+        )?),
+        (Method::Post, ["api", "journal"]) => json(201, commands::create_journal_entry(
+            conn,
+            required(&body, "title")?,
+            required(&body, "content")?,
+            optional(&body, "workstream_id"),
+            optional(&body, "persona_id"),
+            optional(&body, "entry_type").unwrap_or_else(|| "note".to_string()),
+            body.get("tags")
+                .and_then(|t| t.as_array())
+                .map(|tags| tags.iter().filter_map(|t| t.as_str().map(str::to_string)).collect())
+                .unwrap_or_default(),
+        )?),
+
+        (Method::Post, ["api", "journal", "query"]) => {
+            let query = serde_json::from_value(body)
+                .map_err(|e| ApiError::new(400, format!("Invalid journal query: {}", e)))?;
+            json(200, commands::query_journal(conn, query)?)
+        }
+
+        // Query language
+        (Method::Get, ["api", "search"]) => json(200, commands::search(
+            conn,
+            param("kind").unwrap_or_else(|| "tasks".to_string()),
+            param("q").unwrap_or_default(),
+            param("cursor"),
+            param("limit").and_then(|l| l.parse().ok()),
+        )?),
+        (Method::Get, ["api", "search", "check"]) => json(200, commands::check_query(
+            param("kind").unwrap_or_else(|| "tasks".to_string()),
+            param("q").unwrap_or_default(),
+        )?),
+        (Method::Get, ["api", "search", "complete"]) => json(200, commands::complete_query(
+            conn,
+            param("kind").unwrap_or_else(|| "tasks".to_string()),
+            param("q").unwrap_or_default(),
+            param("cursor").and_then(|c| c.parse().ok()),
+        )?),
+
+        (Method::Get, ["api", "agenda"]) => json(200, commands::get_agenda(conn, param("date"))?),
+        (Method::Get, ["api", "deadlines"]) => json(200, commands::get_upcoming_deadlines(conn, param("days").and_then(|days| days.parse().ok()))?),
+
+        // Saved filters
+        (Method::Get, ["api", "filters"]) => json(200, commands::get_saved_filters(conn, param("pinned").as_deref() == Some("true"))?),
+        (Method::Post, ["api", "filters"]) => json(201, commands::create_saved_filter(
+            conn,
+            required(&body, "name")?,
+            optional(&body, "kind").unwrap_or_else(|| "tasks".to_string()),
+            body.get("query").cloned().unwrap_or_else(|| serde_json::json!({})),
+            body.get("pinned").and_then(|p| p.as_bool()).unwrap_or(false),
+        )?),
+        (Method::Patch, ["api", "filters", id]) => json(200, commands::update_saved_filter(
+            conn,
+            id.to_string(),
+            optional(&body, "name"),
+            body.get("query").cloned(),
+            body.get("pinned").and_then(|p| p.as_bool()),
+        )?),
+        (Method::Delete, ["api", "filters", id]) => message(commands::delete_saved_filter(conn, id.to_string())?),
+        (Method::Get, ["api", "filters", id, "results"]) => json(200, commands::run_saved_filter(
+            conn,
+            id.to_string(),
+            param("cursor"),
+            param("limit").and_then(|l| l.parse().ok()),
+        )?),
+
+        (_, ["api", ..]) => Err(ApiError::new(404, format!("No route for {} {}", method, path))),
+        _ => Err(ApiError::new(404, "Not found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::generate_id;
+
+    const TOKEN: &str = "secret-token";
+
+    // A server on a database file, so GETs run on read-only connections as they do in the app.
+    struct TestServer {
+        server: Option<ApiServer>,
+        path: std::path::PathBuf,
+    }
+
+    impl TestServer {
+        fn start() -> TestServer {
+            let path = std::env::temp_dir().join(format!("http-api-test-{}.db", generate_id()));
+            let pool = DbPool::open(path.to_str().unwrap(), 2).unwrap();
+            let server = ApiServer::start(pool, 0, TOKEN.to_string()).unwrap();
+            TestServer { server: Some(server), path }
+        }
+
+        fn call(&self, method: &str, url: &str, authorization: Option<&str>, body: Option<Value>) -> (u16, Value) {
+            let port = self.server.as_ref().unwrap().port();
+            let mut request = ureq::request(method, &format!("http://127.0.0.1:{}{}", port, url));
+            if let Some(authorization) = authorization {
+                request = request.set("Authorization", authorization);
+            }
+            let result = match body {
+                Some(body) => request.set("Content-Type", "application/json").send_string(&body.to_string()),
+                None => request.call(),
+            };
+            let response = match result {
+                Ok(response) => response,
+                Err(ureq::Error::Status(_, response)) => response,
+                Err(e) => panic!("{} {} failed: {}", method, url, e),
+            };
+            let status = response.status();
+            (status, serde_json::from_str(&response.into_string().unwrap()).unwrap())
+        }
+
+        fn get(&self, url: &str) -> (u16, Value) {
+            self.call("GET", url, Some(&format!("Bearer {}", TOKEN)), None)
+        }
+
+        fn post(&self, url: &str, body: Value) -> (u16, Value) {
+            self.call("POST", url, Some(&format!("Bearer {}", TOKEN)), Some(body))
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            if let Some(server) = self.server.take() {
+                server.stop();
+            }
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+            }
+        }
+    }
+
+    #[test]
+    fn requests_need_the_api_token() {
+        let server = TestServer::start();
+        let unauthorized = (401, serde_json::json!({ "error": "Missing or invalid API token" }));
+
+        assert_eq!(server.call("GET", "/api/personas", None, None), unauthorized);
+        for authorization in ["Bearer wrong-token", "Bearer secret-toke", "Bearer secret-token-and-more", "Bearer ", TOKEN, "Basic secret-token"] {
+            assert_eq!(server.call("GET", "/api/personas", Some(authorization), None), unauthorized, "{}", authorization);
+        }
+        assert_eq!(
+            server.call("POST", "/api/personas", Some("Bearer wrong-token"), Some(serde_json::json!({ "name": "Work" }))),
+            unauthorized
+        );
+        assert_eq!(server.get("/api/personas"), (200, serde_json::json!([])));
+
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[test]
+    fn only_reads_go_to_the_reader_connections() {
+        assert!(reads_only(&Method::Get, "/api/tasks"));
+        assert!(reads_only(&Method::Get, "/api/focus/stats"));
+        assert!(reads_only(&Method::Post, "/api/tasks/query"));
+        assert!(reads_only(&Method::Post, "/api/journal/query"));
+        assert!(!reads_only(&Method::Get, "/api/focus"));
+        assert!(!reads_only(&Method::Post, "/api/personas"));
+        assert!(!reads_only(&Method::Patch, "/api/tasks/abc"));
+        assert!(!reads_only(&Method::Delete, "/api/filters/abc"));
+    }
+
+    #[test]
+    fn writes_go_through_the_writer_and_gets_only_read() {
+        let server = TestServer::start();
+
+        // A write routed to a read-only connection would fail with "attempt to write a readonly database".
+        let (status, persona) = server.post("/api/personas", serde_json::json!({ "name": "Work" }));
+        assert_eq!(status, 201, "{}", persona);
+        let persona_id = persona["id"].as_str().unwrap();
+        let (status, workstream) = server.post("/api/workstreams", serde_json::json!({ "persona_id": persona_id, "name": "Launch", "status": "active" }));
+        assert_eq!(status, 201, "{}", workstream);
+        let workstream_id = workstream["id"].as_str().unwrap();
+        let (status, task) = server.post("/api/tasks", serde_json::json!({ "workstream_id": workstream_id, "title": "Write the post" }));
+        assert_eq!(status, 201, "{}", task);
+        let task_id = task["id"].as_str().unwrap();
+        let (status, filter) = server.post("/api/filters", serde_json::json!({ "name": "Everything" }));
+        assert_eq!(status, 201, "{}", filter);
+        let filter_id = filter["id"].as_str().unwrap();
+
+        // The readers see what the writer committed
+        let (status, personas) = server.get("/api/personas");
+        assert_eq!(status, 200);
+        assert_eq!(personas[0]["id"], persona["id"]);
+
+        // Every GET, on a reader (or the writer for the focus session), answers without writing
+        let gets = [
+            "/api/personas".to_string(),
+            "/api/workstreams".to_string(),
+            format!("/api/workstreams?persona_id={}", persona_id),
+            format!("/api/tasks?workstream_id={}", workstream_id),
+            "/api/recurrence/preview?rule=FREQ%3DWEEKLY".to_string(),
+            format!("/api/tasks/{}/tree", task_id),
+            "/api/policies/wip".to_string(),
+            "/api/policies/transitions".to_string(),
+            "/api/policies/completion".to_string(),
+            format!("/api/workstreams/{}/milestones", workstream_id),
+            format!("/api/workstreams/{}/burndown", workstream_id),
+            "/api/workflows".to_string(),
+            format!("/api/workstreams/{}/workflow", workstream_id),
+            "/api/timer".to_string(),
+            "/api/time-entries".to_string(),
+            "/api/reports/time".to_string(),
+            "/api/reports/estimates".to_string(),
+            format!("/api/estimates/calibrate?workstream_id={}&hours=2", workstream_id),
+            "/api/focus".to_string(),
+            "/api/focus/stats".to_string(),
+            "/api/focus/settings".to_string(),
+            "/api/habits".to_string(),
+            "/api/journal".to_string(),
+            "/api/search?q=status%3Atodo".to_string(),
+            "/api/search/check?q=status%3Atodo".to_string(),
+            "/api/search/complete?q=sta".to_string(),
+            "/api/agenda".to_string(),
+            "/api/deadlines".to_string(),
+            "/api/filters".to_string(),
+            format!("/api/filters/{}/results", filter_id),
+        ];
+        for url in &gets {
+            let (status, body) = server.get(url);
+            assert_eq!(status, 200, "GET {}: {}", url, body);
+        }
+
+        let (status, tasks) = server.post("/api/tasks/query", serde_json::json!({ "workstream_id": workstream_id }));
+        assert_eq!(status, 200, "{}", tasks);
+        assert_eq!(server.get("/api/nowhere").0, 404);
+    }
+}
@@ -0,0 +1,935 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::database::{generate_id, get_current_timestamp, JournalEntryType};
+
+// Journal vault: mirrors journal_entries as Markdown files with YAML front matter.
+//
+// Layout inside the vault folder:
+//   <persona>/<workstream>/<date>-<title>-<short id>.md
+//   <persona>/_general/...      entries attached to a persona only
+//   _unfiled/...                entries with neither persona nor workstream
+//
+// The last synced content hash of every file is kept in journal_vault_files so
+// external edits can be told apart from files we wrote ourselves.
+
+const GENERAL_FOLDER: &str = "_general";
+const UNFILED_FOLDER: &str = "_unfiled";
+const CONFLICT_SUFFIX: &str = ".conflict.md";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultConflict {
+    pub entry_id: String,
+    pub path: String,
+    pub database_updated_at: Option<String>, // None when the entry was deleted in the app
+    pub file_updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VaultReport {
+    pub vault_path: String,
+    pub exported: usize,
+    pub imported: usize,
+    pub created: usize,
+    pub unchanged: usize,
+    pub conflicts: Vec<VaultConflict>,
+    pub errors: Vec<String>,
+}
+
+// A journal entry joined with the names used for the folder layout.
+struct VaultEntry {
+    id: String,
+    workstream_id: Option<String>,
+    persona_id: Option<String>,
+    persona_name: Option<String>,
+    workstream_name: Option<String>,
+    title: String,
+    content: String,
+    entry_type: JournalEntryType,
+    tags: Vec<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+// A file change held back until the database side is committed, so a failed
+// sync leaves the files as they were too.
+enum FileChange {
+    Write(PathBuf, String),
+    Rename(PathBuf, PathBuf),
+    Remove(PathBuf),
+}
+
+// The Markdown files found in the vault, keyed by entry ID with their text,
+// and the files that couldn't be read and must be left alone.
+struct VaultScan {
+    files: HashMap<String, (PathBuf, String, VaultFile)>,
+    unreadable: Vec<PathBuf>,
+}
+
+// The parsed form of a Markdown file in the vault.
+#[derive(Debug, Default)]
+struct VaultFile {
+    id: Option<String>,
+    title: Option<String>,
+    persona: Option<String>,
+    workstream: Option<String>,
+    entry_type: Option<String>,
+    tags: Vec<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    body: String,
+}
+
+// Write every journal entry to the vault, overwriting the mirrored files.
+pub fn export_vault(conn: &Connection, vault_path: &str) -> Result<VaultReport, String> {
+    let root = PathBuf::from(vault_path);
+    fs::create_dir_all(&root).map_err(|e| format!("Vault directory error: {}", e))?;
+
+    let mut report = VaultReport {
+        vault_path: vault_path.to_string(),
+        ..Default::default()
+    };
+
+    let existing = scan_vault(&root, &mut report)?.files;
+    let mut changes = Vec::new();
+    for entry in load_entries(conn)? {
+        let previous_path = existing.get(&entry.id).map(|(path, _, _)| path.clone());
+        write_entry(conn, &root, &entry, previous_path.as_deref(), &mut changes)?;
+        report.exported += 1;
+    }
+    apply_changes(changes)?;
+
+    Ok(report)
+}
+
+// Two-way sync between journal_entries and the vault.
+//
+// - files edited outside the app are imported back into the database
+// - entries changed in the app since the last sync are written out again
+// - new files without an `id` become new journal entries
+// - when both sides changed, the file's `updated_at` is compared with the
+//   database row and the entry is reported as a conflict: the edited file is
+//   kept next to it as *.conflict.md and the database version takes its place
+// - files of entries deleted in the app are removed, unless they were edited
+//   since; those are reported and kept as *.conflict.md too
+//
+// The database side runs in one transaction and the files are only touched
+// once it has committed.
+pub fn sync_vault(conn: &Connection, vault_path: &str) -> Result<VaultReport, String> {
+    let root = PathBuf::from(vault_path);
+    fs::create_dir_all(&root).map_err(|e| format!("Vault directory error: {}", e))?;
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    let conn = &tx;
+
+    let mut report = VaultReport {
+        vault_path: vault_path.to_string(),
+        ..Default::default()
+    };
+
+    let VaultScan { mut files, unreadable } = scan_vault(&root, &mut report)?;
+    let entries = load_entries(conn)?;
+    let mut changes = Vec::new();
+
+    for entry in &entries {
+        let Some((path, raw, file)) = files.remove(&entry.id) else {
+            // A file we couldn't read may be this entry's; it's reported, so keep it.
+            let synced_path = synced_path(conn, &entry.id)?.map(|relative| root.join(relative));
+            if unreadable.iter().any(|path| *path == root.join(entry_relative_path(entry)) || Some(path) == synced_path.as_ref()) {
+                continue;
+            }
+            // Never exported, or the file was removed: write it (again).
+            write_entry(conn, &root, entry, None, &mut changes)?;
+            report.exported += 1;
+            continue;
+        };
+
+        let synced_hash = synced_hash(conn, &entry.id)?;
+        let file_changed = synced_hash.as_deref() != Some(content_hash(&raw).as_str());
+        let database_changed = file.updated_at.as_deref() != Some(entry.updated_at.as_str());
+
+        match (file_changed, database_changed) {
+            (false, false) => report.unchanged += 1,
+            (false, true) => {
+                write_entry(conn, &root, entry, Some(&path), &mut changes)?;
+                report.exported += 1;
+            }
+            (true, false) => {
+                import_file(conn, entry, &file)?;
+                // Re-render so the file carries the new updated_at and canonical location.
+                let refreshed = load_entry(conn, &entry.id)?;
+                write_entry(conn, &root, &refreshed, Some(&path), &mut changes)?;
+                report.imported += 1;
+            }
+            (true, true) => {
+                // Writing the database version records its hash, so the conflict is reported once.
+                changes.push(FileChange::Write(conflict_path(&path), raw));
+                write_entry(conn, &root, entry, Some(&path), &mut changes)?;
+
+                report.conflicts.push(VaultConflict {
+                    entry_id: entry.id.clone(),
+                    path: relative_path(&root, &path),
+                    database_updated_at: Some(entry.updated_at.clone()),
+                    file_updated_at: file.updated_at.clone(),
+                });
+            }
+        }
+    }
+
+    // Whatever is left either has no id yet or points at an entry missing from the database.
+    for (path, raw, file) in files.into_values() {
+        if let Some(id) = &file.id {
+            if let Some(hash) = synced_hash(conn, id)? {
+                // Synced before, so the entry was deleted in the app: drop the mirror
+                // too, unless it was edited since.
+                if content_hash(&raw) == hash {
+                    changes.push(FileChange::Remove(path.clone()));
+                } else {
+                    changes.push(FileChange::Rename(path.clone(), conflict_path(&path)));
+                    report.conflicts.push(VaultConflict {
+                        entry_id: id.clone(),
+                        path: relative_path(&root, &path),
+                        database_updated_at: None,
+                        file_updated_at: file.updated_at.clone(),
+                    });
+                }
+                conn.execute("DELETE FROM journal_vault_files WHERE entry_id = ?1", [id])
+                    .map_err(|e| format!("SQL delete error: {}", e))?;
+                continue;
+            }
+        }
+
+        match create_from_file(conn, &file) {
+            Ok(id) => {
+                let entry = load_entry(conn, &id)?;
+                write_entry(conn, &root, &entry, Some(&path), &mut changes)?;
+                report.created += 1;
+            }
+            Err(e) => report.errors.push(format!("{}: {}", relative_path(&root, &path), e)),
+        }
+    }
+
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    apply_changes(changes)?;
+    Ok(report)
+}
+
+// Apply staged file changes in order, stopping at the first failure so a
+// conflict copy is never skipped before the file it saves is overwritten.
+fn apply_changes(changes: Vec<FileChange>) -> Result<(), String> {
+    for change in changes {
+        match change {
+            FileChange::Write(path, content) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| format!("Vault directory error: {}", e))?;
+                }
+                fs::write(&path, content).map_err(|e| format!("Vault write error: {}", e))?;
+            }
+            FileChange::Rename(from, to) => {
+                fs::rename(&from, &to).map_err(|e| format!("Vault write error: {}", e))?;
+            }
+            FileChange::Remove(path) => {
+                if path.exists() {
+                    fs::remove_file(&path).map_err(|e| format!("Vault remove error: {}", e))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Where the losing side of a conflict is kept; sync skips these files.
+fn conflict_path(path: &Path) -> PathBuf {
+    path.with_file_name(format!(
+        "{}{}",
+        path.file_stem().and_then(|s| s.to_str()).unwrap_or("entry"),
+        CONFLICT_SUFFIX
+    ))
+}
+
+fn load_entries(conn: &Connection) -> Result<Vec<VaultEntry>, String> {
+    let mut stmt = conn.prepare(&format!("{} ORDER BY j.created_at", ENTRY_SELECT))
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let rows = stmt.query_map([], entry_from_row)
+        .map_err(|e| format!("SQL query error: {}", e))?;
+
+    let entries: Result<Vec<VaultEntry>, rusqlite::Error> = rows.collect();
+    entries.map_err(|e| format!("SQL collect error: {}", e))
+}
+
+fn load_entry(conn: &Connection, id: &str) -> Result<VaultEntry, String> {
+    conn.query_row(&format!("{} WHERE j.id = ?1", ENTRY_SELECT), [id], entry_from_row)
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+const ENTRY_SELECT: &str = "
+    SELECT
+        j.id,
+        j.workstream_id,
+        COALESCE(j.persona_id, w.persona_id),
+        p.name,
+        w.name,
+        j.title,
+        j.content,
+        j.entry_type,
+        j.tags,
+        j.created_at,
+        j.updated_at
+    FROM journal_entries j
+    LEFT JOIN workstreams w ON j.workstream_id = w.id
+    LEFT JOIN personas p ON COALESCE(j.persona_id, w.persona_id) = p.id
+";
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<VaultEntry> {
+    let entry_type: String = row.get(7)?;
+    let tags: Option<String> = row.get(8)?;
+
+    Ok(VaultEntry {
+        id: row.get(0)?,
+        workstream_id: row.get(1)?,
+        persona_id: row.get(2)?,
+        persona_name: row.get(3)?,
+        workstream_name: row.get(4)?,
+        title: row.get(5)?,
+        content: row.get(6)?,
+        entry_type: parse_entry_type(&entry_type).unwrap_or(JournalEntryType::Note),
+        tags: tags
+            .and_then(|t| serde_json::from_str(&t).ok())
+            .unwrap_or_default(),
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn parse_entry_type(value: &str) -> Option<JournalEntryType> {
+    match value.trim().trim_matches('"').to_lowercase().as_str() {
+        "note" => Some(JournalEntryType::Note),
+        "update" => Some(JournalEntryType::Update),
+        "reflection" => Some(JournalEntryType::Reflection),
+        "meeting" => Some(JournalEntryType::Meeting),
+        _ => None,
+    }
+}
+
+fn entry_type_name(entry_type: &JournalEntryType) -> &'static str {
+    match entry_type {
+        JournalEntryType::Note => "Note",
+        JournalEntryType::Update => "Update",
+        JournalEntryType::Reflection => "Reflection",
+        JournalEntryType::Meeting => "Meeting",
+    }
+}
+
+// Apply an edited file to an existing entry.
+fn import_file(conn: &Connection, entry: &VaultEntry, file: &VaultFile) -> Result<(), String> {
+    let entry_type = match &file.entry_type {
+        Some(value) => parse_entry_type(value)
+            .ok_or_else(|| format!("Invalid entry_type: {}. Valid types: Note, Update, Reflection, Meeting", value))?,
+        None => entry.entry_type.clone(),
+    };
+
+    let (persona_id, workstream_id) = resolve_location(
+        conn,
+        file.persona.as_deref(),
+        file.workstream.as_deref(),
+        (entry.persona_id.clone(), entry.workstream_id.clone()),
+    )?;
+    // The file names the persona a workstream entry inherits; only store one
+    // when the file actually moved the entry.
+    let persona_changed = file.persona != entry.persona_name;
+    let workstream_changed = file.workstream != entry.workstream_name;
+
+    conn.execute(
+        "UPDATE journal_entries SET title = ?1, content = ?2, entry_type = ?3, tags = ?4,
+             persona_id = CASE WHEN ?9 THEN ?5 ELSE persona_id END,
+             workstream_id = CASE WHEN ?10 THEN ?6 ELSE workstream_id END,
+             updated_at = ?7
+         WHERE id = ?8",
+        rusqlite::params![
+            file.title.clone().unwrap_or_else(|| entry.title.clone()),
+            file.body,
+            serde_json::to_string(&entry_type).map_err(|e| format!("Entry type serialization error: {}", e))?,
+            serde_json::to_string(&file.tags).map_err(|e| format!("Tags serialization error: {}", e))?,
+            persona_id,
+            workstream_id,
+            get_current_timestamp().to_rfc3339(),
+            entry.id,
+            persona_changed,
+            workstream_changed
+        ]
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    Ok(())
+}
+
+// Insert a new entry for a file written outside the app.
+fn create_from_file(conn: &Connection, file: &VaultFile) -> Result<String, String> {
+    let entry_type = match &file.entry_type {
+        Some(value) => parse_entry_type(value)
+            .ok_or_else(|| format!("Invalid entry_type: {}. Valid types: Note, Update, Reflection, Meeting", value))?,
+        None => JournalEntryType::Note,
+    };
+
+    let (persona_id, workstream_id) = resolve_location(
+        conn,
+        file.persona.as_deref(),
+        file.workstream.as_deref(),
+        (None, None),
+    )?;
+
+    let now = get_current_timestamp().to_rfc3339();
+    let created_at = file.created_at
+        .as_deref()
+        .and_then(|c| DateTime::parse_from_rfc3339(c).ok())
+        .map(|c| c.with_timezone(&Utc).to_rfc3339())
+        .unwrap_or_else(|| now.clone());
+    let id = file.id.clone().unwrap_or_else(generate_id);
+
+    conn.execute(
+        "INSERT INTO journal_entries (id, workstream_id, persona_id, title, content, entry_type, tags, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            id,
+            workstream_id,
+            persona_id,
+            file.title.clone().unwrap_or_else(|| "Untitled".to_string()),
+            file.body,
+            serde_json::to_string(&entry_type).map_err(|e| format!("Entry type serialization error: {}", e))?,
+            serde_json::to_string(&file.tags).map_err(|e| format!("Tags serialization error: {}", e))?,
+            created_at,
+            now
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    Ok(id)
+}
+
+// Map persona/workstream names from front matter back to IDs. Unknown names keep the fallback.
+fn resolve_location(
+    conn: &Connection,
+    persona: Option<&str>,
+    workstream: Option<&str>,
+    fallback: (Option<String>, Option<String>),
+) -> Result<(Option<String>, Option<String>), String> {
+    let persona_id: Option<String> = match persona {
+        Some(name) => conn.query_row("SELECT id FROM personas WHERE name = ?1", [name], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("SQL query error: {}", e))?
+            .or(fallback.0),
+        None => fallback.0,
+    };
+
+    let workstream_id: Option<String> = match workstream {
+        Some(name) => conn.query_row(
+            "SELECT id FROM workstreams WHERE name = ?1 AND (?2 IS NULL OR persona_id = ?2)",
+            rusqlite::params![name, persona_id],
+            |row| row.get(0),
+        )
+            .optional()
+            .map_err(|e| format!("SQL query error: {}", e))?
+            .or(fallback.1),
+        None => fallback.1,
+    };
+
+    Ok((persona_id, workstream_id))
+}
+
+fn synced_path(conn: &Connection, entry_id: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT relative_path FROM journal_vault_files WHERE entry_id = ?1",
+        [entry_id],
+        |row| row.get(0),
+    )
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+fn synced_hash(conn: &Connection, entry_id: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT content_hash FROM journal_vault_files WHERE entry_id = ?1",
+        [entry_id],
+        |row| row.get(0),
+    )
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+// Stage an entry's file at its canonical path and remember what will be written.
+fn write_entry(
+    conn: &Connection,
+    root: &Path,
+    entry: &VaultEntry,
+    previous_path: Option<&Path>,
+    changes: &mut Vec<FileChange>,
+) -> Result<(), String> {
+    let path = root.join(entry_relative_path(entry));
+    let rendered = render_entry(entry);
+    let hash = content_hash(&rendered);
+    changes.push(FileChange::Write(path.clone(), rendered));
+
+    // The entry moved (renamed persona, new title, file created by hand): drop the old copy.
+    if let Some(previous) = previous_path {
+        if previous != path {
+            changes.push(FileChange::Remove(previous.to_path_buf()));
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO journal_vault_files (entry_id, relative_path, content_hash, synced_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(entry_id) DO UPDATE SET relative_path = excluded.relative_path, content_hash = excluded.content_hash, synced_at = excluded.synced_at",
+        rusqlite::params![
+            entry.id,
+            relative_path(root, &path),
+            hash,
+            get_current_timestamp().to_rfc3339()
+        ]
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    Ok(())
+}
+
+fn entry_relative_path(entry: &VaultEntry) -> PathBuf {
+    let mut path = PathBuf::new();
+    match (&entry.persona_name, &entry.workstream_name) {
+        (Some(persona), Some(workstream)) => {
+            path.push(sanitize_component(persona));
+            path.push(sanitize_component(workstream));
+        }
+        (Some(persona), None) => {
+            path.push(sanitize_component(persona));
+            path.push(GENERAL_FOLDER);
+        }
+        (None, Some(workstream)) => {
+            path.push(UNFILED_FOLDER);
+            path.push(sanitize_component(workstream));
+        }
+        (None, None) => path.push(UNFILED_FOLDER),
+    }
+
+    let date = entry.created_at.get(..10).unwrap_or("undated");
+    let short_id = entry.id.get(..8).unwrap_or(&entry.id);
+    path.push(format!("{}-{}-{}.md", date, slugify(&entry.title), short_id));
+    path
+}
+
+fn sanitize_component(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').to_string();
+    if cleaned.is_empty() { "_".to_string() } else { cleaned }
+}
+
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(|c| c.to_lowercase()) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') && !slug.is_empty() {
+            slug.push('-');
+        }
+        if slug.chars().count() >= 48 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-').to_string();
+    if slug.is_empty() { "entry".to_string() } else { slug }
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+// Collect every Markdown file in the vault, keyed by entry ID. Files without an
+// ID get a synthetic "new:<path>" key so they can be imported as new entries.
+fn scan_vault(root: &Path, report: &mut VaultReport) -> Result<VaultScan, String> {
+    let mut files: HashMap<String, (PathBuf, String, VaultFile)> = HashMap::new();
+    let mut unreadable = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let listing = fs::read_dir(&dir).map_err(|e| format!("Vault read error: {}", e))?;
+        for item in listing {
+            let path = item.map_err(|e| format!("Vault read error: {}", e))?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+
+            if name.starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if !name.ends_with(".md") || name.ends_with(CONFLICT_SUFFIX) {
+                continue;
+            }
+
+            // One unreadable or non-UTF-8 file shouldn't stop the others from syncing.
+            let raw = match fs::read_to_string(&path) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    report.errors.push(format!("{}: {}", relative_path(root, &path), e));
+                    unreadable.push(path);
+                    continue;
+                }
+            };
+            let file = parse_file(&raw);
+            let key = match &file.id {
+                Some(id) => id.clone(),
+                None => format!("new:{}", relative_path(root, &path)),
+            };
+
+            if let Some((other, _, _)) = files.get(&key) {
+                report.errors.push(format!(
+                    "{}: duplicate ID also used by {}",
+                    relative_path(root, &path),
+                    relative_path(root, other)
+                ));
+                continue;
+            }
+            files.insert(key, (path, raw, file));
+        }
+    }
+
+    Ok(VaultScan { files, unreadable })
+}
+
+fn render_entry(entry: &VaultEntry) -> String {
+    let mut out = String::from("---\n");
+    out.push_str(&format!("id: {}\n", entry.id));
+    out.push_str(&format!("title: {}\n", yaml_string(&entry.title)));
+    if let Some(persona) = &entry.persona_name {
+        out.push_str(&format!("persona: {}\n", yaml_string(persona)));
+    }
+    if let Some(workstream) = &entry.workstream_name {
+        out.push_str(&format!("workstream: {}\n", yaml_string(workstream)));
+    }
+    out.push_str(&format!("entry_type: {}\n", entry_type_name(&entry.entry_type)));
+    let tags: Vec<String> = entry.tags.iter().map(|t| yaml_string(t)).collect();
+    out.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    out.push_str(&format!("created_at: {}\n", entry.created_at));
+    out.push_str(&format!("updated_at: {}\n", entry.updated_at));
+    out.push_str("---\n\n");
+    out.push_str(&entry.content);
+    if !entry.content.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+// Quote a scalar only when plain YAML would misread it.
+fn yaml_string(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.trim() != value
+        || value.contains([':', '#', '[', ']', '{', '}', ',', '"', '\'', '\n', '\\'])
+        || value.starts_with(['-', '?', '!', '&', '*', '|', '>', '@', '`', '%'])
+        || matches!(value.to_lowercase().as_str(), "true" | "false" | "null" | "yes" | "no" | "~");
+
+    if needs_quotes {
+        serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_file(raw: &str) -> VaultFile {
+    let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+    let mut file = VaultFile::default();
+
+    let Some(rest) = raw.strip_prefix("---\n").or_else(|| raw.strip_prefix("---\r\n")) else {
+        file.body = raw.to_string();
+        return file;
+    };
+
+    let mut front_matter = Vec::new();
+    let mut body_start = None;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == "---" {
+            body_start = Some(offset);
+            break;
+        }
+        front_matter.push(line.trim_end_matches(['\n', '\r']));
+    }
+
+    let Some(body_start) = body_start else {
+        file.body = raw.to_string();
+        return file;
+    };
+    file.body = rest[body_start..].trim_start_matches(['\n', '\r']).to_string();
+
+    let mut list_key: Option<String> = None;
+    for line in front_matter {
+        // Block-style list items, e.g. "tags:\n  - health\n  - sleep".
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            if list_key.as_deref() == Some("tags") {
+                file.tags.push(yaml_scalar(item));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = value.trim();
+        list_key = None;
+
+        match key.as_str() {
+            "tags" if value.is_empty() => list_key = Some(key),
+            "tags" => file.tags = yaml_inline_list(value),
+            "id" => file.id = non_empty(yaml_scalar(value)),
+            "title" => file.title = non_empty(yaml_scalar(value)),
+            "persona" => file.persona = non_empty(yaml_scalar(value)),
+            "workstream" => file.workstream = non_empty(yaml_scalar(value)),
+            "entry_type" => file.entry_type = non_empty(yaml_scalar(value)),
+            "created_at" => file.created_at = non_empty(yaml_scalar(value)),
+            "updated_at" => file.updated_at = non_empty(yaml_scalar(value)),
+            _ => {}
+        }
+    }
+
+    file
+}
+
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() { None } else { Some(value) }
+}
+
+fn yaml_scalar(value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('"') && value.ends_with('"') && value.len() >= 2 {
+        return serde_json::from_str(value).unwrap_or_else(|_| value[1..value.len() - 1].to_string());
+    }
+    if value.starts_with('\'') && value.ends_with('\'') && value.len() >= 2 {
+        return value[1..value.len() - 1].replace("''", "'");
+    }
+    value.to_string()
+}
+
+fn yaml_inline_list(value: &str) -> Vec<String> {
+    let inner = value.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in inner.chars() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            (None, ',') => {
+                items.push(yaml_scalar(&current));
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    items.push(yaml_scalar(&current));
+
+    items.into_iter().filter(|t| !t.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    struct Vault {
+        root: PathBuf,
+        conn: Connection,
+    }
+
+    impl Vault {
+        fn new() -> Vault {
+            let root = std::env::temp_dir().join(format!("vault-test-{}", generate_id()));
+            let conn = commands::open_database(":memory:").unwrap();
+            Vault { root, conn }
+        }
+
+        fn sync(&self) -> VaultReport {
+            sync_vault(&self.conn, self.root.to_str().unwrap()).unwrap()
+        }
+
+        // The single mirrored file of an entry, and its conflict copy.
+        fn files(&self) -> (Vec<PathBuf>, Vec<PathBuf>) {
+            let folder = self.root.join(UNFILED_FOLDER);
+            let mut paths: Vec<PathBuf> = fs::read_dir(folder).unwrap().map(|item| item.unwrap().path()).collect();
+            paths.sort();
+            paths.into_iter().partition(|path| !path.to_string_lossy().ends_with(CONFLICT_SUFFIX))
+        }
+
+        fn edit_file(&self, path: &Path) {
+            let raw = fs::read_to_string(path).unwrap();
+            fs::write(path, format!("{}\nWritten in the editor.\n", raw)).unwrap();
+        }
+    }
+
+    impl Drop for Vault {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn add_entry(vault: &Vault) -> String {
+        commands::create_journal_entry(&vault.conn, "Standup".into(), "Shipped the beta.".into(), None, None, "note".into(), Vec::new())
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn a_conflict_keeps_both_versions_and_is_reported_once() {
+        let vault = Vault::new();
+        let id = add_entry(&vault);
+        vault.sync();
+
+        let (files, _) = vault.files();
+        vault.edit_file(&files[0]);
+        vault.conn.execute(
+            "UPDATE journal_entries SET content = 'Beta slipped a week.', updated_at = ?1 WHERE id = ?2",
+            [get_current_timestamp().to_rfc3339(), id],
+        ).unwrap();
+
+        let report = vault.sync();
+        assert_eq!(report.conflicts.len(), 1);
+        let (files, conflicts) = vault.files();
+        assert!(fs::read_to_string(&files[0]).unwrap().contains("Beta slipped a week."));
+        assert!(fs::read_to_string(&conflicts[0]).unwrap().contains("Written in the editor."));
+
+        let again = vault.sync();
+        assert!(again.conflicts.is_empty());
+        assert_eq!(again.unchanged, 1);
+    }
+
+    #[test]
+    fn the_file_of_a_deleted_entry_goes_unless_it_was_edited() {
+        let vault = Vault::new();
+        let kept = add_entry(&vault);
+        let edited = add_entry(&vault);
+        vault.sync();
+
+        let (files, _) = vault.files();
+        let edited_file = files.iter().find(|path| path.to_string_lossy().contains(&edited[..8])).unwrap();
+        vault.edit_file(edited_file);
+        vault.conn.execute("DELETE FROM journal_entries WHERE id IN (?1, ?2)", [&kept, &edited]).unwrap();
+
+        let report = vault.sync();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].entry_id, edited);
+        assert!(report.conflicts[0].database_updated_at.is_none());
+        let (files, conflicts) = vault.files();
+        assert!(files.is_empty());
+        assert!(fs::read_to_string(&conflicts[0]).unwrap().contains("Written in the editor."));
+
+        let again = vault.sync();
+        assert!(again.conflicts.is_empty() && again.created == 0);
+    }
+
+    #[test]
+    fn a_failed_sync_leaves_the_database_and_the_files_untouched() {
+        let vault = Vault::new();
+        let imported = add_entry(&vault);
+        let broken = add_entry(&vault);
+        vault.sync();
+
+        // The first entry is imported, then the second file can't be.
+        let (files, _) = vault.files();
+        let find = |id: &str| files.iter().find(|path| path.to_string_lossy().contains(&id[..8])).unwrap().clone();
+        let (imported_file, broken_file) = (find(&imported), find(&broken));
+        vault.edit_file(&imported_file);
+        let edited = fs::read_to_string(&imported_file).unwrap();
+        let valid = fs::read_to_string(&broken_file).unwrap();
+        fs::write(&broken_file, valid.replace("entry_type: Note", "entry_type: Memo")).unwrap();
+
+        assert!(sync_vault(&vault.conn, vault.root.to_str().unwrap()).is_err());
+        let content: String = vault.conn
+            .query_row("SELECT content FROM journal_entries WHERE id = ?1", [&imported], |row| row.get(0))
+            .unwrap();
+        assert_eq!(content, "Shipped the beta.");
+        assert_eq!(fs::read_to_string(&imported_file).unwrap(), edited);
+
+        // Once the file is fixed the edit comes through, not as a conflict.
+        fs::write(&broken_file, valid).unwrap();
+        let report = vault.sync();
+        assert!(report.conflicts.is_empty(), "{:?}", report.conflicts);
+        assert_eq!(report.imported, 1);
+        let content: String = vault.conn
+            .query_row("SELECT content FROM journal_entries WHERE id = ?1", [&imported], |row| row.get(0))
+            .unwrap();
+        assert!(content.contains("Written in the editor."));
+    }
+
+    #[test]
+    fn an_unreadable_file_is_reported_and_kept() {
+        let vault = Vault::new();
+        let unreadable = add_entry(&vault);
+        let edited = add_entry(&vault);
+        vault.sync();
+
+        let (files, _) = vault.files();
+        let find = |id: &str| files.iter().find(|path| path.to_string_lossy().contains(&id[..8])).unwrap().clone();
+        let (unreadable_file, edited_file) = (find(&unreadable), find(&edited));
+        fs::write(&unreadable_file, b"---\nid: \xff\xfe\n---\n").unwrap();
+        vault.edit_file(&edited_file);
+
+        let report = vault.sync();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert!(report.errors[0].starts_with(&relative_path(&vault.root, &unreadable_file)));
+        assert_eq!(report.imported, 1);
+        assert_eq!(fs::read(&unreadable_file).unwrap(), b"---\nid: \xff\xfe\n---\n");
+    }
+
+    #[test]
+    fn an_edited_workstream_entry_keeps_inheriting_its_persona() {
+        let vault = Vault::new();
+        let persona = commands::create_persona(&vault.conn, "Work".into(), None, "#3366ff".into()).unwrap();
+        let launch = commands::create_workstream(&vault.conn, persona.id.clone(), "Launch".into(), None, "active".into(), None, None, None).unwrap();
+        commands::create_workstream(&vault.conn, persona.id, "Hiring".into(), None, "active".into(), None, None, None).unwrap();
+        let id = commands::create_journal_entry(&vault.conn, "Standup".into(), "Shipped the beta.".into(), Some(launch.id), None, "note".into(), Vec::new())
+            .unwrap()
+            .id;
+        // Entries written before personas were stored with them.
+        vault.conn.execute("UPDATE journal_entries SET persona_id = NULL WHERE id = ?1", [&id]).unwrap();
+        vault.sync();
+
+        let location = |vault: &Vault| -> (Option<String>, Option<String>) {
+            vault.conn
+                .query_row("SELECT j.persona_id, w.name FROM journal_entries j LEFT JOIN workstreams w ON j.workstream_id = w.id WHERE j.id = ?1", [&id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+        };
+        let file = |vault: &Vault| -> PathBuf {
+            let relative: String = vault.conn
+                .query_row("SELECT relative_path FROM journal_vault_files WHERE entry_id = ?1", [&id], |row| row.get(0))
+                .unwrap();
+            vault.root.join(relative)
+        };
+
+        vault.edit_file(&file(&vault));
+        assert_eq!(vault.sync().imported, 1);
+        assert_eq!(location(&vault), (None, Some("Launch".to_string())));
+
+        let path = file(&vault);
+        let raw = fs::read_to_string(&path).unwrap();
+        fs::write(&path, raw.replace("workstream: Launch", "workstream: Hiring")).unwrap();
+        assert_eq!(vault.sync().imported, 1);
+        assert_eq!(location(&vault), (None, Some("Hiring".to_string())));
+    }
+}
@@ -1,10 +1,28 @@
-mod database;
-
-use database::{get_database_schema, Persona, Workstream, WorkstreamStatus, generate_id, get_current_timestamp};
-use tauri_plugin_sql::{Builder, Migration, MigrationKind};
+mod backup;
+pub mod commands;
+pub mod database;
+pub mod estimates;
+pub mod filters;
+pub mod forecast;
+mod http_api;
+mod journal_vault;
+pub mod pomodoro;
+pub mod pool;
+pub mod query_lang;
+pub mod rank;
+pub mod recurrence;
+pub mod repository;
+pub mod sync;
+pub mod webdav;
+
+use database::{ChecklistItem, CompletionPolicy, HabitCompletion, HabitTracker, JournalEntry, Persona, ProjectTask, SavedFilter, StatusTransition, WipLimit, Workflow, Workstream};
+use repository::{HabitView, TaskView, TimeEntryView, WorkstreamView};
+use tauri::Emitter;
 use serde_json::Value;
-use std::sync::Mutex;
-use rusqlite::{Connection, Result, OptionalExtension};
+use pool::DbPool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use rusqlite::Result;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -12,143 +30,67 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+pub const DATABASE_PATH: &str = "./data.db";
+
 // Database state management
 struct AppState {
-    db: Mutex<Connection>,
+    db: Arc<DbPool>,
+    http_api: Mutex<Option<http_api::ApiServer>>,
 }
 
 impl AppState {
-    fn new() -> Result<Self, rusqlite::Error> {
-        let conn = Connection::open("./data.db")?;
+    fn new() -> Result<Self, String> {
         Ok(AppState {
-            db: Mutex::new(conn),
+            db: DbPool::open(DATABASE_PATH, pool::DEFAULT_READERS)?,
+            http_api: Mutex::new(None),
         })
     }
+
+    // Stop any running HTTP API and start it again from the saved settings.
+    fn restart_http_api(&self) -> Result<Option<u16>, String> {
+        let settings = self.db.with_reader(http_api::load_settings)?;
+
+        let mut server = self.http_api.lock().map_err(|e| format!("HTTP API lock error: {}", e))?;
+        if let Some(running) = server.take() {
+            running.stop();
+        }
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let token = settings.token
+            .ok_or_else(|| "HTTP API has no token. Generate one first".to_string())?;
+        let started = http_api::ApiServer::start(self.db.clone(), settings.port, token)?;
+        let port = started.port();
+        *server = Some(started);
+        Ok(Some(port))
+    }
 }
 
 // Test database commands
 #[tauri::command]
 async fn test_database_connection(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Test basic query to check if tables exist
-    let mut stmt = db.prepare("SELECT name FROM sqlite_master WHERE type='table'")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let table_names: Result<Vec<String>, rusqlite::Error> = stmt.query_map([], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).map_err(|e| format!("SQL query error: {}", e))?
-    .collect();
-    
-    let tables = table_names.map_err(|e| format!("SQL collect error: {}", e))?;
-    
-    Ok(format!("Database connected successfully! Found {} tables: {:?}", tables.len(), tables))
+    state.db.read(commands::test_database_connection).await
 }
 
 #[tauri::command]
 async fn create_test_persona(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let persona = Persona {
-        id: generate_id(),
-        name: "Test Persona".to_string(),
-        description: Some("This is a test persona created from Rust".to_string()),
-        color: "#3b82f6".to_string(),
-        created_at: get_current_timestamp(),
-        updated_at: get_current_timestamp(),
-        is_active: true,
-    };
-    
-    // Insert the persona into the database
-    db.execute(
-        "INSERT INTO personas (id, name, description, color, created_at, updated_at, is_active) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        rusqlite::params![
-            persona.id,
-            persona.name,
-            persona.description,
-            persona.color,
-            persona.created_at.to_rfc3339(),
-            persona.updated_at.to_rfc3339(),
-            persona.is_active
-        ]
-    ).map_err(|e| format!("SQL insert error: {}", e))?;
-    
-    Ok(format!("Test persona created successfully! ID: {}, Name: {}", persona.id, persona.name))
-}
-
-#[tauri::command]
-async fn get_all_personas(state: tauri::State<'_, AppState>) -> Result<Vec<Value>, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.prepare("SELECT id, name, description, color, created_at, updated_at, is_active FROM personas ORDER BY created_at DESC")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let persona_iter = stmt.query_map([], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "name": row.get::<_, String>(1)?,
-            "description": row.get::<_, Option<String>>(2)?,
-            "color": row.get::<_, String>(3)?,
-            "created_at": row.get::<_, String>(4)?,
-            "updated_at": row.get::<_, String>(5)?,
-            "is_active": row.get::<_, bool>(6)?
-        }))
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-    
-    let personas: Result<Vec<Value>, rusqlite::Error> = persona_iter.collect();
-    personas.map_err(|e| format!("SQL collect error: {}", e))
+    state.db.write(|db| commands::create_test_persona(db)).await
+}
+
+#[tauri::command]
+async fn get_all_personas(state: tauri::State<'_, AppState>) -> Result<Vec<Persona>, String> {
+    state.db.read(commands::get_all_personas).await
 }
 
 #[tauri::command]
 async fn delete_persona(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Check if persona exists first
-    let mut stmt = db.prepare("SELECT name FROM personas WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let persona_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
-    
-    let persona_name = persona_name.map_err(|e| format!("SQL query error: {}", e))?;
-    
-    if persona_name.is_none() {
-        return Err(format!("Persona with ID '{}' not found", id));
-    }
-    
-    // Delete the persona
-    let changes = db.execute("DELETE FROM personas WHERE id = ?1", [&id])
-        .map_err(|e| format!("SQL delete error: {}", e))?;
-    
-    if changes == 0 {
-        return Err(format!("No persona was deleted with ID '{}'", id));
-    }
-    
-    Ok(format!("Successfully deleted persona '{}' with ID: {}", persona_name.unwrap(), id))
+    state.db.write(move |db| commands::delete_persona(db, id)).await
 }
 
 #[tauri::command]
 async fn clear_all_personas(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Count personas before deletion
-    let mut stmt = db.prepare("SELECT COUNT(*) FROM personas")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let count: i32 = stmt.query_row([], |row| {
-        Ok(row.get::<_, i32>(0)?)
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-    
-    if count == 0 {
-        return Ok("No personas found to delete".to_string());
-    }
-    
-    // Delete all personas
-    db.execute("DELETE FROM personas", [])
-        .map_err(|e| format!("SQL delete error: {}", e))?;
-    
-    Ok(format!("Successfully deleted {} persona(s) from the database", count))
+    state.db.write(|db| commands::clear_all_personas(db)).await
 }
 
 // Persona Management Commands
@@ -158,33 +100,8 @@ async fn create_persona(
     name: String, 
     description: Option<String>, 
     color: String
-) -> Result<Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let persona = Persona {
-        id: generate_id(),
-        name: name.clone(),
-        description,
-        color,
-        created_at: get_current_timestamp(),
-        updated_at: get_current_timestamp(),
-        is_active: true,
-    };
-    
-    db.execute(
-        "INSERT INTO personas (id, name, description, color, created_at, updated_at, is_active) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        rusqlite::params![
-            persona.id,
-            persona.name,
-            persona.description,
-            persona.color,
-            persona.created_at.to_rfc3339(),
-            persona.updated_at.to_rfc3339(),
-            persona.is_active
-        ]
-    ).map_err(|e| format!("SQL insert error: {}", e))?;
-    
-    Ok(serde_json::to_value(persona).map_err(|e| format!("Serialization error: {}", e))?)
+) -> Result<Persona, String> {
+    state.db.write(move |db| commands::create_persona(db, name, description, color)).await
 }
 
 #[tauri::command]
@@ -195,280 +112,94 @@ async fn update_persona(
     description: Option<String>,
     color: Option<String>,
     is_active: Option<bool>
-) -> Result<Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Check if persona exists
-    let mut stmt = db.prepare("SELECT id, name, description, color, created_at, updated_at, is_active FROM personas WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let existing_persona: Result<Option<(String, String, Option<String>, String, String, String, bool)>, rusqlite::Error> = 
-        stmt.query_row([&id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, Option<String>>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, bool>(6)?
-            ))
-        }).optional();
-    
-    let existing_persona = existing_persona.map_err(|e| format!("SQL query error: {}", e))?;
-    
-    if existing_persona.is_none() {
-        return Err(format!("Persona with ID '{}' not found", id));
-    }
-    
-    let (_, old_name, old_description, old_color, created_at, _, old_is_active) = existing_persona.unwrap();
-    
-    let updated_name = name.unwrap_or(old_name);
-    let updated_description = description.or(old_description);
-    let updated_color = color.unwrap_or(old_color);
-    let updated_is_active = is_active.unwrap_or(old_is_active);
-    let updated_at = get_current_timestamp();
-    
-    db.execute(
-        "UPDATE personas SET name = ?1, description = ?2, color = ?3, is_active = ?4, updated_at = ?5 WHERE id = ?6",
-        rusqlite::params![updated_name, updated_description, updated_color, updated_is_active, updated_at.to_rfc3339(), id]
-    ).map_err(|e| format!("SQL update error: {}", e))?;
-    
-    let updated_persona = Persona {
-        id: id.clone(),
-        name: updated_name,
-        description: updated_description,
-        color: updated_color,
-        created_at: chrono::DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&chrono::Utc),
-        updated_at,
-        is_active: updated_is_active,
-    };
-    
-    Ok(serde_json::to_value(updated_persona).map_err(|e| format!("Serialization error: {}", e))?)
+) -> Result<Persona, String> {
+    state.db.write(move |db| commands::update_persona(db, id, name, description, color, is_active)).await
 }
 
 // Workstream Management Commands
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn create_workstream(
     state: tauri::State<'_, AppState>, 
     persona_id: String,
     name: String, 
     description: Option<String>, 
-    status: String
-) -> Result<Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Verify persona exists
-    let mut stmt = db.prepare("SELECT id FROM personas WHERE id = ?1 AND is_active = 1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let persona_exists: Result<Option<String>, rusqlite::Error> = stmt.query_row([&persona_id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
-    
-    let persona_exists = persona_exists.map_err(|e| format!("SQL query error: {}", e))?;
-    
-    if persona_exists.is_none() {
-        return Err(format!("Persona with ID '{}' not found or inactive", persona_id));
-    }
-    
-    // Parse status
-    let workstream_status = match status.as_str() {
-        "planning" => WorkstreamStatus::Planning,
-        "active" => WorkstreamStatus::Active,
-        "paused" => WorkstreamStatus::Paused,
-        "completed" => WorkstreamStatus::Completed,
-        "cancelled" => WorkstreamStatus::Cancelled,
-        _ => return Err(format!("Invalid status: {}. Valid statuses: planning, active, paused, completed, cancelled", status)),
-    };
-    
-    let workstream = Workstream {
-        id: generate_id(),
-        persona_id,
-        name: name.clone(),
-        description,
-        status: workstream_status,
-        priority: database::Priority::Medium,
-        start_date: None,
-        target_date: None,
-        completed_date: None,
-        progress_percentage: 0,
-        created_at: get_current_timestamp(),
-        updated_at: get_current_timestamp(),
-    };
-    
-    db.execute(
-        "INSERT INTO workstreams (id, persona_id, name, description, status, priority, start_date, target_date, completed_date, progress_percentage, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-        rusqlite::params![
-            workstream.id,
-            workstream.persona_id,
-            workstream.name,
-            workstream.description,
-            serde_json::to_string(&workstream.status).map_err(|e| format!("Status serialization error: {}", e))?,
-            serde_json::to_string(&workstream.priority).map_err(|e| format!("Priority serialization error: {}", e))?,
-            workstream.start_date.map(|d| d.to_rfc3339()),
-            workstream.target_date.map(|d| d.to_rfc3339()),
-            workstream.completed_date.map(|d| d.to_rfc3339()),
-            workstream.progress_percentage,
-            workstream.created_at.to_rfc3339(),
-            workstream.updated_at.to_rfc3339()
-        ]
-    ).map_err(|e| format!("SQL insert error: {}", e))?;
-    
-    Ok(serde_json::to_value(workstream).map_err(|e| format!("Serialization error: {}", e))?)
+    status: String,
+    priority: Option<String>,
+    start_date: Option<String>,
+    target_date: Option<String>,
+) -> Result<Workstream, String> {
+    state.db.write(move |db| commands::create_workstream(db, persona_id, name, description, status, priority, start_date, target_date)).await
 }
 
 #[tauri::command]
 async fn get_workstreams_by_persona(
     state: tauri::State<'_, AppState>,
     persona_id: String
-) -> Result<Vec<Value>, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.prepare("SELECT id, persona_id, name, description, status, created_at, updated_at FROM workstreams WHERE persona_id = ?1 ORDER BY created_at DESC")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let workstream_iter = stmt.query_map([&persona_id], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "persona_id": row.get::<_, String>(1)?,
-            "name": row.get::<_, String>(2)?,
-            "description": row.get::<_, Option<String>>(3)?,
-            "status": row.get::<_, String>(4)?,
-            "created_at": row.get::<_, String>(5)?,
-            "updated_at": row.get::<_, String>(6)?
-        }))
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-    
-    let workstreams: Result<Vec<Value>, rusqlite::Error> = workstream_iter.collect();
-    workstreams.map_err(|e| format!("SQL collect error: {}", e))
-}
-
-#[tauri::command]
-async fn get_all_workstreams(state: tauri::State<'_, AppState>) -> Result<Vec<Value>, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.prepare("SELECT w.id, w.persona_id, w.name, w.description, w.status, w.created_at, w.updated_at, p.name as persona_name, p.color as persona_color FROM workstreams w JOIN personas p ON w.persona_id = p.id ORDER BY w.created_at DESC")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let workstream_iter = stmt.query_map([], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "persona_id": row.get::<_, String>(1)?,
-            "name": row.get::<_, String>(2)?,
-            "description": row.get::<_, Option<String>>(3)?,
-            "status": row.get::<_, String>(4)?,
-            "created_at": row.get::<_, String>(5)?,
-            "updated_at": row.get::<_, String>(6)?,
-            "persona_name": row.get::<_, String>(7)?,
-            "persona_color": row.get::<_, String>(8)?
-        }))
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-    
-    let workstreams: Result<Vec<Value>, rusqlite::Error> = workstream_iter.collect();
-    workstreams.map_err(|e| format!("SQL collect error: {}", e))
+) -> Result<Vec<Workstream>, String> {
+    state.db.read(move |db| commands::get_workstreams_by_persona(db, persona_id)).await
+}
+
+#[tauri::command]
+async fn get_all_workstreams(state: tauri::State<'_, AppState>) -> Result<Vec<WorkstreamView>, String> {
+    state.db.read(commands::get_all_workstreams).await
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn update_workstream(
     state: tauri::State<'_, AppState>,
     id: String,
     name: Option<String>,
     description: Option<String>,
-    status: String
-) -> Result<Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Check if workstream exists
-    let mut stmt = db.prepare("SELECT id, persona_id, name, description, status, created_at, updated_at FROM workstreams WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let existing_workstream: Result<Option<(String, String, String, Option<String>, String, String, String)>, rusqlite::Error> = 
-        stmt.query_row([&id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, Option<String>>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, String>(6)?
-            ))
-        }).optional();
-    
-    let existing_workstream = existing_workstream.map_err(|e| format!("SQL query error: {}", e))?;
-    
-    if existing_workstream.is_none() {
-        return Err(format!("Workstream with ID '{}' not found", id));
-    }
-    
-    let (_, persona_id, old_name, old_description, _old_status_str, created_at, _) = existing_workstream.unwrap();
-    
-    let updated_name = name.unwrap_or(old_name);
-    let updated_description = description.or(old_description);
-    let updated_status_str = status;
-    let updated_at = get_current_timestamp();
-    
-    // Clean the status string - remove surrounding quotes if present
-    let cleaned_status = updated_status_str.trim_matches('"');
-    println!("🔍 DEBUG: Cleaned status: '{}'", cleaned_status);
-    
-    // Validate status
-    match cleaned_status {
-        "planning" | "active" | "paused" | "completed" | "cancelled" => {},
-        _ => return Err(format!("Invalid status: {}. Valid statuses: planning, active, paused, completed, cancelled", cleaned_status)),
-    }
-    
-    db.execute(
-        "UPDATE workstreams SET name = ?1, description = ?2, status = ?3, updated_at = ?4 WHERE id = ?5",
-        rusqlite::params![updated_name, updated_description, cleaned_status, updated_at.to_rfc3339(), id]
-    ).map_err(|e| format!("SQL update error: {}", e))?;
-    
-    let updated_workstream = Workstream {
-        id: id.clone(),
-        persona_id,
-        name: updated_name,
-        description: updated_description,
-        status: serde_json::from_str(&format!("\"{}\"", cleaned_status)).unwrap_or(WorkstreamStatus::Planning),
-        priority: database::Priority::Medium,
-        start_date: None,
-        target_date: None,
-        completed_date: None,
-        progress_percentage: 0,
-        created_at: chrono::DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&chrono::Utc),
-        updated_at,
-    };
-    
-    Ok(serde_json::to_value(updated_workstream).map_err(|e| format!("Serialization error: {}", e))?)
+    status: Option<String>,
+    priority: Option<String>,
+    start_date: Option<String>,
+    target_date: Option<String>,
+) -> Result<Workstream, String> {
+    state.db.write(move |db| commands::update_workstream(db, id, name, description, status, priority, start_date, target_date)).await
+}
+
+#[tauri::command]
+async fn change_workstream_status(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    status: String,
+    reopen: Option<bool>,
+    habits: Option<bool>,
+    open_tasks: Option<String>,
+    target_workstream_id: Option<String>,
+) -> Result<commands::WorkstreamStatusChange, String> {
+    state.db.write(move |db| commands::change_workstream_status(
+        db,
+        id,
+        status,
+        reopen.unwrap_or(false),
+        habits.unwrap_or(false),
+        open_tasks,
+        target_workstream_id,
+    )).await
+}
+
+#[tauri::command]
+async fn move_workstream_to_persona(state: tauri::State<'_, AppState>, workstream_id: String, persona_id: String) -> Result<commands::WorkstreamTransfer, String> {
+    state.db.write(move |db| commands::move_workstream_to_persona(db, workstream_id, persona_id)).await
+}
+
+#[tauri::command]
+async fn copy_workstream(
+    state: tauri::State<'_, AppState>,
+    workstream_id: String,
+    name: Option<String>,
+    persona_id: Option<String>,
+    reset_progress: Option<bool>,
+) -> Result<commands::WorkstreamCopy, String> {
+    state.db.write(move |db| commands::copy_workstream(db, workstream_id, name, persona_id, reset_progress.unwrap_or(false))).await
 }
 
 #[tauri::command]
 async fn delete_workstream(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Check if workstream exists first
-    let mut stmt = db.prepare("SELECT name FROM workstreams WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-    
-    let workstream_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
-    
-    let workstream_name = workstream_name.map_err(|e| format!("SQL query error: {}", e))?;
-    
-    if workstream_name.is_none() {
-        return Err(format!("Workstream with ID '{}' not found", id));
-    }
-    
-    // Delete the workstream
-    let changes = db.execute("DELETE FROM workstreams WHERE id = ?1", [&id])
-        .map_err(|e| format!("SQL delete error: {}", e))?;
-    
-    if changes == 0 {
-        return Err(format!("No workstream was deleted with ID '{}'", id));
-    }
-    
-    Ok(format!("Successfully deleted workstream '{}' with ID: {}", workstream_name.unwrap(), id))
+    state.db.write(move |db| commands::delete_workstream(db, id)).await
 }
 
 // Project Task Management Commands
@@ -480,146 +211,21 @@ async fn create_project_task(
     description: Option<String>,
     status: String,
     priority: String
-) -> Result<Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-
-    // Verify workstream exists
-    let mut stmt = db.prepare("SELECT id FROM workstreams WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let workstream_exists: Result<Option<String>, rusqlite::Error> = stmt.query_row([&workstream_id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
-
-    let workstream_exists = workstream_exists.map_err(|e| format!("SQL query error: {}", e))?;
-
-    if workstream_exists.is_none() {
-        return Err(format!("Workstream with ID '{}' not found", workstream_id));
-    }
-
-    // Parse status and priority
-    let task_status = match status.as_str() {
-        "backlog" => database::TaskStatus::Backlog,
-        "todo" => database::TaskStatus::ToDo,
-        "inprogress" => database::TaskStatus::InProgress,
-        "review" => database::TaskStatus::Review,
-        "done" => database::TaskStatus::Done,
-        _ => return Err(format!("Invalid status: {}. Valid statuses: backlog, todo, inprogress, review, done", status)),
-    };
-
-    let task_priority = match priority.as_str() {
-        "low" => database::Priority::Low,
-        "medium" => database::Priority::Medium,
-        "high" => database::Priority::High,
-        "critical" => database::Priority::Critical,
-        _ => return Err(format!("Invalid priority: {}. Valid priorities: low, medium, high, critical", priority)),
-    };
-
-    let task = database::ProjectTask {
-        id: database::generate_id(),
-        workstream_id,
-        title: title.clone(),
-        description,
-        status: task_status,
-        priority: task_priority,
-        due_date: None,
-        completed_date: None,
-        estimated_hours: None,
-        actual_hours: None,
-        tags: Vec::new(),
-        dependencies: Vec::new(),
-        created_at: database::get_current_timestamp(),
-        updated_at: database::get_current_timestamp(),
-    };
-
-    db.execute(
-        "INSERT INTO project_tasks (id, workstream_id, title, description, status, priority, due_date, completed_date, estimated_hours, actual_hours, tags, dependencies, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-        rusqlite::params![
-            task.id,
-            task.workstream_id,
-            task.title,
-            task.description,
-            serde_json::to_string(&task.status).map_err(|e| format!("Status serialization error: {}", e))?,
-            serde_json::to_string(&task.priority).map_err(|e| format!("Priority serialization error: {}", e))?,
-            task.due_date.map(|d| d.to_rfc3339()),
-            task.completed_date.map(|d| d.to_rfc3339()),
-            task.estimated_hours,
-            task.actual_hours,
-            serde_json::to_string(&task.tags).map_err(|e| format!("Tags serialization error: {}", e))?,
-            serde_json::to_string(&task.dependencies).map_err(|e| format!("Dependencies serialization error: {}", e))?,
-            task.created_at.to_rfc3339(),
-            task.updated_at.to_rfc3339()
-        ]
-    ).map_err(|e| format!("SQL insert error: {}", e))?;
-
-    Ok(serde_json::to_value(task).map_err(|e| format!("Serialization error: {}", e))?)
+) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::create_project_task(db, workstream_id, title, description, status, priority)).await
 }
 
 #[tauri::command]
 async fn get_tasks_by_workstream(
     state: tauri::State<'_, AppState>,
     workstream_id: String
-) -> Result<Vec<Value>, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-
-    let mut stmt = db.prepare("SELECT id, workstream_id, title, description, status, priority, due_date, completed_date, estimated_hours, actual_hours, tags, dependencies, created_at, updated_at FROM project_tasks WHERE workstream_id = ?1 ORDER BY created_at DESC")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let task_iter = stmt.query_map([&workstream_id], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "workstream_id": row.get::<_, String>(1)?,
-            "title": row.get::<_, String>(2)?,
-            "description": row.get::<_, Option<String>>(3)?,
-            "status": row.get::<_, String>(4)?,
-            "priority": row.get::<_, String>(5)?,
-            "due_date": row.get::<_, Option<String>>(6)?,
-            "completed_date": row.get::<_, Option<String>>(7)?,
-            "estimated_hours": row.get::<_, Option<f64>>(8)?,
-            "actual_hours": row.get::<_, Option<f64>>(9)?,
-            "tags": row.get::<_, String>(10)?,
-            "dependencies": row.get::<_, String>(11)?,
-            "created_at": row.get::<_, String>(12)?,
-            "updated_at": row.get::<_, String>(13)?
-        }))
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-
-    let tasks: Result<Vec<Value>, rusqlite::Error> = task_iter.collect();
-    tasks.map_err(|e| format!("SQL collect error: {}", e))
-}
-
-#[tauri::command]
-async fn get_all_project_tasks(state: tauri::State<'_, AppState>) -> Result<Vec<Value>, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-
-    let mut stmt = db.prepare("SELECT t.id, t.workstream_id, t.title, t.description, t.status, t.priority, t.due_date, t.completed_date, t.estimated_hours, t.actual_hours, t.tags, t.dependencies, t.created_at, t.updated_at, w.name as workstream_name, w.persona_id, p.name as persona_name, p.color as persona_color FROM project_tasks t JOIN workstreams w ON t.workstream_id = w.id JOIN personas p ON w.persona_id = p.id ORDER BY t.created_at DESC")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let task_iter = stmt.query_map([], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "workstream_id": row.get::<_, String>(1)?,
-            "title": row.get::<_, String>(2)?,
-            "description": row.get::<_, Option<String>>(3)?,
-            "status": row.get::<_, String>(4)?,
-            "priority": row.get::<_, String>(5)?,
-            "due_date": row.get::<_, Option<String>>(6)?,
-            "completed_date": row.get::<_, Option<String>>(7)?,
-            "estimated_hours": row.get::<_, Option<f64>>(8)?,
-            "actual_hours": row.get::<_, Option<f64>>(9)?,
-            "tags": row.get::<_, String>(10)?,
-            "dependencies": row.get::<_, String>(11)?,
-            "created_at": row.get::<_, String>(12)?,
-            "updated_at": row.get::<_, String>(13)?,
-            "workstream_name": row.get::<_, String>(14)?,
-            "persona_id": row.get::<_, String>(15)?,
-            "persona_name": row.get::<_, String>(16)?,
-            "persona_color": row.get::<_, String>(17)?
-        }))
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-
-    let tasks: Result<Vec<Value>, rusqlite::Error> = task_iter.collect();
-    tasks.map_err(|e| format!("SQL collect error: {}", e))
+) -> Result<Vec<ProjectTask>, String> {
+    state.db.read(move |db| commands::get_tasks_by_workstream(db, workstream_id)).await
+}
+
+#[tauri::command]
+async fn get_all_project_tasks(state: tauri::State<'_, AppState>) -> Result<Vec<TaskView>, String> {
+    state.db.read(commands::get_all_project_tasks).await
 }
 
 #[tauri::command]
@@ -630,524 +236,828 @@ async fn update_project_task(
     description: Option<String>,
     status: String,
     priority: String
-) -> Result<Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-
-    // Check if task exists
-    let mut stmt = db.prepare("SELECT id, workstream_id, title, description, status, priority, created_at, updated_at FROM project_tasks WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let existing_task: Result<Option<(String, String, String, Option<String>, String, String, String, String)>, rusqlite::Error> =
-        stmt.query_row([&id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, Option<String>>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, String>(6)?,
-                row.get::<_, String>(7)?
-            ))
-        }).optional();
-
-    let existing_task = existing_task.map_err(|e| format!("SQL query error: {}", e))?;
-
-    if existing_task.is_none() {
-        return Err(format!("Project task with ID '{}' not found", id));
-    }
+) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::update_project_task(db, id, title, description, status, priority)).await
+}
 
-    let (_, workstream_id, old_title, old_description, _old_status_str, _old_priority_str, created_at, _) = existing_task.unwrap();
 
-    let updated_title = title.unwrap_or(old_title);
-    let updated_description = description.or(old_description);
-    let updated_status_str = status;
-    let updated_priority_str = priority;
-    let updated_at = database::get_current_timestamp();
+#[tauri::command]
+async fn delete_project_task(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_project_task(db, id)).await
+}
 
-    // Clean the status string - remove surrounding quotes if present
-    let cleaned_status = updated_status_str.trim_matches('"');
-    let cleaned_priority = updated_priority_str.trim_matches('"');
+// Dependency checking functions
+#[tauri::command]
+async fn check_persona_dependencies(state: tauri::State<'_, AppState>, id: String) -> Result<serde_json::Value, String> {
+    state.db.read(move |db| commands::check_persona_dependencies(db, id)).await
+}
 
-    // Validate status and priority
-    match cleaned_status {
-        "backlog" | "todo" | "inprogress" | "review" | "done" => {},
-        _ => return Err(format!("Invalid status: {}. Valid statuses: backlog, todo, inprogress, review, done", cleaned_status)),
-    }
+#[tauri::command]
+async fn check_workstream_dependencies(state: tauri::State<'_, AppState>, id: String) -> Result<serde_json::Value, String> {
+    state.db.read(move |db| commands::check_workstream_dependencies(db, id)).await
+}
 
-    match cleaned_priority {
-        "low" | "medium" | "high" | "critical" => {},
-        _ => return Err(format!("Invalid priority: {}. Valid priorities: low, medium, high, critical", cleaned_priority)),
-    }
+// Cascade delete functions
+#[tauri::command]
+async fn cascade_delete_persona(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::cascade_delete_persona(db, id)).await
+}
 
-    db.execute(
-        "UPDATE project_tasks SET title = ?1, description = ?2, status = ?3, priority = ?4, updated_at = ?5 WHERE id = ?6",
-        rusqlite::params![updated_title, updated_description, cleaned_status, cleaned_priority, updated_at.to_rfc3339(), id]
-    ).map_err(|e| format!("SQL update error: {}", e))?;
+#[tauri::command]
+async fn cascade_delete_workstream(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::cascade_delete_workstream(db, id)).await
+}
 
-    let updated_task = database::ProjectTask {
-        id: id.clone(),
-        workstream_id,
-        title: updated_title,
-        description: updated_description,
-        status: serde_json::from_str(&format!("\"{}\"", cleaned_status)).unwrap_or(database::TaskStatus::Backlog),
-        priority: serde_json::from_str(&format!("\"{}\"", cleaned_priority)).unwrap_or(database::Priority::Medium),
-        due_date: None,
-        completed_date: None,
-        estimated_hours: None,
-        actual_hours: None,
-        tags: Vec::new(),
-        dependencies: Vec::new(),
-        created_at: chrono::DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&chrono::Utc),
-        updated_at,
-    };
+// Kanban board specific functions
+#[tauri::command]
+async fn get_tasks_for_kanban(
+    state: tauri::State<'_, AppState>,
+    workstream_filter: Option<String>,
+    status_filter: Option<String>
+) -> Result<Vec<TaskView>, String> {
+    state.db.read(move |db| commands::get_tasks_for_kanban(db, workstream_filter, status_filter)).await
+}
 
-    Ok(serde_json::to_value(updated_task).map_err(|e| format!("Serialization error: {}", e))?)
+#[tauri::command]
+async fn query_tasks(state: tauri::State<'_, AppState>, query: filters::TaskQuery) -> Result<filters::TaskPage, String> {
+    state.db.read(move |db| commands::query_tasks(db, query)).await
 }
 
+#[tauri::command]
+async fn get_task_counts_by_status(
+    state: tauri::State<'_, AppState>,
+    workstream_filter: Option<String>
+) -> Result<serde_json::Value, String> {
+    state.db.read(move |db| commands::get_task_counts_by_status(db, workstream_filter)).await
+}
 
 #[tauri::command]
-async fn delete_project_task(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+async fn update_task_status(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    new_status: String
+) -> Result<commands::TaskStatusChange, String> {
+    state.db.write(move |db| commands::update_task_status(db, task_id, new_status)).await
+}
 
-    // Get task title for confirmation message
-    let mut stmt = db.prepare("SELECT title FROM project_tasks WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
+#[tauri::command]
+async fn move_task(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    status: String,
+    after_id: Option<String>,
+    before_id: Option<String>
+) -> Result<commands::TaskMove, String> {
+    state.db.write(move |db| commands::move_task(db, task_id, status, after_id, before_id)).await
+}
 
-    let task_title: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
+// Kanban column policy commands
+#[tauri::command]
+async fn set_wip_limit(
+    state: tauri::State<'_, AppState>,
+    workstream_id: Option<String>,
+    status: String,
+    max_tasks: i64,
+    enforcement: Option<String>
+) -> Result<WipLimit, String> {
+    state.db.write(move |db| commands::set_wip_limit(db, workstream_id, status, max_tasks, enforcement)).await
+}
 
-    let task_title = task_title.map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+async fn delete_wip_limit(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_wip_limit(db, id)).await
+}
 
-    if task_title.is_none() {
-        return Err(format!("Project task with ID '{}' not found", id));
-    }
+#[tauri::command]
+async fn get_wip_limits(state: tauri::State<'_, AppState>, workstream_id: Option<String>) -> Result<Vec<WipLimit>, String> {
+    state.db.read(move |db| commands::get_wip_limits(db, workstream_id)).await
+}
 
-    let changes = db.execute("DELETE FROM project_tasks WHERE id = ?1", rusqlite::params![id])
-        .map_err(|e| format!("SQL delete error: {}", e))?;
+#[tauri::command]
+async fn set_status_transition(
+    state: tauri::State<'_, AppState>,
+    workstream_id: Option<String>,
+    from_status: String,
+    to_status: String,
+    allowed: bool,
+    enforcement: Option<String>
+) -> Result<StatusTransition, String> {
+    state.db.write(move |db| commands::set_status_transition(db, workstream_id, from_status, to_status, allowed, enforcement)).await
+}
 
-    if changes == 0 {
-        return Err(format!("No task was deleted with ID '{}'", id));
-    }
+#[tauri::command]
+async fn delete_status_transition(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_status_transition(db, id)).await
+}
 
-    Ok(format!("Successfully deleted project task '{}' with ID: {}", task_title.unwrap(), id))
+#[tauri::command]
+async fn get_status_transitions(state: tauri::State<'_, AppState>, workstream_id: Option<String>) -> Result<Vec<StatusTransition>, String> {
+    state.db.read(move |db| commands::get_status_transitions(db, workstream_id)).await
 }
 
-// Dependency checking functions
+// Due date and recurrence commands
 #[tauri::command]
-async fn check_persona_dependencies(state: tauri::State<'_, AppState>, id: String) -> Result<serde_json::Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+async fn set_task_due_date(state: tauri::State<'_, AppState>, task_id: String, due_date: Option<String>) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::set_task_due_date(db, task_id, due_date)).await
+}
+
+#[tauri::command]
+async fn set_task_recurrence(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    rule: Option<String>,
+    from_completion: Option<bool>
+) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::set_task_recurrence(db, task_id, rule, from_completion.unwrap_or(false))).await
+}
 
-    // Check if persona exists
-    let mut stmt = db.prepare("SELECT name FROM personas WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
+#[tauri::command]
+fn preview_recurrence(rule: String, start: Option<String>, limit: Option<usize>) -> Result<Vec<String>, String> {
+    commands::preview_recurrence(rule, start, limit)
+}
 
-    let persona_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
+// Subtask and checklist commands
+#[tauri::command]
+async fn get_task_tree(state: tauri::State<'_, AppState>, task_id: String) -> Result<commands::TaskTree, String> {
+    state.db.read(move |db| commands::get_task_tree(db, task_id)).await
+}
 
-    let persona_name = persona_name.map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn create_subtask(
+    state: tauri::State<'_, AppState>,
+    parent_id: String,
+    title: String,
+    description: Option<String>,
+    status: Option<String>,
+    priority: Option<String>,
+    estimated_hours: Option<f32>
+) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::create_subtask(db, parent_id, title, description, status, priority, estimated_hours)).await
+}
 
-    if persona_name.is_none() {
-        return Err(format!("Persona with ID '{}' not found", id));
-    }
+#[tauri::command]
+async fn move_subtask(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    parent_id: Option<String>,
+    after_id: Option<String>,
+    before_id: Option<String>
+) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::move_subtask(db, task_id, parent_id, after_id, before_id)).await
+}
 
-    // Count workstreams
-    let mut stmt = db.prepare("SELECT COUNT(*) FROM workstreams WHERE persona_id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let workstream_count: i64 = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, i64>(0)?)
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-
-    // Count tasks through workstreams
-    let mut stmt = db.prepare("
-        SELECT COUNT(*) FROM project_tasks pt 
-        JOIN workstreams w ON pt.workstream_id = w.id 
-        WHERE w.persona_id = ?1
-    ")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let task_count: i64 = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, i64>(0)?)
-    }).map_err(|e| format!("SQL query error: {}", e))?;
-
-    let result = serde_json::json!({
-        "persona_name": persona_name.unwrap(),
-        "workstream_count": workstream_count,
-        "task_count": task_count,
-        "has_dependencies": workstream_count > 0 || task_count > 0
-    });
+#[tauri::command]
+async fn set_task_estimate(state: tauri::State<'_, AppState>, task_id: String, estimated_hours: Option<f32>) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::set_task_estimate(db, task_id, estimated_hours)).await
+}
 
-    Ok(result)
+#[tauri::command]
+async fn move_task_to_workstream(state: tauri::State<'_, AppState>, task_id: String, workstream_id: String) -> Result<commands::TaskTransfer, String> {
+    state.db.write(move |db| commands::move_task_to_workstream(db, task_id, workstream_id)).await
 }
 
 #[tauri::command]
-async fn check_workstream_dependencies(state: tauri::State<'_, AppState>, id: String) -> Result<serde_json::Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+async fn set_task_milestone(state: tauri::State<'_, AppState>, task_id: String, milestone_id: Option<String>) -> Result<ProjectTask, String> {
+    state.db.write(move |db| commands::set_task_milestone(db, task_id, milestone_id)).await
+}
+
+#[tauri::command]
+async fn create_milestone(
+    state: tauri::State<'_, AppState>,
+    workstream_id: String,
+    name: String,
+    description: Option<String>,
+    due_date: String,
+) -> Result<commands::MilestoneView, String> {
+    state.db.write(move |db| commands::create_milestone(db, workstream_id, name, description, due_date)).await
+}
+
+#[tauri::command]
+async fn update_milestone(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    due_date: Option<String>,
+) -> Result<commands::MilestoneView, String> {
+    state.db.write(move |db| commands::update_milestone(db, id, name, description, due_date)).await
+}
 
-    // Check if workstream exists
-    let mut stmt = db.prepare("SELECT name FROM workstreams WHERE id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
+#[tauri::command]
+async fn delete_milestone(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_milestone(db, id)).await
+}
 
-    let workstream_name: Result<Option<String>, rusqlite::Error> = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, String>(0)?)
-    }).optional();
+#[tauri::command]
+async fn get_milestones(state: tauri::State<'_, AppState>, workstream_id: String) -> Result<Vec<commands::MilestoneView>, String> {
+    state.db.read(move |db| commands::get_milestones(db, workstream_id)).await
+}
 
-    let workstream_name = workstream_name.map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+async fn add_checklist_item(state: tauri::State<'_, AppState>, task_id: String, text: String) -> Result<ChecklistItem, String> {
+    state.db.write(move |db| commands::add_checklist_item(db, task_id, text)).await
+}
 
-    if workstream_name.is_none() {
-        return Err(format!("Workstream with ID '{}' not found", id));
-    }
+#[tauri::command]
+async fn update_checklist_item(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    text: Option<String>,
+    done: Option<bool>
+) -> Result<ChecklistItem, String> {
+    state.db.write(move |db| commands::update_checklist_item(db, id, text, done)).await
+}
 
-    // Count tasks
-    let mut stmt = db.prepare("SELECT COUNT(*) FROM project_tasks WHERE workstream_id = ?1")
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
+#[tauri::command]
+async fn move_checklist_item(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    after_id: Option<String>,
+    before_id: Option<String>
+) -> Result<Vec<ChecklistItem>, String> {
+    state.db.write(move |db| commands::move_checklist_item(db, id, after_id, before_id)).await
+}
 
-    let task_count: i64 = stmt.query_row([&id], |row| {
-        Ok(row.get::<_, i64>(0)?)
-    }).map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+async fn delete_checklist_item(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_checklist_item(db, id)).await
+}
 
-    let result = serde_json::json!({
-        "workstream_name": workstream_name.unwrap(),
-        "task_count": task_count,
-        "has_dependencies": task_count > 0
-    });
+#[tauri::command]
+async fn set_completion_policy(
+    state: tauri::State<'_, AppState>,
+    workstream_id: Option<String>,
+    require_subtasks_done: bool,
+    require_checklist_done: bool,
+    enforcement: Option<String>
+) -> Result<CompletionPolicy, String> {
+    state.db.write(move |db| commands::set_completion_policy(db, workstream_id, require_subtasks_done, require_checklist_done, enforcement)).await
+}
 
-    Ok(result)
+#[tauri::command]
+async fn delete_completion_policy(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_completion_policy(db, id)).await
 }
 
-// Cascade delete functions
 #[tauri::command]
-async fn cascade_delete_persona(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
-    let mut db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+async fn get_completion_policies(state: tauri::State<'_, AppState>, workstream_id: Option<String>) -> Result<Vec<CompletionPolicy>, String> {
+    state.db.read(move |db| commands::get_completion_policies(db, workstream_id)).await
+}
 
-    // Get persona name for confirmation message
-    let persona_name: Result<Option<String>, rusqlite::Error> = {
-        let mut stmt = db.prepare("SELECT name FROM personas WHERE id = ?1")
-            .map_err(|e| format!("SQL prepare error: {}", e))?;
+// Workflow commands
+#[tauri::command]
+async fn create_workflow(
+    state: tauri::State<'_, AppState>,
+    name: String,
+    persona_id: Option<String>,
+    workstream_id: Option<String>,
+    states: Vec<commands::WorkflowStateInput>
+) -> Result<Workflow, String> {
+    state.db.write(move |db| commands::create_workflow(db, name, persona_id, workstream_id, states)).await
+}
 
-        stmt.query_row([&id], |row| {
-            Ok(row.get::<_, String>(0)?)
-        }).optional()
-    };
+#[tauri::command]
+async fn update_workflow(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    name: Option<String>,
+    states: Option<Vec<commands::WorkflowStateInput>>
+) -> Result<Workflow, String> {
+    state.db.write(move |db| commands::update_workflow(db, id, name, states)).await
+}
 
-    let persona_name = persona_name.map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+async fn delete_workflow(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_workflow(db, id)).await
+}
 
-    if persona_name.is_none() {
-        return Err(format!("Persona with ID '{}' not found", id));
-    }
+#[tauri::command]
+async fn get_workflows(state: tauri::State<'_, AppState>) -> Result<Vec<Workflow>, String> {
+    state.db.read(commands::get_workflows).await
+}
 
-    // Start transaction for cascade delete
-    let tx = db.transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+#[tauri::command]
+async fn get_workflow_for_workstream(state: tauri::State<'_, AppState>, workstream_id: String) -> Result<Workflow, String> {
+    state.db.read(move |db| commands::get_workflow_for_workstream(db, workstream_id)).await
+}
 
-    // Delete tasks first (deepest level)
-    tx.execute("DELETE FROM project_tasks WHERE workstream_id IN (SELECT id FROM workstreams WHERE persona_id = ?1)", [&id])
-        .map_err(|e| format!("SQL delete tasks error: {}", e))?;
+// Time tracking commands
+#[tauri::command]
+async fn start_timer(state: tauri::State<'_, AppState>, task_id: String, note: Option<String>) -> Result<commands::TimerChange, String> {
+    state.db.write(move |db| commands::start_timer(db, task_id, note)).await
+}
 
-    // Delete workstreams
-    tx.execute("DELETE FROM workstreams WHERE persona_id = ?1", [&id])
-        .map_err(|e| format!("SQL delete workstreams error: {}", e))?;
+#[tauri::command]
+async fn pause_timer(state: tauri::State<'_, AppState>) -> Result<TimeEntryView, String> {
+    state.db.write(|db| commands::pause_timer(db)).await
+}
 
-    // Delete persona
-    tx.execute("DELETE FROM personas WHERE id = ?1", [&id])
-        .map_err(|e| format!("SQL delete persona error: {}", e))?;
+#[tauri::command]
+async fn resume_timer(state: tauri::State<'_, AppState>) -> Result<TimeEntryView, String> {
+    state.db.write(|db| commands::resume_timer(db)).await
+}
 
-    // Commit transaction
-    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+#[tauri::command]
+async fn stop_timer(state: tauri::State<'_, AppState>) -> Result<TimeEntryView, String> {
+    state.db.write(|db| commands::stop_timer(db)).await
+}
 
-    Ok(format!("Successfully deleted persona '{}' and all associated workstreams and tasks", persona_name.unwrap()))
+#[tauri::command]
+async fn get_running_timer(state: tauri::State<'_, AppState>) -> Result<Option<TimeEntryView>, String> {
+    state.db.read(commands::get_running_timer).await
 }
 
 #[tauri::command]
-async fn cascade_delete_workstream(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
-    let mut db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+async fn resolve_orphaned_timer(state: tauri::State<'_, AppState>, action: String) -> Result<Option<TimeEntryView>, String> {
+    state.db.write(move |db| commands::resolve_orphaned_timer(db, action)).await
+}
 
-    // Get workstream name for confirmation message
-    let workstream_name: Result<Option<String>, rusqlite::Error> = {
-        let mut stmt = db.prepare("SELECT name FROM workstreams WHERE id = ?1")
-            .map_err(|e| format!("SQL prepare error: {}", e))?;
+#[tauri::command]
+async fn add_time_entry(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    started_at: String,
+    ended_at: Option<String>,
+    minutes: Option<f64>,
+    note: Option<String>
+) -> Result<TimeEntryView, String> {
+    state.db.write(move |db| commands::add_time_entry(db, task_id, started_at, ended_at, minutes, note)).await
+}
 
-        stmt.query_row([&id], |row| {
-            Ok(row.get::<_, String>(0)?)
-        }).optional()
-    };
+#[tauri::command]
+async fn update_time_entry(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    started_at: Option<String>,
+    ended_at: Option<String>,
+    note: Option<String>
+) -> Result<TimeEntryView, String> {
+    state.db.write(move |db| commands::update_time_entry(db, id, started_at, ended_at, note)).await
+}
 
-    let workstream_name = workstream_name.map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+async fn delete_time_entry(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_time_entry(db, id)).await
+}
 
-    if workstream_name.is_none() {
-        return Err(format!("Workstream with ID '{}' not found", id));
+#[tauri::command]
+async fn get_time_entries(
+    state: tauri::State<'_, AppState>,
+    task_id: Option<String>,
+    from: Option<String>,
+    to: Option<String>
+) -> Result<Vec<TimeEntryView>, String> {
+    state.db.read(move |db| commands::get_time_entries(db, task_id, from, to)).await
+}
+
+#[tauri::command]
+async fn get_time_report(
+    state: tauri::State<'_, AppState>,
+    group_by: String,
+    from: Option<String>,
+    to: Option<String>,
+    persona_id: Option<String>,
+    workstream_id: Option<String>
+) -> Result<commands::TimeReport, String> {
+    state.db.read(move |db| commands::get_time_report(db, group_by, from, to, persona_id, workstream_id)).await
+}
+
+// Pomodoro commands. Phase changes and completed pomodoros a command causes
+// are sent as events too, so every window and the tray see them.
+fn emit_focus_events(app: &tauri::AppHandle, change: &commands::FocusChange) {
+    for event in &change.events {
+        let name = match event {
+            pomodoro::FocusEvent::PhaseChanged { .. } => pomodoro::PHASE_EVENT,
+            pomodoro::FocusEvent::Completed { .. } => pomodoro::COMPLETED_EVENT,
+        };
+        let _ = app.emit(name, event);
     }
+}
+
+#[tauri::command]
+async fn get_pomodoro_settings(state: tauri::State<'_, AppState>) -> Result<pomodoro::PomodoroSettings, String> {
+    state.db.read(commands::get_pomodoro_settings).await
+}
 
-    // Start transaction for cascade delete
-    let tx = db.transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+#[tauri::command]
+async fn configure_pomodoro(
+    state: tauri::State<'_, AppState>,
+    work_minutes: Option<u32>,
+    short_break_minutes: Option<u32>,
+    long_break_minutes: Option<u32>,
+    long_break_every: Option<u32>,
+    auto_start_breaks: Option<bool>,
+    auto_start_work: Option<bool>
+) -> Result<pomodoro::PomodoroSettings, String> {
+    state.db.write(move |db| commands::configure_pomodoro(
+        db, work_minutes, short_break_minutes, long_break_minutes, long_break_every, auto_start_breaks, auto_start_work
+    )).await
+}
 
-    // Delete tasks first
-    tx.execute("DELETE FROM project_tasks WHERE workstream_id = ?1", [&id])
-        .map_err(|e| format!("SQL delete tasks error: {}", e))?;
+#[tauri::command]
+async fn get_focus_status(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<commands::FocusChange, String> {
+    let change = state.db.write(|db| commands::get_focus_status(db)).await?;
+    emit_focus_events(&app, &change);
+    Ok(change)
+}
 
-    // Delete workstream
-    tx.execute("DELETE FROM workstreams WHERE id = ?1", [&id])
-        .map_err(|e| format!("SQL delete workstream error: {}", e))?;
+#[tauri::command]
+async fn start_focus_session(app: tauri::AppHandle, state: tauri::State<'_, AppState>, task_id: Option<String>) -> Result<commands::FocusChange, String> {
+    let change = state.db.write(move |db| commands::start_focus_session(db, task_id)).await?;
+    emit_focus_events(&app, &change);
+    Ok(change)
+}
 
-    // Commit transaction
-    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+#[tauri::command]
+async fn pause_focus_session(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<commands::FocusChange, String> {
+    let change = state.db.write(|db| commands::pause_focus_session(db)).await?;
+    emit_focus_events(&app, &change);
+    Ok(change)
+}
 
-    Ok(format!("Successfully deleted workstream '{}' and all associated tasks", workstream_name.unwrap()))
+#[tauri::command]
+async fn resume_focus_session(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<commands::FocusChange, String> {
+    let change = state.db.write(|db| commands::resume_focus_session(db)).await?;
+    emit_focus_events(&app, &change);
+    Ok(change)
 }
 
-// Kanban board specific functions
 #[tauri::command]
-async fn get_tasks_for_kanban(
+async fn skip_focus_phase(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<commands::FocusChange, String> {
+    let change = state.db.write(|db| commands::skip_focus_phase(db)).await?;
+    emit_focus_events(&app, &change);
+    Ok(change)
+}
+
+#[tauri::command]
+async fn stop_focus_session(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<commands::FocusChange, String> {
+    let change = state.db.write(|db| commands::stop_focus_session(db)).await?;
+    emit_focus_events(&app, &change);
+    Ok(change)
+}
+
+#[tauri::command]
+async fn get_focus_stats(state: tauri::State<'_, AppState>, from: Option<String>, to: Option<String>) -> Result<pomodoro::FocusStats, String> {
+    state.db.read(move |db| commands::get_focus_stats(db, from, to)).await
+}
+
+// Estimate and forecast commands
+#[tauri::command]
+async fn get_estimate_accuracy(
     state: tauri::State<'_, AppState>,
-    workstream_filter: Option<String>,
-    status_filter: Option<String>
-) -> Result<Vec<serde_json::Value>, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-
-    let mut query = "
-        SELECT 
-            pt.id,
-            pt.workstream_id,
-            pt.title,
-            pt.description,
-            pt.status,
-            pt.priority,
-            pt.due_date,
-            pt.completed_date,
-            pt.estimated_hours,
-            pt.actual_hours,
-            pt.tags,
-            pt.dependencies,
-            pt.created_at,
-            pt.updated_at,
-            w.name as workstream_name,
-            w.persona_id,
-            p.name as persona_name,
-            p.color as persona_color
-        FROM project_tasks pt
-        JOIN workstreams w ON pt.workstream_id = w.id
-        JOIN personas p ON w.persona_id = p.id
-        WHERE 1=1
-    ".to_string();
-
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(workstream_id) = workstream_filter {
-        if workstream_id != "all" {
-            query.push_str(" AND pt.workstream_id = ?");
-            params.push(workstream_id);
-        }
-    }
+    group_by: String,
+    period: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    persona_id: Option<String>,
+    workstream_id: Option<String>,
+) -> Result<estimates::EstimateAccuracy, String> {
+    state.db.read(move |db| commands::get_estimate_accuracy(db, group_by, period, from, to, persona_id, workstream_id)).await
+}
 
-    if let Some(status) = status_filter {
-        if status != "all" {
-            query.push_str(" AND pt.status = ?");
-            params.push(status);
-        }
-    }
+#[tauri::command]
+async fn get_calibrated_estimate(state: tauri::State<'_, AppState>, workstream_id: String, estimated_hours: f64) -> Result<Option<estimates::CalibratedEstimate>, String> {
+    state.db.read(move |db| commands::get_calibrated_estimate(db, workstream_id, estimated_hours)).await
+}
 
-    query.push_str(" ORDER BY pt.created_at DESC");
-
-    let mut stmt = db.prepare(&query)
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "workstream_id": row.get::<_, String>(1)?,
-            "title": row.get::<_, String>(2)?,
-            "description": row.get::<_, Option<String>>(3)?,
-            "status": row.get::<_, String>(4)?,
-            "priority": row.get::<_, String>(5)?,
-            "due_date": row.get::<_, Option<String>>(6)?,
-            "completed_date": row.get::<_, Option<String>>(7)?,
-            "estimated_hours": row.get::<_, Option<f32>>(8)?,
-            "actual_hours": row.get::<_, Option<f32>>(9)?,
-            "tags": row.get::<_, Option<String>>(10)?,
-            "dependencies": row.get::<_, Option<String>>(11)?,
-            "created_at": row.get::<_, String>(12)?,
-            "updated_at": row.get::<_, String>(13)?,
-            "workstream_name": row.get::<_, String>(14)?,
-            "persona_id": row.get::<_, String>(15)?,
-            "persona_name": row.get::<_, String>(16)?,
-            "persona_color": row.get::<_, String>(17)?
-        }))
-    })
-    .map_err(|e| format!("SQL query error: {}", e))?;
-
-    let mut tasks = Vec::new();
-    for row in rows {
-        tasks.push(row.map_err(|e| format!("Row processing error: {}", e))?);
-    }
+#[tauri::command]
+async fn get_workstream_burndown(state: tauri::State<'_, AppState>, workstream_id: String, unit: Option<String>) -> Result<forecast::Burndown, String> {
+    state.db.read(move |db| commands::get_workstream_burndown(db, workstream_id, unit)).await
+}
+
+// Habit commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn create_habit(
+    state: tauri::State<'_, AppState>,
+    workstream_id: String,
+    name: String,
+    description: Option<String>,
+    frequency: String,
+    target_days: Option<u8>,
+    target_quantity: Option<u32>,
+    unit: Option<String>,
+    color: Option<String>
+) -> Result<HabitTracker, String> {
+    state.db.write(move |db| commands::create_habit(db, workstream_id, name, description, frequency, target_days, target_quantity, unit, color)).await
+}
 
-    Ok(tasks)
+#[tauri::command]
+async fn get_habits(state: tauri::State<'_, AppState>, date: Option<String>) -> Result<Vec<HabitView>, String> {
+    state.db.read(move |db| commands::get_habits(db, date)).await
 }
 
 #[tauri::command]
-async fn get_task_counts_by_status(
+async fn log_habit_completion(
     state: tauri::State<'_, AppState>,
-    workstream_filter: Option<String>
-) -> Result<serde_json::Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-
-    let mut query = "
-        SELECT 
-            pt.status,
-            COUNT(*) as count
-        FROM project_tasks pt
-        JOIN workstreams w ON pt.workstream_id = w.id
-        WHERE 1=1
-    ".to_string();
-
-    let mut params: Vec<String> = Vec::new();
-
-    if let Some(workstream_id) = workstream_filter {
-        if workstream_id != "all" {
-            query.push_str(" AND pt.workstream_id = ?");
-            params.push(workstream_id);
-        }
-    }
+    habit_id: String,
+    date: Option<String>,
+    quantity: Option<u32>,
+    notes: Option<String>
+) -> Result<HabitCompletion, String> {
+    state.db.write(move |db| commands::log_habit_completion(db, habit_id, date, quantity, notes)).await
+}
 
-    query.push_str(" GROUP BY pt.status");
+// Journal commands
+#[tauri::command]
+async fn create_journal_entry(
+    state: tauri::State<'_, AppState>,
+    title: String,
+    content: String,
+    workstream_id: Option<String>,
+    persona_id: Option<String>,
+    entry_type: String,
+    tags: Vec<String>
+) -> Result<JournalEntry, String> {
+    state.db.write(move |db| commands::create_journal_entry(db, title, content, workstream_id, persona_id, entry_type, tags)).await
+}
 
-    let mut stmt = db.prepare(&query)
-        .map_err(|e| format!("SQL prepare error: {}", e))?;
+#[tauri::command]
+async fn get_journal_entries(
+    state: tauri::State<'_, AppState>,
+    workstream_id: Option<String>,
+    persona_id: Option<String>,
+    limit: Option<u32>
+) -> Result<Vec<JournalEntry>, String> {
+    state.db.read(move |db| commands::get_journal_entries(db, workstream_id, persona_id, limit)).await
+}
 
-    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
-        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
-    })
-    .map_err(|e| format!("SQL query error: {}", e))?;
+#[tauri::command]
+async fn query_journal(state: tauri::State<'_, AppState>, query: filters::JournalQuery) -> Result<filters::JournalPage, String> {
+    state.db.read(move |db| commands::query_journal(db, query)).await
+}
 
-    let mut counts = serde_json::json!({
-        "backlog": 0,
-        "todo": 0,
-        "inprogress": 0,
-        "review": 0,
-        "done": 0
-    });
+// Query language commands
+#[tauri::command]
+async fn search(
+    state: tauri::State<'_, AppState>,
+    kind: String,
+    q: String,
+    cursor: Option<String>,
+    limit: Option<u32>
+) -> Result<filters::FilterResults, String> {
+    state.db.read(move |db| commands::search(db, kind, q, cursor, limit)).await
+}
 
-    for row in rows {
-        let (status, count): (String, i64) = row.map_err(|e| format!("Row processing error: {}", e))?;
-        let status_key = status.to_lowercase();
-        if counts.get(&status_key).is_some() {
-            counts[&status_key] = serde_json::Value::Number(serde_json::Number::from(count));
-        }
-    }
+#[tauri::command]
+fn check_query(kind: String, q: String) -> Result<query_lang::QueryCheck, String> {
+    commands::check_query(kind, q)
+}
 
-    Ok(counts)
+#[tauri::command]
+async fn complete_query(
+    state: tauri::State<'_, AppState>,
+    kind: String,
+    q: String,
+    cursor: Option<usize>
+) -> Result<Vec<query_lang::Completion>, String> {
+    state.db.read(move |db| commands::complete_query(db, kind, q, cursor)).await
 }
 
+// Saved filter commands
 #[tauri::command]
-async fn update_task_status(
+async fn create_saved_filter(
     state: tauri::State<'_, AppState>,
-    task_id: String,
-    new_status: String
-) -> Result<serde_json::Value, String> {
-    let db = state.db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    name: String,
+    kind: String,
+    query: Value,
+    pinned: bool
+) -> Result<SavedFilter, String> {
+    state.db.write(move |db| commands::create_saved_filter(db, name, kind, query, pinned)).await
+}
 
-    // Validate status
-    let valid_statuses = ["backlog", "todo", "inprogress", "review", "done"];
-    if !valid_statuses.contains(&new_status.to_lowercase().as_str()) {
-        return Err(format!("Invalid status: {}. Valid statuses are: {:?}", new_status, valid_statuses));
-    }
+#[tauri::command]
+async fn update_saved_filter(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    name: Option<String>,
+    query: Option<Value>,
+    pinned: Option<bool>
+) -> Result<SavedFilter, String> {
+    state.db.write(move |db| commands::update_saved_filter(db, id, name, query, pinned)).await
+}
+
+#[tauri::command]
+async fn delete_saved_filter(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    state.db.write(move |db| commands::delete_saved_filter(db, id)).await
+}
 
-    // Update the task status
-    let changes = db.execute(
-        "UPDATE project_tasks SET status = ?, updated_at = ? WHERE id = ?",
-        rusqlite::params![new_status.to_lowercase(), get_current_timestamp().to_string(), task_id]
-    )
-    .map_err(|e| format!("SQL update error: {}", e))?;
+#[tauri::command]
+async fn get_saved_filters(state: tauri::State<'_, AppState>, pinned_only: bool) -> Result<Vec<commands::SavedFilterView>, String> {
+    state.db.read(move |db| commands::get_saved_filters(db, pinned_only)).await
+}
 
-    if changes == 0 {
-        return Err(format!("Task with ID '{}' not found", task_id));
-    }
+#[tauri::command]
+async fn reorder_saved_filters(state: tauri::State<'_, AppState>, ids: Vec<String>) -> Result<Vec<commands::SavedFilterView>, String> {
+    state.db.write(move |db| commands::reorder_saved_filters(db, ids)).await
+}
+
+#[tauri::command]
+async fn run_saved_filter(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    cursor: Option<String>,
+    limit: Option<u32>
+) -> Result<filters::FilterResults, String> {
+    state.db.read(move |db| commands::run_saved_filter(db, id, cursor, limit)).await
+}
+
+#[tauri::command]
+async fn get_agenda(state: tauri::State<'_, AppState>, date: Option<String>) -> Result<commands::Agenda, String> {
+    state.db.read(move |db| commands::get_agenda(db, date)).await
+}
+
+#[tauri::command]
+async fn get_upcoming_deadlines(state: tauri::State<'_, AppState>, days: Option<u32>) -> Result<Vec<commands::Deadline>, String> {
+    state.db.read(move |db| commands::get_upcoming_deadlines(db, days)).await
+}
+
+// Journal vault (Markdown mirror) commands
+#[tauri::command]
+async fn export_journal_vault(state: tauri::State<'_, AppState>, vault_path: String) -> Result<Value, String> {
+    state.db.write(move |db| commands::export_journal_vault(db, vault_path)).await
+}
+
+#[tauri::command]
+async fn sync_journal_vault(state: tauri::State<'_, AppState>, vault_path: String) -> Result<Value, String> {
+    state.db.write(move |db| commands::sync_journal_vault(db, vault_path)).await
+}
+
+// Folder sync commands
+#[tauri::command]
+async fn enable_folder_sync(state: tauri::State<'_, AppState>, folder: String) -> Result<Value, String> {
+    state.db.write(move |db| commands::enable_folder_sync(db, folder)).await
+}
+
+#[tauri::command]
+async fn run_folder_sync(state: tauri::State<'_, AppState>, folder: Option<String>) -> Result<Value, String> {
+    state.db.write(move |db| commands::run_folder_sync(db, folder)).await
+}
+
+#[tauri::command]
+async fn get_sync_conflicts(state: tauri::State<'_, AppState>) -> Result<Value, String> {
+    state.db.read(commands::get_sync_conflicts).await
+}
+
+#[tauri::command]
+async fn resolve_sync_conflict(state: tauri::State<'_, AppState>, conflict_id: String, keep: String) -> Result<Value, String> {
+    state.db.write(move |db| commands::resolve_sync_conflict(db, conflict_id, keep)).await
+}
+
+// WebDAV sync commands
+#[tauri::command]
+async fn configure_webdav_sync(
+    state: tauri::State<'_, AppState>,
+    url: String,
+    username: Option<String>,
+    password: Option<String>
+) -> Result<Value, String> {
+    state.db.write(move |db| commands::configure_webdav_sync(db, url, username, password)).await
+}
+
+#[tauri::command]
+async fn run_webdav_sync(state: tauri::State<'_, AppState>) -> Result<Value, String> {
+    state.db.write(|db| commands::run_webdav_sync(db)).await
+}
+
+// Backup commands
+#[tauri::command]
+async fn configure_backups(
+    state: tauri::State<'_, AppState>,
+    directory: String,
+    interval_hours: u32,
+    keep: u32,
+    compress: bool,
+    passphrase: Option<String>
+) -> Result<Value, String> {
+    state.db.write(move |db| commands::configure_backups(db, directory, interval_hours, keep, compress, passphrase)).await
+}
+
+#[tauri::command]
+async fn get_backup_settings(state: tauri::State<'_, AppState>) -> Result<Value, String> {
+    state.db.read(commands::get_backup_settings).await
+}
+
+#[tauri::command]
+async fn create_backup_now(
+    state: tauri::State<'_, AppState>,
+    directory: Option<String>,
+    passphrase: Option<String>
+) -> Result<Value, String> {
+    // The backup reads a snapshot, so it runs on a reader and doesn't hold up writes.
+    state.db.read(move |db| commands::create_backup_now(db, directory, passphrase)).await
+}
+
+#[tauri::command]
+async fn list_backups(state: tauri::State<'_, AppState>, directory: Option<String>) -> Result<Value, String> {
+    state.db.read(move |db| commands::list_backups(db, directory)).await
+}
+
+#[tauri::command]
+async fn restore_backup(state: tauri::State<'_, AppState>, file: String, passphrase: Option<String>) -> Result<Value, String> {
+    state.db.write(move |db| commands::restore_backup(db, file, passphrase)).await
+}
+
+// Local HTTP API commands
+#[tauri::command]
+async fn configure_http_api(state: tauri::State<'_, AppState>, enabled: bool, port: Option<u16>) -> Result<Value, String> {
+    let port = port.unwrap_or(http_api::DEFAULT_PORT);
+    let settings = state.db.write(move |db| http_api::save_settings(db, enabled, port)).await?;
+
+    let running_port = state.restart_http_api()?;
+
+    Ok(serde_json::json!({
+        "enabled": settings.enabled,
+        "running": running_port.is_some(),
+        "url": running_port.map(|p| format!("http://127.0.0.1:{}/api", p)),
+        "token": settings.token
+    }))
+}
+
+#[tauri::command]
+async fn regenerate_http_api_token(state: tauri::State<'_, AppState>) -> Result<Value, String> {
+    let token = state.db.write(|db| http_api::regenerate_token(db)).await?;
+
+    // The running server holds the old token, so restart it.
+    let running_port = state.restart_http_api()?;
+
+    Ok(serde_json::json!({
+        "running": running_port.is_some(),
+        "token": token
+    }))
+}
 
-    // Return updated task data
-    let mut stmt = db.prepare("
-        SELECT 
-            pt.id,
-            pt.workstream_id,
-            pt.title,
-            pt.description,
-            pt.status,
-            pt.priority,
-            pt.due_date,
-            pt.completed_date,
-            pt.estimated_hours,
-            pt.actual_hours,
-            pt.tags,
-            pt.dependencies,
-            pt.created_at,
-            pt.updated_at,
-            w.name as workstream_name,
-            w.persona_id,
-            p.name as persona_name,
-            p.color as persona_color
-        FROM project_tasks pt
-        JOIN workstreams w ON pt.workstream_id = w.id
-        JOIN personas p ON w.persona_id = p.id
-        WHERE pt.id = ?
-    ")
-    .map_err(|e| format!("SQL prepare error: {}", e))?;
-
-    let task = stmt.query_row([&task_id], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "workstream_id": row.get::<_, String>(1)?,
-            "title": row.get::<_, String>(2)?,
-            "description": row.get::<_, Option<String>>(3)?,
-            "status": row.get::<_, String>(4)?,
-            "priority": row.get::<_, String>(5)?,
-            "due_date": row.get::<_, Option<String>>(6)?,
-            "completed_date": row.get::<_, Option<String>>(7)?,
-            "estimated_hours": row.get::<_, Option<f32>>(8)?,
-            "actual_hours": row.get::<_, Option<f32>>(9)?,
-            "tags": row.get::<_, Option<String>>(10)?,
-            "dependencies": row.get::<_, Option<String>>(11)?,
-            "created_at": row.get::<_, String>(12)?,
-            "updated_at": row.get::<_, String>(13)?,
-            "workstream_name": row.get::<_, String>(14)?,
-            "persona_id": row.get::<_, String>(15)?,
-            "persona_name": row.get::<_, String>(16)?,
-            "persona_color": row.get::<_, String>(17)?
-        }))
-    })
-    .map_err(|e| format!("SQL query error: {}", e))?;
-
-    Ok(task)
+#[tauri::command]
+async fn get_http_api_status(state: tauri::State<'_, AppState>) -> Result<Value, String> {
+    let settings = state.db.read(http_api::load_settings).await?;
+    let running_port = state.http_api.lock()
+        .map_err(|e| format!("HTTP API lock error: {}", e))?
+        .as_ref()
+        .map(|server| server.port());
+
+    Ok(serde_json::json!({
+        "enabled": settings.enabled,
+        "port": settings.port,
+        "running": running_port.is_some(),
+        "url": running_port.map(|p| format!("http://127.0.0.1:{}/api", p)),
+        "token": settings.token
+    }))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize database connection and create schema
     let app_state = AppState::new().expect("Failed to initialize database");
-    
-    // Create database schema if it doesn't exist
-    {
-        let db = app_state.db.lock().expect("Failed to lock database");
-        db.execute_batch(get_database_schema())
-            .expect("Failed to create database schema");
-    }
-    
+
+    // The HTTP API is opt-in; a port clash leaves it stopped and shows up in get_http_api_status.
+    let _ = app_state.restart_http_api();
+
+    backup::spawn_scheduler(DATABASE_PATH);
+
+    // Keep the running timer's heartbeat fresh so a timer left behind by a crash shows up as orphaned.
+    let heartbeat_db = app_state.db.clone();
+    std::thread::spawn(move || loop {
+        let _ = heartbeat_db.with_writer(|db| commands::timer_heartbeat(db));
+        std::thread::sleep(Duration::from_secs(60));
+    });
+    let focus_db = app_state.db.clone();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            Builder::default()
-                .add_migrations(
-                    "sqlite:./data.db",
-                    vec![Migration {
-                        version: 1,
-                        description: "create initial database schema",
-                        kind: MigrationKind::Up,
-                        sql: get_database_schema(),
-                    }],
-                )
-                .build(),
-        )
+        // open_database creates and migrates the schema; the SQL plugin runs no migrations of its own.
+        .plugin(tauri_plugin_sql::Builder::default().build())
         .manage(app_state)
-        .invoke_handler(tauri::generate_handler![greet, test_database_connection, create_test_persona, get_all_personas, delete_persona, clear_all_personas, create_persona, update_persona, create_workstream, get_workstreams_by_persona, get_all_workstreams, update_workstream, delete_workstream, create_project_task, get_tasks_by_workstream, get_all_project_tasks, update_project_task, delete_project_task, check_persona_dependencies, check_workstream_dependencies, cascade_delete_persona, cascade_delete_workstream, get_tasks_for_kanban, get_task_counts_by_status, update_task_status])
+        .setup(move |app| {
+            // Pomodoro ticker: moves the focus session on when a phase runs out and
+            // sends the countdown every second, plus one last tick once it ends.
+            // It reads on a reader and only takes the writer when a phase is due.
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                let mut active = false;
+                loop {
+                    std::thread::sleep(Duration::from_secs(1));
+                    let Ok((status, due)) = focus_db.with_reader(commands::peek_focus_status) else {
+                        continue;
+                    };
+                    let change = if due {
+                        let Ok(change) = focus_db.with_writer(|db| commands::get_focus_status(db)) else {
+                            continue;
+                        };
+                        change
+                    } else {
+                        commands::FocusChange { status, events: Vec::new(), stopped_timers: Vec::new() }
+                    };
+                    emit_focus_events(&handle, &change);
+                    if active || change.status.is_some() {
+                        let _ = handle.emit(pomodoro::TICK_EVENT, &change.status);
+                    }
+                    active = change.status.is_some();
+                }
+            });
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![greet, test_database_connection, create_test_persona, get_all_personas, delete_persona, clear_all_personas, create_persona, update_persona, create_workstream, get_workstreams_by_persona, get_all_workstreams, update_workstream, change_workstream_status, move_workstream_to_persona, copy_workstream, delete_workstream, create_project_task, get_tasks_by_workstream, get_all_project_tasks, update_project_task, delete_project_task, check_persona_dependencies, check_workstream_dependencies, cascade_delete_persona, cascade_delete_workstream, get_tasks_for_kanban, query_tasks, get_task_counts_by_status, update_task_status, move_task, move_task_to_workstream, set_wip_limit, delete_wip_limit, get_wip_limits, set_status_transition, delete_status_transition, get_status_transitions, set_task_due_date, set_task_recurrence, preview_recurrence, get_task_tree, create_subtask, move_subtask, set_task_estimate, set_task_milestone, create_milestone, update_milestone, delete_milestone, get_milestones, add_checklist_item, update_checklist_item, move_checklist_item, delete_checklist_item, set_completion_policy, delete_completion_policy, get_completion_policies, create_workflow, update_workflow, delete_workflow, get_workflows, get_workflow_for_workstream, start_timer, pause_timer, resume_timer, stop_timer, get_running_timer, resolve_orphaned_timer, add_time_entry, update_time_entry, delete_time_entry, get_time_entries, get_time_report, get_pomodoro_settings, configure_pomodoro, get_focus_status, start_focus_session, pause_focus_session, resume_focus_session, skip_focus_phase, stop_focus_session, get_focus_stats, get_estimate_accuracy, get_calibrated_estimate, get_workstream_burndown, create_habit, get_habits, log_habit_completion, create_journal_entry, get_journal_entries, query_journal, search, check_query, complete_query, get_agenda, get_upcoming_deadlines, create_saved_filter, update_saved_filter, delete_saved_filter, get_saved_filters, reorder_saved_filters, run_saved_filter, export_journal_vault, sync_journal_vault, enable_folder_sync, run_folder_sync, get_sync_conflicts, resolve_sync_conflict, configure_webdav_sync, run_webdav_sync, configure_backups, get_backup_settings, create_backup_now, list_backups, restore_backup, configure_http_api, regenerate_http_api_token, get_http_api_status])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
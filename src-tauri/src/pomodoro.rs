@@ -0,0 +1,535 @@
+use crate::backup::{get_setting, set_setting};
+use crate::commands;
+use crate::database::{generate_id, get_current_timestamp, FocusPhase, FocusSession, Pomodoro, TimeEntrySource};
+use crate::{recurrence, repository};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+// Pomodoro focus sessions: work phases separated by short breaks, with a long
+// break after every `long_break_every` work phases. Phases end by the clock,
+// so the session moves on whenever anything looks at it (the app's ticker,
+// a command, the CLI) rather than only while the app runs.
+
+// A phase that ended longer ago than this was missed, e.g. while the computer
+// slept: it still counts, but the next phase waits to be started.
+const CATCH_UP_GRACE_SECONDS: i64 = 60;
+
+pub const TICK_EVENT: &str = "pomodoro:tick";
+pub const PHASE_EVENT: &str = "pomodoro:phase-changed";
+pub const COMPLETED_EVENT: &str = "pomodoro:completed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroSettings {
+    pub work_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    pub long_break_every: u32,
+    pub auto_start_breaks: bool,
+    pub auto_start_work: bool,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        PomodoroSettings {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+            auto_start_breaks: true,
+            auto_start_work: false,
+        }
+    }
+}
+
+// The active session as the UI and tray show it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStatus {
+    #[serde(flatten)]
+    pub session: FocusSession,
+    pub task_title: Option<String>,
+    pub seconds_left: i64,
+    pub running: bool, // false while paused or waiting to be started
+    pub next_phase: FocusPhase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FocusEvent {
+    PhaseChanged {
+        session_id: String,
+        from: FocusPhase,
+        to: FocusPhase,
+        cycle: i64,
+        waiting: bool,
+    },
+    Completed {
+        pomodoro: Pomodoro,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusDay {
+    pub date: String,
+    pub completed: i64,
+    pub interrupted: i64,
+    pub focus_seconds: i64,
+    pub focus_minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusTaskStats {
+    pub task_id: String,
+    pub task_title: String,
+    pub completed: i64,
+    pub focus_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStats {
+    pub from: String,
+    pub to: String,
+    pub days: Vec<FocusDay>, // every day of the range, oldest first
+    pub tasks: Vec<FocusTaskStats>,
+    pub completed: i64,
+    pub interrupted: i64,
+    pub focus_seconds: i64,
+}
+
+// Settings (device-local, like the other app_settings)
+pub fn load_settings(conn: &Connection) -> Result<PomodoroSettings, String> {
+    let defaults = PomodoroSettings::default();
+    let number = |key: &str, default: u32| -> Result<u32, String> {
+        Ok(get_setting(conn, key)?.and_then(|v| v.parse().ok()).unwrap_or(default))
+    };
+    let flag = |key: &str, default: bool| -> Result<bool, String> {
+        Ok(get_setting(conn, key)?.map(|v| v == "1").unwrap_or(default))
+    };
+    Ok(PomodoroSettings {
+        work_minutes: number("pomodoro_work_minutes", defaults.work_minutes)?,
+        short_break_minutes: number("pomodoro_short_break_minutes", defaults.short_break_minutes)?,
+        long_break_minutes: number("pomodoro_long_break_minutes", defaults.long_break_minutes)?,
+        long_break_every: number("pomodoro_long_break_every", defaults.long_break_every)?,
+        auto_start_breaks: flag("pomodoro_auto_start_breaks", defaults.auto_start_breaks)?,
+        auto_start_work: flag("pomodoro_auto_start_work", defaults.auto_start_work)?,
+    })
+}
+
+pub fn save_settings(conn: &Connection, settings: &PomodoroSettings) -> Result<PomodoroSettings, String> {
+    if !(1..=180).contains(&settings.work_minutes) {
+        return Err("Work phases must last between 1 and 180 minutes".to_string());
+    }
+    if !(1..=60).contains(&settings.short_break_minutes) || !(1..=60).contains(&settings.long_break_minutes) {
+        return Err("Breaks must last between 1 and 60 minutes".to_string());
+    }
+    if !(1..=12).contains(&settings.long_break_every) {
+        return Err("A long break must come every 1 to 12 work phases".to_string());
+    }
+    set_setting(conn, "pomodoro_work_minutes", &settings.work_minutes.to_string())?;
+    set_setting(conn, "pomodoro_short_break_minutes", &settings.short_break_minutes.to_string())?;
+    set_setting(conn, "pomodoro_long_break_minutes", &settings.long_break_minutes.to_string())?;
+    set_setting(conn, "pomodoro_long_break_every", &settings.long_break_every.to_string())?;
+    set_setting(conn, "pomodoro_auto_start_breaks", if settings.auto_start_breaks { "1" } else { "0" })?;
+    set_setting(conn, "pomodoro_auto_start_work", if settings.auto_start_work { "1" } else { "0" })?;
+    load_settings(conn)
+}
+
+// Session state
+fn active_session(conn: &Connection) -> Result<Option<FocusSession>, String> {
+    repository::query_one(conn, "SELECT * FROM focus_sessions WHERE ended_at IS NULL ORDER BY started_at DESC LIMIT 1", [])
+}
+
+fn require_active(conn: &Connection) -> Result<FocusSession, String> {
+    active_session(conn)?.ok_or_else(|| "No focus session is active".to_string())
+}
+
+fn phase_seconds(session: &FocusSession, phase: FocusPhase) -> i64 {
+    match phase {
+        FocusPhase::Work => session.work_seconds,
+        FocusPhase::ShortBreak => session.short_break_seconds,
+        FocusPhase::LongBreak => session.long_break_seconds,
+    }
+}
+
+fn next_phase(session: &FocusSession) -> FocusPhase {
+    match session.phase {
+        FocusPhase::Work if session.cycle % session.long_break_every.max(1) == 0 => FocusPhase::LongBreak,
+        FocusPhase::Work => FocusPhase::ShortBreak,
+        _ => FocusPhase::Work,
+    }
+}
+
+fn seconds_left(session: &FocusSession, now: DateTime<Utc>) -> i64 {
+    match session.phase_ends_at {
+        Some(ends_at) => (ends_at - now).num_seconds().max(0),
+        None => session.remaining_seconds.unwrap_or_else(|| phase_seconds(session, session.phase)),
+    }
+}
+
+fn status(conn: &Connection, session: FocusSession, now: DateTime<Utc>) -> Result<FocusStatus, String> {
+    let task_title = match &session.task_id {
+        Some(task_id) => conn
+            .query_row("SELECT title FROM project_tasks WHERE id = ?1", [task_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("SQL query error: {}", e))?,
+        None => None,
+    };
+    Ok(FocusStatus {
+        task_title,
+        seconds_left: seconds_left(&session, now),
+        running: session.phase_ends_at.is_some(),
+        next_phase: next_phase(&session),
+        session,
+    })
+}
+
+pub fn active_status(conn: &Connection, now: DateTime<Utc>) -> Result<Option<FocusStatus>, String> {
+    active_session(conn)?.map(|session| status(conn, session, now)).transpose()
+}
+
+// Write down a work phase ending at `end`. A completed one on a task is also
+// logged as time on it, leaving out the time it was paused.
+fn record_pomodoro(conn: &Connection, session: &FocusSession, end: DateTime<Utc>, focus_seconds: i64, completed: bool) -> Result<Pomodoro, String> {
+    let start = session.phase_started_at.unwrap_or(end - Duration::seconds(focus_seconds)).min(end);
+    let time_entry_id = match &session.task_id {
+        Some(task_id) if completed && repository::get_task(conn, task_id)?.is_some() => {
+            let paused = ((end - start).num_seconds() - focus_seconds).max(0);
+            Some(commands::insert_time_entry(conn, task_id, TimeEntrySource::Pomodoro, start, end, paused, None)?)
+        }
+        _ => None,
+    };
+    let id = generate_id();
+    conn.execute(
+        "INSERT INTO pomodoros (id, session_id, task_id, started_at, ended_at, focus_seconds, completed, time_entry_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            id,
+            session.id,
+            session.task_id,
+            start.to_rfc3339(),
+            end.to_rfc3339(),
+            focus_seconds,
+            completed,
+            time_entry_id,
+            get_current_timestamp().to_rfc3339()
+        ],
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    repository::query_one(conn, "SELECT * FROM pomodoros WHERE id = ?1", [&id])?
+        .ok_or_else(|| format!("Pomodoro with ID '{}' not found", id))
+}
+
+// Move the session on to its next phase at `at`, counting down right away or
+// waiting to be started.
+fn enter_next_phase(conn: &Connection, session: &FocusSession, at: DateTime<Utc>, start: bool) -> Result<FocusEvent, String> {
+    let phase = next_phase(session);
+    let cycle = if phase == FocusPhase::Work { session.cycle + 1 } else { session.cycle };
+    let length = phase_seconds(session, phase);
+    let (started_at, ends_at, remaining) = if start {
+        (Some(at.to_rfc3339()), Some((at + Duration::seconds(length)).to_rfc3339()), None)
+    } else {
+        (None, None, Some(length))
+    };
+    conn.execute(
+        "UPDATE focus_sessions SET phase = ?1, cycle = ?2, phase_started_at = ?3, phase_ends_at = ?4, remaining_seconds = ?5, updated_at = ?6 WHERE id = ?7",
+        rusqlite::params![phase.key(), cycle, started_at, ends_at, remaining, get_current_timestamp().to_rfc3339(), session.id],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(FocusEvent::PhaseChanged { session_id: session.id.clone(), from: session.phase, to: phase, cycle, waiting: !start })
+}
+
+// Finish every phase of the active session whose time is up.
+pub fn advance(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<FocusEvent>, String> {
+    let mut events = Vec::new();
+    while let Some(session) = active_session(conn)? {
+        let Some(ends_at) = session.phase_ends_at.filter(|ends_at| *ends_at <= now) else {
+            break;
+        };
+        if session.phase == FocusPhase::Work {
+            let pomodoro = record_pomodoro(conn, &session, ends_at, session.work_seconds, true)?;
+            conn.execute(
+                "UPDATE focus_sessions SET completed_pomodoros = completed_pomodoros + 1 WHERE id = ?1",
+                [&session.id],
+            ).map_err(|e| format!("SQL update error: {}", e))?;
+            events.push(FocusEvent::Completed { pomodoro });
+        }
+        let auto_start = if session.phase == FocusPhase::Work { session.auto_start_breaks } else { session.auto_start_work };
+        let missed = (now - ends_at).num_seconds() > CATCH_UP_GRACE_SECONDS;
+        events.push(enter_next_phase(conn, &session, ends_at, auto_start && !missed)?);
+    }
+    Ok(events)
+}
+
+pub fn start(conn: &Connection, task_id: Option<String>, now: DateTime<Utc>) -> Result<FocusSession, String> {
+    if active_session(conn)?.is_some() {
+        return Err("A focus session is already active. Stop it first".to_string());
+    }
+    let settings = load_settings(conn)?;
+    let id = generate_id();
+    let work_seconds = settings.work_minutes as i64 * 60;
+    conn.execute(
+        "INSERT INTO focus_sessions (id, task_id, phase, cycle, work_seconds, short_break_seconds, long_break_seconds, long_break_every,
+         auto_start_breaks, auto_start_work, phase_started_at, phase_ends_at, completed_pomodoros, started_at, created_at, updated_at)
+         VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 0, ?10, ?12, ?12)",
+        rusqlite::params![
+            id,
+            task_id,
+            FocusPhase::Work.key(),
+            work_seconds,
+            settings.short_break_minutes as i64 * 60,
+            settings.long_break_minutes as i64 * 60,
+            settings.long_break_every,
+            settings.auto_start_breaks,
+            settings.auto_start_work,
+            now.to_rfc3339(),
+            (now + Duration::seconds(work_seconds)).to_rfc3339(),
+            get_current_timestamp().to_rfc3339()
+        ],
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+    require_active(conn)
+}
+
+pub fn pause(conn: &Connection, now: DateTime<Utc>) -> Result<FocusSession, String> {
+    let session = require_active(conn)?;
+    if session.phase_ends_at.is_none() {
+        return Err("The focus session is already paused".to_string());
+    }
+    conn.execute(
+        "UPDATE focus_sessions SET phase_ends_at = NULL, remaining_seconds = ?1, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![seconds_left(&session, now), get_current_timestamp().to_rfc3339(), session.id],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    require_active(conn)
+}
+
+// Continue a paused phase, or start one that is waiting.
+pub fn resume(conn: &Connection, now: DateTime<Utc>) -> Result<FocusSession, String> {
+    let session = require_active(conn)?;
+    if session.phase_ends_at.is_some() {
+        return Err("The focus session is not paused".to_string());
+    }
+    let ends_at = now + Duration::seconds(seconds_left(&session, now));
+    conn.execute(
+        "UPDATE focus_sessions SET phase_started_at = COALESCE(phase_started_at, ?1), phase_ends_at = ?2, remaining_seconds = NULL, updated_at = ?3 WHERE id = ?4",
+        rusqlite::params![now.to_rfc3339(), ends_at.to_rfc3339(), get_current_timestamp().to_rfc3339(), session.id],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    require_active(conn)
+}
+
+// A work phase cut short is kept as an interrupted pomodoro, without logging time.
+fn interrupt_work(conn: &Connection, session: &FocusSession, now: DateTime<Utc>) -> Result<(), String> {
+    if session.phase == FocusPhase::Work && session.phase_started_at.is_some() {
+        let focus_seconds = session.work_seconds - seconds_left(session, now);
+        if focus_seconds > 0 {
+            record_pomodoro(conn, session, now, focus_seconds, false)?;
+        }
+    }
+    Ok(())
+}
+
+// End the current phase now and start the next one.
+pub fn skip(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<FocusEvent>, String> {
+    let session = require_active(conn)?;
+    interrupt_work(conn, &session, now)?;
+    Ok(vec![enter_next_phase(conn, &session, now, true)?])
+}
+
+pub fn stop(conn: &Connection, now: DateTime<Utc>) -> Result<FocusSession, String> {
+    let session = require_active(conn)?;
+    interrupt_work(conn, &session, now)?;
+    conn.execute(
+        "UPDATE focus_sessions SET ended_at = ?1, phase_ends_at = NULL, remaining_seconds = NULL, updated_at = ?2 WHERE id = ?3",
+        rusqlite::params![now.to_rfc3339(), get_current_timestamp().to_rfc3339(), session.id],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    repository::query_one(conn, "SELECT * FROM focus_sessions WHERE id = ?1", [&session.id])?
+        .ok_or_else(|| format!("Focus session with ID '{}' not found", session.id))
+}
+
+// Pomodoros per local day (by when they ended) and per task.
+pub fn stats(conn: &Connection, from: NaiveDate, to: NaiveDate) -> Result<FocusStats, String> {
+    let day_start = |day: NaiveDate| {
+        recurrence::local_time(&chrono::Local, day, NaiveTime::MIN)
+            .map(|instant| instant.with_timezone(&Utc).to_rfc3339())
+            .ok_or_else(|| format!("Invalid day: {}", day))
+    };
+    let pomodoros: Vec<Pomodoro> = repository::query_all(
+        conn,
+        "SELECT * FROM pomodoros WHERE ended_at >= ?1 AND ended_at < ?2 ORDER BY ended_at",
+        [day_start(from)?, day_start(to.succ_opt().unwrap_or(to))?],
+    )?;
+
+    let mut days: Vec<FocusDay> = from
+        .iter_days()
+        .take_while(|day| *day <= to)
+        .map(|day| FocusDay { date: day.format("%Y-%m-%d").to_string(), completed: 0, interrupted: 0, focus_seconds: 0, focus_minutes: 0 })
+        .collect();
+    let mut tasks: Vec<FocusTaskStats> = Vec::new();
+    for pomodoro in &pomodoros {
+        let date = pomodoro.ended_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string();
+        if let Some(day) = days.iter_mut().find(|day| day.date == date) {
+            if pomodoro.completed {
+                day.completed += 1;
+            } else {
+                day.interrupted += 1;
+            }
+            day.focus_seconds += pomodoro.focus_seconds;
+        }
+        let Some(task_id) = &pomodoro.task_id else {
+            continue;
+        };
+        let index = match tasks.iter().position(|task| &task.task_id == task_id) {
+            Some(index) => index,
+            None => {
+                let task_title = conn
+                    .query_row("SELECT title FROM project_tasks WHERE id = ?1", [task_id], |row| row.get(0))
+                    .optional()
+                    .map_err(|e| format!("SQL query error: {}", e))?
+                    .unwrap_or_default();
+                tasks.push(FocusTaskStats { task_id: task_id.clone(), task_title, completed: 0, focus_seconds: 0 });
+                tasks.len() - 1
+            }
+        };
+        tasks[index].completed += pomodoro.completed as i64;
+        tasks[index].focus_seconds += pomodoro.focus_seconds;
+    }
+    for day in &mut days {
+        day.focus_minutes = day.focus_seconds / 60;
+    }
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.focus_seconds));
+
+    Ok(FocusStats {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        completed: days.iter().map(|day| day.completed).sum(),
+        interrupted: days.iter().map(|day| day.interrupted).sum(),
+        focus_seconds: days.iter().map(|day| day.focus_seconds).sum(),
+        days,
+        tasks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TimeEntry;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-04T09:00:00Z").unwrap().with_timezone(&Utc) + Duration::minutes(minutes)
+    }
+
+    fn setup(settings: PomodoroSettings) -> (Connection, String) {
+        let db = commands::open_database(":memory:").unwrap();
+        save_settings(&db, &settings).unwrap();
+        let persona = commands::create_persona(&db, "Work".into(), None, "#3366ff".into()).unwrap();
+        let workstream = commands::create_workstream(&db, persona.id, "Launch".into(), None, "active".into(), None, None, None).unwrap();
+        let task = commands::create_project_task(&db, workstream.id, "Write".into(), None, "todo".into(), "medium".into()).unwrap();
+        (db, task.id)
+    }
+
+    // The phases entered, and whether each waits to be started.
+    fn phases(events: &[FocusEvent]) -> Vec<(FocusPhase, i64, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                FocusEvent::PhaseChanged { to, cycle, waiting, .. } => Some((*to, *cycle, *waiting)),
+                FocusEvent::Completed { .. } => None,
+            })
+            .collect()
+    }
+
+    fn completed(events: &[FocusEvent]) -> Vec<&Pomodoro> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                FocusEvent::Completed { pomodoro } => Some(pomodoro),
+                FocusEvent::PhaseChanged { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_long_break_follows_every_nth_pomodoro() {
+        let settings = PomodoroSettings {
+            work_minutes: 1,
+            short_break_minutes: 1,
+            long_break_minutes: 2,
+            long_break_every: 2,
+            auto_start_breaks: true,
+            auto_start_work: true,
+        };
+        let (db, _) = setup(settings);
+        start(&db, None, at(0)).unwrap();
+        assert!(advance(&db, at(0)).unwrap().is_empty());
+
+        let events = advance(&db, at(1)).unwrap();
+        assert_eq!(completed(&events).len(), 1);
+        assert_eq!(phases(&events), [(FocusPhase::ShortBreak, 1, false)]);
+        assert_eq!(phases(&advance(&db, at(2)).unwrap()), [(FocusPhase::Work, 2, false)]);
+        assert_eq!(phases(&advance(&db, at(3)).unwrap()), [(FocusPhase::LongBreak, 2, false)]);
+        assert_eq!(phases(&advance(&db, at(5)).unwrap()), [(FocusPhase::Work, 3, false)]);
+        assert_eq!(active_status(&db, at(5)).unwrap().unwrap().session.completed_pomodoros, 2);
+    }
+
+    #[test]
+    fn a_phase_missed_for_long_waits_to_be_started() {
+        let (db, _) = setup(PomodoroSettings::default());
+        start(&db, None, at(0)).unwrap();
+
+        // Back after the grace period: the work still counts, the break waits.
+        let late = at(25) + Duration::seconds(CATCH_UP_GRACE_SECONDS + 1);
+        let events = advance(&db, late).unwrap();
+        assert_eq!(completed(&events)[0].ended_at, at(25));
+        assert_eq!(phases(&events), [(FocusPhase::ShortBreak, 1, true)]);
+        let status = active_status(&db, late).unwrap().unwrap();
+        assert!(!status.running);
+        assert_eq!(status.seconds_left, 5 * 60);
+        assert!(advance(&db, at(60)).unwrap().is_empty());
+
+        resume(&db, at(60)).unwrap();
+        assert_eq!(phases(&advance(&db, at(65)).unwrap()), [(FocusPhase::Work, 2, true)]);
+    }
+
+    #[test]
+    fn a_completed_pomodoro_logs_its_time_without_the_pauses() {
+        let (db, task_id) = setup(PomodoroSettings::default());
+        start(&db, Some(task_id.clone()), at(0)).unwrap();
+        pause(&db, at(10)).unwrap();
+        // A paused phase doesn't run out.
+        assert!(advance(&db, at(26)).unwrap().is_empty());
+        resume(&db, at(26)).unwrap();
+
+        let events = advance(&db, at(41)).unwrap();
+        let pomodoro = completed(&events)[0];
+        assert_eq!((pomodoro.started_at, pomodoro.ended_at, pomodoro.focus_seconds), (at(0), at(41), 25 * 60));
+        let entry: TimeEntry = repository::query_one(&db, "SELECT * FROM time_entries WHERE id = ?1", [pomodoro.time_entry_id.as_ref().unwrap()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.source, TimeEntrySource::Pomodoro);
+        assert_eq!((entry.paused_seconds, entry.duration_seconds), (16 * 60, Some(25 * 60)));
+        let task = repository::get_task(&db, &task_id).unwrap().unwrap();
+        assert_eq!(task.actual_hours.map(|hours| (hours * 100.0).round()), Some(42.0));
+    }
+
+    #[test]
+    fn an_interrupted_pomodoro_logs_no_time() {
+        let (db, task_id) = setup(PomodoroSettings::default());
+        start(&db, Some(task_id.clone()), at(0)).unwrap();
+        advance(&db, at(25)).unwrap();
+        advance(&db, at(30)).unwrap();
+        resume(&db, at(30)).unwrap();
+
+        // Skipping the second work phase ten minutes in starts the break.
+        assert_eq!(phases(&skip(&db, at(40)).unwrap()), [(FocusPhase::ShortBreak, 2, false)]);
+        let stopped = stop(&db, at(42)).unwrap();
+        assert_eq!(stopped.ended_at, Some(at(42)));
+        assert!(active_status(&db, at(42)).unwrap().is_none());
+
+        let interrupted: Pomodoro = repository::query_one(&db, "SELECT * FROM pomodoros WHERE completed = 0", []).unwrap().unwrap();
+        assert_eq!((interrupted.focus_seconds, interrupted.time_entry_id), (10 * 60, None));
+        let entries: i64 = db.query_row("SELECT COUNT(*) FROM time_entries", [], |row| row.get(0)).unwrap();
+        assert_eq!(entries, 1);
+
+        let day = at(0).with_timezone(&chrono::Local).date_naive();
+        let week = stats(&db, day - Duration::days(1), day + Duration::days(1)).unwrap();
+        assert_eq!(week.days.len(), 3);
+        assert_eq!((week.completed, week.interrupted, week.focus_seconds), (1, 1, 35 * 60));
+        assert_eq!((week.tasks[0].task_title.as_str(), week.tasks[0].completed), ("Write", 1));
+    }
+}
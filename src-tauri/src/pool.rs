@@ -0,0 +1,172 @@
+// Database executor.
+//
+// SQLite runs in WAL mode so readers never wait for the writer. The pool keeps
+// one writer connection behind a mutex and a handful of read-only connections;
+// commands hand it a closure and the work runs on Tauri's blocking thread pool
+// instead of the async executor, so a long export or sync can't stall the
+// kanban board.
+
+use crate::commands;
+use rusqlite::{Connection, OpenFlags};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_READERS: usize = 4;
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct DbPool {
+    writer: Mutex<Connection>,
+    readers: Mutex<Vec<Connection>>,
+    reader_count: usize,
+    reader_returned: Condvar,
+}
+
+impl DbPool {
+    pub fn open(path: &str, readers: usize) -> Result<Arc<Self>, String> {
+        let writer = commands::open_database(path)?;
+
+        // An in-memory database is private to its connection, so everything
+        // goes through the writer.
+        let readers = if path == ":memory:" {
+            Vec::new()
+        } else {
+            (0..readers).map(|_| open_reader(path)).collect::<Result<Vec<_>, _>>()?
+        };
+
+        Ok(Arc::new(DbPool {
+            writer: Mutex::new(writer),
+            reader_count: readers.len(),
+            readers: Mutex::new(readers),
+            reader_returned: Condvar::new(),
+        }))
+    }
+
+    // Run read-only work on a pooled connection, waiting for one to be free.
+    pub fn with_reader<T>(&self, f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        if self.reader_count == 0 {
+            return self.with_writer(|conn| f(conn));
+        }
+
+        let reader = {
+            let mut readers = self.readers.lock().map_err(|e| format!("Database lock error: {}", e))?;
+            loop {
+                if let Some(conn) = readers.pop() {
+                    break PooledReader { pool: self, conn: Some(conn) };
+                }
+                readers = self.reader_returned.wait(readers)
+                    .map_err(|e| format!("Database lock error: {}", e))?;
+            }
+        };
+
+        f(reader.conn.as_ref().expect("a pooled reader holds its connection until dropped"))
+    }
+
+    // Run work that modifies the database on the single writer connection.
+    pub fn with_writer<T>(&self, f: impl FnOnce(&mut Connection) -> Result<T, String>) -> Result<T, String> {
+        // A panic in earlier work poisons the lock, but its transaction has
+        // already rolled back while unwinding, so the connection is fine to reuse.
+        let mut conn = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut conn)
+    }
+
+    // Async versions for Tauri commands; the closure runs on a blocking thread.
+    pub async fn read<T, F>(self: &Arc<Self>, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.clone();
+        tauri::async_runtime::spawn_blocking(move || pool.with_reader(f))
+            .await
+            .map_err(|e| format!("Database task error: {}", e))?
+    }
+
+    pub async fn write<T, F>(self: &Arc<Self>, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.clone();
+        tauri::async_runtime::spawn_blocking(move || pool.with_writer(f))
+            .await
+            .map_err(|e| format!("Database task error: {}", e))?
+    }
+}
+
+// A reader taken from the pool, put back when dropped so a panic in the work
+// doesn't lose it.
+struct PooledReader<'a> {
+    pool: &'a DbPool,
+    conn: Option<Connection>,
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let mut readers = self.pool.readers.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            readers.push(conn);
+        }
+        self.pool.reader_returned.notify_one();
+    }
+}
+
+fn open_reader(path: &str) -> Result<Connection, String> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI,
+    )
+    .map_err(|e| format!("Database connection error: {}", e))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| format!("Database connection error: {}", e))?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_returns_to_pool_after_panic() {
+        let path = std::env::temp_dir().join(format!("pool-panic-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = DbPool::open(path.to_str().unwrap(), 1).unwrap();
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.with_reader(|_| -> Result<(), String> { panic!("failed read") })
+        }));
+        assert!(panicked.is_err());
+
+        // With a single reader, this would wait forever if the panic had lost it
+        let count = pool.with_reader(|conn| {
+            conn.query_row("SELECT COUNT(*) FROM personas", [], |row| row.get::<_, i64>(0))
+                .map_err(|e| e.to_string())
+        });
+        assert_eq!(count, Ok(0));
+        drop(pool);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn writer_recovers_after_panic() {
+        let pool = DbPool::open(":memory:", 0).unwrap();
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.with_writer(|conn| -> Result<(), String> {
+                let tx = conn.transaction().map_err(|e| e.to_string())?;
+                commands::create_persona(&tx, "Lost".into(), None, "#000000".into())?;
+                panic!("failed write")
+            })
+        }));
+        assert!(panicked.is_err());
+
+        // The half-done write rolled back and later writes still go through.
+        let count = pool.with_writer(|conn| {
+            commands::create_persona(conn, "Work".into(), None, "#3366ff".into())?;
+            conn.query_row("SELECT COUNT(*) FROM personas", [], |row| row.get::<_, i64>(0))
+                .map_err(|e| e.to_string())
+        });
+        assert_eq!(count, Ok(1));
+    }
+}
@@ -0,0 +1,720 @@
+// Query language for tasks and journal entries.
+//
+//     persona:Work status:todo,inprogress priority>=high due<7d tag:release -tag:blocked "search text"
+//
+// Terms are separated by spaces and all have to match. A term is either
+// `field:value` (or `=`, `<`, `<=`, `>`, `>=` where the field allows it), a bare
+// word or a "quoted phrase" searched in the title and body. A leading `-`
+// negates a term and `a,b` matches either value. Terms compile to the same
+// parameterised conditions as the form filters in filters.rs; no user text is
+// ever spliced into the SQL.
+
+use crate::database::{JournalEntryType, Priority, SavedFilterKind, StateCategory, TaskStatus};
+use crate::filters::{self, SqlFilter, TaskSort};
+use chrono::NaiveDate;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const MAX_COMPLETIONS: usize = 20;
+
+// (name, aliases, description). Field names are matched case-insensitively.
+const TASK_FIELDS: &[(&str, &[&str], &str)] = &[
+    ("persona", &["p"], "persona name or ID"),
+    ("workstream", &["ws", "w"], "workstream name or ID"),
+    ("status", &["s"], "backlog, todo, inprogress, review or done"),
+    ("state", &[], "workflow state, e.g. to_read"),
+    ("category", &["cat"], "not_started, active or done"),
+    ("priority", &["pri", "prio"], "low, medium, high or critical; supports < and >="),
+    ("tag", &["tags", "t"], "task tag"),
+    ("due", &[], "due date: YYYY-MM-DD, today, 7d, end_of_week or none; supports < and >="),
+    ("created", &[], "creation date; supports < and >="),
+    ("is", &[], "overdue, blocked, unblocked, open or done"),
+    ("sort", &[], "created, due, priority or rank"),
+    ("order", &[], "asc or desc"),
+];
+
+const JOURNAL_FIELDS: &[(&str, &[&str], &str)] = &[
+    ("persona", &["p"], "persona name or ID"),
+    ("workstream", &["ws", "w"], "workstream name or ID"),
+    ("type", &[], "note, update, reflection or meeting"),
+    ("tag", &["tags", "t"], "entry tag"),
+    ("created", &[], "creation date; supports < and >="),
+];
+
+const STATUS_VALUES: &[&str] = &["backlog", "todo", "inprogress", "review", "done"];
+const CATEGORY_VALUES: &[&str] = &["not_started", "active", "done"];
+const PRIORITY_VALUES: &[&str] = &["low", "medium", "high", "critical"];
+const ENTRY_TYPE_VALUES: &[&str] = &["note", "update", "reflection", "meeting"];
+const IS_VALUES: &[&str] = &["overdue", "blocked", "unblocked", "open", "done"];
+const SORT_VALUES: &[&str] = &["created", "due", "priority", "rank"];
+const ORDER_VALUES: &[&str] = &["asc", "desc"];
+const DATE_VALUES: &[&str] = &["today", "tomorrow", "yesterday", "7d", "14d", "-7d", "end_of_week", "end_of_month", "none"];
+
+// A problem with part of the query. `start` and `end` are character offsets
+// into the query text so the editor can underline them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    pub suggestion: Option<String>,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        QueryError { message: message.into(), start, end, suggestion: None }
+    }
+
+    fn suggest(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at {}..{})", self.message, self.start, self.end)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ". Did you mean '{}'?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn errors_to_string(errors: &[QueryError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Colon,
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Colon | Op::Eq => "=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        !matches!(self, Op::Colon | Op::Eq)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negated: bool,
+    // (name, start, end) of the field; None for free text
+    field: Option<(String, usize, usize)>,
+    op: Op,
+    value: String,
+    start: usize,
+    value_start: usize,
+    end: usize,
+}
+
+// Read a quoted string starting at the opening quote; returns the text and the index after the closing quote.
+fn read_quoted(chars: &[char], open: usize) -> Result<(String, usize), QueryError> {
+    let mut value = String::new();
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                value.push(chars[i + 1]);
+                i += 2;
+            }
+            '"' => return Ok((value, i + 1)),
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(QueryError::new("Missing closing quote", open, chars.len()))
+}
+
+fn tokenize(input: &str) -> (Vec<Term>, Vec<QueryError>) {
+    let chars: Vec<char> = input.chars().collect();
+    let mut terms = Vec::new();
+    let mut errors = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let negated = chars[i] == '-' && chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+        if negated {
+            i += 1;
+        }
+
+        if chars[i] == '"' {
+            match read_quoted(&chars, i) {
+                Ok((value, next)) => {
+                    terms.push(Term { negated, field: None, op: Op::Colon, value, start, value_start: i, end: next });
+                    i = next;
+                }
+                Err(e) => {
+                    errors.push(e);
+                    break;
+                }
+            }
+            continue;
+        }
+
+        let field_start = i;
+        while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+            i += 1;
+        }
+
+        if i > field_start && i < chars.len() && matches!(chars[i], ':' | '<' | '>' | '=') {
+            let field: String = chars[field_start..i].iter().collect();
+            let field_end = i;
+            let op = match (chars[i], chars.get(i + 1)) {
+                ('<', Some('=')) => Op::Le,
+                ('>', Some('=')) => Op::Ge,
+                ('<', _) => Op::Lt,
+                ('>', _) => Op::Gt,
+                ('=', _) => Op::Eq,
+                _ => Op::Colon,
+            };
+            i += if matches!(op, Op::Le | Op::Ge) { 2 } else { 1 };
+
+            let value_start = i;
+            let value = if chars.get(i) == Some(&'"') {
+                match read_quoted(&chars, i) {
+                    Ok((value, next)) => {
+                        i = next;
+                        value
+                    }
+                    Err(e) => {
+                        errors.push(e);
+                        break;
+                    }
+                }
+            } else {
+                while i < chars.len() && !chars[i].is_whitespace() {
+                    i += 1;
+                }
+                chars[value_start..i].iter().collect()
+            };
+
+            terms.push(Term { negated, field: Some((field, field_start, field_end)), op, value, start, value_start, end: i });
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+            let value = chars[field_start..i].iter().collect();
+            terms.push(Term { negated, field: None, op: Op::Colon, value, start, value_start: field_start, end: i });
+        }
+    }
+
+    (terms, errors)
+}
+
+fn fields(target: SavedFilterKind) -> &'static [(&'static str, &'static [&'static str], &'static str)] {
+    match target {
+        SavedFilterKind::Tasks => TASK_FIELDS,
+        SavedFilterKind::Journal => JOURNAL_FIELDS,
+    }
+}
+
+fn canonical_field(target: SavedFilterKind, name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    fields(target).iter()
+        .find(|(field, aliases, _)| *field == name || aliases.contains(&name.as_str()))
+        .map(|(field, _, _)| *field)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// The closest candidate within a couple of typos, or one that starts with the input.
+fn closest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let input = input.to_lowercase();
+    candidates.into_iter()
+        .map(|c| (if c.starts_with(&input) && !input.is_empty() { 0 } else { edit_distance(&input, c) }, c))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c.to_string())
+}
+
+// The result of compiling a query: conditions ANDed together plus any sort it asked for.
+#[derive(Debug, Clone, Default)]
+pub struct CompiledQuery {
+    pub filter: SqlFilter,
+    pub sort: Option<TaskSort>,
+    pub descending: Option<bool>,
+}
+
+struct Columns {
+    persona: &'static str,
+    workstream: &'static str,
+    tags: &'static str,
+    created: &'static str,
+    text: [&'static str; 2],
+}
+
+fn columns(target: SavedFilterKind) -> Columns {
+    match target {
+        SavedFilterKind::Tasks => Columns {
+            persona: "w.persona_id",
+            workstream: "pt.workstream_id",
+            tags: "pt.tags",
+            created: "pt.created_at",
+            text: ["pt.title", "pt.description"],
+        },
+        SavedFilterKind::Journal => Columns {
+            persona: "je.persona_id",
+            workstream: "je.workstream_id",
+            tags: "je.tags",
+            created: "je.created_at",
+            text: ["je.title", "je.content"],
+        },
+    }
+}
+
+pub fn compile(target: SavedFilterKind, input: &str, today: NaiveDate) -> Result<CompiledQuery, Vec<QueryError>> {
+    let (terms, mut errors) = tokenize(input);
+    let mut compiled = CompiledQuery::default();
+
+    for term in &terms {
+        match compile_term(target, term, today, &mut compiled) {
+            Ok(Some((condition, params))) => {
+                // NULL columns count as "doesn't match", so negations keep them.
+                let condition = if term.negated { format!("NOT COALESCE(({}), 0)", condition) } else { condition };
+                compiled.filter.push(condition, params);
+            }
+            Ok(None) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(compiled)
+    } else {
+        Err(errors)
+    }
+}
+
+type Condition = Option<(String, Vec<SqlValue>)>;
+
+fn compile_term(target: SavedFilterKind, term: &Term, today: NaiveDate, compiled: &mut CompiledQuery) -> Result<Condition, QueryError> {
+    let cols = columns(target);
+    let value_error = |message: String, candidates: &[&str], value: &str| {
+        QueryError::new(message, term.value_start, term.end).suggest(closest(value, candidates.iter().copied()))
+    };
+
+    let Some((name, field_start, field_end)) = &term.field else {
+        if term.value.is_empty() {
+            return Ok(None);
+        }
+        let pattern = SqlValue::Text(filters::like_pattern(&term.value));
+        return Ok(Some((
+            format!("({} LIKE ? ESCAPE '\\' OR {} LIKE ? ESCAPE '\\')", cols.text[0], cols.text[1]),
+            vec![pattern.clone(), pattern],
+        )));
+    };
+
+    let field = canonical_field(target, name).ok_or_else(|| {
+        let names = fields(target).iter().map(|(field, _, _)| *field);
+        QueryError::new(format!("Unknown field '{}'", name), *field_start, *field_end).suggest(closest(name, names))
+    })?;
+
+    // A value of only commas is as empty as no value at all.
+    let values: Vec<&str> = term.value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect();
+    if values.is_empty() {
+        return Err(QueryError::new(format!("Missing value after '{}{}'", name, if term.op == Op::Colon { ":" } else { term.op.sql() }), term.start, term.end));
+    }
+
+    let comparable = matches!(field, "priority" | "due" | "created");
+    if term.op.is_comparison() && !comparable {
+        return Err(QueryError::new(format!("'{}' only supports ':'", field), *field_end, term.value_start));
+    }
+    if term.op.is_comparison() && values.len() > 1 {
+        return Err(QueryError::new("Comparisons take a single value", term.value_start, term.end));
+    }
+
+    let placeholders = |n: usize| vec!["?"; n].join(", ");
+    let text_params = |values: &[String]| values.iter().cloned().map(SqlValue::Text).collect::<Vec<_>>();
+
+    match field {
+        "persona" | "workstream" => {
+            let (column, table) = if field == "persona" { (cols.persona, "personas") } else { (cols.workstream, "workstreams") };
+            let matches = vec!["(LOWER(name) = LOWER(?) OR id = ?)"; values.len()].join(" OR ");
+            let params = values.iter().flat_map(|v| [SqlValue::Text(v.to_string()), SqlValue::Text(v.to_string())]).collect();
+            Ok(Some((format!("{} IN (SELECT id FROM {} WHERE {})", column, table, matches), params)))
+        }
+        "status" => {
+            let keys = values.iter()
+                .map(|v| v.parse::<TaskStatus>().map(|s| s.key().to_string())
+                    .map_err(|_| value_error(format!("Unknown status '{}'", v), STATUS_VALUES, v)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some((format!("{} IN ({})", filters::STATUS_KEY, placeholders(keys.len())), text_params(&keys))))
+        }
+        "state" => {
+            let keys: Vec<String> = values.iter().map(|v| v.to_lowercase()).collect();
+            Ok(Some((format!("{} IN ({})", filters::STATE_KEY, placeholders(keys.len())), text_params(&keys))))
+        }
+        "category" => {
+            let categories = values.iter()
+                .map(|v| v.parse::<StateCategory>()
+                    .map_err(|_| value_error(format!("Unknown category '{}'", v), CATEGORY_VALUES, v)))
+                .collect::<Result<Vec<_>, _>>()?;
+            // Every state is stored with a status of its category.
+            let keys: Vec<String> = STATUS_VALUES.iter()
+                .filter(|v| v.parse::<TaskStatus>().is_ok_and(|s| categories.contains(&s.category())))
+                .map(|v| v.to_string())
+                .collect();
+            Ok(Some((format!("{} IN ({})", filters::STATUS_KEY, placeholders(keys.len())), text_params(&keys))))
+        }
+        "priority" => {
+            let levels = values.iter()
+                .map(|v| v.parse::<Priority>().map(|p| p.key())
+                    .map_err(|_| value_error(format!("Unknown priority '{}'", v), PRIORITY_VALUES, v)))
+                .collect::<Result<Vec<_>, _>>()?;
+            // Comparisons follow the order low < medium < high < critical.
+            let keys: Vec<String> = if term.op.is_comparison() {
+                let level = PRIORITY_VALUES.iter().position(|p| *p == levels[0]).unwrap_or(0);
+                PRIORITY_VALUES.iter().enumerate()
+                    .filter(|(i, _)| match term.op {
+                        Op::Lt => *i < level,
+                        Op::Le => *i <= level,
+                        Op::Gt => *i > level,
+                        _ => *i >= level,
+                    })
+                    .map(|(_, p)| p.to_string())
+                    .collect()
+            } else {
+                levels.iter().map(|p| p.to_string()).collect()
+            };
+            if keys.is_empty() {
+                return Ok(Some(("0".to_string(), Vec::new())));
+            }
+            Ok(Some((format!("{} IN ({})", filters::PRIORITY_KEY, placeholders(keys.len())), text_params(&keys))))
+        }
+        "type" => {
+            let keys = values.iter()
+                .map(|v| v.parse::<JournalEntryType>().map(|t| t.key().to_string())
+                    .map_err(|_| value_error(format!("Unknown entry type '{}'", v), ENTRY_TYPE_VALUES, v)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some((format!("LOWER(TRIM(je.entry_type, '\"')) IN ({})", placeholders(keys.len())), text_params(&keys))))
+        }
+        "tag" => {
+            let conditions = vec![filters::has_tag(cols.tags); values.len()].join(" OR ");
+            Ok(Some((format!("({})", conditions), values.iter().map(|v| SqlValue::Text(v.to_string())).collect())))
+        }
+        "due" | "created" => {
+            let column = if field == "due" { "pt.due_date" } else { cols.created };
+            if values.len() == 1 && values[0].eq_ignore_ascii_case("none") && field == "due" {
+                if term.op.is_comparison() {
+                    return Err(QueryError::new("'none' can't be compared", term.value_start, term.end));
+                }
+                return Ok(Some((format!("{} IS NULL", column), Vec::new())));
+            }
+            let dates = values.iter()
+                .map(|v| filters::resolve_date(v, today)
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .map_err(|e| QueryError::new(e, term.value_start, term.end)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let condition = if term.op.is_comparison() {
+                format!("substr({}, 1, 10) {} ?", column, term.op.sql())
+            } else {
+                format!("substr({}, 1, 10) IN ({})", column, placeholders(dates.len()))
+            };
+            Ok(Some((condition, text_params(&dates))))
+        }
+        "is" => {
+            let mut conditions = Vec::new();
+            let mut params = Vec::new();
+            for value in &values {
+                match value.to_lowercase().as_str() {
+                    "overdue" => {
+                        conditions.push(format!("(pt.due_date IS NOT NULL AND substr(pt.due_date, 1, 10) < ? AND {} != 'done')", filters::STATUS_KEY));
+                        params.push(SqlValue::Text(today.format("%Y-%m-%d").to_string()));
+                    }
+                    "blocked" => conditions.push(filters::BLOCKED.to_string()),
+                    "unblocked" => conditions.push(format!("NOT {}", filters::BLOCKED)),
+                    "open" => conditions.push(format!("{} != 'done'", filters::STATUS_KEY)),
+                    "done" => conditions.push(format!("{} = 'done'", filters::STATUS_KEY)),
+                    _ => return Err(value_error(format!("Unknown state '{}'", value), IS_VALUES, value)),
+                }
+            }
+            Ok(Some((format!("({})", conditions.join(" OR ")), params)))
+        }
+        "sort" => {
+            if term.negated || values.len() > 1 {
+                return Err(QueryError::new("Sort by a single field", term.start, term.end));
+            }
+            compiled.sort = Some(match values[0].to_lowercase().as_str() {
+                "created" => TaskSort::Created,
+                "due" => TaskSort::DueDate,
+                "priority" => TaskSort::Priority,
+                "rank" => TaskSort::Rank,
+                other => return Err(value_error(format!("Can't sort by '{}'", other), SORT_VALUES, other)),
+            });
+            Ok(None)
+        }
+        "order" => {
+            if term.negated || values.len() > 1 {
+                return Err(QueryError::new("Order is either asc or desc", term.start, term.end));
+            }
+            compiled.descending = Some(match values[0].to_lowercase().as_str() {
+                "asc" => false,
+                "desc" => true,
+                other => return Err(value_error(format!("Unknown order '{}'", other), ORDER_VALUES, other)),
+            });
+            Ok(None)
+        }
+        _ => Err(QueryError::new(format!("Unknown field '{}'", name), *field_start, *field_end)),
+    }
+}
+
+// Validation for the query editor: every error at once plus the SQL it compiles to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryCheck {
+    pub valid: bool,
+    pub errors: Vec<QueryError>,
+    pub sql: Option<String>,
+}
+
+pub fn check(target: SavedFilterKind, input: &str, today: NaiveDate) -> QueryCheck {
+    match compile(target, input, today) {
+        Ok(compiled) => QueryCheck { valid: true, errors: Vec::new(), sql: Some(compiled.filter.conditions.join(" AND ")) },
+        Err(errors) => QueryCheck { valid: false, errors, sql: None },
+    }
+}
+
+// Autocomplete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Completion {
+    pub label: String,
+    // Text that replaces the characters from `start` to `end`
+    pub insert: String,
+    pub kind: String,
+    pub detail: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn names(db: &Connection, sql: &str) -> Result<Vec<String>, String> {
+    let mut stmt = db.prepare(sql)
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let values = stmt.query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e));
+    values
+}
+
+fn field_values(db: &Connection, target: SavedFilterKind, field: &str) -> Result<Vec<String>, String> {
+    let values = match field {
+        "persona" => names(db, "SELECT name FROM personas WHERE is_active = 1 ORDER BY name")?,
+        "workstream" => names(db, "SELECT DISTINCT name FROM workstreams ORDER BY name")?,
+        "tag" => {
+            let table = if target == SavedFilterKind::Tasks { "project_tasks" } else { "journal_entries" };
+            names(db, &format!(
+                "SELECT DISTINCT value FROM {}, json_each(CASE WHEN json_valid(tags) THEN tags ELSE '[]' END) WHERE type = 'text' ORDER BY value",
+                table
+            ))?
+        }
+        "status" => STATUS_VALUES.iter().map(|v| v.to_string()).collect(),
+        "state" => {
+            let mut states: Vec<String> = STATUS_VALUES.iter().map(|v| v.to_string()).collect();
+            for state in names(db, "SELECT DISTINCT key FROM workflow_states ORDER BY key")? {
+                if !states.contains(&state) {
+                    states.push(state);
+                }
+            }
+            states
+        }
+        "category" => CATEGORY_VALUES.iter().map(|v| v.to_string()).collect(),
+        "priority" => PRIORITY_VALUES.iter().map(|v| v.to_string()).collect(),
+        "type" => ENTRY_TYPE_VALUES.iter().map(|v| v.to_string()).collect(),
+        "is" => IS_VALUES.iter().map(|v| v.to_string()).collect(),
+        "sort" => SORT_VALUES.iter().map(|v| v.to_string()).collect(),
+        "order" => ORDER_VALUES.iter().map(|v| v.to_string()).collect(),
+        "due" => DATE_VALUES.iter().map(|v| v.to_string()).collect(),
+        "created" => DATE_VALUES.iter().filter(|v| **v != "none").map(|v| v.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    Ok(values)
+}
+
+fn quote_if_needed(value: &str) -> String {
+    if value.chars().any(|c| c.is_whitespace() || c == ',' || c == '"') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Suggestions for the term under the cursor (a character offset): field names
+// while typing a field, values once the field and operator are there.
+pub fn complete(db: &Connection, target: SavedFilterKind, input: &str, cursor: usize) -> Result<Vec<Completion>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let cursor = cursor.min(chars.len());
+
+    // Start of the term under the cursor, ignoring spaces inside quotes.
+    let mut term_start = 0;
+    let mut in_quote = false;
+    for (i, c) in chars[..cursor].iter().enumerate() {
+        match c {
+            '"' => in_quote = !in_quote,
+            c if c.is_whitespace() && !in_quote => term_start = i + 1,
+            _ => {}
+        }
+    }
+    if chars.get(term_start) == Some(&'-') {
+        term_start += 1;
+    }
+    let term: String = chars[term_start..cursor].iter().collect();
+
+    let Some(op_at) = term.find([':', '<', '>', '=']) else {
+        let prefix = term.to_lowercase();
+        return Ok(fields(target).iter()
+            .filter(|(field, _, _)| field.starts_with(&prefix))
+            .map(|(field, _, detail)| Completion {
+                label: field.to_string(),
+                insert: format!("{}:", field),
+                kind: "field".to_string(),
+                detail: Some(detail.to_string()),
+                start: term_start,
+                end: cursor,
+            })
+            .collect());
+    };
+
+    let Some(field) = canonical_field(target, &term[..op_at]) else {
+        return Ok(Vec::new());
+    };
+
+    // The value being typed: after the operator and after the last comma.
+    let op_len = term[op_at..].chars().take_while(|c| matches!(c, ':' | '<' | '>' | '=')).count();
+    let value_offset = term[..op_at].chars().count() + op_len;
+    let value: String = term.chars().skip(value_offset).collect();
+    let piece_offset = value.rfind(',').map(|i| value[..i].chars().count() + 1).unwrap_or(0);
+    let piece: String = value.chars().skip(piece_offset).collect();
+    let partial = piece.trim_start_matches('"').to_lowercase();
+    let start = term_start + value_offset + piece_offset;
+
+    let mut values = field_values(db, target, field)?;
+    // Prefix matches first, then anything containing what was typed.
+    values.sort_by_key(|v| !v.to_lowercase().starts_with(&partial));
+    Ok(values.into_iter()
+        .filter(|v| v.to_lowercase().contains(&partial))
+        .take(MAX_COMPLETIONS)
+        .map(|v| Completion {
+            insert: quote_if_needed(&v),
+            label: v,
+            kind: "value".to_string(),
+            detail: None,
+            start,
+            end: cursor,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 4).unwrap()
+    }
+
+    fn errors(input: &str) -> Vec<QueryError> {
+        compile(SavedFilterKind::Tasks, input, today()).unwrap_err()
+    }
+
+    #[test]
+    fn unknown_fields_and_values_point_at_the_text_and_suggest_a_fix() {
+        let error = &errors("stauts:todo")[0];
+        assert_eq!(error.message, "Unknown field 'stauts'");
+        assert_eq!((error.start, error.end), (0, 6));
+        assert_eq!(error.suggestion.as_deref(), Some("status"));
+
+        let error = &errors("tag:x priority:hgih")[0];
+        assert_eq!(error.message, "Unknown priority 'hgih'");
+        assert_eq!((error.start, error.end), (15, 19));
+        assert_eq!(error.suggestion.as_deref(), Some("high"));
+
+        let error = &errors("is:overdu")[0];
+        assert_eq!(error.suggestion.as_deref(), Some("overdue"));
+    }
+
+    #[test]
+    fn every_error_is_reported_at_once() {
+        let errors = errors("status:nope tag:ok due:someday sort:title");
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert_eq!(messages[0], "Unknown status 'nope'");
+        assert!(messages[1].starts_with("Invalid date: someday"), "{}", messages[1]);
+        assert_eq!(messages[2], "Can't sort by 'title'");
+    }
+
+    #[test]
+    fn malformed_terms_are_rejected() {
+        assert_eq!(errors("status:")[0].message, "Missing value after 'status:'");
+        for (input, message) in [
+            ("sort:,", "Missing value after 'sort:'"),
+            ("order:,", "Missing value after 'order:'"),
+            ("priority>,", "Missing value after 'priority>'"),
+            ("tag:,", "Missing value after 'tag:'"),
+            ("is:,", "Missing value after 'is:'"),
+            ("persona:,,", "Missing value after 'persona:'"),
+        ] {
+            assert_eq!(errors(input)[0].message, message, "{}", input);
+        }
+        assert_eq!(errors("tag>=x")[0].message, "'tag' only supports ':'");
+        assert_eq!(errors("due<today,7d")[0].message, "Comparisons take a single value");
+        assert_eq!(errors("due<none")[0].message, "'none' can't be compared");
+        assert_eq!(errors("-sort:due")[0].message, "Sort by a single field");
+        let error = &errors("tag:x \"unfinished")[0];
+        assert_eq!(error.message, "Missing closing quote");
+        assert_eq!((error.start, error.end), (6, 17));
+        // Journal queries don't know task fields.
+        assert!(compile(SavedFilterKind::Journal, "status:todo", today()).is_err());
+    }
+
+    #[test]
+    fn terms_compile_to_parameterised_conditions() {
+        let compiled = compile(SavedFilterKind::Tasks, "-tag:\"x'); DROP TABLE t; --\" priority>=high sort:due order:asc", today()).unwrap();
+        assert_eq!(compiled.filter.conditions.len(), 2);
+        assert!(compiled.filter.conditions[0].starts_with("NOT COALESCE(("));
+        assert!(!compiled.filter.conditions.join(" ").contains("DROP"));
+        assert_eq!(compiled.filter.params.len(), 3); // the tag, then high and critical
+        assert_eq!(compiled.sort, Some(TaskSort::DueDate));
+        assert_eq!(compiled.descending, Some(false));
+
+        let compiled = compile(SavedFilterKind::Tasks, "due<7d", today()).unwrap();
+        assert_eq!(compiled.filter.params, [SqlValue::Text("2026-03-11".into())]);
+        assert!(check(SavedFilterKind::Tasks, "  ", today()).valid);
+    }
+}
@@ -0,0 +1,234 @@
+// Lexicographic ranks for manually ordered lists such as kanban columns.
+//
+// A rank is a base-62 string compared byte by byte, like a decimal fraction
+// after the point: "V" sits halfway, "8" before it, "Vk" just after it. There
+// is always room between two ranks, so moving an item only rewrites its own
+// rank. Ranks never end in '0', which keeps room before every rank as well.
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+
+fn digits(rank: &str) -> Option<Vec<u8>> {
+    rank.bytes()
+        .map(|b| DIGITS.iter().position(|d| *d == b).map(|i| i as u8))
+        .collect()
+}
+
+fn to_rank(digits: &[u8]) -> String {
+    digits.iter().map(|d| DIGITS[*d as usize] as char).collect()
+}
+
+pub fn is_valid(rank: &str) -> bool {
+    !rank.is_empty() && !rank.ends_with('0') && digits(rank).is_some()
+}
+
+// A rank strictly between `lower` and `upper`; None means the start or end of the list.
+pub fn between(lower: Option<&str>, upper: Option<&str>) -> Result<String, String> {
+    let parse = |rank: &str| {
+        if is_valid(rank) {
+            digits(rank).ok_or_else(|| format!("Invalid rank '{}'", rank))
+        } else {
+            Err(format!("Invalid rank '{}'", rank))
+        }
+    };
+    let lower = lower.map(parse).transpose()?.unwrap_or_default();
+    let upper = upper.map(parse).transpose()?;
+
+    match &upper {
+        Some(upper) if lower >= *upper => Err(format!("Rank '{}' is not below '{}'", to_rank(&lower), to_rank(upper))),
+        // Items keep being added at either end, so step by one digit there
+        // rather than halving, which would make ranks grow much faster.
+        None if !lower.is_empty() => Ok(to_rank(&step_up(&lower))),
+        Some(upper) if lower.is_empty() => Ok(to_rank(&step_down(upper))),
+        _ => Ok(to_rank(&midpoint(&lower, upper.as_deref()))),
+    }
+}
+
+fn step_up(lower: &[u8]) -> Vec<u8> {
+    match lower.iter().position(|d| (*d as usize) < BASE - 1) {
+        Some(i) => [&lower[..i], &[lower[i] + 1]].concat(),
+        None => midpoint(lower, None),
+    }
+}
+
+fn step_down(upper: &[u8]) -> Vec<u8> {
+    match upper.iter().position(|d| *d > 1) {
+        Some(i) => [&upper[..i], &[upper[i] - 1]].concat(),
+        None => midpoint(&[], Some(upper)),
+    }
+}
+
+fn midpoint(lower: &[u8], upper: Option<&[u8]>) -> Vec<u8> {
+    // Keep any shared prefix (missing lower digits count as 0) and split after it.
+    if let Some(upper) = upper {
+        let shared = upper.iter().enumerate()
+            .take_while(|(i, d)| lower.get(*i).copied().unwrap_or(0) == **d)
+            .count();
+        if shared > 0 {
+            let mut rank = upper[..shared].to_vec();
+            rank.extend(midpoint(lower.get(shared..).unwrap_or_default(), Some(&upper[shared..])));
+            return rank;
+        }
+    }
+
+    let low = lower.first().copied().unwrap_or(0) as usize;
+    let high = upper.map(|u| u[0] as usize).unwrap_or(BASE);
+    if high - low > 1 {
+        return vec![((low + high) / 2) as u8];
+    }
+    // Adjacent first digits: the upper rank's first digit alone is in between
+    // when it has more digits, otherwise go one digit deeper after `low`.
+    if let Some(upper) = upper.filter(|u| u.len() > 1) {
+        return vec![upper[0]];
+    }
+    let mut rank = vec![low as u8];
+    rank.extend(midpoint(lower.get(1..).unwrap_or_default(), None));
+    rank
+}
+
+// `count` evenly spaced ranks in ascending order, for renumbering a whole list.
+pub fn spread(count: usize) -> Vec<String> {
+    let mut width = 1;
+    let mut space = BASE as u128;
+    // Leave a few free slots between neighbours.
+    while space <= count as u128 * 4 {
+        width += 1;
+        space *= BASE as u128;
+    }
+
+    (1..=count as u128)
+        .map(|i| {
+            let mut value = i * space / (count as u128 + 1);
+            let mut rank = vec![0u8; width];
+            for digit in rank.iter_mut().rev() {
+                *digit = (value % BASE as u128) as u8;
+                value /= BASE as u128;
+            }
+            to_rank(&rank).trim_end_matches('0').to_string()
+        })
+        .collect()
+}
+
+// Ranks to write after placing `id` in a short ordered list of (id, rank)
+// pairs that does not contain it: below `after_id`, above `before_id`, or at
+// the end. Only the placed item changes while its neighbours leave room;
+// otherwise the whole list is renumbered.
+pub fn place(
+    items: &[(String, Option<String>)],
+    id: &str,
+    after_id: Option<&str>,
+    before_id: Option<&str>,
+) -> Result<Vec<(String, String)>, String> {
+    let position = |wanted: &str| {
+        items.iter().position(|(item, _)| item == wanted)
+            .ok_or_else(|| format!("'{}' is not in this list", wanted))
+    };
+    let index = match (after_id, before_id) {
+        (Some(after), Some(before)) => {
+            let index = position(after)? + 1;
+            if position(before)? != index {
+                return Err(format!("'{}' is not directly above '{}'", after, before));
+            }
+            index
+        }
+        (Some(after), None) => position(after)? + 1,
+        (None, Some(before)) => position(before)?,
+        (None, None) => items.len(),
+    };
+
+    let lower = if index > 0 { items[index - 1].1.as_deref() } else { None };
+    let upper = items.get(index).and_then(|(_, rank)| rank.as_deref());
+    let has_room = (index == 0 || lower.is_some()) && (index == items.len() || upper.is_some());
+    if has_room {
+        if let Ok(rank) = between(lower, upper) {
+            return Ok(vec![(id.to_string(), rank)]);
+        }
+    }
+
+    let mut ids: Vec<String> = items.iter().map(|(item, _)| item.clone()).collect();
+    ids.insert(index, id.to_string());
+    let ranks = spread(ids.len());
+    Ok(ids.into_iter().zip(ranks).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn between_sorts_strictly_inside_its_bounds() {
+        let cases = [
+            (None, None),
+            (Some("V"), None),
+            (None, Some("V")),
+            (Some("V"), Some("W")),
+            (Some("V"), Some("V1")),
+            (Some("z"), None),
+            (None, Some("1")),
+            (None, Some("01")),
+            (Some("Vzzz"), Some("W")),
+        ];
+        for (lower, upper) in cases {
+            let rank = between(lower, upper).unwrap();
+            assert!(is_valid(&rank), "{:?} {:?} -> {}", lower, upper, rank);
+            assert!(lower.is_none_or(|lower| lower < rank.as_str()), "{:?} {:?} -> {}", lower, upper, rank);
+            assert!(upper.is_none_or(|upper| rank.as_str() < upper), "{:?} {:?} -> {}", lower, upper, rank);
+        }
+    }
+
+    #[test]
+    fn between_keeps_finding_room() {
+        // Inserting right below the same rank over and over never runs out.
+        let (lower, mut upper) = ("V".to_string(), "W".to_string());
+        for _ in 0..200 {
+            let rank = between(Some(&lower), Some(&upper)).unwrap();
+            assert!(lower < rank && rank < upper);
+            upper = rank;
+        }
+        // Appending adds a digit about every 30 items, where halving would add one every 6.
+        let mut last = between(None, None).unwrap();
+        for _ in 0..200 {
+            last = between(Some(&last), None).unwrap();
+        }
+        assert!(last.len() <= 8, "{}", last);
+    }
+
+    #[test]
+    fn between_rejects_bad_bounds() {
+        assert!(between(Some("W"), Some("V")).is_err());
+        assert!(between(Some("V"), Some("V")).is_err());
+        assert!(between(Some("V0"), None).is_err());
+        assert!(between(None, Some("a-b")).is_err());
+    }
+
+    #[test]
+    fn spread_is_ascending_and_valid() {
+        for count in [1, 2, 61, 62, 500] {
+            let ranks = spread(count);
+            assert_eq!(ranks.len(), count);
+            assert!(ranks.iter().all(|rank| is_valid(rank)));
+            assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn is_valid_rejects_empty_trailing_zero_and_foreign_digits() {
+        assert!(is_valid("V") && is_valid("0V") && is_valid("zz"));
+        assert!(!is_valid("") && !is_valid("V0") && !is_valid("V-") && !is_valid("é"));
+    }
+
+    #[test]
+    fn place_renumbers_only_without_room() {
+        let items = vec![("a".to_string(), Some("V".to_string())), ("b".to_string(), Some("W".to_string()))];
+        let placed = place(&items, "c", Some("a"), Some("b")).unwrap();
+        assert_eq!(placed.len(), 1);
+        assert!("V" < placed[0].1.as_str() && placed[0].1.as_str() < "W");
+
+        let unranked = vec![("a".to_string(), None), ("b".to_string(), Some("W".to_string()))];
+        let placed = place(&unranked, "c", None, Some("b")).unwrap();
+        assert_eq!(placed.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), ["a", "c", "b"]);
+
+        assert!(place(&items, "c", Some("b"), Some("a")).is_err());
+        assert!(place(&items, "c", Some("missing"), None).is_err());
+    }
+}
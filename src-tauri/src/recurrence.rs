@@ -0,0 +1,352 @@
+// Recurrence rules for repeating tasks: the part of RFC 5545 RRULEs we need.
+//
+//     FREQ=DAILY;INTERVAL=2
+//     FREQ=WEEKLY;BYDAY=MO,WE,FR
+//     FREQ=MONTHLY;BYDAY=2TU           second Tuesday, -1FR is the last Friday
+//     FREQ=MONTHLY;BYMONTHDAY=-1       last day of the month
+//
+// plus COUNT and UNTIL. Dates follow the RFC: a monthly rule on the 31st skips
+// the months without one (BYMONTHDAY=-1 is the way to say "month end"), and the
+// week starts on Monday.
+
+use chrono::{DateTime, Datelike, Days, Duration, LocalResult, Months, NaiveDate, NaiveTime, Offset, TimeZone, Weekday};
+use std::fmt;
+
+// Upper bound on the periods searched for a match, e.g. 5TH Fridays are rare but not that rare.
+const MAX_PERIODS: u32 = 1200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<(Option<i32>, Weekday)>, // numbered only in monthly rules
+    pub by_month_day: Vec<i32>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+}
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+fn parse_day(value: &str) -> Result<(Option<i32>, Weekday), String> {
+    let value = value.trim().to_uppercase();
+    let invalid = || format!("Invalid BYDAY value '{}'. Use MO..SU, optionally numbered like 2TU or -1FR", value);
+    if value.len() < 2 || !value.is_char_boundary(value.len() - 2) {
+        return Err(invalid());
+    }
+    let (number, code) = value.split_at(value.len() - 2);
+    let weekday = WEEKDAYS.iter().find(|(name, _)| *name == code).map(|(_, day)| *day).ok_or_else(invalid)?;
+    let number = match number {
+        "" => None,
+        number => match number.trim_start_matches('+').parse::<i32>() {
+            Ok(n) if n != 0 && n.abs() <= 5 => Some(n),
+            _ => return Err(invalid()),
+        },
+    };
+    Ok((number, weekday))
+}
+
+fn positive(name: &str, value: &str) -> Result<u32, String> {
+    value.parse().ok().filter(|n| *n > 0).ok_or_else(|| format!("Invalid {} '{}'", name, value))
+}
+
+pub fn parse(text: &str) -> Result<Rule, String> {
+    let text = text.trim();
+    let body = if text.len() > 6 && text[..6].eq_ignore_ascii_case("RRULE:") { &text[6..] } else { text };
+
+    let mut frequency = None;
+    let mut rule = Rule { frequency: Frequency::Daily, interval: 1, by_day: Vec::new(), by_month_day: Vec::new(), count: None, until: None };
+    for part in body.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+        let (name, value) = part.split_once('=').ok_or_else(|| format!("Invalid rule part '{}'", part))?;
+        let value = value.trim();
+        match name.trim().to_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match value.to_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    _ => return Err(format!("Unsupported FREQ '{}'. Use DAILY, WEEKLY or MONTHLY", value)),
+                })
+            }
+            "INTERVAL" => rule.interval = positive("INTERVAL", value)?,
+            "COUNT" => rule.count = Some(positive("COUNT", value)?),
+            "UNTIL" => {
+                // a date, or a date-time of which only the day matters here
+                let day = value.get(..8).unwrap_or(value);
+                rule.until = Some(NaiveDate::parse_from_str(day, "%Y%m%d").map_err(|_| format!("Invalid UNTIL '{}'. Use YYYYMMDD", value))?);
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    rule.by_day.push(parse_day(day)?);
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in value.split(',') {
+                    match day.trim().parse::<i32>() {
+                        Ok(n) if n != 0 && n.abs() <= 31 => rule.by_month_day.push(n),
+                        _ => return Err(format!("Invalid BYMONTHDAY value '{}'", day.trim())),
+                    }
+                }
+            }
+            "WKST" if value.eq_ignore_ascii_case("MO") => {}
+            "WKST" => return Err("Only WKST=MO is supported".to_string()),
+            _ => return Err(format!("Unsupported rule part '{}'", name.trim())),
+        }
+    }
+
+    rule.frequency = frequency.ok_or("A rule needs a FREQ, e.g. FREQ=WEEKLY;BYDAY=MO")?;
+    if rule.count.is_some() && rule.until.is_some() {
+        return Err("A rule can't have both COUNT and UNTIL".to_string());
+    }
+    if rule.frequency != Frequency::Monthly {
+        if rule.by_day.iter().any(|(number, _)| number.is_some()) {
+            return Err("Numbered weekdays like 2TU need FREQ=MONTHLY".to_string());
+        }
+        if !rule.by_month_day.is_empty() {
+            return Err("BYMONTHDAY needs FREQ=MONTHLY".to_string());
+        }
+    } else if !rule.by_day.is_empty() && !rule.by_month_day.is_empty() {
+        return Err("Use either BYDAY or BYMONTHDAY, not both".to_string());
+    }
+    Ok(rule)
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self.by_day.iter()
+                .map(|(number, day)| {
+                    let code = WEEKDAYS.iter().find(|(_, d)| d == day).map(|(name, _)| *name).unwrap_or_default();
+                    format!("{}{}", number.map(|n| n.to_string()).unwrap_or_default(), code)
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self.by_month_day.iter().map(|d| d.to_string()).collect();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        Ok(())
+    }
+}
+
+fn last_day_of_month(first: NaiveDate) -> NaiveDate {
+    (first + Months::new(1)).pred_opt().unwrap_or(first)
+}
+
+impl Rule {
+    // The first occurrence after `after` of the series that starts on `start`
+    // (DTSTART). COUNT is left to the caller, which knows how many occurrences
+    // came before.
+    pub fn next_after(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        let found = match self.frequency {
+            Frequency::Daily => self.next_daily(start, after),
+            Frequency::Weekly => self.next_weekly(start, after),
+            Frequency::Monthly => self.next_monthly(start, after),
+        }?;
+        match self.until {
+            Some(until) if found > until => None,
+            _ => Some(found),
+        }
+    }
+
+    fn next_daily(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        let first = if after < start { 0 } else { (after - start).num_days() as u64 / self.interval as u64 + 1 };
+        (first..first + MAX_PERIODS as u64)
+            .filter_map(|k| start.checked_add_days(Days::new(k * self.interval as u64)))
+            .find(|date| self.by_day.is_empty() || self.by_day.iter().any(|(_, day)| *day == date.weekday()))
+    }
+
+    fn next_weekly(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        let week_start = start - Days::new(start.weekday().num_days_from_monday() as u64);
+        let mut days: Vec<u64> = if self.by_day.is_empty() {
+            vec![start.weekday().num_days_from_monday() as u64]
+        } else {
+            self.by_day.iter().map(|(_, day)| day.num_days_from_monday() as u64).collect()
+        };
+        days.sort_unstable();
+        days.dedup();
+
+        let first = if after < start { 0 } else { (after - week_start).num_days() as u64 / 7 / self.interval as u64 };
+        for period in first..first + MAX_PERIODS as u64 {
+            let week = week_start.checked_add_days(Days::new(period * self.interval as u64 * 7))?;
+            if let Some(date) = days.iter()
+                .filter_map(|day| week.checked_add_days(Days::new(*day)))
+                .find(|date| *date >= start && *date > after)
+            {
+                return Some(date);
+            }
+        }
+        None
+    }
+
+    fn next_monthly(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        let month_start = start.with_day(1)?;
+        let months_between = |date: NaiveDate| (date.year() - start.year()) * 12 + date.month() as i32 - start.month() as i32;
+        let first = if after < start { 0 } else { months_between(after).max(0) as u32 / self.interval };
+
+        for period in first..first + MAX_PERIODS {
+            let month = month_start.checked_add_months(Months::new(period * self.interval))?;
+            let mut dates = self.month_days(month, start);
+            dates.sort_unstable();
+            if let Some(date) = dates.into_iter().find(|date| *date >= start && *date > after) {
+                return Some(date);
+            }
+        }
+        None
+    }
+
+    // The days of the month starting on `month` that match the rule.
+    fn month_days(&self, month: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+        let last = last_day_of_month(month);
+        let day = |n: i32| {
+            let n = if n < 0 { last.day() as i32 + n + 1 } else { n };
+            if n < 1 { None } else { month.with_day(n as u32) }
+        };
+
+        if !self.by_month_day.is_empty() {
+            return self.by_month_day.iter().filter_map(|n| day(*n)).collect();
+        }
+        if !self.by_day.is_empty() {
+            let mut dates = Vec::new();
+            for (number, weekday) in &self.by_day {
+                let offset = (7 + weekday.num_days_from_monday() as i32 - month.weekday().num_days_from_monday() as i32) % 7;
+                let all: Vec<NaiveDate> = (0..5)
+                    .filter_map(|week| day(1 + offset + week * 7))
+                    .filter(|date| date.month() == month.month())
+                    .collect();
+                match number {
+                    Some(n) if *n > 0 => dates.extend(all.get(*n as usize - 1)),
+                    Some(n) => dates.extend(all.len().checked_sub(n.unsigned_abs() as usize).and_then(|i| all.get(i))),
+                    None => dates.extend(all),
+                }
+            }
+            return dates;
+        }
+        // Same day of the month as the start; months without it are skipped.
+        day(start.day() as i32).into_iter().collect()
+    }
+
+    // The first `limit` occurrences, starting with `start` itself.
+    pub fn preview(&self, start: NaiveDate, limit: usize) -> Vec<NaiveDate> {
+        let limit = limit.min(self.count.map(|c| c as usize).unwrap_or(usize::MAX));
+        let mut dates = vec![start];
+        while dates.len() < limit {
+            match self.next_after(start, dates[dates.len() - 1]) {
+                Some(next) => dates.push(next),
+                None => break,
+            }
+        }
+        dates.truncate(limit);
+        dates
+    }
+}
+
+// A wall-clock time on a day in `tz`. When the clocks go back the earlier
+// instant is used; a time skipped when they go forward is read with the offset
+// from before the gap, as RFC 5545 prescribes, so 02:30 becomes 03:30.
+pub fn local_time<Tz: TimeZone>(tz: &Tz, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Tz>> {
+    let naive = date.and_time(time);
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(instant) => Some(instant),
+        LocalResult::Ambiguous(a, b) => Some(if a <= b { a } else { b }),
+        LocalResult::None => {
+            let before = tz.from_local_datetime(&(naive - Duration::hours(3))).earliest()?;
+            let offset = before.offset().fix().local_minus_utc();
+            Some(tz.from_utc_datetime(&(naive - Duration::seconds(offset as i64))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Timelike, Utc};
+    use chrono_tz::Europe::Berlin;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(rule: &str, start: &str, limit: usize) -> Vec<String> {
+        parse(rule).unwrap().preview(date(start), limit).iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn monthly_on_the_31st_skips_shorter_months() {
+        assert_eq!(
+            dates("FREQ=MONTHLY", "2026-01-31", 4),
+            ["2026-01-31", "2026-03-31", "2026-05-31", "2026-07-31"]
+        );
+    }
+
+    #[test]
+    fn last_day_of_the_month_follows_february() {
+        assert_eq!(dates("FREQ=MONTHLY;BYMONTHDAY=-1", "2026-01-31", 3), ["2026-01-31", "2026-02-28", "2026-03-31"]);
+        assert_eq!(dates("FREQ=MONTHLY;BYMONTHDAY=-1", "2028-01-31", 2), ["2028-01-31", "2028-02-29"]);
+    }
+
+    #[test]
+    fn last_friday_of_the_month() {
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYDAY=-1FR", "2026-01-30", 4),
+            ["2026-01-30", "2026-02-27", "2026-03-27", "2026-04-24"]
+        );
+    }
+
+    #[test]
+    fn every_other_week_on_two_days() {
+        // 2026-01-05 is a Monday; the week after is skipped
+        assert_eq!(
+            dates("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", "2026-01-05", 5),
+            ["2026-01-05", "2026-01-08", "2026-01-19", "2026-01-22", "2026-02-02"]
+        );
+        let rule = parse("FREQ=WEEKLY;INTERVAL=2").unwrap();
+        assert_eq!(rule.next_after(date("2026-01-05"), date("2026-01-12")), Some(date("2026-01-19")));
+    }
+
+    #[test]
+    fn times_in_a_skipped_hour_move_forward() {
+        // Berlin skips 02:00-03:00 on 2026-03-29
+        let instant = local_time(&Berlin, date("2026-03-29"), NaiveTime::from_hms_opt(2, 30, 0).unwrap()).unwrap();
+        assert_eq!(instant.hour(), 3);
+        assert_eq!(instant.minute(), 30);
+        assert_eq!(instant.with_timezone(&Utc).to_rfc3339(), "2026-03-29T01:30:00+00:00");
+    }
+
+    #[test]
+    fn times_in_a_repeated_hour_take_the_first_one() {
+        // Berlin goes through 02:00-03:00 twice on 2026-10-25, first in summer time
+        let instant = local_time(&Berlin, date("2026-10-25"), NaiveTime::from_hms_opt(2, 30, 0).unwrap()).unwrap();
+        assert_eq!(instant.with_timezone(&Utc).to_rfc3339(), "2026-10-25T00:30:00+00:00");
+    }
+}
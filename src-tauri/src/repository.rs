@@ -0,0 +1,846 @@
+use crate::database::{
+    ChecklistItem, CompletionPolicy, FocusPhase, FocusSession, HabitCompletion, HabitFrequency, HabitTracker, JournalEntry, JournalEntryType, Milestone, Persona,
+    PolicyEnforcement, Pomodoro, Priority, ProjectTask, SavedFilter, SavedFilterKind, StateCategory, StatusTransition, TaskRecurrence,
+    TaskStatus, TimeEntry, TimeEntrySource, WipLimit, Workflow, WorkflowState, Workstream, WorkstreamStatus,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, Type, ValueRef};
+use rusqlite::{Connection, OptionalExtension, Params, Row};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// Typed reads. Rows are mapped by column name so queries can select extra
+// columns (or `table.*`) without breaking the conversions.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> rusqlite::Result<Self>;
+}
+
+pub fn query_all<T: FromRow, P: Params>(conn: &Connection, sql: &str, params: P) -> Result<Vec<T>, String> {
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let rows = stmt.query_map(params, |row| T::from_row(row))
+        .map_err(|e| format!("SQL query error: {}", e))?;
+    rows.collect::<Result<Vec<T>, _>>()
+        .map_err(|e| format!("SQL collect error: {}", e))
+}
+
+pub fn query_one<T: FromRow, P: Params>(conn: &Connection, sql: &str, params: P) -> Result<Option<T>, String> {
+    conn.query_row(sql, params, |row| T::from_row(row))
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+// Joined views
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkstreamView {
+    #[serde(flatten)]
+    pub workstream: Workstream,
+    pub persona_name: String,
+    pub persona_color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskView {
+    #[serde(flatten)]
+    pub task: ProjectTask,
+    pub workstream_name: String,
+    pub persona_id: String,
+    pub persona_name: String,
+    pub persona_color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HabitView {
+    #[serde(flatten)]
+    pub habit: HabitTracker,
+    pub workstream_name: String,
+    pub date: String,
+    pub completed: bool,
+    pub quantity_completed: Option<u32>,
+}
+
+// A time entry with its task and where that lives. elapsed_seconds is the
+// duration so far for a running timer; orphaned marks a running timer whose
+// heartbeat stopped, e.g. after a crash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntryView {
+    #[serde(flatten)]
+    pub entry: TimeEntry,
+    pub task_title: String,
+    pub workstream_id: String,
+    pub workstream_name: String,
+    pub persona_id: String,
+    pub persona_name: String,
+    pub elapsed_seconds: i64,
+    pub orphaned: bool,
+}
+
+// A running timer not seen by the app for this long is reported as orphaned.
+pub const ORPHAN_AFTER_SECONDS: i64 = 300;
+
+pub const WORKSTREAM_VIEW_SELECT: &str = "
+    SELECT w.*, p.name AS persona_name, p.color AS persona_color
+    FROM workstreams w
+    JOIN personas p ON w.persona_id = p.id";
+
+pub const TASK_VIEW_SELECT: &str = "
+    SELECT pt.*, w.name AS workstream_name, w.persona_id AS persona_id, p.name AS persona_name, p.color AS persona_color
+    FROM project_tasks pt
+    JOIN workstreams w ON pt.workstream_id = w.id
+    JOIN personas p ON w.persona_id = p.id";
+
+pub const TIME_ENTRY_VIEW_SELECT: &str = "
+    SELECT te.*, pt.title AS task_title, pt.workstream_id AS workstream_id, w.name AS workstream_name,
+           w.persona_id AS persona_id, p.name AS persona_name
+    FROM time_entries te
+    JOIN project_tasks pt ON te.task_id = pt.id
+    JOIN workstreams w ON pt.workstream_id = w.id
+    JOIN personas p ON w.persona_id = p.id";
+
+// Lookups
+pub fn get_persona(conn: &Connection, id: &str) -> Result<Option<Persona>, String> {
+    query_one(conn, "SELECT * FROM personas WHERE id = ?1", [id])
+}
+
+pub fn get_workstream(conn: &Connection, id: &str) -> Result<Option<Workstream>, String> {
+    query_one(conn, "SELECT * FROM workstreams WHERE id = ?1", [id])
+}
+
+pub fn get_task(conn: &Connection, id: &str) -> Result<Option<ProjectTask>, String> {
+    query_one(conn, "SELECT * FROM project_tasks WHERE id = ?1", [id])
+}
+
+pub fn get_task_view(conn: &Connection, id: &str) -> Result<Option<TaskView>, String> {
+    query_one(conn, &format!("{} WHERE pt.id = ?1", TASK_VIEW_SELECT), [id])
+}
+
+pub fn get_habit(conn: &Connection, id: &str) -> Result<Option<HabitTracker>, String> {
+    query_one(conn, "SELECT * FROM habit_trackers WHERE id = ?1", [id])
+}
+
+pub fn get_journal_entry(conn: &Connection, id: &str) -> Result<Option<JournalEntry>, String> {
+    query_one(conn, "SELECT * FROM journal_entries WHERE id = ?1", [id])
+}
+
+pub fn get_saved_filter(conn: &Connection, id: &str) -> Result<Option<SavedFilter>, String> {
+    query_one(conn, "SELECT * FROM saved_filters WHERE id = ?1", [id])
+}
+
+// Tolerant value parsing. Older rows hold enums as JSON strings ("\"InProgress\"")
+// while status updates write bare lowercase words ("inprogress").
+fn normalize(value: &str) -> String {
+    value.trim().trim_matches('"').to_lowercase().replace([' ', '_', '-'], "")
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "backlog" => Ok(TaskStatus::Backlog),
+            "todo" => Ok(TaskStatus::ToDo),
+            "inprogress" => Ok(TaskStatus::InProgress),
+            "review" => Ok(TaskStatus::Review),
+            "done" => Ok(TaskStatus::Done),
+            _ => Err(format!("Invalid status: {}. Valid statuses: backlog, todo, inprogress, review, done", value)),
+        }
+    }
+}
+
+impl TaskStatus {
+    // The lowercase key used by the kanban columns and status updates.
+    pub fn key(&self) -> &'static str {
+        match self {
+            TaskStatus::Backlog => "backlog",
+            TaskStatus::ToDo => "todo",
+            TaskStatus::InProgress => "inprogress",
+            TaskStatus::Review => "review",
+            TaskStatus::Done => "done",
+        }
+    }
+
+    pub fn category(&self) -> StateCategory {
+        match self {
+            TaskStatus::Backlog | TaskStatus::ToDo => StateCategory::NotStarted,
+            TaskStatus::InProgress | TaskStatus::Review => StateCategory::Active,
+            TaskStatus::Done => StateCategory::Done,
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            "critical" => Ok(Priority::Critical),
+            _ => Err(format!("Invalid priority: {}. Valid priorities: low, medium, high, critical", value)),
+        }
+    }
+}
+
+impl Priority {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for WorkstreamStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "planning" => Ok(WorkstreamStatus::Planning),
+            "active" => Ok(WorkstreamStatus::Active),
+            "paused" => Ok(WorkstreamStatus::Paused),
+            "completed" => Ok(WorkstreamStatus::Completed),
+            "cancelled" => Ok(WorkstreamStatus::Cancelled),
+            _ => Err(format!("Invalid status: {}. Valid statuses: planning, active, paused, completed, cancelled", value)),
+        }
+    }
+}
+
+impl WorkstreamStatus {
+    pub fn key(&self) -> &'static str {
+        match self {
+            WorkstreamStatus::Planning => "planning",
+            WorkstreamStatus::Active => "active",
+            WorkstreamStatus::Paused => "paused",
+            WorkstreamStatus::Completed => "completed",
+            WorkstreamStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for JournalEntryType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "note" => Ok(JournalEntryType::Note),
+            "update" => Ok(JournalEntryType::Update),
+            "reflection" => Ok(JournalEntryType::Reflection),
+            "meeting" => Ok(JournalEntryType::Meeting),
+            _ => Err(format!("Invalid entry type: {}. Valid types: note, update, reflection, meeting", value)),
+        }
+    }
+}
+
+impl JournalEntryType {
+    pub fn key(&self) -> &'static str {
+        match self {
+            JournalEntryType::Note => "note",
+            JournalEntryType::Update => "update",
+            JournalEntryType::Reflection => "reflection",
+            JournalEntryType::Meeting => "meeting",
+        }
+    }
+}
+
+impl FromStr for SavedFilterKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "tasks" | "task" => Ok(SavedFilterKind::Tasks),
+            "journal" => Ok(SavedFilterKind::Journal),
+            _ => Err(format!("Invalid filter kind: {}. Valid kinds: tasks, journal", value)),
+        }
+    }
+}
+
+impl SavedFilterKind {
+    pub fn key(&self) -> &'static str {
+        match self {
+            SavedFilterKind::Tasks => "tasks",
+            SavedFilterKind::Journal => "journal",
+        }
+    }
+}
+
+impl FromStr for PolicyEnforcement {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "warn" => Ok(PolicyEnforcement::Warn),
+            "block" => Ok(PolicyEnforcement::Block),
+            _ => Err(format!("Invalid enforcement: {}. Valid values: warn, block", value)),
+        }
+    }
+}
+
+impl PolicyEnforcement {
+    pub fn key(&self) -> &'static str {
+        match self {
+            PolicyEnforcement::Warn => "warn",
+            PolicyEnforcement::Block => "block",
+        }
+    }
+}
+
+impl FromStr for TimeEntrySource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "timer" => Ok(TimeEntrySource::Timer),
+            "manual" => Ok(TimeEntrySource::Manual),
+            "pomodoro" => Ok(TimeEntrySource::Pomodoro),
+            _ => Err(format!("Invalid time entry source: {}. Valid sources: timer, manual, pomodoro", value)),
+        }
+    }
+}
+
+impl TimeEntrySource {
+    pub fn key(&self) -> &'static str {
+        match self {
+            TimeEntrySource::Timer => "timer",
+            TimeEntrySource::Manual => "manual",
+            TimeEntrySource::Pomodoro => "pomodoro",
+        }
+    }
+}
+
+impl FromStr for FocusPhase {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "work" => Ok(FocusPhase::Work),
+            "shortbreak" => Ok(FocusPhase::ShortBreak),
+            "longbreak" => Ok(FocusPhase::LongBreak),
+            _ => Err(format!("Invalid focus phase: {}. Valid phases: work, short_break, long_break", value)),
+        }
+    }
+}
+
+impl FocusPhase {
+    pub fn key(&self) -> &'static str {
+        match self {
+            FocusPhase::Work => "work",
+            FocusPhase::ShortBreak => "short_break",
+            FocusPhase::LongBreak => "long_break",
+        }
+    }
+}
+
+impl TimeEntry {
+    // Seconds worked, leaving out pauses; a running timer counts up to `now`.
+    pub fn elapsed_seconds(&self, now: DateTime<Utc>) -> i64 {
+        if let Some(duration) = self.duration_seconds.filter(|_| self.ended_at.is_some()) {
+            return duration;
+        }
+        let end = self.ended_at.or(self.paused_at).unwrap_or(now);
+        ((end - self.started_at).num_seconds() - self.paused_seconds).max(0)
+    }
+
+    // Timers synced from another device have no heartbeat here and are never orphaned.
+    pub fn is_orphaned(&self, now: DateTime<Utc>) -> bool {
+        self.ended_at.is_none()
+            && self.paused_at.is_none()
+            && self.last_seen_at.is_some_and(|seen| (now - seen).num_seconds() > ORPHAN_AFTER_SECONDS)
+    }
+}
+
+impl FromStr for StateCategory {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "notstarted" => Ok(StateCategory::NotStarted),
+            "active" => Ok(StateCategory::Active),
+            "done" => Ok(StateCategory::Done),
+            _ => Err(format!("Invalid category: {}. Valid categories: not_started, active, done", value)),
+        }
+    }
+}
+
+impl StateCategory {
+    pub fn key(&self) -> &'static str {
+        match self {
+            StateCategory::NotStarted => "not_started",
+            StateCategory::Active => "active",
+            StateCategory::Done => "done",
+        }
+    }
+
+    // The built-in status tasks in a state of this category are stored with.
+    pub fn status(&self) -> TaskStatus {
+        match self {
+            StateCategory::NotStarted => TaskStatus::ToDo,
+            StateCategory::Active => TaskStatus::InProgress,
+            StateCategory::Done => TaskStatus::Done,
+        }
+    }
+}
+
+fn from_text<T: FromStr<Err = String>>(value: ValueRef<'_>) -> FromSqlResult<T> {
+    value.as_str()?.parse().map_err(|e: String| FromSqlError::Other(e.into()))
+}
+
+impl FromSql for TaskStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for Priority {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for WorkstreamStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for JournalEntryType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for SavedFilterKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for PolicyEnforcement {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for TimeEntrySource {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for FocusPhase {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+impl FromSql for StateCategory {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_text(value)
+    }
+}
+
+// Timestamps are mostly RFC 3339, but some rows were written with chrono's
+// Display format ("2024-01-01 10:00:00.123 UTC") and date columns may be bare days.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Ok(parsed) = NaiveDateTime::parse_from_str(value.trim_end_matches(" UTC"), "%Y-%m-%d %H:%M:%S%.f") {
+        return Some(parsed.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+fn conversion_error(row: &Row, column: &str, message: String) -> rusqlite::Error {
+    let index = row.as_ref().column_index(column).unwrap_or(0);
+    rusqlite::Error::FromSqlConversionFailure(index, Type::Text, message.into())
+}
+
+fn timestamp(row: &Row, column: &str) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(column)?;
+    parse_timestamp(&value)
+        .ok_or_else(|| conversion_error(row, column, format!("Invalid timestamp in {}: {}", column, value)))
+}
+
+fn optional_timestamp(row: &Row, column: &str) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    match row.get::<_, Option<String>>(column)? {
+        Some(value) if !value.trim().is_empty() => parse_timestamp(&value)
+            .map(Some)
+            .ok_or_else(|| conversion_error(row, column, format!("Invalid timestamp in {}: {}", column, value))),
+        _ => Ok(None),
+    }
+}
+
+// JSON array columns; anything unreadable comes back as an empty list.
+fn string_list(row: &Row, column: &str) -> rusqlite::Result<Vec<String>> {
+    Ok(row.get::<_, Option<String>>(column)?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+impl FromRow for Persona {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Persona {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            color: row.get("color")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+            is_active: row.get("is_active")?,
+        })
+    }
+}
+
+impl FromRow for Workstream {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Workstream {
+            id: row.get("id")?,
+            persona_id: row.get("persona_id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            status: row.get("status")?,
+            priority: row.get("priority")?,
+            start_date: optional_timestamp(row, "start_date")?,
+            target_date: optional_timestamp(row, "target_date")?,
+            completed_date: optional_timestamp(row, "completed_date")?,
+            progress_percentage: row.get::<_, i64>("progress_percentage")?.clamp(0, 100) as u8,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+fn task_recurrence(row: &Row) -> rusqlite::Result<Option<TaskRecurrence>> {
+    let Some(rule) = row.get::<_, Option<String>>("recurrence_rule")? else {
+        return Ok(None);
+    };
+    let start = match row.get::<_, Option<String>>("recurrence_start")? {
+        Some(start) => NaiveDate::parse_from_str(&start, "%Y-%m-%d")
+            .map_err(|_| conversion_error(row, "recurrence_start", format!("Invalid date in recurrence_start: {}", start)))?,
+        None => timestamp(row, "created_at")?.date_naive(),
+    };
+    Ok(Some(TaskRecurrence {
+        rule,
+        from_completion: row.get("recurrence_from_completion")?,
+        start,
+        series_id: match row.get::<_, Option<String>>("recurrence_series")? {
+            Some(series) => series,
+            None => row.get("id")?,
+        },
+        index: row.get::<_, Option<i64>>("recurrence_index")?.unwrap_or(1),
+    }))
+}
+
+impl FromRow for ProjectTask {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(ProjectTask {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            title: row.get("title")?,
+            description: row.get("description")?,
+            status: row.get("status")?,
+            priority: row.get("priority")?,
+            due_date: optional_timestamp(row, "due_date")?,
+            completed_date: optional_timestamp(row, "completed_date")?,
+            estimated_hours: row.get::<_, Option<f64>>("estimated_hours")?.map(|h| h as f32),
+            actual_hours: row.get::<_, Option<f64>>("actual_hours")?.map(|h| h as f32),
+            tags: string_list(row, "tags")?,
+            dependencies: string_list(row, "dependencies")?,
+            rank: row.get("rank")?,
+            workflow_state: row.get("workflow_state")?,
+            parent_id: row.get("parent_id")?,
+            subtask_rank: row.get("subtask_rank")?,
+            milestone_id: row.get("milestone_id")?,
+            archived_at: optional_timestamp(row, "archived_at")?,
+            recurrence: task_recurrence(row)?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for HabitTracker {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let frequency: String = row.get("target_frequency")?;
+        let target_frequency = serde_json::from_str(&frequency)
+            .unwrap_or(HabitFrequency::Custom { pattern: frequency });
+
+        Ok(HabitTracker {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            target_frequency,
+            target_quantity: row.get("target_quantity")?,
+            unit: row.get("unit")?,
+            is_active: row.get("is_active")?,
+            color: row.get("color")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for HabitCompletion {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(HabitCompletion {
+            id: row.get("id")?,
+            habit_tracker_id: row.get("habit_tracker_id")?,
+            date: row.get("date")?,
+            completed: row.get("completed")?,
+            quantity_completed: row.get("quantity_completed")?,
+            notes: row.get("notes")?,
+            completed_at: optional_timestamp(row, "completed_at")?,
+        })
+    }
+}
+
+impl FromRow for JournalEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(JournalEntry {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            persona_id: row.get("persona_id")?,
+            title: row.get("title")?,
+            content: row.get("content")?,
+            entry_type: row.get("entry_type")?,
+            tags: string_list(row, "tags")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for WorkstreamView {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(WorkstreamView {
+            workstream: Workstream::from_row(row)?,
+            persona_name: row.get("persona_name")?,
+            persona_color: row.get("persona_color")?,
+        })
+    }
+}
+
+impl FromRow for TaskView {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(TaskView {
+            task: ProjectTask::from_row(row)?,
+            workstream_name: row.get("workstream_name")?,
+            persona_id: row.get("persona_id")?,
+            persona_name: row.get("persona_name")?,
+            persona_color: row.get("persona_color")?,
+        })
+    }
+}
+
+impl FromRow for TimeEntryView {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let entry = TimeEntry::from_row(row)?;
+        let now = Utc::now();
+        Ok(TimeEntryView {
+            elapsed_seconds: entry.elapsed_seconds(now),
+            orphaned: entry.is_orphaned(now),
+            entry,
+            task_title: row.get("task_title")?,
+            workstream_id: row.get("workstream_id")?,
+            workstream_name: row.get("workstream_name")?,
+            persona_id: row.get("persona_id")?,
+            persona_name: row.get("persona_name")?,
+        })
+    }
+}
+
+impl FromRow for HabitView {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(HabitView {
+            habit: HabitTracker::from_row(row)?,
+            workstream_name: row.get("workstream_name")?,
+            date: row.get("completion_date")?,
+            completed: row.get::<_, Option<bool>>("completed")?.unwrap_or(false),
+            quantity_completed: row.get("quantity_completed")?,
+        })
+    }
+}
+
+impl FromRow for SavedFilter {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let query: String = row.get("query")?;
+        Ok(SavedFilter {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            kind: row.get("kind")?,
+            query: serde_json::from_str(&query).unwrap_or_default(),
+            pinned: row.get("pinned")?,
+            position: row.get("position")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for WipLimit {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(WipLimit {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            status: row.get("status")?,
+            max_tasks: row.get("max_tasks")?,
+            enforcement: row.get("enforcement")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for StatusTransition {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(StatusTransition {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            from_status: row.get("from_status")?,
+            to_status: row.get("to_status")?,
+            allowed: row.get("allowed")?,
+            enforcement: row.get("enforcement")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+// The states are loaded separately, see commands::load_workflow.
+impl FromRow for Workflow {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Workflow {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            persona_id: row.get("persona_id")?,
+            workstream_id: row.get("workstream_id")?,
+            states: Vec::new(),
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for WorkflowState {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(WorkflowState {
+            id: row.get("id")?,
+            workflow_id: row.get("workflow_id")?,
+            key: row.get("key")?,
+            name: row.get("name")?,
+            category: row.get("category")?,
+            position: row.get("position")?,
+        })
+    }
+}
+
+impl FromRow for Milestone {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Milestone {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            due_date: timestamp(row, "due_date")?,
+            completed_date: optional_timestamp(row, "completed_date")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for ChecklistItem {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(ChecklistItem {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            text: row.get("text")?,
+            done: row.get("done")?,
+            rank: row.get("rank")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for CompletionPolicy {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(CompletionPolicy {
+            id: row.get("id")?,
+            workstream_id: row.get("workstream_id")?,
+            require_subtasks_done: row.get("require_subtasks_done")?,
+            require_checklist_done: row.get("require_checklist_done")?,
+            enforcement: row.get("enforcement")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for TimeEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(TimeEntry {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            source: row.get("source")?,
+            started_at: timestamp(row, "started_at")?,
+            ended_at: optional_timestamp(row, "ended_at")?,
+            paused_at: optional_timestamp(row, "paused_at")?,
+            paused_seconds: row.get("paused_seconds")?,
+            duration_seconds: row.get("duration_seconds")?,
+            last_seen_at: optional_timestamp(row, "last_seen_at")?,
+            note: row.get("note")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for FocusSession {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(FocusSession {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            phase: row.get("phase")?,
+            cycle: row.get("cycle")?,
+            work_seconds: row.get("work_seconds")?,
+            short_break_seconds: row.get("short_break_seconds")?,
+            long_break_seconds: row.get("long_break_seconds")?,
+            long_break_every: row.get("long_break_every")?,
+            auto_start_breaks: row.get("auto_start_breaks")?,
+            auto_start_work: row.get("auto_start_work")?,
+            phase_started_at: optional_timestamp(row, "phase_started_at")?,
+            phase_ends_at: optional_timestamp(row, "phase_ends_at")?,
+            remaining_seconds: row.get("remaining_seconds")?,
+            completed_pomodoros: row.get("completed_pomodoros")?,
+            started_at: timestamp(row, "started_at")?,
+            ended_at: optional_timestamp(row, "ended_at")?,
+            created_at: timestamp(row, "created_at")?,
+            updated_at: timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+impl FromRow for Pomodoro {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Pomodoro {
+            id: row.get("id")?,
+            session_id: row.get("session_id")?,
+            task_id: row.get("task_id")?,
+            started_at: timestamp(row, "started_at")?,
+            ended_at: timestamp(row, "ended_at")?,
+            focus_seconds: row.get("focus_seconds")?,
+            completed: row.get("completed")?,
+            time_entry_id: row.get("time_entry_id")?,
+            created_at: timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,1036 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::database::{generate_id, get_current_timestamp};
+
+// Multi-device sync through a shared folder (Syncthing, Dropbox, a USB stick...)
+// or any other ChangeStore, such as the WebDAV backend in webdav.rs.
+//
+// Every mutation of a synced table is captured by SQLite triggers into
+// sync_outbox. A sync run turns the outbox into change operations stamped with
+// a hybrid logical clock (HLC) taken from when each change was captured, and
+// publishes them as this device's change log. It then reads the other devices'
+// logs from where it stopped last time and merges them field by field: the
+// higher HLC wins. Concurrent edits of the same field
+// and edits racing a delete are recorded in sync_conflicts so a human can pick
+// the right value afterwards.
+
+// Tables and columns that take part in sync. `id` is implied for every table.
+pub(crate) const SYNCED_TABLES: &[(&str, &[&str])] = &[
+    ("personas", &["name", "description", "color", "created_at", "updated_at", "is_active"]),
+    ("workstreams", &["persona_id", "name", "description", "status", "priority", "start_date", "target_date", "completed_date", "progress_percentage", "created_at", "updated_at"]),
+    ("project_tasks", &["workstream_id", "title", "description", "status", "priority", "due_date", "completed_date", "estimated_hours", "actual_hours", "tags", "dependencies", "rank", "workflow_state", "parent_id", "subtask_rank", "recurrence_rule", "recurrence_from_completion", "recurrence_start", "recurrence_series", "recurrence_index", "milestone_id", "archived_at", "created_at", "updated_at"]),
+    ("habit_trackers", &["workstream_id", "name", "description", "target_frequency", "target_quantity", "unit", "is_active", "color", "created_at", "updated_at"]),
+    ("habit_completions", &["habit_tracker_id", "date", "completed", "quantity_completed", "notes", "completed_at"]),
+    ("journal_entries", &["workstream_id", "persona_id", "title", "content", "entry_type", "tags", "created_at", "updated_at"]),
+    ("saved_filters", &["name", "kind", "query", "pinned", "position", "created_at", "updated_at"]),
+    ("wip_limits", &["workstream_id", "status", "max_tasks", "enforcement", "created_at", "updated_at"]),
+    ("status_transitions", &["workstream_id", "from_status", "to_status", "allowed", "enforcement", "created_at", "updated_at"]),
+    ("workflows", &["name", "persona_id", "workstream_id", "created_at", "updated_at"]),
+    ("workflow_states", &["workflow_id", "key", "name", "category", "position"]),
+    ("task_checklist_items", &["task_id", "text", "done", "rank", "created_at", "updated_at"]),
+    ("completion_policies", &["workstream_id", "require_subtasks_done", "require_checklist_done", "enforcement", "created_at", "updated_at"]),
+    // last_seen_at is this device's heartbeat and stays local
+    ("focus_sessions", &["task_id", "phase", "cycle", "work_seconds", "short_break_seconds", "long_break_seconds", "long_break_every", "auto_start_breaks", "auto_start_work", "phase_started_at", "phase_ends_at", "remaining_seconds", "completed_pomodoros", "started_at", "ended_at", "created_at", "updated_at"]),
+    ("pomodoros", &["session_id", "task_id", "started_at", "ended_at", "focus_seconds", "completed", "time_entry_id", "created_at"]),
+    ("time_entries", &["task_id", "source", "started_at", "ended_at", "paused_at", "paused_seconds", "duration_seconds", "note", "created_at", "updated_at"]),
+    ("milestones", &["workstream_id", "name", "description", "due_date", "completed_date", "created_at", "updated_at"]),
+    ("task_status_history", &["task_id", "from_status", "to_status", "changed_at"]),
+];
+
+// Bookkeeping columns: still merged last-writer-wins, but never worth a conflict.
+const UNCONTESTED_FIELDS: &[&str] = &["created_at", "updated_at"];
+
+// Pseudo field holding a row's tombstone clock, and the field name used for row-level conflicts.
+const DELETED_FIELD: &str = "_deleted";
+const ROW_FIELD: &str = "_row";
+
+const LOG_SUFFIX: &str = ".changes.jsonl";
+
+// Hybrid logical clock: wall time in milliseconds, a logical counter for events
+// within the same millisecond, and the device ID as a deterministic tie-breaker.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hlc {
+    pub wall_ms: i64,
+    pub counter: u32,
+    pub device: String,
+}
+
+impl Hlc {
+    pub fn parse(value: &str) -> Option<Hlc> {
+        let mut parts = value.splitn(3, ':');
+        let wall_ms = parts.next()?.parse().ok()?;
+        let counter = parts.next()?.parse().ok()?;
+        let device = parts.next()?.to_string();
+        Some(Hlc { wall_ms, counter, device })
+    }
+
+    // Next clock for a local event.
+    fn tick(&self, now_ms: i64) -> Hlc {
+        if now_ms > self.wall_ms {
+            Hlc { wall_ms: now_ms, counter: 0, device: self.device.clone() }
+        } else {
+            Hlc { wall_ms: self.wall_ms, counter: self.counter + 1, device: self.device.clone() }
+        }
+    }
+
+    // Clock after receiving a remote event, so later local events sort after it.
+    fn receive(&self, remote: &Hlc, now_ms: i64) -> Hlc {
+        let wall_ms = now_ms.max(self.wall_ms).max(remote.wall_ms);
+        let counter = if wall_ms == self.wall_ms && wall_ms == remote.wall_ms {
+            self.counter.max(remote.counter) + 1
+        } else if wall_ms == self.wall_ms {
+            self.counter + 1
+        } else if wall_ms == remote.wall_ms {
+            remote.counter + 1
+        } else {
+            0
+        };
+        Hlc { wall_ms, counter, device: self.device.clone() }
+    }
+}
+
+impl fmt::Display for Hlc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:013}:{:05}:{}", self.wall_ms, self.counter, self.device)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Upsert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub value: Value,
+    // Clock of the value this edit replaced, used to spot concurrent edits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+}
+
+// One line of a device's change log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeOp {
+    pub hlc: String,
+    pub device: String,
+    pub table: String,
+    pub row_id: String,
+    pub op: ChangeKind,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncConflict {
+    pub id: String,
+    pub table_name: String,
+    pub row_id: String,
+    pub field: String,
+    pub local_value: Value,
+    pub remote_value: Value,
+    pub local_hlc: Option<String>,
+    pub remote_hlc: String,
+    pub remote_device: String,
+    pub winner: String,
+    pub detected_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub device_id: String,
+    pub pushed: usize,
+    pub pulled: usize,
+    pub applied_fields: usize,
+    pub peers: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+    pub errors: Vec<String>,
+}
+
+struct FieldClock {
+    hlc: Hlc,
+    value: Value,
+}
+
+// Create the capture triggers. They are rebuilt on every start so that column
+// changes in SYNCED_TABLES are picked up.
+pub fn install_triggers(conn: &Connection) -> Result<(), String> {
+    let gate = "EXISTS (SELECT 1 FROM sync_state WHERE key = 'capture' AND value = '1') \
+                AND NOT EXISTS (SELECT 1 FROM sync_state WHERE key = 'applying' AND value = '1')";
+
+    let mut sql = String::new();
+    for (table, columns) in SYNCED_TABLES {
+        let row_json = row_json_expr("NEW", columns);
+        for (suffix, event) in [("insert", "INSERT"), ("update", "UPDATE")] {
+            sql.push_str(&format!(
+                "DROP TRIGGER IF EXISTS sync_{table}_{suffix};
+                 CREATE TRIGGER sync_{table}_{suffix} AFTER {event} ON {table} WHEN {gate}
+                 BEGIN
+                     INSERT INTO sync_outbox (table_name, row_id, op, row_json, changed_at)
+                     VALUES ('{table}', NEW.id, 'upsert', {row_json}, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+                 END;\n"
+            ));
+        }
+        sql.push_str(&format!(
+            "DROP TRIGGER IF EXISTS sync_{table}_delete;
+             CREATE TRIGGER sync_{table}_delete AFTER DELETE ON {table} WHEN {gate}
+             BEGIN
+                 INSERT INTO sync_outbox (table_name, row_id, op, row_json, changed_at)
+                 VALUES ('{table}', OLD.id, 'delete', NULL, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+             END;\n"
+        ));
+    }
+
+    conn.execute_batch(&sql).map_err(|e| format!("Sync trigger error: {}", e))
+}
+
+fn row_json_expr(alias: &str, columns: &[&str]) -> String {
+    let pairs: Vec<String> = columns
+        .iter()
+        .map(|c| format!("'{c}', {alias}.{c}"))
+        .collect();
+    format!("json_object({})", pairs.join(", "))
+}
+
+pub fn get_state(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM sync_state WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("SQL query error: {}", e))
+}
+
+pub fn set_state(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO sync_state (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+// This database's device ID, created on first use.
+pub fn device_id(conn: &Connection) -> Result<String, String> {
+    if let Some(id) = get_state(conn, "device_id")? {
+        return Ok(id);
+    }
+    let id = generate_id();
+    set_state(conn, "device_id", &id)?;
+    Ok(id)
+}
+
+// Turn on change capture. The first time, every existing row is queued so the
+// other devices receive a full copy of this database.
+pub fn enable_capture(conn: &Connection) -> Result<(), String> {
+    if get_state(conn, "capture")?.as_deref() == Some("1") {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+    for (table, columns) in SYNCED_TABLES {
+        tx.execute(
+            &format!(
+                "INSERT INTO sync_outbox (table_name, row_id, op, row_json, changed_at)
+                 SELECT '{table}', id, 'upsert', {}, strftime('%Y-%m-%dT%H:%M:%fZ', 'now') FROM {table}",
+                row_json_expr(table, columns)
+            ),
+            [],
+        ).map_err(|e| format!("SQL insert error: {}", e))?;
+    }
+    set_state(&tx, "capture", "1")?;
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))
+}
+
+// Where change logs are exchanged. Each device only ever appends to its own
+// log; a cursor string, opaque to the engine, remembers how far a peer's log
+// has been read.
+pub trait ChangeStore {
+    // Persist a batch of this device's operations (one JSON object per line).
+    // Called inside the transaction that consumes the outbox, so stores may
+    // stage work in the database.
+    fn publish(&mut self, conn: &Connection, device: &str, lines: &str) -> Result<(), String>;
+
+    // Finish work left over by publish or by an interrupted earlier run.
+    fn flush(&mut self, _conn: &Connection, _device: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Devices other than `own` that have a log in the store.
+    fn peers(&mut self, own: &str) -> Result<Vec<String>, String>;
+
+    // Complete lines of `peer`'s log after `cursor`, and the cursor to save once they are merged.
+    fn fetch(&mut self, peer: &str, cursor: Option<&str>) -> Result<(Vec<u8>, String), String>;
+
+    // sync_state key holding the cursor for `peer`.
+    fn cursor_key(&self, peer: &str) -> String {
+        format!("cursor:{}", peer)
+    }
+}
+
+// Change logs as append-only files in a shared folder: <folder>/<device>.changes.jsonl
+pub struct FolderStore {
+    folder: PathBuf,
+}
+
+impl FolderStore {
+    pub fn new(folder: &Path) -> Result<Self, String> {
+        fs::create_dir_all(folder).map_err(|e| format!("Sync folder error: {}", e))?;
+        Ok(FolderStore { folder: folder.to_path_buf() })
+    }
+
+    fn log_path(&self, device: &str) -> PathBuf {
+        self.folder.join(format!("{}{}", device, LOG_SUFFIX))
+    }
+}
+
+impl ChangeStore for FolderStore {
+    fn publish(&mut self, _conn: &Connection, device: &str, lines: &str) -> Result<(), String> {
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(device))
+            .map_err(|e| format!("Sync log error: {}", e))?;
+        log.write_all(lines.as_bytes()).map_err(|e| format!("Sync log error: {}", e))?;
+        log.sync_all().map_err(|e| format!("Sync log error: {}", e))
+    }
+
+    fn peers(&mut self, own: &str) -> Result<Vec<String>, String> {
+        let listing = fs::read_dir(&self.folder).map_err(|e| format!("Sync folder error: {}", e))?;
+        let mut peers = Vec::new();
+        for item in listing {
+            let name = item.map_err(|e| format!("Sync folder error: {}", e))?.file_name();
+            let name = name.to_string_lossy();
+            if let Some(device) = name.strip_suffix(LOG_SUFFIX) {
+                if device != own {
+                    peers.push(device.to_string());
+                }
+            }
+        }
+        peers.sort();
+        Ok(peers)
+    }
+
+    fn fetch(&mut self, peer: &str, cursor: Option<&str>) -> Result<(Vec<u8>, String), String> {
+        let offset: u64 = cursor.and_then(|c| c.parse().ok()).unwrap_or(0);
+
+        let mut file = fs::File::open(self.log_path(peer)).map_err(|e| format!("Sync log error: {}", e))?;
+        file.seek(SeekFrom::Start(offset)).map_err(|e| format!("Sync log error: {}", e))?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).map_err(|e| format!("Sync log error: {}", e))?;
+
+        // A file still being copied by the sync tool may end mid-line; leave that part for next time.
+        let complete = complete_lines(&buffer);
+        buffer.truncate(complete);
+        Ok((buffer, (offset + complete as u64).to_string()))
+    }
+}
+
+// Length of the prefix of `buffer` that ends with a full line.
+pub(crate) fn complete_lines(buffer: &[u8]) -> usize {
+    buffer.iter().rposition(|b| *b == b'\n').map(|i| i + 1).unwrap_or(0)
+}
+
+// The sync engine for one database and one change store.
+pub struct SyncEngine<'a, S: ChangeStore> {
+    conn: &'a Connection,
+    store: S,
+    device_id: String,
+    clock: Hlc,
+}
+
+impl<'a, S: ChangeStore> SyncEngine<'a, S> {
+    pub fn open(conn: &'a Connection, store: S) -> Result<Self, String> {
+        enable_capture(conn)?;
+
+        let device_id = device_id(conn)?;
+        let clock = get_state(conn, "hlc")?
+            .and_then(|c| Hlc::parse(&c))
+            .unwrap_or(Hlc { wall_ms: 0, counter: 0, device: device_id.clone() });
+
+        Ok(SyncEngine {
+            conn,
+            store,
+            device_id,
+            clock,
+        })
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    // Push local changes, then pull and merge everyone else's.
+    pub fn sync(&mut self) -> Result<SyncReport, String> {
+        let mut report = SyncReport {
+            device_id: self.device_id.clone(),
+            ..Default::default()
+        };
+
+        report.pushed = self.push()?;
+        self.store.flush(self.conn, &self.device_id)?;
+        self.pull(&mut report)?;
+
+        Ok(report)
+    }
+
+    // Convert the outbox into change operations and publish them as our log.
+    fn push(&mut self) -> Result<usize, String> {
+        let tx = self.conn.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+        let pending: Vec<(i64, String, String, String, Option<String>, String)> = {
+            let mut stmt = tx.prepare("SELECT seq, table_name, row_id, op, row_json, changed_at FROM sync_outbox ORDER BY seq")
+                .map_err(|e| format!("SQL prepare error: {}", e))?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)))
+                .map_err(|e| format!("SQL query error: {}", e))?;
+            let pending: Result<Vec<_>, rusqlite::Error> = rows.collect();
+            pending.map_err(|e| format!("SQL collect error: {}", e))?
+        };
+
+        let mut ops = Vec::new();
+        for (_, table, row_id, op, row_json, changed_at) in &pending {
+            if op == "delete" {
+                let hlc = self.clock_at(changed_at);
+                // Forget the field clocks so a later re-insert sends every column again.
+                tx.execute(
+                    "DELETE FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2",
+                    [table, row_id],
+                ).map_err(|e| format!("SQL delete error: {}", e))?;
+                set_clock(&tx, table, row_id, DELETED_FIELD, &hlc, &Value::Bool(true))?;
+                ops.push(ChangeOp {
+                    hlc: hlc.to_string(),
+                    device: self.device_id.clone(),
+                    table: table.clone(),
+                    row_id: row_id.clone(),
+                    op: ChangeKind::Delete,
+                    fields: BTreeMap::new(),
+                });
+                continue;
+            }
+
+            let row: BTreeMap<String, Value> = row_json
+                .as_deref()
+                .and_then(|r| serde_json::from_str(r).ok())
+                .unwrap_or_default();
+
+            // Only fields whose value differs from the last known one are sent.
+            let mut fields = BTreeMap::new();
+            for (field, value) in row {
+                let clock = get_clock(&tx, table, row_id, &field)?;
+                if clock.as_ref().map(|c| c.value != value).unwrap_or(true) {
+                    fields.insert(field, FieldChange {
+                        value,
+                        base: clock.map(|c| c.hlc.to_string()),
+                    });
+                }
+            }
+            if fields.is_empty() {
+                continue;
+            }
+
+            let hlc = self.clock_at(changed_at);
+            for (field, change) in &fields {
+                set_clock(&tx, table, row_id, field, &hlc, &change.value)?;
+            }
+            // A row that comes back after a delete is no longer a tombstone.
+            tx.execute(
+                "DELETE FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2 AND field = ?3",
+                rusqlite::params![table, row_id, DELETED_FIELD],
+            ).map_err(|e| format!("SQL delete error: {}", e))?;
+
+            ops.push(ChangeOp {
+                hlc: hlc.to_string(),
+                device: self.device_id.clone(),
+                table: table.clone(),
+                row_id: row_id.clone(),
+                op: ChangeKind::Upsert,
+                fields,
+            });
+        }
+
+        if let Some((last_seq, ..)) = pending.last() {
+            tx.execute("DELETE FROM sync_outbox WHERE seq <= ?1", [last_seq])
+                .map_err(|e| format!("SQL delete error: {}", e))?;
+        }
+        set_state(&tx, "hlc", &self.clock.to_string())?;
+
+        // Publish before committing: a crash in between only means the same
+        // operations are sent twice, which the merge ignores.
+        if !ops.is_empty() {
+            let mut lines = String::new();
+            for op in &ops {
+                lines.push_str(&serde_json::to_string(op).map_err(|e| format!("Serialization error: {}", e))?);
+                lines.push('\n');
+            }
+            self.store.publish(&tx, &self.device_id, &lines)?;
+        }
+
+        tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+        Ok(ops.len())
+    }
+
+    // Read every other device's log from its saved cursor and merge it.
+    fn pull(&mut self, report: &mut SyncReport) -> Result<(), String> {
+        for peer in self.store.peers(&self.device_id)? {
+            let cursor_key = self.store.cursor_key(&peer);
+            let cursor = get_state(self.conn, &cursor_key)?;
+            let (buffer, next_cursor) = self.store.fetch(&peer, cursor.as_deref())?;
+
+            let ops: Vec<ChangeOp> = String::from_utf8_lossy(&buffer)
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| match serde_json::from_str(line) {
+                    Ok(op) => Some(op),
+                    Err(e) => {
+                        report.errors.push(format!("{}: unreadable change: {}", peer, e));
+                        None
+                    }
+                })
+                .collect();
+
+            self.merge(&ops, report)?;
+            set_state(self.conn, &cursor_key, &next_cursor)?;
+            report.pulled += ops.len();
+            report.peers.push(peer);
+        }
+
+        Ok(())
+    }
+
+    // Apply remote operations in one transaction with capture switched off.
+    pub fn merge(&mut self, ops: &[ChangeOp], report: &mut SyncReport) -> Result<(), String> {
+        if ops.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.conn.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+        set_state(&tx, "applying", "1")?;
+
+        for op in ops {
+            let Some(remote) = Hlc::parse(&op.hlc) else {
+                report.errors.push(format!("{}: invalid clock '{}'", op.device, op.hlc));
+                continue;
+            };
+            let Some(columns) = synced_columns(&op.table) else {
+                report.errors.push(format!("{}: unknown table '{}'", op.device, op.table));
+                continue;
+            };
+            self.clock = self.clock.receive(&remote, now_ms());
+
+            match op.op {
+                ChangeKind::Upsert => self.merge_upsert(&tx, op, &remote, columns, report)?,
+                ChangeKind::Delete => self.merge_delete(&tx, op, &remote, report)?,
+            }
+        }
+
+        set_state(&tx, "applying", "0")?;
+        set_state(&tx, "hlc", &self.clock.to_string())?;
+        tx.commit().map_err(|e| format!("Transaction commit error: {}", e))
+    }
+
+    fn merge_upsert(&self, conn: &Connection, op: &ChangeOp, remote: &Hlc, columns: &[&str], report: &mut SyncReport) -> Result<(), String> {
+        if let Some(tombstone) = get_clock(conn, &op.table, &op.row_id, DELETED_FIELD)? {
+            // A full re-creation made after the delete (no field has a base) brings the row back.
+            let recreated = *remote > tombstone.hlc && op.fields.values().all(|f| f.base.is_none());
+            if !recreated {
+                // An edit of a row deleted here: keep it deleted until a person decides.
+                let remote_row: serde_json::Map<String, Value> = op.fields.iter().map(|(k, v)| (k.clone(), v.value.clone())).collect();
+                report.conflicts.push(record_conflict(conn, op, ROW_FIELD, Value::Null, Value::Object(remote_row), Some(&tombstone.hlc), "local")?);
+                return Ok(());
+            }
+            conn.execute(
+                "DELETE FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2",
+                [&op.table, &op.row_id],
+            ).map_err(|e| format!("SQL delete error: {}", e))?;
+        }
+
+        let mut winners: Vec<(&str, &Value)> = Vec::new();
+        for (field, change) in &op.fields {
+            let Some(column) = columns.iter().find(|c| **c == field.as_str()) else {
+                report.errors.push(format!("{}: unknown column '{}.{}'", op.device, op.table, field));
+                continue;
+            };
+
+            let local = get_clock(conn, &op.table, &op.row_id, field)?;
+            let remote_wins = local.as_ref().map(|l| *remote > l.hlc).unwrap_or(true);
+
+            // Both sides changed the field without seeing each other's edit.
+            if let Some(local) = &local {
+                let concurrent = local.hlc.device != op.device
+                    && change.base.as_deref() != Some(local.hlc.to_string().as_str())
+                    && local.value != change.value
+                    && !UNCONTESTED_FIELDS.contains(column);
+                if concurrent && *remote != local.hlc {
+                    let winner = if remote_wins { "remote" } else { "local" };
+                    report.conflicts.push(record_conflict(conn, op, field, local.value.clone(), change.value.clone(), Some(&local.hlc), winner)?);
+                }
+            }
+
+            if remote_wins {
+                winners.push((column, &change.value));
+                set_clock(conn, &op.table, &op.row_id, field, remote, &change.value)?;
+            }
+        }
+
+        if winners.is_empty() {
+            return Ok(());
+        }
+
+        let exists: bool = conn.query_row(
+            &format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = ?1)", op.table),
+            [&op.row_id],
+            |row| row.get(0),
+        ).map_err(|e| format!("SQL query error: {}", e))?;
+
+        let mut params: Vec<rusqlite::types::Value> = winners.iter().map(|(_, v)| sql_value(v)).collect();
+        params.push(rusqlite::types::Value::Text(op.row_id.clone()));
+
+        let changed = if exists {
+            let assignments: Vec<String> = winners.iter().enumerate().map(|(i, (c, _))| format!("{} = ?{}", c, i + 1)).collect();
+            conn.execute(
+                &format!("UPDATE {} SET {} WHERE id = ?{}", op.table, assignments.join(", "), params.len()),
+                rusqlite::params_from_iter(params.iter()),
+            )
+        } else {
+            let names: Vec<&str> = winners.iter().map(|(c, _)| *c).collect();
+            let placeholders: Vec<String> = (1..params.len()).map(|i| format!("?{}", i)).collect();
+            conn.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO {} ({}, id) VALUES ({}, ?{})",
+                    op.table,
+                    names.join(", "),
+                    placeholders.join(", "),
+                    params.len()
+                ),
+                rusqlite::params_from_iter(params.iter()),
+            )
+        };
+
+        match changed {
+            Ok(0) if !exists => {
+                // Another row already holds the same unique key (e.g. a habit logged twice for one day).
+                let remote_row: serde_json::Map<String, Value> = op.fields.iter().map(|(k, v)| (k.clone(), v.value.clone())).collect();
+                report.conflicts.push(record_conflict(conn, op, ROW_FIELD, Value::Null, Value::Object(remote_row), None, "local")?);
+            }
+            Ok(_) => report.applied_fields += winners.len(),
+            Err(e) => report.errors.push(format!("{} {}: {}", op.table, op.row_id, e)),
+        }
+
+        Ok(())
+    }
+
+    fn merge_delete(&self, conn: &Connection, op: &ChangeOp, remote: &Hlc, report: &mut SyncReport) -> Result<(), String> {
+        // A newer local edit on a row the other device deleted: keep it and ask.
+        let newest_local = newest_field_clock(conn, &op.table, &op.row_id)?;
+        if let Some(local) = newest_local {
+            if local > *remote && local.device != op.device {
+                let local_row = load_row(conn, &op.table, &op.row_id)?;
+                report.conflicts.push(record_conflict(conn, op, ROW_FIELD, local_row, Value::Null, Some(&local), "local")?);
+                return Ok(());
+            }
+        }
+
+        if let Err(e) = conn.execute(&format!("DELETE FROM {} WHERE id = ?1", op.table), [&op.row_id]) {
+            report.errors.push(format!("{} {}: {}", op.table, op.row_id, e));
+            return Ok(());
+        }
+        conn.execute(
+            "DELETE FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2",
+            [&op.table, &op.row_id],
+        ).map_err(|e| format!("SQL delete error: {}", e))?;
+        set_clock(conn, &op.table, &op.row_id, DELETED_FIELD, remote, &Value::Bool(true))?;
+        report.applied_fields += 1;
+
+        Ok(())
+    }
+
+    // Clock for a change captured at `changed_at`, so an edit that waited in the
+    // outbox doesn't beat a later edit made on another device in the meantime.
+    fn clock_at(&mut self, changed_at: &str) -> Hlc {
+        let wall_ms = chrono::DateTime::parse_from_rfc3339(changed_at)
+            .map(|t| t.timestamp_millis())
+            .unwrap_or_else(|_| now_ms());
+        self.clock = self.clock.tick(wall_ms);
+        self.clock.clone()
+    }
+}
+
+fn now_ms() -> i64 {
+    get_current_timestamp().timestamp_millis()
+}
+
+fn synced_columns(table: &str) -> Option<&'static [&'static str]> {
+    SYNCED_TABLES.iter().find(|(t, _)| *t == table).map(|(_, c)| *c)
+}
+
+fn get_clock(conn: &Connection, table: &str, row_id: &str, field: &str) -> Result<Option<FieldClock>, String> {
+    let row: Option<(String, Option<String>)> = conn.query_row(
+        "SELECT hlc, value FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2 AND field = ?3",
+        [table, row_id, field],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional().map_err(|e| format!("SQL query error: {}", e))?;
+
+    Ok(row.and_then(|(hlc, value)| {
+        Some(FieldClock {
+            hlc: Hlc::parse(&hlc)?,
+            value: value.and_then(|v| serde_json::from_str(&v).ok()).unwrap_or(Value::Null),
+        })
+    }))
+}
+
+fn set_clock(conn: &Connection, table: &str, row_id: &str, field: &str, hlc: &Hlc, value: &Value) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO sync_field_clocks (table_name, row_id, field, hlc, value) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(table_name, row_id, field) DO UPDATE SET hlc = excluded.hlc, value = excluded.value",
+        rusqlite::params![table, row_id, field, hlc.to_string(), value.to_string()],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+    Ok(())
+}
+
+fn newest_field_clock(conn: &Connection, table: &str, row_id: &str) -> Result<Option<Hlc>, String> {
+    let mut stmt = conn.prepare("SELECT hlc FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2 AND field != ?3")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+    let rows = stmt.query_map([table, row_id, DELETED_FIELD], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("SQL query error: {}", e))?;
+
+    let mut newest: Option<Hlc> = None;
+    for row in rows {
+        let hlc = row.map_err(|e| format!("SQL collect error: {}", e))?;
+        if let Some(hlc) = Hlc::parse(&hlc) {
+            if newest.as_ref().map(|n| hlc > *n).unwrap_or(true) {
+                newest = Some(hlc);
+            }
+        }
+    }
+    Ok(newest)
+}
+
+fn load_row(conn: &Connection, table: &str, row_id: &str) -> Result<Value, String> {
+    let columns = synced_columns(table).ok_or_else(|| format!("Unknown table '{}'", table))?;
+    let row: Option<String> = conn.query_row(
+        &format!("SELECT {} FROM {} WHERE id = ?1", row_json_expr(table, columns), table),
+        [row_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("SQL query error: {}", e))?;
+
+    Ok(row.and_then(|r| serde_json::from_str(&r).ok()).unwrap_or(Value::Null))
+}
+
+fn sql_value(value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as Sql;
+    match value {
+        Value::Null => Sql::Null,
+        Value::Bool(b) => Sql::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Sql::Integer(i),
+            None => Sql::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => Sql::Text(s.clone()),
+        other => Sql::Text(other.to_string()),
+    }
+}
+
+fn record_conflict(
+    conn: &Connection,
+    op: &ChangeOp,
+    field: &str,
+    local_value: Value,
+    remote_value: Value,
+    local_hlc: Option<&Hlc>,
+    winner: &str,
+) -> Result<SyncConflict, String> {
+    let conflict = SyncConflict {
+        id: generate_id(),
+        table_name: op.table.clone(),
+        row_id: op.row_id.clone(),
+        field: field.to_string(),
+        local_value,
+        remote_value,
+        local_hlc: local_hlc.map(|h| h.to_string()),
+        remote_hlc: op.hlc.clone(),
+        remote_device: op.device.clone(),
+        winner: winner.to_string(),
+        detected_at: get_current_timestamp().to_rfc3339(),
+    };
+
+    conn.execute(
+        "INSERT INTO sync_conflicts (id, table_name, row_id, field, local_value, remote_value, local_hlc, remote_hlc, remote_device, winner, detected_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![
+            conflict.id,
+            conflict.table_name,
+            conflict.row_id,
+            conflict.field,
+            conflict.local_value.to_string(),
+            conflict.remote_value.to_string(),
+            conflict.local_hlc,
+            conflict.remote_hlc,
+            conflict.remote_device,
+            conflict.winner,
+            conflict.detected_at
+        ],
+    ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+    Ok(conflict)
+}
+
+pub fn list_conflicts(conn: &Connection) -> Result<Vec<SyncConflict>, String> {
+    let mut stmt = conn.prepare("SELECT id, table_name, row_id, field, local_value, remote_value, local_hlc, remote_hlc, remote_device, winner, detected_at FROM sync_conflicts WHERE resolved_at IS NULL ORDER BY detected_at")
+        .map_err(|e| format!("SQL prepare error: {}", e))?;
+
+    let rows = stmt.query_map([], |row| {
+        let local: String = row.get(4)?;
+        let remote: String = row.get(5)?;
+        Ok(SyncConflict {
+            id: row.get(0)?,
+            table_name: row.get(1)?,
+            row_id: row.get(2)?,
+            field: row.get(3)?,
+            local_value: serde_json::from_str(&local).unwrap_or(Value::Null),
+            remote_value: serde_json::from_str(&remote).unwrap_or(Value::Null),
+            local_hlc: row.get(6)?,
+            remote_hlc: row.get(7)?,
+            remote_device: row.get(8)?,
+            winner: row.get(9)?,
+            detected_at: row.get(10)?,
+        })
+    }).map_err(|e| format!("SQL query error: {}", e))?;
+
+    let conflicts: Result<Vec<SyncConflict>, rusqlite::Error> = rows.collect();
+    conflicts.map_err(|e| format!("SQL collect error: {}", e))
+}
+
+// Settle a conflict by writing the chosen side as a normal local edit. Capture
+// turns it into a fresh change, so every device converges on the decision at
+// the next sync.
+pub fn resolve_conflict(conn: &Connection, conflict_id: &str, keep: &str) -> Result<SyncConflict, String> {
+    let conflict = list_conflicts(conn)?
+        .into_iter()
+        .find(|c| c.id == conflict_id)
+        .ok_or_else(|| format!("Sync conflict with ID '{}' not found", conflict_id))?;
+
+    let chosen = match keep {
+        "local" => conflict.local_value.clone(),
+        "remote" => conflict.remote_value.clone(),
+        _ => return Err(format!("Invalid choice: {}. Valid choices: local, remote", keep)),
+    };
+    let columns = synced_columns(&conflict.table_name)
+        .ok_or_else(|| format!("Unknown table '{}'", conflict.table_name))?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Transaction start error: {}", e))?;
+
+    if conflict.field == ROW_FIELD {
+        match chosen {
+            Value::Null => {
+                let deleted = tx.execute(&format!("DELETE FROM {} WHERE id = ?1", conflict.table_name), [&conflict.row_id])
+                    .map_err(|e| format!("SQL delete error: {}", e))?;
+                // Already gone here, so no trigger fired: queue the delete for the other devices by hand.
+                if deleted == 0 {
+                    tx.execute(
+                        "INSERT INTO sync_outbox (table_name, row_id, op, row_json, changed_at) VALUES (?1, ?2, 'delete', NULL, ?3)",
+                        rusqlite::params![conflict.table_name, conflict.row_id, get_current_timestamp().to_rfc3339()],
+                    ).map_err(|e| format!("SQL insert error: {}", e))?;
+                }
+            }
+            Value::Object(fields) => {
+                let fields: Vec<(&str, &Value)> = fields
+                    .iter()
+                    .filter_map(|(k, v)| columns.iter().find(|c| **c == k.as_str()).map(|c| (*c, v)))
+                    .collect();
+                let mut params: Vec<rusqlite::types::Value> = fields.iter().map(|(_, v)| sql_value(v)).collect();
+                params.push(rusqlite::types::Value::Text(conflict.row_id.clone()));
+                let names: Vec<&str> = fields.iter().map(|(c, _)| *c).collect();
+                let placeholders: Vec<String> = (1..params.len()).map(|i| format!("?{}", i)).collect();
+                let updates: Vec<String> = names.iter().map(|c| format!("{c} = excluded.{c}")).collect();
+
+                tx.execute(
+                    &format!(
+                        "INSERT INTO {} ({}, id) VALUES ({}, ?{}) ON CONFLICT(id) DO UPDATE SET {}",
+                        conflict.table_name,
+                        names.join(", "),
+                        placeholders.join(", "),
+                        params.len(),
+                        updates.join(", ")
+                    ),
+                    rusqlite::params_from_iter(params.iter()),
+                ).map_err(|e| match e {
+                    rusqlite::Error::SqliteFailure(..) => format!(
+                        "The row was deleted on this device and the conflict only holds the changed fields. Resolve it on the device that still has the row ({})",
+                        e
+                    ),
+                    e => format!("SQL insert error: {}", e),
+                })?;
+
+                // Drop the field clocks so the next push sends the whole row, which
+                // lets devices that applied the delete recreate it.
+                tx.execute(
+                    "DELETE FROM sync_field_clocks WHERE table_name = ?1 AND row_id = ?2",
+                    [&conflict.table_name, &conflict.row_id],
+                ).map_err(|e| format!("SQL delete error: {}", e))?;
+                tx.execute(
+                    &format!(
+                        "INSERT INTO sync_outbox (table_name, row_id, op, row_json, changed_at) SELECT ?1, id, 'upsert', {}, ?2 FROM {} WHERE id = ?3",
+                        row_json_expr(&conflict.table_name, columns),
+                        conflict.table_name
+                    ),
+                    rusqlite::params![conflict.table_name, get_current_timestamp().to_rfc3339(), conflict.row_id],
+                ).map_err(|e| format!("SQL insert error: {}", e))?;
+            }
+            other => return Err(format!("Unexpected row value in conflict: {}", other)),
+        }
+    } else {
+        let column = columns.iter().find(|c| **c == conflict.field.as_str())
+            .ok_or_else(|| format!("Unknown column '{}.{}'", conflict.table_name, conflict.field))?;
+        tx.execute(
+            &format!("UPDATE {} SET {} = ?1 WHERE id = ?2", conflict.table_name, column),
+            rusqlite::params![sql_value(&chosen), conflict.row_id],
+        ).map_err(|e| format!("SQL update error: {}", e))?;
+    }
+
+    tx.execute(
+        "UPDATE sync_conflicts SET resolved_at = ?1, resolution = ?2 WHERE id = ?3",
+        rusqlite::params![get_current_timestamp().to_rfc3339(), keep, conflict_id],
+    ).map_err(|e| format!("SQL update error: {}", e))?;
+
+    tx.commit().map_err(|e| format!("Transaction commit error: {}", e))?;
+    Ok(conflict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    // Two databases on disk syncing through one shared folder.
+    struct Devices {
+        dir: PathBuf,
+        a: Connection,
+        b: Connection,
+    }
+
+    impl Devices {
+        fn new() -> Devices {
+            let dir = std::env::temp_dir().join(format!("sync-test-{}", generate_id()));
+            fs::create_dir_all(dir.join("shared")).unwrap();
+            let open = |name: &str| commands::open_database(dir.join(name).to_str().unwrap()).unwrap();
+            let (a, b) = (open("a.db"), open("b.db"));
+            Devices { dir, a, b }
+        }
+
+        fn sync(&self, conn: &Connection) -> SyncReport {
+            let store = FolderStore::new(&self.dir.join("shared")).unwrap();
+            SyncEngine::open(conn, store).unwrap().sync().unwrap()
+        }
+
+        // A persona created on A and copied to B.
+        fn shared_persona(&self) -> String {
+            let persona = commands::create_persona(&self.a, "Work".into(), Some("Day job".into()), "#3366ff".into()).unwrap();
+            self.sync(&self.a);
+            self.sync(&self.b);
+            persona.id
+        }
+    }
+
+    impl Drop for Devices {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn field(conn: &Connection, id: &str, column: &str) -> Option<String> {
+        conn.query_row(&format!("SELECT {} FROM personas WHERE id = ?1", column), [id], |row| row.get(0))
+            .optional()
+            .unwrap()
+            .flatten()
+    }
+
+    fn pause() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+
+    #[test]
+    fn concurrent_edits_of_a_field_keep_the_later_one() {
+        let devices = Devices::new();
+        let id = devices.shared_persona();
+
+        // B edits first but pushes last; the edit time decides, not the push time.
+        commands::update_persona(&devices.b, id.clone(), Some("Office".into()), None, None, None).unwrap();
+        pause();
+        commands::update_persona(&devices.a, id.clone(), Some("Studio".into()), None, None, None).unwrap();
+        devices.sync(&devices.a);
+        pause();
+        let report = devices.sync(&devices.b);
+        devices.sync(&devices.a);
+
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].field, "name");
+        assert_eq!(report.conflicts[0].winner, "remote");
+        assert_eq!(field(&devices.a, &id, "name").as_deref(), Some("Studio"));
+        assert_eq!(field(&devices.b, &id, "name").as_deref(), Some("Studio"));
+    }
+
+    #[test]
+    fn edits_of_different_fields_both_apply() {
+        let devices = Devices::new();
+        let id = devices.shared_persona();
+
+        commands::update_persona(&devices.a, id.clone(), Some("Studio".into()), None, None, None).unwrap();
+        commands::update_persona(&devices.b, id.clone(), None, None, Some("#ff0000".into()), None).unwrap();
+        devices.sync(&devices.a);
+        let report = devices.sync(&devices.b);
+        let back = devices.sync(&devices.a);
+
+        assert!(report.conflicts.is_empty() && back.conflicts.is_empty());
+        for conn in [&devices.a, &devices.b] {
+            assert_eq!(field(conn, &id, "name").as_deref(), Some("Studio"));
+            assert_eq!(field(conn, &id, "color").as_deref(), Some("#ff0000"));
+        }
+    }
+
+    #[test]
+    fn an_edit_racing_a_delete_is_kept_for_review() {
+        let devices = Devices::new();
+        let id = devices.shared_persona();
+
+        commands::delete_persona(&devices.a, id.clone()).unwrap();
+        pause();
+        commands::update_persona(&devices.b, id.clone(), Some("Studio".into()), None, None, None).unwrap();
+        devices.sync(&devices.a);
+        let on_b = devices.sync(&devices.b);
+        let on_a = devices.sync(&devices.a);
+
+        // Neither side loses data silently: B keeps its edit, A keeps its delete, both ask.
+        assert_eq!(on_b.conflicts.len(), 1);
+        assert_eq!(on_b.conflicts[0].field, ROW_FIELD);
+        assert_eq!(on_a.conflicts.len(), 1);
+        assert_eq!(on_a.conflicts[0].field, ROW_FIELD);
+        assert_eq!(field(&devices.b, &id, "name").as_deref(), Some("Studio"));
+        assert_eq!(field(&devices.a, &id, "name"), None);
+    }
+
+    #[test]
+    fn a_row_inserted_again_after_a_delete_comes_back() {
+        let devices = Devices::new();
+        let id = devices.shared_persona();
+
+        commands::delete_persona(&devices.a, id.clone()).unwrap();
+        devices.sync(&devices.a);
+        devices.sync(&devices.b);
+        assert_eq!(field(&devices.b, &id, "name"), None);
+
+        pause();
+        let now = get_current_timestamp().to_rfc3339();
+        devices.a.execute(
+            "INSERT INTO personas (id, name, description, color, created_at, updated_at, is_active) VALUES (?1, 'Work again', NULL, '#3366ff', ?2, ?2, 1)",
+            [&id, &now],
+        ).unwrap();
+        devices.sync(&devices.a);
+        let report = devices.sync(&devices.b);
+
+        assert!(report.conflicts.is_empty());
+        assert_eq!(field(&devices.b, &id, "name").as_deref(), Some("Work again"));
+    }
+}
@@ -0,0 +1,600 @@
+use base64::Engine;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+use crate::database::get_current_timestamp;
+use crate::sync::{complete_lines, ChangeStore};
+
+// WebDAV backend for the sync engine (Nextcloud, ownCloud, Apache mod_dav...).
+//
+// Layout on the server, relative to the configured base URL:
+//   <device>/manifest.json        ordered list of the device's change sets
+//   <device>/<segment>.jsonl      immutable change sets, one per push
+//
+// Change sets are staged in sync_upload_queue in the same transaction that
+// consumes the outbox, uploaded with If-None-Match so a retry never clobbers
+// anything, and only then appended to the manifest with If-Match on its ETag.
+// Readers keep a "<segment index>:<byte offset>" cursor and continue an
+// interrupted download with a Range request.
+
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_ATTEMPTS: usize = 5;
+
+pub enum Precondition<'a> {
+    None,
+    IfMatch(&'a str),
+    IfNoneMatch,
+}
+
+pub enum PutOutcome {
+    Stored(Option<String>),
+    PreconditionFailed,
+}
+
+pub struct DavResponse {
+    pub body: Vec<u8>,
+    pub etag: Option<String>,
+    // False when the transfer broke off before the end of the resource.
+    pub complete: bool,
+}
+
+// The handful of WebDAV operations the sync store needs. Paths are relative to the base URL.
+pub trait DavClient {
+    // GET from `offset` onwards; None when the resource does not exist.
+    fn get(&mut self, path: &str, offset: u64) -> Result<Option<DavResponse>, String>;
+    fn put(&mut self, path: &str, body: &[u8], precondition: Precondition) -> Result<PutOutcome, String>;
+    // Create a collection; succeeds when it already exists.
+    fn mkcol(&mut self, path: &str) -> Result<(), String>;
+    // Names of the child collections of `path`.
+    fn list(&mut self, path: &str) -> Result<Vec<String>, String>;
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    device: String,
+    segments: Vec<String>,
+}
+
+pub struct WebDavStore<C: DavClient> {
+    client: C,
+}
+
+impl<C: DavClient> WebDavStore<C> {
+    pub fn new(client: C) -> Self {
+        WebDavStore { client }
+    }
+
+    fn read_manifest(&mut self, device: &str) -> Result<(Manifest, Option<String>), String> {
+        match self.client.get(&format!("{}/{}", device, MANIFEST), 0)? {
+            Some(response) if response.complete => {
+                let manifest = serde_json::from_slice(&response.body)
+                    .map_err(|e| format!("WebDAV manifest error for {}: {}", device, e))?;
+                Ok((manifest, response.etag))
+            }
+            Some(_) => Err(format!("WebDAV manifest download for {} was interrupted", device)),
+            None => Ok((Manifest { device: device.to_string(), segments: Vec::new() }, None)),
+        }
+    }
+
+    // Append uploaded segments to the manifest, retrying when another writer got there first.
+    fn append_to_manifest(&mut self, device: &str, segments: &[String]) -> Result<(), String> {
+        for _ in 0..MANIFEST_ATTEMPTS {
+            let (mut manifest, etag) = self.read_manifest(device)?;
+            for segment in segments {
+                if !manifest.segments.contains(segment) {
+                    manifest.segments.push(segment.clone());
+                }
+            }
+
+            let body = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Serialization error: {}", e))?;
+            let precondition = match &etag {
+                Some(etag) => Precondition::IfMatch(etag),
+                None => Precondition::IfNoneMatch,
+            };
+
+            match self.client.put(&format!("{}/{}", device, MANIFEST), &body, precondition)? {
+                PutOutcome::Stored(_) => return Ok(()),
+                PutOutcome::PreconditionFailed => continue,
+            }
+        }
+
+        Err(format!("WebDAV manifest for {} kept changing; try again later", device))
+    }
+}
+
+impl<C: DavClient> ChangeStore for WebDavStore<C> {
+    fn publish(&mut self, conn: &Connection, device: &str, lines: &str) -> Result<(), String> {
+        let segment = format!(
+            "{:013}-{}.jsonl",
+            get_current_timestamp().timestamp_millis(),
+            &crate::database::generate_id()[..8]
+        );
+
+        conn.execute(
+            "INSERT INTO sync_upload_queue (segment, device, body, queued_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![segment, device, lines, get_current_timestamp().to_rfc3339()],
+        ).map_err(|e| format!("SQL insert error: {}", e))?;
+
+        Ok(())
+    }
+
+    fn flush(&mut self, conn: &Connection, device: &str) -> Result<(), String> {
+        let queued: Vec<(String, String)> = {
+            let mut stmt = conn.prepare("SELECT segment, body FROM sync_upload_queue WHERE device = ?1 ORDER BY rowid")
+                .map_err(|e| format!("SQL prepare error: {}", e))?;
+            let rows = stmt.query_map([device], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| format!("SQL query error: {}", e))?;
+            let queued: Result<Vec<_>, rusqlite::Error> = rows.collect();
+            queued.map_err(|e| format!("SQL collect error: {}", e))?
+        };
+
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        self.client.mkcol(device)?;
+        for (segment, body) in &queued {
+            // PreconditionFailed just means an earlier run was cut off after this upload.
+            self.client.put(&format!("{}/{}", device, segment), body.as_bytes(), Precondition::IfNoneMatch)?;
+        }
+
+        let segments: Vec<String> = queued.into_iter().map(|(segment, _)| segment).collect();
+        self.append_to_manifest(device, &segments)?;
+
+        for segment in &segments {
+            conn.execute("DELETE FROM sync_upload_queue WHERE segment = ?1", [segment])
+                .map_err(|e| format!("SQL delete error: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn peers(&mut self, own: &str) -> Result<Vec<String>, String> {
+        let mut peers: Vec<String> = self.client.list("")?
+            .into_iter()
+            .filter(|device| device != own)
+            .collect();
+        peers.sort();
+        Ok(peers)
+    }
+
+    fn fetch(&mut self, peer: &str, cursor: Option<&str>) -> Result<(Vec<u8>, String), String> {
+        let (manifest, _) = self.read_manifest(peer)?;
+
+        let (mut index, mut offset) = cursor
+            .and_then(|c| c.split_once(':'))
+            .and_then(|(i, o)| Some((i.parse::<usize>().ok()?, o.parse::<u64>().ok()?)))
+            .unwrap_or((0, 0));
+
+        let mut out = Vec::new();
+        while let Some(segment) = manifest.segments.get(index) {
+            let response = self.client.get(&format!("{}/{}", peer, segment), offset)?
+                .ok_or_else(|| format!("WebDAV change set {}/{} is listed but missing", peer, segment))?;
+
+            let complete = complete_lines(&response.body);
+            out.extend_from_slice(&response.body[..complete]);
+
+            if response.complete && complete == response.body.len() {
+                index += 1;
+                offset = 0;
+            } else {
+                // Broken transfer: resume from the last full line next time.
+                offset += complete as u64;
+                break;
+            }
+        }
+
+        Ok((out, format!("{}:{}", index, offset)))
+    }
+
+    fn cursor_key(&self, peer: &str) -> String {
+        format!("webdav_cursor:{}", peer)
+    }
+}
+
+// DavClient over HTTP(S).
+pub struct HttpDav {
+    base_url: String,
+    authorization: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl HttpDav {
+    pub fn new(base_url: &str, username: Option<&str>, password: Option<&str>) -> Self {
+        let authorization = username.map(|user| {
+            let credentials = format!("{}:{}", user, password.unwrap_or_default());
+            format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials))
+        });
+
+        HttpDav {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            authorization,
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(std::time::Duration::from_secs(15))
+                .timeout_read(std::time::Duration::from_secs(60))
+                .build(),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let url = if path.is_empty() {
+            format!("{}/", self.base_url)
+        } else {
+            format!("{}/{}", self.base_url, path)
+        };
+        let request = self.agent.request(method, &url);
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    fn base_path(&self) -> String {
+        url_path(&self.base_url).trim_end_matches('/').to_string()
+    }
+}
+
+fn dav_error(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, response) => format!("WebDAV error: {} {}", code, response.status_text()),
+        ureq::Error::Transport(transport) => format!("WebDAV connection error: {}", transport),
+    }
+}
+
+impl DavClient for HttpDav {
+    fn get(&mut self, path: &str, offset: u64) -> Result<Option<DavResponse>, String> {
+        let mut request = self.request("GET", path);
+        if offset > 0 {
+            request = request.set("Range", &format!("bytes={}-", offset));
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            // Nothing past the offset yet.
+            Err(ureq::Error::Status(416, _)) => {
+                return Ok(Some(DavResponse { body: Vec::new(), etag: None, complete: true }));
+            }
+            Err(e) => return Err(dav_error(e)),
+        };
+
+        let status = response.status();
+        let etag = response.header("ETag").map(|e| e.to_string());
+        let mut body = Vec::new();
+        // Keep whatever arrived before a broken connection; the caller resumes from there.
+        let complete = response.into_reader().read_to_end(&mut body).is_ok();
+
+        // The server ignored the Range header and sent the whole resource.
+        if offset > 0 && status == 200 {
+            body.drain(..(offset as usize).min(body.len()));
+        }
+
+        Ok(Some(DavResponse { body, etag, complete }))
+    }
+
+    fn put(&mut self, path: &str, body: &[u8], precondition: Precondition) -> Result<PutOutcome, String> {
+        let request = match precondition {
+            Precondition::None => self.request("PUT", path),
+            Precondition::IfMatch(etag) => self.request("PUT", path).set("If-Match", etag),
+            Precondition::IfNoneMatch => self.request("PUT", path).set("If-None-Match", "*"),
+        };
+
+        match request.send_bytes(body) {
+            Ok(response) => Ok(PutOutcome::Stored(response.header("ETag").map(|e| e.to_string()))),
+            Err(ureq::Error::Status(412, _)) => Ok(PutOutcome::PreconditionFailed),
+            Err(e) => Err(dav_error(e)),
+        }
+    }
+
+    fn mkcol(&mut self, path: &str) -> Result<(), String> {
+        match self.request("MKCOL", path).call() {
+            // 405 Method Not Allowed: the collection already exists.
+            Ok(_) | Err(ureq::Error::Status(405, _)) => Ok(()),
+            Err(e) => Err(dav_error(e)),
+        }
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<String>, String> {
+        let response = self.request("PROPFIND", path)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(r#"<?xml version="1.0" encoding="utf-8"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#)
+            .map_err(dav_error)?;
+
+        let xml = response.into_string().map_err(|e| format!("WebDAV read error: {}", e))?;
+        let own_path = format!("{}/{}", self.base_path(), path).trim_end_matches('/').to_string();
+
+        Ok(parse_collections(&xml, &own_path))
+    }
+}
+
+// Child collection names from a PROPFIND multistatus body, skipping the collection itself.
+fn parse_collections(xml: &str, own_path: &str) -> Vec<String> {
+    let xml = strip_namespace_prefixes(xml);
+    let mut names = Vec::new();
+
+    for block in xml.split("<response").skip(1) {
+        let Some(start) = block.find("<href>") else { continue };
+        let Some(end) = block[start..].find("</href>") else { continue };
+        let href = percent_decode(block[start + 6..start + end].trim());
+        let href_path = url_path(&href).trim_end_matches('/').to_string();
+
+        if href_path == own_path || !block.contains("<collection") {
+            continue;
+        }
+        if let Some(name) = href_path.rsplit('/').next() {
+            if !name.is_empty() {
+                names.push(name.to_string());
+            }
+        }
+    }
+
+    names
+}
+
+// "<d:href>" -> "<href>", whatever prefix the server picked for the DAV: namespace.
+fn strip_namespace_prefixes(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let name_start = if rest.starts_with("</") { 2 } else { 1 };
+        let name_end = rest[name_start..]
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .map(|i| i + name_start)
+            .unwrap_or(rest.len());
+        let name = &rest[name_start..name_end];
+        out.push_str(&rest[..name_start]);
+        out.push_str(name.rsplit(':').next().unwrap_or(name));
+        rest = &rest[name_end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn url_path(url: &str) -> &str {
+    match url.find("://") {
+        Some(scheme_end) => {
+            let after_scheme = &url[scheme_end + 3..];
+            after_scheme.find('/').map(|i| &after_scheme[i..]).unwrap_or("")
+        }
+        None => url,
+    }
+}
+
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// In-process WebDAV stand-in keeping everything in memory. Clones share the
+// same server, so two sync engines can talk through it; `interrupt_next_get`
+// cuts the next download of a matching path short to exercise resuming.
+#[derive(Clone, Default)]
+pub struct MemoryDav {
+    state: Arc<Mutex<MemoryDavState>>,
+}
+
+#[derive(Default)]
+struct MemoryDavState {
+    files: HashMap<String, (Vec<u8>, u64)>,
+    collections: HashSet<String>,
+    next_etag: u64,
+    interrupt: Option<(String, usize)>, // path suffix and the bytes let through
+}
+
+impl MemoryDav {
+    pub fn new() -> Self {
+        MemoryDav::default()
+    }
+
+    pub fn interrupt_next_get(&self, path_suffix: &str, after_bytes: usize) {
+        if let Ok(mut state) = self.state.lock() {
+            state.interrupt = Some((path_suffix.to_string(), after_bytes));
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, MemoryDavState>, String> {
+        self.state.lock().map_err(|e| format!("WebDAV stand-in lock error: {}", e))
+    }
+}
+
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+impl DavClient for MemoryDav {
+    fn get(&mut self, path: &str, offset: u64) -> Result<Option<DavResponse>, String> {
+        let mut state = self.lock()?;
+        let interrupt_after = match &state.interrupt {
+            Some((suffix, after_bytes)) if path.ends_with(suffix.as_str()) => {
+                let after_bytes = *after_bytes;
+                state.interrupt = None;
+                Some(after_bytes)
+            }
+            _ => None,
+        };
+
+        let Some((body, etag)) = state.files.get(path) else {
+            return Ok(None);
+        };
+        let mut body = body.get(offset as usize..).unwrap_or_default().to_vec();
+        let mut complete = true;
+        if let Some(limit) = interrupt_after {
+            if limit < body.len() {
+                body.truncate(limit);
+                complete = false;
+            }
+        }
+
+        Ok(Some(DavResponse { body, etag: Some(format!("\"{}\"", etag)), complete }))
+    }
+
+    fn put(&mut self, path: &str, body: &[u8], precondition: Precondition) -> Result<PutOutcome, String> {
+        let mut state = self.lock()?;
+
+        let parent = parent_of(path);
+        if !parent.is_empty() && !state.collections.contains(parent) {
+            return Err("WebDAV error: 409 Conflict".to_string());
+        }
+
+        let current = state.files.get(path).map(|(_, etag)| format!("\"{}\"", etag));
+        let allowed = match precondition {
+            Precondition::None => true,
+            Precondition::IfMatch(etag) => current.as_deref() == Some(etag),
+            Precondition::IfNoneMatch => current.is_none(),
+        };
+        if !allowed {
+            return Ok(PutOutcome::PreconditionFailed);
+        }
+
+        state.next_etag += 1;
+        let etag = state.next_etag;
+        state.files.insert(path.to_string(), (body.to_vec(), etag));
+        Ok(PutOutcome::Stored(Some(format!("\"{}\"", etag))))
+    }
+
+    fn mkcol(&mut self, path: &str) -> Result<(), String> {
+        self.lock()?.collections.insert(path.trim_end_matches('/').to_string());
+        Ok(())
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<String>, String> {
+        let path = path.trim_end_matches('/');
+        let state = self.lock()?;
+        Ok(state.collections
+            .iter()
+            .filter(|c| parent_of(c) == path && c.as_str() != path)
+            .map(|c| c.rsplit('/').next().unwrap_or(c).to_string())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+    use crate::sync::SyncEngine;
+
+    fn sync(conn: &Connection, server: &MemoryDav) -> crate::sync::SyncReport {
+        SyncEngine::open(conn, WebDavStore::new(server.clone())).unwrap().sync().unwrap()
+    }
+
+    fn persona_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM personas", [], |row| row.get(0)).unwrap()
+    }
+
+    fn manifest(server: &MemoryDav, device: &str) -> Manifest {
+        let response = server.clone().get(&format!("{}/{}", device, MANIFEST), 0).unwrap().unwrap();
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn first_sync_against_an_empty_server() {
+        let server = MemoryDav::new();
+        let empty = commands::open_database(":memory:").unwrap();
+        let report = sync(&empty, &server);
+        assert_eq!((report.pushed, report.pulled), (0, 0));
+        assert!(report.peers.is_empty() && report.errors.is_empty());
+
+        let a = commands::open_database(":memory:").unwrap();
+        commands::create_persona(&a, "Work".into(), None, "#3366ff".into()).unwrap();
+        let report = sync(&a, &server);
+        assert_eq!(report.pushed, 1);
+        assert_eq!(manifest(&server, &report.device_id).segments.len(), 1);
+
+        let b = commands::open_database(":memory:").unwrap();
+        let report = sync(&b, &server);
+        assert_eq!(report.pulled, 1);
+        assert_eq!(persona_count(&b), 1);
+    }
+
+    #[test]
+    fn an_interrupted_download_resumes_from_the_last_full_line() {
+        let server = MemoryDav::new();
+        let a = commands::open_database(":memory:").unwrap();
+        for name in ["Work", "Home", "Side project"] {
+            commands::create_persona(&a, name.into(), None, "#3366ff".into()).unwrap();
+        }
+        let device = sync(&a, &server).device_id;
+        let segment = &manifest(&server, &device).segments[0];
+        let length = server.clone().get(&format!("{}/{}", device, segment), 0).unwrap().unwrap().body.len();
+
+        // Cut the change set off halfway through its second line.
+        let b = commands::open_database(":memory:").unwrap();
+        server.interrupt_next_get(".jsonl", length / 2);
+        let first = sync(&b, &server);
+        assert_eq!(first.pulled, 1);
+        assert_eq!(persona_count(&b), 1);
+
+        let second = sync(&b, &server);
+        assert_eq!(second.pulled, 2);
+        assert_eq!(persona_count(&b), 3);
+        assert!(first.errors.is_empty() && second.errors.is_empty());
+    }
+
+    // Writes another change set into the manifest just before the first manifest upload lands.
+    struct RacingDav {
+        server: MemoryDav,
+        raced: bool,
+    }
+
+    impl DavClient for RacingDav {
+        fn get(&mut self, path: &str, offset: u64) -> Result<Option<DavResponse>, String> {
+            self.server.get(path, offset)
+        }
+
+        fn put(&mut self, path: &str, body: &[u8], precondition: Precondition) -> Result<PutOutcome, String> {
+            if path.ends_with(MANIFEST) && !self.raced {
+                self.raced = true;
+                let device = parent_of(path).to_string();
+                let other = Manifest { device, segments: vec!["0000000000000-other.jsonl".to_string()] };
+                self.server.put(path, &serde_json::to_vec(&other).unwrap(), Precondition::None)?;
+            }
+            self.server.put(path, body, precondition)
+        }
+
+        fn mkcol(&mut self, path: &str) -> Result<(), String> {
+            self.server.mkcol(path)
+        }
+
+        fn list(&mut self, path: &str) -> Result<Vec<String>, String> {
+            self.server.list(path)
+        }
+    }
+
+    #[test]
+    fn a_manifest_changed_underneath_is_read_again() {
+        let server = MemoryDav::new();
+        let a = commands::open_database(":memory:").unwrap();
+        commands::create_persona(&a, "Work".into(), None, "#3366ff".into()).unwrap();
+
+        let store = WebDavStore::new(RacingDav { server: server.clone(), raced: false });
+        let report = SyncEngine::open(&a, store).unwrap().sync().unwrap();
+
+        // The first upload failed its ETag precondition; the retry kept both change sets.
+        let segments = manifest(&server, &report.device_id).segments;
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0], "0000000000000-other.jsonl");
+        let queued: i64 = a.query_row("SELECT COUNT(*) FROM sync_upload_queue", [], |row| row.get(0)).unwrap();
+        assert_eq!(queued, 0);
+    }
+}
@@ -1,4 +1,5 @@
-import React from 'react';
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import './Dashboard.css';
 
 const Dashboard = () => {
@@ -15,11 +16,14 @@ const Dashboard = () => {
     { id: 3, type: 'journal', description: 'Added journal entry to Q4 Goals', time: '1 day ago' },
   ];
 
-  const upcomingDeadlines = [
-    { id: 1, title: 'API Documentation', dueDate: 'Tomorrow', priority: 'high' },
-    { id: 2, title: 'Fitness Assessment', dueDate: 'In 3 days', priority: 'medium' },
-    { id: 3, title: 'Learning Project Demo', dueDate: 'Next week', priority: 'low' },
-  ];
+  // Open tasks and milestones due in the next week, overdue ones included
+  const [upcomingDeadlines, setUpcomingDeadlines] = useState([]);
+
+  useEffect(() => {
+    invoke('get_upcoming_deadlines', { days: 7 })
+      .then(setUpcomingDeadlines)
+      .catch((error) => console.error('Failed to load deadlines:', error));
+  }, []);
 
   return (
     <div className="dashboard">
@@ -91,15 +95,27 @@ const Dashboard = () => {
           <section className="dashboard-section">
             <h2 className="section-title">Upcoming Deadlines</h2>
             <div className="deadlines-list card">
+              {upcomingDeadlines.length === 0 && (
+                <p className="text-muted">Nothing due this week</p>
+              )}
               {upcomingDeadlines.map((deadline) => (
-                <div key={deadline.id} className="deadline-item">
+                <div key={`${deadline.kind}-${deadline.id}`} className="deadline-item">
                   <div className="deadline-content">
                     <h4 className="deadline-title">{deadline.title}</h4>
-                    <span className="deadline-date text-muted">{deadline.dueDate}</span>
+                    <span className="deadline-date text-muted">
+                      {deadline.overdue ? `Overdue since ${deadline.due_date}` : deadline.due_date}
+                      {' · '}{deadline.workstream_name}
+                    </span>
                   </div>
-                  <span className={`priority-badge priority-${deadline.priority}`}>
-                    {deadline.priority}
-                  </span>
+                  {deadline.kind === 'milestone' ? (
+                    <span className="priority-badge priority-milestone">
+                      Milestone {deadline.progress_percentage}%
+                    </span>
+                  ) : (
+                    <span className={`priority-badge priority-${deadline.priority}`}>
+                      {deadline.priority}
+                    </span>
+                  )}
                 </div>
               ))}
             </div>
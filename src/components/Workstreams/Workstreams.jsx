@@ -23,9 +23,27 @@ const Workstreams = () => {
     persona_id: '',
     name: '',
     description: '',
-    status: 'planning'
+    status: 'planning',
+    reopen: false,
+    habits: true,
+    open_tasks: 'keep',
+    target_workstream_id: ''
   });
 
+  // What happens to open tasks when a workstream is completed or cancelled
+  const openTaskOptions = {
+    completed: [
+      { value: 'keep', label: 'Keep them open' },
+      { value: 'archive', label: 'Archive them' },
+      { value: 'close', label: 'Mark them done' }
+    ],
+    cancelled: [
+      { value: 'keep', label: 'Keep them open' },
+      { value: 'backlog', label: 'Move them to the backlog' },
+      { value: 'move', label: 'Move them to another workstream' }
+    ]
+  };
+
   // Status options
   const statusOptions = [
     { value: 'planning', label: 'Planning', icon: '📋', color: '#6b7280' },
@@ -104,18 +122,42 @@ const Workstreams = () => {
     setIsLoading(true);
     setError('');
     try {
-      const result = await invoke('update_workstream', {
+      const updated = await invoke('update_workstream', {
         id: editingWorkstream.id,
         name: formData.name.trim(),
-        description: formData.description.trim() || null,
-        status: formData.status
+        description: formData.description.trim() || null
       });
-      
-      setWorkstreams(prev => prev.map(w => w.id === editingWorkstream.id ? result : w));
+      setWorkstreams(prev => prev.map(w => w.id === editingWorkstream.id ? updated : w));
+    } catch (error) {
+      setError(`Failed to update workstream: ${error}`);
+      setIsLoading(false);
+      return;
+    }
+
+    // The status change runs last, with its side effects, so a failure leaves
+    // the saved name and description as they are.
+    try {
+      if (statusChanged) {
+        const tasksAction = openTaskOptions[formData.status] ? formData.open_tasks : null;
+        const change = await invoke('change_workstream_status', {
+          id: editingWorkstream.id,
+          status: formData.status,
+          reopen: formData.reopen,
+          habits: formData.habits,
+          openTasks: tasksAction,
+          targetWorkstreamId: tasksAction === 'move' ? formData.target_workstream_id || null : null
+        });
+        setWorkstreams(prev => prev.map(w => w.id === editingWorkstream.id ? change.workstream : w));
+        if (change.changes.length > 0) {
+          setSuccessMessage(`${change.changes.join('. ')}.`);
+          setSuccessModalType('success');
+          setShowSuccessModal(true);
+        }
+      }
       setEditingWorkstream(null);
       resetForm();
     } catch (error) {
-      setError(`Failed to update workstream: ${error}`);
+      setError(`Saved the name and description, but the status change failed: ${error}`);
     } finally {
       setIsLoading(false);
     }
@@ -161,7 +203,11 @@ const Workstreams = () => {
       persona_id: workstream.persona_id,
       name: workstream.name,
       description: workstream.description || '',
-      status: workstream.status.toLowerCase()
+      status: workstream.status.toLowerCase(),
+      reopen: false,
+      habits: true,
+      open_tasks: 'keep',
+      target_workstream_id: ''
     });
     setShowCreateForm(true);
   };
@@ -171,7 +217,11 @@ const Workstreams = () => {
       persona_id: '',
       name: '',
       description: '',
-      status: 'planning'
+      status: 'planning',
+      reopen: false,
+      habits: true,
+      open_tasks: 'keep',
+      target_workstream_id: ''
     });
     setEditingWorkstream(null);
     setError('');
@@ -183,6 +233,13 @@ const Workstreams = () => {
     resetForm();
   };
 
+  const originalStatus = editingWorkstream ? editingWorkstream.status.toLowerCase() : null;
+  const statusChanged = editingWorkstream !== null && formData.status !== originalStatus;
+  const reopening = statusChanged && ['completed', 'cancelled'].includes(originalStatus);
+  const moveTargets = workstreams.filter(w =>
+    editingWorkstream && w.id !== editingWorkstream.id && !['completed', 'cancelled'].includes(w.status.toLowerCase())
+  );
+
   const getStatusInfo = (status) => {
     // Handle both lowercase and capitalized status values for backward compatibility
     const normalizedStatus = status.toLowerCase();
@@ -321,7 +378,7 @@ const Workstreams = () => {
                   id="status"
                   className="form-select"
                   value={formData.status}
-                  onChange={(e) => setFormData(prev => ({ ...prev, status: e.target.value }))}
+                  onChange={(e) => setFormData(prev => ({ ...prev, status: e.target.value, open_tasks: 'keep' }))}
                   disabled={isLoading}
                 >
                   {statusOptions.map((status) => (
@@ -332,6 +389,78 @@ const Workstreams = () => {
                 </select>
               </div>
 
+              {reopening && (
+                <div className="form-group">
+                  <label className="checkbox-label">
+                    <input
+                      type="checkbox"
+                      checked={formData.reopen}
+                      onChange={(e) => setFormData(prev => ({ ...prev, reopen: e.target.checked }))}
+                      disabled={isLoading}
+                    />
+                    <span>Reopen this {originalStatus} workstream</span>
+                  </label>
+                </div>
+              )}
+
+              {statusChanged && (
+                <div className="form-group">
+                  <label className="checkbox-label">
+                    <input
+                      type="checkbox"
+                      checked={formData.habits}
+                      onChange={(e) => setFormData(prev => ({ ...prev, habits: e.target.checked }))}
+                      disabled={isLoading}
+                    />
+                    <span>Pause or resume the workstream's habits along with it</span>
+                  </label>
+                </div>
+              )}
+
+              {statusChanged && openTaskOptions[formData.status] && (
+                <div className="form-group">
+                  <label htmlFor="open_tasks" className="form-label">
+                    Open tasks
+                  </label>
+                  <select
+                    id="open_tasks"
+                    className="form-select"
+                    value={formData.open_tasks}
+                    onChange={(e) => setFormData(prev => ({ ...prev, open_tasks: e.target.value }))}
+                    disabled={isLoading}
+                  >
+                    {openTaskOptions[formData.status].map((option) => (
+                      <option key={option.value} value={option.value}>
+                        {option.label}
+                      </option>
+                    ))}
+                  </select>
+                </div>
+              )}
+
+              {statusChanged && formData.status === 'cancelled' && formData.open_tasks === 'move' && (
+                <div className="form-group">
+                  <label htmlFor="target_workstream_id" className="form-label">
+                    Move them to *
+                  </label>
+                  <select
+                    id="target_workstream_id"
+                    className="form-select"
+                    value={formData.target_workstream_id}
+                    onChange={(e) => setFormData(prev => ({ ...prev, target_workstream_id: e.target.value }))}
+                    disabled={isLoading}
+                    required
+                  >
+                    <option value="">Select a workstream</option>
+                    {moveTargets.map((workstream) => (
+                      <option key={workstream.id} value={workstream.id}>
+                        {workstream.name}
+                      </option>
+                    ))}
+                  </select>
+                </div>
+              )}
+
               <div className="form-actions">
                 <button 
                   type="button"
@@ -344,7 +473,7 @@ const Workstreams = () => {
                 <button 
                   type="submit"
                   className="btn btn-primary"
-                  disabled={isLoading || !formData.name.trim() || !formData.persona_id}
+                  disabled={isLoading || !formData.name.trim() || !formData.persona_id || (reopening && !formData.reopen)}
                 >
                   {isLoading ? '⏳ Saving...' : (editingWorkstream ? '💾 Update Workstream' : '➕ Create Workstream')}
                 </button>